@@ -0,0 +1,155 @@
+//! Clipping rectangles for the draw pass
+//!
+//! Containers push their absolute boundaries before drawing the widgets and pop
+//! them after, so the CTX always knows the visible area of the widget that is drawing.
+//! A CTX implements Clip for apply the clip rectangles to the renderer, ClipStack
+//! can be used for keep track of the intersections
+
+use crate::widget::Boundaries;
+
+use std::ops::{Add, Sub};
+
+/// Clip Trait for a CTX
+///
+/// Containers need it for draw their widgets, the default methods don't clip
+/// anything, so a CTX without clipping only needs an empty implementation:
+///
+/// ```
+/// use trgui::clip::Clip;
+///
+/// struct Context;
+/// impl Clip<f32> for Context {}
+///
+/// let mut ctx = Context;
+/// ctx.push_clip((0.0, 0.0, 10.0, 10.0));
+/// assert_eq!(ctx.clip(), None);
+/// ```
+pub trait Clip<T> {
+    /// Push a clip rectangle, the new clip is the intersection with the current clip
+    fn push_clip(&mut self, _: Boundaries<T>) {}
+    /// Restore the previous clip rectangle
+    fn pop_clip(&mut self) {}
+    /// Get the current clip rectangle, None means there is nothing clipped
+    fn clip(&self) -> Option<Boundaries<T>> {
+        None
+    }
+}
+
+/// Stack of clip rectangles, each rectangle is already intersected with the previous
+pub struct ClipStack<T> {
+    stack: Vec<Boundaries<T>>,
+}
+
+#[inline]
+fn max<T: PartialOrd>(a: T, b: T) -> T {
+    if a > b {
+        a
+    } else {
+        b
+    }
+}
+
+#[inline]
+fn min<T: PartialOrd>(a: T, b: T) -> T {
+    if a < b {
+        a
+    } else {
+        b
+    }
+}
+
+/// Intersection of two boundaries, the dimensions are zero if they don't overlap
+pub fn intersect<T>(a: Boundaries<T>, b: Boundaries<T>) -> Boundaries<T>
+where
+    T: Copy + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+{
+    let (x1, y1) = (max(a.0, b.0), max(a.1, b.1));
+    let (x2, y2) = (min(a.0 + a.2, b.0 + b.2), min(a.1 + a.3, b.1 + b.3));
+
+    (
+        x1,
+        y1,
+        if x2 > x1 { x2 - x1 } else { T::default() },
+        if y2 > y1 { y2 - y1 } else { T::default() },
+    )
+}
+
+/// Check if two boundaries share some area
+#[inline]
+pub fn overlaps<T>(a: Boundaries<T>, b: Boundaries<T>) -> bool
+where
+    T: Copy + Add<Output = T> + PartialOrd,
+{
+    a.0 < b.0 + b.2 && b.0 < a.0 + a.2 && a.1 < b.1 + b.3 && b.1 < a.1 + a.3
+}
+
+impl<T> ClipStack<T>
+where
+    T: Copy + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+{
+    /// Creates an empty ClipStack
+    pub fn new() -> Self {
+        ClipStack { stack: Vec::new() }
+    }
+
+    /// Count pushed clip rectangles
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Check if there is no clip rectangle
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    /// Check if boundaries are visible with the current clip
+    #[inline]
+    pub fn is_visible(&self, bounds: Boundaries<T>) -> bool {
+        match self.stack.last() {
+            Some(&clip) => overlaps(clip, bounds),
+            None => true,
+        }
+    }
+
+    /// Remove every clip rectangle
+    pub fn clear(&mut self) {
+        self.stack.clear();
+    }
+}
+
+impl<T> Default for ClipStack<T>
+where
+    T: Copy + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clip<T> for ClipStack<T>
+where
+    T: Copy + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+{
+    fn push_clip(&mut self, bounds: Boundaries<T>) {
+        let clip = match self.stack.last() {
+            Some(&clip) => intersect(clip, bounds),
+            None => bounds,
+        };
+
+        self.stack.push(clip);
+    }
+
+    fn pop_clip(&mut self) {
+        self.stack.pop();
+    }
+
+    #[inline]
+    fn clip(&self) -> Option<Boundaries<T>> {
+        self.stack.last().copied()
+    }
+}
+
+/// Implementation for contexts without clipping
+impl<T> Clip<T> for () {}
@@ -0,0 +1,385 @@
+//! Color spaces
+//!
+//! Conversions between RGB, HSV and hex text. The channels are f32 from 0 to 1 and
+//! the hue is in degrees, so a color can go around the spaces without rounding to
+//! 8 bits in between. The hue and the saturation of grays are undefined, Hsv::set_rgb
+//! keeps the old ones for that the pickers don't jump when the color passes a gray
+
+use crate::paint::Color;
+
+/// RGB color with alpha, the channels are from 0 to 1
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rgb {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+/// HSV color with alpha, the hue is from 0 to 360 and the other channels from 0 to 1
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hsv {
+    pub h: f32,
+    pub s: f32,
+    pub v: f32,
+    pub a: f32,
+}
+
+/// Channel of a color edited by a picker
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Channel {
+    Red,
+    Green,
+    Blue,
+    Hue,
+    Saturation,
+    Value,
+    Alpha,
+}
+
+#[inline]
+fn unit(value: f32) -> f32 {
+    if value.is_nan() {
+        0.0
+    } else {
+        value.clamp(0.0, 1.0)
+    }
+}
+
+/// Hue in the range from 0 to 360, 360 is 0
+#[inline]
+fn hue(value: f32) -> f32 {
+    if !value.is_finite() {
+        return 0.0;
+    }
+
+    let h = value.rem_euclid(360.0);
+    if h >= 360.0 {
+        0.0
+    } else {
+        h
+    }
+}
+
+#[inline]
+fn to_u8(value: f32) -> u8 {
+    (unit(value) * 255.0).round() as u8
+}
+
+impl Rgb {
+    pub fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Rgb {
+            r: unit(r),
+            g: unit(g),
+            b: unit(b),
+            a: unit(a),
+        }
+    }
+
+    /// HSV of the color, the hue and the saturation of grays are 0
+    pub fn to_hsv(self) -> Hsv {
+        let max = self.r.max(self.g).max(self.b);
+        let min = self.r.min(self.g).min(self.b);
+        let delta = max - min;
+
+        let h = if delta <= 0.0 {
+            0.0
+        } else if max == self.r {
+            60.0 * ((self.g - self.b) / delta)
+        } else if max == self.g {
+            60.0 * ((self.b - self.r) / delta + 2.0)
+        } else {
+            60.0 * ((self.r - self.g) / delta + 4.0)
+        };
+
+        let s = if max > 0.0 { delta / max } else { 0.0 };
+
+        Hsv {
+            h: hue(h),
+            s,
+            v: max,
+            a: self.a,
+        }
+    }
+
+    /// Color with the channels rounded to 8 bits
+    pub fn to_color(self) -> Color {
+        Color::rgba(to_u8(self.r), to_u8(self.g), to_u8(self.b), to_u8(self.a))
+    }
+
+    /// Color between two colors, t is from 0 to 1
+    pub fn lerp(self, other: Rgb, t: f32) -> Rgb {
+        let t = unit(t);
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+
+        Rgb {
+            r: mix(self.r, other.r),
+            g: mix(self.g, other.g),
+            b: mix(self.b, other.b),
+            a: mix(self.a, other.a),
+        }
+    }
+}
+
+impl From<Color> for Rgb {
+    fn from(color: Color) -> Self {
+        Rgb {
+            r: color.r as f32 / 255.0,
+            g: color.g as f32 / 255.0,
+            b: color.b as f32 / 255.0,
+            a: color.a as f32 / 255.0,
+        }
+    }
+}
+
+impl From<Rgb> for Color {
+    fn from(rgb: Rgb) -> Self {
+        rgb.to_color()
+    }
+}
+
+impl Hsv {
+    pub fn new(h: f32, s: f32, v: f32, a: f32) -> Self {
+        Hsv {
+            h: hue(h),
+            s: unit(s),
+            v: unit(v),
+            a: unit(a),
+        }
+    }
+
+    pub fn to_rgb(self) -> Rgb {
+        let (h, s, v) = (hue(self.h) / 60.0, unit(self.s), unit(self.v));
+        let sector = h.floor();
+        let f = h - sector;
+
+        let p = v * (1.0 - s);
+        let q = v * (1.0 - s * f);
+        let t = v * (1.0 - s * (1.0 - f));
+
+        let (r, g, b) = match sector as u8 {
+            0 => (v, t, p),
+            1 => (q, v, p),
+            2 => (p, v, t),
+            3 => (p, q, v),
+            4 => (t, p, v),
+            _ => (v, p, q),
+        };
+
+        Rgb {
+            r,
+            g,
+            b,
+            a: unit(self.a),
+        }
+    }
+
+    #[inline]
+    pub fn to_color(self) -> Color {
+        self.to_rgb().to_color()
+    }
+
+    /// Replace the color with a RGB color, the hue is kept if the new color is gray
+    /// and the saturation is kept if it's black
+    pub fn set_rgb(&mut self, rgb: Rgb) {
+        let new = rgb.to_hsv();
+        let gray = rgb.r == rgb.g && rgb.g == rgb.b;
+
+        if !gray {
+            self.h = new.h;
+        }
+        if new.v > 0.0 {
+            self.s = new.s;
+        }
+
+        self.v = new.v;
+        self.a = new.a;
+    }
+
+    /// Value of a channel from 0 to 1, the hue is divided by 360
+    pub fn channel(self, channel: Channel) -> f32 {
+        let rgb = self.to_rgb();
+
+        match channel {
+            Channel::Red => rgb.r,
+            Channel::Green => rgb.g,
+            Channel::Blue => rgb.b,
+            Channel::Hue => hue(self.h) / 360.0,
+            Channel::Saturation => self.s,
+            Channel::Value => self.v,
+            Channel::Alpha => self.a,
+        }
+    }
+
+    /// Color with a channel changed, the value is from 0 to 1 like Hsv::channel
+    pub fn with_channel(self, channel: Channel, value: f32) -> Hsv {
+        let value = unit(value);
+        let mut hsv = self;
+
+        match channel {
+            Channel::Hue => hsv.h = hue(value * 360.0),
+            Channel::Saturation => hsv.s = value,
+            Channel::Value => hsv.v = value,
+            Channel::Alpha => hsv.a = value,
+            _ => {
+                let mut rgb = self.to_rgb();
+                match channel {
+                    Channel::Red => rgb.r = value,
+                    Channel::Green => rgb.g = value,
+                    _ => rgb.b = value,
+                }
+
+                hsv.set_rgb(rgb);
+            }
+        }
+
+        hsv
+    }
+}
+
+impl From<Color> for Hsv {
+    fn from(color: Color) -> Self {
+        Rgb::from(color).to_hsv()
+    }
+}
+
+impl From<Hsv> for Color {
+    fn from(hsv: Hsv) -> Self {
+        hsv.to_color()
+    }
+}
+
+impl Default for Hsv {
+    /// Opaque black
+    fn default() -> Self {
+        Hsv::new(0.0, 0.0, 0.0, 1.0)
+    }
+}
+
+/// Parse a color of 3, 4, 6 or 8 hex digits with an optional '#', the colors
+/// without alpha are opaque
+pub fn parse_hex(text: &str) -> Option<Color> {
+    let text = text.trim();
+    let digits = text.strip_prefix('#').unwrap_or(text);
+
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let value = u32::from_str_radix(digits, 16).ok();
+    match digits.len() {
+        3 | 4 => {
+            let short = value?;
+            let count = digits.len() as u32;
+            let nibble = |n: u32| ((short >> ((count - 1 - n) * 4)) & 0xf) as u8 * 17;
+            let a = if count == 4 { nibble(3) } else { 255 };
+
+            Some(Color::rgba(nibble(0), nibble(1), nibble(2), a))
+        }
+        6 => Some(Color::hex(value? << 8 | 0xff)),
+        8 => Some(Color::hex(value?)),
+        _ => None,
+    }
+}
+
+/// Text of a color as "#RRGGBB", or "#RRGGBBAA" with alpha
+pub fn format_hex(color: Color, alpha: bool) -> String {
+    if alpha {
+        format!("#{:08X}", color.to_hex())
+    } else {
+        format!("#{:06X}", color.to_hex() >> 8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rgb_hsv_round_trip() {
+        for r in 0..=255u8 {
+            for g in 0..=255u8 {
+                for b in 0..=255u8 {
+                    let color = Color::rgba(r, g, b, 255);
+                    let hsv = Hsv::from(color);
+
+                    assert!(hsv.h >= 0.0 && hsv.h < 360.0);
+                    assert_eq!(Color::from(hsv), color, "{:?}", hsv);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hsv_values() {
+        let red = Hsv::from(Color::rgb(255, 0, 0));
+        assert_eq!((red.h, red.s, red.v), (0.0, 1.0, 1.0));
+
+        let cyan = Hsv::from(Color::rgb(0, 255, 255));
+        assert_eq!((cyan.h, cyan.s, cyan.v), (180.0, 1.0, 1.0));
+
+        // 360 is 0 and the hue goes around
+        assert_eq!(Hsv::new(360.0, 1.0, 1.0, 1.0).h, 0.0);
+        assert_eq!(Hsv::new(-90.0, 1.0, 1.0, 1.0).h, 270.0);
+        assert_eq!(
+            Hsv::new(f32::NAN, 2.0, -1.0, 1.0),
+            Hsv::new(0.0, 1.0, 0.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn hex() {
+        assert_eq!(parse_hex("#f80"), Some(Color::rgb(255, 136, 0)));
+        assert_eq!(parse_hex("f80c"), Some(Color::rgba(255, 136, 0, 204)));
+        assert_eq!(parse_hex("#12AbEf"), Some(Color::rgb(0x12, 0xab, 0xef)));
+        assert_eq!(
+            parse_hex(" 12abef80 "),
+            Some(Color::rgba(0x12, 0xab, 0xef, 0x80))
+        );
+
+        for invalid in [
+            "",
+            "#",
+            "#12",
+            "#12345",
+            "#1234567",
+            "#123456789",
+            "#ggg",
+            "+123",
+            "#-12",
+        ] {
+            assert_eq!(parse_hex(invalid), None, "{}", invalid);
+        }
+
+        let color = Color::rgba(0x12, 0xab, 0xef, 0x80);
+        assert_eq!(format_hex(color, false), "#12ABEF");
+        assert_eq!(format_hex(color, true), "#12ABEF80");
+        assert_eq!(parse_hex(&format_hex(color, true)), Some(color));
+        assert_eq!(
+            parse_hex(&format_hex(color, false)),
+            Some(Color::rgb(0x12, 0xab, 0xef))
+        );
+    }
+
+    #[test]
+    fn gray_keeps_hue() {
+        let mut hsv = Hsv::new(120.0, 0.8, 0.6, 1.0);
+
+        hsv.set_rgb(Rgb::new(0.5, 0.5, 0.5, 1.0));
+        assert_eq!((hsv.h, hsv.s, hsv.v), (120.0, 0.0, 0.5));
+
+        // Black keeps the saturation too
+        let mut hsv = Hsv::new(200.0, 0.7, 0.6, 1.0);
+        hsv.set_rgb(Rgb::new(0.0, 0.0, 0.0, 0.5));
+        assert_eq!((hsv.h, hsv.s, hsv.v, hsv.a), (200.0, 0.7, 0.0, 0.5));
+
+        // A color replaces both
+        hsv.set_rgb(Rgb::new(1.0, 0.0, 0.0, 1.0));
+        assert_eq!((hsv.h, hsv.s, hsv.v), (0.0, 1.0, 1.0));
+
+        // Editing a RGB channel of a gray to the same value keeps the hue
+        let gray = Hsv::new(40.0, 0.0, 0.5, 1.0);
+        assert_eq!(gray.with_channel(Channel::Red, 0.5).h, 40.0);
+        assert_eq!(gray.with_channel(Channel::Red, 0.5).s, 0.0);
+    }
+}
@@ -3,8 +3,11 @@
 //! A Container implements Widget trait, so Containers can be nested. The widget list
 //! of a Container cannot be modified after moved to a "parent" Container
 
+use crate::clip::{overlaps, Clip};
+use crate::damage::DamageRegion;
 use crate::group::{Group, GroupID};
-use crate::state::{KeyState, MouseState};
+use crate::state::{Cursor, KeyState, MouseState, PointerID, TextInput, PRIMARY_POINTER};
+use crate::text::SharedMeasure;
 use crate::widget::flags::*;
 use crate::widget::{Boundaries, Dimensions, Widget, WidgetInternal};
 use crate::{Decorator, Layout};
@@ -23,13 +26,31 @@ const PARTIAL: Flags = 0b1_0000000000;
 type WidgetList<T, CTX> = Vec<Box<dyn Widget<T, CTX>>>;
 type InternalList<T> = Vec<WidgetInternal<T>>;
 
+/// Widget index and grab status of a pointer
+#[derive(Clone, Copy)]
+struct PointerSlot {
+    id: PointerID,
+    widget: Option<usize>,
+    grab: bool,
+}
+
 /// Widget List that handle widget trait functions
 pub struct Container<T, CTX: Sized, DE: Decorator<T, CTX>> {
     widgets_i: InternalList<T>,
     widgets: WidgetList<T, CTX>,
     layout: Box<dyn Layout<T>>,
     focus_id: Option<usize>,
-    mouse_id: Option<usize>,
+    pointers: Vec<PointerSlot>,
+    /// Widgets drawn on the last frame
+    drawn: Vec<usize>,
+    /// Widgets that requested another frame
+    pending: usize,
+    /// Old boundaries of moved widgets
+    moved: DamageRegion<T>,
+    /// Boundaries before applying the Layout
+    bounds: Vec<Boundaries<T>>,
+    /// The Decorator was updated, so the whole area is painted again
+    decorated: bool,
     decorator: DE,
 }
 
@@ -45,7 +66,12 @@ where
             widgets: WidgetList::new(),
             layout,
             focus_id: None,
-            mouse_id: None,
+            pointers: Vec::new(),
+            drawn: Vec::new(),
+            pending: 0,
+            moved: DamageRegion::new(),
+            bounds: Vec::new(),
+            decorated: false,
             decorator,
         }
     }
@@ -119,6 +145,61 @@ where
         }
     }
 
+    fn pointer_slot(&mut self, pointer: PointerID) -> usize {
+        match self.pointers.iter().position(|slot| slot.id == pointer) {
+            Some(n) => n,
+            None => {
+                self.pointers.push(PointerSlot {
+                    id: pointer,
+                    widget: None,
+                    grab: false,
+                });
+
+                self.pointers.len() - 1
+            }
+        }
+    }
+
+    fn pointer_grab(&self, n: usize, pointer: PointerID) -> bool {
+        self.pointers
+            .iter()
+            .any(|slot| slot.id != pointer && slot.widget == Some(n) && slot.grab)
+    }
+
+    /// Call hover_in if the pointer is the first pointer on the widget
+    fn pointer_enter(&mut self, internal: &mut WidgetInternal<T>, n: usize, pointer: PointerID) {
+        let hover = self
+            .pointers
+            .iter()
+            .any(|slot| slot.id != pointer && slot.widget == Some(n));
+
+        if !hover {
+            let w_internal = &mut self.widgets_i[n];
+
+            w_internal.on(HOVER);
+            self.widgets[n].hover_in(w_internal);
+            internal.on(w_internal.drain(REACTIVE, PREV_LAYOUT));
+        }
+    }
+
+    /// Call hover_out if no pointer remains on the widget, otherwise call pointer_out
+    fn pointer_leave(&mut self, internal: &mut WidgetInternal<T>, n: usize, pointer: PointerID) {
+        let hover = self.pointers.iter().any(|slot| slot.widget == Some(n));
+        let grab = self.pointer_grab(n, pointer);
+        let w_internal = &mut self.widgets_i[n];
+
+        if hover {
+            self.widgets[n].pointer_out(w_internal, pointer);
+            internal.on(w_internal.drain(REACTIVE, PREV_LAYOUT));
+
+            w_internal.turn(GRAB, grab);
+        } else {
+            // HOVER | GRAB | PREV_LAYOUT
+            self.widgets[n].hover_out(w_internal);
+            internal.on(w_internal.drain(REACTIVE, 0b10_01100000));
+        }
+    }
+
     fn focus_check(&mut self, internal: &mut WidgetInternal<T>) {
         if let Some(id) = self.focus_id {
             let w_internal = &mut self.widgets_i[id];
@@ -131,38 +212,87 @@ where
             }
         }
     }
+
+    /// Call a function of the focused widget, it loses the focus if it's no longer focusable
+    fn focused<F>(&mut self, internal: &mut WidgetInternal<T>, f: F)
+    where
+        F: FnOnce(&mut Box<dyn Widget<T, CTX>>, &mut WidgetInternal<T>),
+    {
+        if let Some(id) = self.focus_id {
+            let w_internal = &mut self.widgets_i[id];
+            let widget = &mut self.widgets[id];
+            let backup = w_internal.flags;
+
+            f(widget, w_internal);
+            internal.on(w_internal.drain(REACTIVE, PREV_LAYOUT));
+
+            // HOVER | GRAB
+            w_internal.replace(0b01100000, backup);
+
+            if !w_internal.check(FOCUSABLE) {
+                widget.focus_out(w_internal);
+                internal.on(w_internal.drain(REACTIVE, DRAIN_FOCUS));
+
+                self.focus_id = None;
+            }
+
+            if internal.check(PREV_LAYOUT) {
+                internal.off_on(PREV_LAYOUT, PARTIAL_TURN);
+            }
+        }
+    }
 }
 
 impl<T: Sized + Copy + Clone, CTX, DE> Widget<T, CTX> for Container<T, CTX, DE>
 where
     T: Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
-    CTX: Sized,
+    CTX: Sized + Clip<T>,
     DE: Decorator<T, CTX>,
 {
     /// Draw widgets from the list that have DRAW flag turned on
     ///
-    /// This function is lazy, if none widget is found, the DRAW flag
+    /// The boundaries of the container are pushed as clip rectangle, widgets
+    /// outside the clip are not drawn.
+    ///
+    /// Widgets that return true keep the DRAW flag for be drawn on the next frame.
+    /// This function is lazy, if none widget requests another frame, the DRAW flag
     /// of the container turns off
     fn draw(&mut self, internal: &WidgetInternal<T>, ctx: &mut CTX) -> bool {
+        ctx.push_clip(internal.boundaries_abs());
         self.decorator.before(internal, ctx);
 
+        let clip = ctx.clip();
+        let drawn = &mut self.drawn;
+        drawn.clear();
+
         let count = self
             .widgets_i
             .iter_mut()
             .zip(self.widgets.iter_mut())
+            .enumerate()
             // DRAW | VISIBLE
-            .filter(|(w_internal, _)| w_internal.check(0b00001010))
-            .fold(0, |_, (w_internal, widget)| {
-                let draw = widget.draw(w_internal, ctx);
-                if !draw {
-                    w_internal.off(DRAW);
+            .filter(|(_, (w_internal, _))| w_internal.check(0b00001010))
+            .fold(0, |count, (n, (w_internal, widget))| {
+                if let Some(clip) = clip {
+                    if !overlaps(clip, w_internal.boundaries_abs()) {
+                        w_internal.off(DRAW);
+                        return count;
+                    }
                 }
 
-                draw as usize
+                drawn.push(n);
+                if widget.draw(w_internal, ctx) {
+                    count + widget.pending().max(1)
+                } else {
+                    w_internal.off(DRAW);
+                    count
+                }
             });
 
         self.decorator.after(internal, ctx);
+        ctx.pop_clip();
 
+        self.pending = count;
         count > 0
     }
 
@@ -201,21 +331,44 @@ where
         let do_layout = complete || internal.check(PARTIAL);
 
         if do_layout {
+            self.bounds.clear();
+            self.bounds.extend(
+                self.widgets_i
+                    .iter()
+                    .map(|w_internal| w_internal.boundaries_abs()),
+            );
+
             self.layout.layout(&mut self.widgets_i, internal);
 
             self.decorator.update(internal);
+            self.decorated = true;
         }
 
+        let bounds = &self.bounds;
+        let moved = &mut self.moved;
+
         self.widgets_i
             .iter_mut()
             .zip(self.widgets.iter_mut())
-            .filter(|(w_internal, _)| do_layout || w_internal.check(LAYOUT))
-            .for_each(|(w_internal, widget)| {
+            .enumerate()
+            .filter(|(_, (w_internal, _))| do_layout || w_internal.check(LAYOUT))
+            .for_each(|(n, (w_internal, widget))| {
+                let old = if do_layout {
+                    bounds[n]
+                } else {
+                    w_internal.boundaries_abs()
+                };
+
                 w_internal.set_pivot(internal.absolute_pos());
 
                 let backup = w_internal.flags;
                 widget.layout(w_internal, complete);
 
+                // The old area of a moved widget needs to be painted again
+                if backup & VISIBLE == VISIBLE && old != w_internal.boundaries_abs() {
+                    moved.add(old);
+                }
+
                 // DRAW | UPDATE & LAYOUT | PREV_LAYOUT
                 w_internal.turn(DRAW, w_internal.check(VISIBLE));
                 internal.on(w_internal.drain(0b00000110, 0b11_00000000));
@@ -228,6 +381,17 @@ where
         internal.off(PARTIAL_TURN);
     }
 
+    /// Measure the widgets of the list and update their minimum dimensions
+    fn measure(&mut self, _: &mut WidgetInternal<T>, measure: &SharedMeasure<T>) {
+        self.widgets_i
+            .iter_mut()
+            .zip(self.widgets.iter_mut())
+            .for_each(|(w_internal, widget)| {
+                widget.measure(w_internal, measure);
+                w_internal.set_min_dimensions(widget.min_dimensions());
+            });
+    }
+
     /// Search widgets that are members of a Group id and call the function of these widgets
     ///
     /// A Nested Container should be member of the same Group id, otherwise, the function couldn't
@@ -277,13 +441,24 @@ where
     }
 
     /// Search the widget that the mouse is pointing and call the function of the widget
+    ///
+    /// Every pointer is tracked separately, so a widget can be grabbed by a pointer
+    /// while other pointers are hovering or grabbing other widgets. The GRAB flag
+    /// that a widget receives is the grab status of the current pointer.
+    ///
+    /// The cursor requested by the widget of the primary pointer becomes the cursor of the Container
     fn handle_mouse(&mut self, internal: &mut WidgetInternal<T>, mouse: &MouseState<T>) {
-        if self.mouse_id.is_some() || !internal.check(GRAB) {
-            let widget_n = self
-                .mouse_id
-                .filter(|&n| {
-                    internal.check(GRAB) || self.widgets_i[n].p_intersect(mouse.absolute_pos())
-                })
+        let pointer = mouse.pointer();
+        let slot_n = self.pointer_slot(pointer);
+        let PointerSlot {
+            widget: mouse_id,
+            mut grab,
+            ..
+        } = self.pointers[slot_n];
+
+        if mouse_id.is_some() || !grab {
+            let widget_n = mouse_id
+                .filter(|&n| grab || self.widgets_i[n].p_intersect(mouse.absolute_pos()))
                 .or_else(|| {
                     self.widgets_i
                         .iter()
@@ -297,20 +472,24 @@ where
                         })
                 });
 
-            if widget_n != self.mouse_id {
-                if let Some(id) = std::mem::replace(&mut self.mouse_id, widget_n) {
-                    let w_internal = &mut self.widgets_i[id];
+            if widget_n != mouse_id {
+                self.pointers[slot_n].widget = widget_n;
 
-                    // HOVER | GRAB | PREV_LAYOUT
-                    self.widgets[id].hover_out(w_internal);
-                    internal.on(w_internal.drain(REACTIVE, 0b10_01100000));
+                if let Some(id) = mouse_id {
+                    self.pointer_leave(internal, id, pointer);
+                }
+
+                if let Some(n) = widget_n {
+                    self.pointer_enter(internal, n, pointer);
                 }
             }
 
             if let Some(n) = widget_n {
+                let others = self.pointer_grab(n, pointer);
                 let w_internal = unsafe { self.widgets_i.get_unchecked_mut(n) };
 
-                if w_internal.check(GRAB) {
+                w_internal.turn(GRAB, grab);
+                if grab {
                     w_internal.turn(HOVER, w_internal.p_intersect(mouse.absolute_pos()));
                 } else {
                     w_internal.on(HOVER);
@@ -319,7 +498,12 @@ where
                 self.widgets[n].handle_mouse(w_internal, mouse);
                 internal.on(w_internal.drain(REACTIVE, PREV_LAYOUT));
 
-                internal.turn(GRAB, w_internal.check(GRAB));
+                grab = w_internal.check(GRAB);
+                w_internal.turn(GRAB, grab || others);
+
+                if pointer == PRIMARY_POINTER {
+                    internal.set_cursor(w_internal.cursor());
+                }
 
                 // ENABLED | VISIBLE
                 let focus_check = w_internal.flags & FOCUSABLE ^ 0b00011000;
@@ -348,14 +532,23 @@ where
                     internal.on(FOCUS);
                 }
             } else {
-                if mouse.m_count > 0 {
-                    internal.on(GRAB);
+                grab = mouse.m_count > 0;
+
+                if pointer == PRIMARY_POINTER {
+                    internal.set_cursor(Cursor::Default);
                 }
             }
+        } else if mouse.m_count == 0 {
+            grab = false;
+        }
+
+        internal.turn(GRAB, grab);
+
+        let slot = &mut self.pointers[slot_n];
+        if slot.widget.is_none() && !grab {
+            self.pointers.swap_remove(slot_n);
         } else {
-            if mouse.m_count == 0 {
-                internal.off(GRAB);
-            }
+            slot.grab = grab;
         }
 
         if internal.check(PREV_LAYOUT) {
@@ -365,28 +558,16 @@ where
 
     /// Call the function of the focused widget
     fn handle_keys(&mut self, internal: &mut WidgetInternal<T>, key: KeyState) {
-        if let Some(id) = self.focus_id {
-            let w_internal = &mut self.widgets_i[id];
-            let widget = &mut self.widgets[id];
-            let backup = w_internal.flags;
-
-            widget.handle_keys(w_internal, key);
-            internal.on(w_internal.drain(REACTIVE, PREV_LAYOUT));
-
-            // HOVER | GRAB
-            w_internal.replace(0b01100000, backup);
-
-            if !w_internal.check(FOCUSABLE) {
-                widget.focus_out(w_internal);
-                internal.on(w_internal.drain(REACTIVE, DRAIN_FOCUS));
-
-                self.focus_id = None;
-            }
+        self.focused(internal, |widget, w_internal| {
+            widget.handle_keys(w_internal, key)
+        });
+    }
 
-            if internal.check(PREV_LAYOUT) {
-                internal.off_on(PREV_LAYOUT, PARTIAL_TURN);
-            }
-        }
+    /// Call the function of the focused widget
+    fn handle_text(&mut self, internal: &mut WidgetInternal<T>, text: TextInput) {
+        self.focused(internal, |widget, w_internal| {
+            widget.handle_text(w_internal, text)
+        });
     }
 
     /// Step the focus id to the next widget that returns true on the function
@@ -435,14 +616,29 @@ where
         step_check
     }
 
-    /// Clear the hover index and call the function of the widget
+    /// Nothing to do, the widgets of the list are hovered when handle_mouse is called
+    fn hover_in(&mut self, _: &mut WidgetInternal<T>) {}
+
+    /// Clear the hover indexes of every pointer and call the function of the widgets
     fn hover_out(&mut self, internal: &mut WidgetInternal<T>) {
-        if let Some(id) = self.mouse_id.take() {
-            let w_internal = &mut self.widgets_i[id];
+        if !self.pointers.is_empty() {
+            let mut pointers = std::mem::take(&mut self.pointers);
+            pointers.sort_unstable_by_key(|slot| slot.widget);
+            pointers.dedup_by_key(|slot| slot.widget);
 
-            // HOVER | GRAB | PREV_LAYOUT
-            self.widgets[id].hover_out(w_internal);
-            internal.on(w_internal.drain(REACTIVE, 0b10_01100000));
+            for id in pointers.iter().filter_map(|slot| slot.widget) {
+                let w_internal = &mut self.widgets_i[id];
+
+                // HOVER | GRAB | PREV_LAYOUT
+                self.widgets[id].hover_out(w_internal);
+                internal.on(w_internal.drain(REACTIVE, 0b10_01100000));
+            }
+
+            // Reuse the allocation
+            pointers.clear();
+            self.pointers = pointers;
+
+            internal.set_cursor(Cursor::Default);
 
             if internal.check(PREV_LAYOUT) {
                 internal.off_on(PREV_LAYOUT, PARTIAL_TURN);
@@ -450,6 +646,23 @@ where
         }
     }
 
+    /// Clear the hover index of a pointer, call hover_out if the widget has no more pointers
+    fn pointer_out(&mut self, internal: &mut WidgetInternal<T>, pointer: PointerID) {
+        if let Some(slot_n) = self.pointers.iter().position(|slot| slot.id == pointer) {
+            if pointer == PRIMARY_POINTER {
+                internal.set_cursor(Cursor::Default);
+            }
+
+            if let Some(id) = self.pointers.swap_remove(slot_n).widget {
+                self.pointer_leave(internal, id, pointer);
+
+                if internal.check(PREV_LAYOUT) {
+                    internal.off_on(PREV_LAYOUT, PARTIAL_TURN);
+                }
+            }
+        }
+    }
+
     /// Clear the focus index and call the function of the widget
     fn focus_out(&mut self, internal: &mut WidgetInternal<T>) {
         if let Some(id) = self.focus_id.take() {
@@ -464,8 +677,390 @@ where
         }
     }
 
+    /// Expose the visible widgets that overlap the area
+    fn expose(&mut self, internal: &mut WidgetInternal<T>, area: Boundaries<T>) {
+        self.widgets_i
+            .iter_mut()
+            .zip(self.widgets.iter_mut())
+            .filter(|(w_internal, _)| {
+                w_internal.check(VISIBLE) && overlaps(area, w_internal.boundaries_abs())
+            })
+            .for_each(|(w_internal, widget)| {
+                widget.expose(w_internal, area);
+                internal.on(w_internal.val(DRAW));
+            });
+    }
+
+    /// Count of widgets that requested another frame, nested Containers are counted
+    /// by their widgets
+    fn pending(&self) -> usize {
+        self.pending
+    }
+
+    /// Add the old boundaries of moved widgets and the area of the drawn widgets, or
+    /// the whole area if the Decorator was updated by a layout
+    fn damage(&mut self, internal: &WidgetInternal<T>, region: &mut DamageRegion<T>) {
+        if self.decorated {
+            region.add(internal.boundaries_abs());
+            self.decorated = false;
+        }
+
+        region.extend(&self.moved);
+        self.moved.clear();
+
+        for &n in self.drawn.iter() {
+            self.widgets[n].damage(&self.widgets_i[n], region);
+        }
+
+        self.drawn.clear();
+    }
+
     /// Get minimum dimensions provided by the Layout
     fn min_dimensions(&self) -> Dimensions<T> {
         self.layout.min_dimensions(&self.widgets_i)
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clip::ClipStack;
+    use crate::empty::{EmptyDecorator, EmptyLayout};
+    use crate::state::MouseType;
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    type Log = Rc<RefCell<Vec<String>>>;
+
+    /// Widget that requests a number of frames
+    struct Frames(u32);
+
+    impl Widget<f32, ()> for Frames {
+        fn draw(&mut self, _: &WidgetInternal<f32>, _: &mut ()) -> bool {
+            self.0 = self.0.saturating_sub(1);
+            self.0 > 0
+        }
+    }
+
+    /// Widget that logs its calls and grabs the pointers that press it
+    struct Probe {
+        name: &'static str,
+        log: Log,
+    }
+
+    impl Probe {
+        fn push(&self, call: &str) {
+            self.log
+                .borrow_mut()
+                .push(format!("{} {}", self.name, call));
+        }
+    }
+
+    impl<CTX> Widget<f32, CTX> for Probe {
+        fn draw(&mut self, _: &WidgetInternal<f32>, _: &mut CTX) -> bool {
+            self.push("draw");
+            false
+        }
+
+        fn handle_mouse(&mut self, internal: &mut WidgetInternal<f32>, mouse: &MouseState<f32>) {
+            match mouse.m_type {
+                MouseType::Pressed(_) => internal.on(GRAB),
+                MouseType::Released(_) if mouse.m_count == 0 => internal.off(GRAB),
+                _ => {}
+            }
+
+            internal.set_cursor(Cursor::Custom(self.name.as_bytes()[0] as u32));
+            self.push(&format!("mouse {}", mouse.pointer()));
+        }
+
+        fn hover_in(&mut self, _: &mut WidgetInternal<f32>) {
+            self.push("hover_in");
+        }
+
+        fn hover_out(&mut self, _: &mut WidgetInternal<f32>) {
+            self.push("hover_out");
+        }
+
+        fn pointer_out(&mut self, _: &mut WidgetInternal<f32>, pointer: PointerID) {
+            self.push(&format!("pointer_out {}", pointer));
+        }
+    }
+
+    /// Container of two probes side by side of 50x50
+    fn probes<CTX>(log: &Log, names: [&'static str; 2]) -> Container<f32, CTX, EmptyDecorator> {
+        let mut container = Container::new(EmptyDecorator, Box::new(EmptyLayout));
+
+        for (n, &name) in names.iter().enumerate() {
+            let log = log.clone();
+            let bounds = (n as f32 * 50.0, 0.0, 50.0, 50.0);
+
+            container.add_widget_b(Box::new(Probe { name, log }), WIDGET, Group::Any, bounds);
+        }
+
+        container
+    }
+
+    /// A widget driven by pointers, the IDs are indexes of their mouse states
+    struct Tree<W> {
+        widget: W,
+        internal: WidgetInternal<f32>,
+        mice: Vec<MouseState<f32>>,
+        log: Log,
+    }
+
+    impl<W: Widget<f32, ()>> Tree<W> {
+        fn new(mut widget: W, log: &Log, dim: (f32, f32)) -> Self {
+            let mut internal = WidgetInternal::new_with((0.0, 0.0), dim, WIDGET, Group::Any);
+            widget.layout(&mut internal, true);
+
+            Tree {
+                widget,
+                internal,
+                mice: (0..3).map(MouseState::new_pointer).collect(),
+                log: log.clone(),
+            }
+        }
+
+        /// Send a mouse event and return the calls that it made
+        fn send(&mut self, pointer: PointerID, m_type: MouseType, pos: (f32, f32)) -> Vec<String> {
+            let mouse = &mut self.mice[pointer];
+            mouse.set_type(m_type);
+            mouse.set_position(pos);
+
+            self.widget.handle_mouse(&mut self.internal, mouse);
+            self.log.borrow_mut().drain(..).collect()
+        }
+
+        fn pointer_out(&mut self, pointer: PointerID) -> Vec<String> {
+            self.widget.pointer_out(&mut self.internal, pointer);
+            self.log.borrow_mut().drain(..).collect()
+        }
+
+        fn hover_out(&mut self) -> Vec<String> {
+            self.widget.hover_out(&mut self.internal);
+            self.log.borrow_mut().drain(..).collect()
+        }
+    }
+
+    #[test]
+    fn two_grabs() {
+        use MouseType::*;
+        let log = Log::default();
+        let mut t = Tree::new(probes(&log, ["a", "b"]), &log, (100.0, 50.0));
+
+        assert_eq!(
+            t.send(1, Pressed(1), (10.0, 10.0)),
+            ["a hover_in", "a mouse 1"]
+        );
+        assert_eq!(
+            t.send(2, Pressed(1), (60.0, 10.0)),
+            ["b hover_in", "b mouse 2"]
+        );
+
+        // Every pointer keeps its grabbed widget while it crosses the other
+        assert_eq!(t.send(1, CursorMoved, (70.0, 10.0)), ["a mouse 1"]);
+        assert_eq!(t.send(2, CursorMoved, (20.0, 10.0)), ["b mouse 2"]);
+
+        // GRAB of the container is the grab of the last pointer, so a parent can
+        // track every pointer
+        assert_eq!(t.send(1, Released(1), (70.0, 10.0)), ["a mouse 1"]);
+        assert!(!t.internal.check(GRAB));
+        assert_eq!(t.send(2, CursorMoved, (25.0, 10.0)), ["b mouse 2"]);
+        assert!(t.internal.check(GRAB));
+
+        // The released pointer moves to the widget under it
+        let moved = t.send(1, CursorMoved, (75.0, 10.0));
+        assert_eq!(moved, ["a hover_out", "b mouse 1"]);
+    }
+
+    #[test]
+    fn pointer_leaves() {
+        use MouseType::*;
+        let log = Log::default();
+        let mut t = Tree::new(probes(&log, ["a", "b"]), &log, (100.0, 50.0));
+
+        assert_eq!(
+            t.send(1, CursorMoved, (10.0, 10.0)),
+            ["a hover_in", "a mouse 1"]
+        );
+        assert_eq!(t.send(2, CursorMoved, (20.0, 10.0)), ["a mouse 2"]);
+
+        // The first pointer moves to b, a is still hovered by the second
+        let moved = t.send(1, CursorMoved, (60.0, 10.0));
+        assert_eq!(moved, ["a pointer_out 1", "b hover_in", "b mouse 1"]);
+
+        // A lifted finger is like a pointer that left
+        assert_eq!(t.pointer_out(2), ["a hover_out"]);
+        assert_eq!(t.hover_out(), ["b hover_out"]);
+    }
+
+    #[test]
+    fn nested_hover() {
+        use MouseType::*;
+        let log = Log::default();
+
+        // A container with a and b at the left of c
+        let mut outer = Container::new(EmptyDecorator, Box::new(EmptyLayout));
+        let inner = Box::new(probes(&log, ["a", "b"]));
+        let c = Box::new(Probe {
+            name: "c",
+            log: log.clone(),
+        });
+
+        outer.add_widget_b(inner, WIDGET, Group::Any, (0.0, 0.0, 100.0, 50.0));
+        outer.add_widget_b(c, WIDGET, Group::Any, (100.0, 0.0, 50.0, 50.0));
+        let mut t = Tree::new(outer, &log, (150.0, 50.0));
+
+        assert_eq!(
+            t.send(0, CursorMoved, (10.0, 10.0)),
+            ["a hover_in", "a mouse 0"]
+        );
+        assert_eq!(
+            t.send(1, CursorMoved, (60.0, 10.0)),
+            ["b hover_in", "b mouse 1"]
+        );
+
+        // Leaving the inner container is leaving its hovered widget
+        let moved = t.send(0, CursorMoved, (110.0, 10.0));
+        assert_eq!(moved, ["a hover_out", "c hover_in", "c mouse 0"]);
+        let moved = t.send(1, CursorMoved, (120.0, 10.0));
+        assert_eq!(moved, ["b hover_out", "c mouse 1"]);
+
+        let moved = t.send(0, CursorMoved, (60.0, 10.0));
+        assert_eq!(moved, ["c pointer_out 0", "b hover_in", "b mouse 0"]);
+        assert_eq!(t.hover_out(), ["b hover_out", "c hover_out"]);
+    }
+
+    #[test]
+    fn primary_cursor() {
+        let log = Log::default();
+        let mut root = crate::Root::new(Box::new(probes(&log, ["a", "b"])), (100.0, 60.0));
+        let mut mice: Vec<MouseState<f32>> = (0..2).map(MouseState::new_pointer).collect();
+        let mut send = |root: &mut crate::Root<f32, ()>, pointer: usize, m_type, pos| {
+            mice[pointer].set_type(m_type);
+            mice[pointer].set_position(pos);
+            root.handle_mouse(&mice[pointer]);
+
+            root.cursor()
+        };
+
+        let (a, b) = (Cursor::Custom('a' as u32), Cursor::Custom('b' as u32));
+        assert_eq!(send(&mut root, 0, MouseType::CursorMoved, (10.0, 10.0)), a);
+
+        // Other pointers don't change the cursor
+        assert_eq!(send(&mut root, 1, MouseType::CursorMoved, (60.0, 10.0)), a);
+        assert_eq!(send(&mut root, 1, MouseType::Pressed(1), (60.0, 10.0)), a);
+
+        // The grabbed widget keeps its cursor until the release
+        assert_eq!(send(&mut root, 0, MouseType::Pressed(1), (10.0, 10.0)), a);
+        assert_eq!(send(&mut root, 0, MouseType::CursorMoved, (70.0, 10.0)), a);
+        assert_eq!(send(&mut root, 0, MouseType::Released(1), (70.0, 10.0)), a);
+        assert_eq!(send(&mut root, 0, MouseType::CursorMoved, (71.0, 10.0)), b);
+
+        assert_eq!(
+            send(&mut root, 0, MouseType::CursorMoved, (50.0, 55.0)),
+            Cursor::Default
+        );
+        send(&mut root, 0, MouseType::CursorMoved, (10.0, 10.0));
+        root.hover_out();
+        assert_eq!(root.cursor(), Cursor::Default);
+    }
+
+    #[test]
+    fn clip_skip() {
+        let log = Log::default();
+        let mut container = probes(&log, ["a", "b"]);
+        let c = Box::new(Probe {
+            name: "c",
+            log: log.clone(),
+        });
+
+        // b is partially visible and c is outside of the container
+        container.add_widget_b(c, WIDGET, Group::Any, (100.0, 0.0, 50.0, 50.0));
+        let mut internal = WidgetInternal::new_with((0.0, 0.0), (60.0, 50.0), WIDGET, Group::Any);
+        let mut ctx = ClipStack::new();
+
+        container.layout(&mut internal, true);
+        container.draw(&internal, &mut ctx);
+        assert_eq!(
+            log.borrow_mut().drain(..).collect::<Vec<_>>(),
+            ["a draw", "b draw"]
+        );
+        assert!(!container.widgets_i[2].check(DRAW));
+        assert!(ctx.is_empty());
+
+        // The clip of a parent is intersected
+        ctx.push_clip((0.0, 0.0, 40.0, 50.0));
+        container.layout(&mut internal, true);
+        container.draw(&internal, &mut ctx);
+        assert_eq!(log.borrow_mut().drain(..).collect::<Vec<_>>(), ["a draw"]);
+        assert_eq!(ctx.len(), 1);
+    }
+
+    #[test]
+    fn damage() {
+        let log = Log::default();
+        let mut container = probes::<()>(&log, ["a", "b"]);
+        let mut internal = WidgetInternal::new_with((0.0, 0.0), (100.0, 60.0), WIDGET, Group::Any);
+        let mut region = DamageRegion::new();
+
+        // The Decorator paints the whole area after a layout
+        container.layout(&mut internal, true);
+        container.draw(&internal, &mut ());
+        container.damage(&internal, &mut region);
+        assert_eq!(region.rects(), [(0.0, 0.0, 100.0, 60.0)]);
+
+        // Only the redrawn widget
+        region.clear();
+        container.widgets_i[1].on(DRAW);
+        container.draw(&internal, &mut ());
+        container.damage(&internal, &mut region);
+        assert_eq!(region.rects(), [(50.0, 0.0, 50.0, 50.0)]);
+
+        region.clear();
+        container.damage(&internal, &mut region);
+        assert!(region.is_empty());
+    }
+
+    #[test]
+    fn pending() {
+        let mut inner = Container::new(EmptyDecorator, Box::new(EmptyLayout));
+        inner.add_widget_b(
+            Box::new(Frames(2)),
+            WIDGET,
+            Group::Any,
+            (0.0, 0.0, 10.0, 10.0),
+        );
+        inner.add_widget_b(
+            Box::new(Frames(3)),
+            WIDGET,
+            Group::Any,
+            (10.0, 0.0, 10.0, 10.0),
+        );
+
+        let mut outer = Container::new(EmptyDecorator, Box::new(EmptyLayout));
+        outer.add_widget_b(Box::new(inner), WIDGET, Group::Any, (0.0, 0.0, 20.0, 10.0));
+        outer.add_widget_b(
+            Box::new(Frames(3)),
+            WIDGET,
+            Group::Any,
+            (20.0, 0.0, 10.0, 10.0),
+        );
+        let mut root = crate::Root::new(Box::new(outer), (30.0, 10.0));
+
+        // Nested widgets are counted by themselves
+        root.draw(&mut ());
+        assert_eq!(root.pending(), 3);
+        assert!(root.need_draw());
+
+        root.draw(&mut ());
+        assert_eq!(root.pending(), 2);
+
+        // Only the widgets that were still animating are drawn again
+        let region = root.draw(&mut ());
+        assert_eq!(region.bounds(), Some((10.0, 0.0, 20.0, 10.0)));
+        assert_eq!(root.pending(), 0);
+        assert!(!root.need_draw());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,144 @@
+//! Damaged areas of a frame for partial redraws
+//!
+//! After drawing, Root asks the widget tree for the areas that were painted with
+//! Widget::damage, Containers answer with the boundaries of their drawn widgets and
+//! the old boundaries of the widgets moved by the layout. The areas are merged in a
+//! DamageRegion, so a backend can upload or blit only those areas
+
+use crate::clip::overlaps;
+use crate::widget::Boundaries;
+
+use std::ops::{Add, Sub};
+
+/// Maximum rectangles of a region before merging all of them
+const MAX_RECTS: usize = 16;
+
+/// A list of non-overlapping rectangles that were painted in a frame
+pub struct DamageRegion<T> {
+    rects: Vec<Boundaries<T>>,
+}
+
+#[inline]
+fn union<T>(a: Boundaries<T>, b: Boundaries<T>) -> Boundaries<T>
+where
+    T: Copy + Add<Output = T> + Sub<Output = T> + PartialOrd,
+{
+    let x1 = if a.0 < b.0 { a.0 } else { b.0 };
+    let y1 = if a.1 < b.1 { a.1 } else { b.1 };
+    let (a_x2, b_x2) = (a.0 + a.2, b.0 + b.2);
+    let (a_y2, b_y2) = (a.1 + a.3, b.1 + b.3);
+    let x2 = if a_x2 > b_x2 { a_x2 } else { b_x2 };
+    let y2 = if a_y2 > b_y2 { a_y2 } else { b_y2 };
+
+    (x1, y1, x2 - x1, y2 - y1)
+}
+
+impl<T> DamageRegion<T>
+where
+    T: Copy + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+{
+    /// Creates an empty region
+    pub fn new() -> Self {
+        DamageRegion { rects: Vec::new() }
+    }
+
+    /// Add a rectangle, overlapping rectangles are merged into their bounding box
+    pub fn add(&mut self, bounds: Boundaries<T>) {
+        let zero = T::default();
+        if !(bounds.2 > zero && bounds.3 > zero) {
+            return;
+        }
+
+        let mut rect = bounds;
+        // Merging can make the rectangle overlap others, so repeat until none is found
+        while let Some(n) = self.rects.iter().position(|&r| overlaps(r, rect)) {
+            rect = union(self.rects.swap_remove(n), rect);
+        }
+
+        if self.rects.len() == MAX_RECTS {
+            rect = self.rects.drain(..).fold(rect, union);
+        }
+
+        self.rects.push(rect);
+    }
+
+    /// Add every rectangle of other region
+    pub fn extend(&mut self, other: &DamageRegion<T>) {
+        for &rect in other.rects.iter() {
+            self.add(rect);
+        }
+    }
+
+    /// Get merged rectangles
+    #[inline]
+    pub fn rects(&self) -> &[Boundaries<T>] {
+        &self.rects
+    }
+
+    /// Get the bounding box of the region
+    pub fn bounds(&self) -> Option<Boundaries<T>> {
+        let mut rects = self.rects.iter().copied();
+        let first = rects.next()?;
+
+        Some(rects.fold(first, union))
+    }
+
+    /// Check if there is no damaged area
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.rects.is_empty()
+    }
+
+    /// Remove every rectangle
+    pub fn clear(&mut self) {
+        self.rects.clear();
+    }
+}
+
+impl<T> Default for DamageRegion<T>
+where
+    T: Copy + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge() {
+        let mut region = DamageRegion::new();
+        region.add((0.0, 0.0, 10.0, 10.0));
+        region.add((20.0, 0.0, 10.0, 10.0));
+        // Empty areas are ignored and touching edges aren't merged
+        region.add((5.0, 5.0, 0.0, 10.0));
+        region.add((10.0, 0.0, 10.0, 10.0));
+        assert_eq!(region.rects().len(), 3);
+
+        // Bridges both rectangles, the union overlaps the third one
+        region.add((5.0, 5.0, 20.0, 2.0));
+        assert_eq!(region.rects(), [(0.0, 0.0, 30.0, 10.0)]);
+        assert_eq!(region.bounds(), Some((0.0, 0.0, 30.0, 10.0)));
+
+        region.clear();
+        assert!(region.is_empty());
+        assert_eq!(region.bounds(), None);
+    }
+
+    #[test]
+    fn max_rects() {
+        let mut region = DamageRegion::new();
+        for n in 0..MAX_RECTS {
+            region.add((n as f32 * 20.0, 0.0, 10.0, 10.0));
+        }
+        assert_eq!(region.rects().len(), MAX_RECTS);
+
+        let mut other = DamageRegion::new();
+        other.add((0.0, 50.0, 10.0, 10.0));
+        region.extend(&other);
+        assert_eq!(region.rects(), [(0.0, 0.0, 310.0, 60.0)]);
+    }
+}
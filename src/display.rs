@@ -0,0 +1,505 @@
+//! Retained display list that records drawing commands
+//!
+//! DisplayList is a CTX that implements Painter by storing every command, so the
+//! output of a draw can be compared in tests or replayed into any Painter later.
+//! A DisplayList is serialized as text, one command per line:
+//!
+//! ```text
+//! fill_rect 0 0 100 20 #202020ff
+//! push_clip 0 0 100 20
+//! text 4 16 0 12 #ffffffff "Hello"
+//! fill_path #ff0000ff M 0 0 L 10 0 L 10 10 Z
+//! pop_clip
+//! ```
+
+use crate::clip::{Clip, ClipStack};
+use crate::paint::{Color, FontID, ImageID, Painter, PathCommand};
+use crate::widget::{Boundaries, Position};
+
+use std::fmt;
+use std::ops::{Add, Sub};
+use std::str::FromStr;
+
+/// A recorded drawing command
+#[derive(Clone, PartialEq, Debug)]
+pub enum Command<T> {
+    FillRect(Boundaries<T>, Color),
+    StrokeRect(Boundaries<T>, T, Color),
+    FillRoundRect(Boundaries<T>, T, Color),
+    Line(Position<T>, Position<T>, T, Color),
+    FillPath(Vec<PathCommand<T>>, Color),
+    StrokePath(Vec<PathCommand<T>>, T, Color),
+    Text(Position<T>, FontID, T, String, Color),
+    Image(Boundaries<T>, ImageID),
+    PushClip(Boundaries<T>),
+    PopClip,
+}
+
+/// Error found when a display list is parsed
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    /// Line number, starting from 1
+    pub line: usize,
+    pub message: &'static str,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Painter that records the drawing commands
+pub struct DisplayList<T> {
+    commands: Vec<Command<T>>,
+    clip: ClipStack<T>,
+}
+
+impl<T> DisplayList<T>
+where
+    T: Copy + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+{
+    /// Creates an empty display list
+    pub fn new() -> Self {
+        DisplayList {
+            commands: Vec::new(),
+            clip: ClipStack::new(),
+        }
+    }
+
+    /// Get recorded commands
+    #[inline]
+    pub fn commands(&self) -> &[Command<T>] {
+        &self.commands
+    }
+
+    /// Remove recorded commands
+    pub fn clear(&mut self) {
+        self.commands.clear();
+        self.clip.clear();
+    }
+
+    /// Send the recorded commands to other Painter
+    pub fn replay<P: Painter<T>>(&self, painter: &mut P) {
+        for command in self.commands.iter() {
+            match command {
+                Command::FillRect(bounds, color) => painter.fill_rect(*bounds, *color),
+                Command::StrokeRect(bounds, width, color) => {
+                    painter.stroke_rect(*bounds, *width, *color)
+                }
+                Command::FillRoundRect(bounds, radius, color) => {
+                    painter.fill_round_rect(*bounds, *radius, *color)
+                }
+                Command::Line(from, to, width, color) => painter.line(*from, *to, *width, *color),
+                Command::FillPath(path, color) => painter.fill_path(path, *color),
+                Command::StrokePath(path, width, color) => {
+                    painter.stroke_path(path, *width, *color)
+                }
+                Command::Text(pos, font, size, text, color) => {
+                    painter.text(*pos, *font, *size, text, *color)
+                }
+                Command::Image(bounds, image) => painter.image(*bounds, *image),
+                Command::PushClip(bounds) => painter.push_clip(*bounds),
+                Command::PopClip => painter.pop_clip(),
+            }
+        }
+    }
+}
+
+impl<T> Default for DisplayList<T>
+where
+    T: Copy + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clip<T> for DisplayList<T>
+where
+    T: Copy + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+{
+    fn push_clip(&mut self, bounds: Boundaries<T>) {
+        self.clip.push_clip(bounds);
+        self.commands.push(Command::PushClip(bounds));
+    }
+
+    fn pop_clip(&mut self) {
+        self.clip.pop_clip();
+        self.commands.push(Command::PopClip);
+    }
+
+    #[inline]
+    fn clip(&self) -> Option<Boundaries<T>> {
+        self.clip.clip()
+    }
+}
+
+impl<T> Painter<T> for DisplayList<T>
+where
+    T: Copy + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+{
+    fn fill_rect(&mut self, bounds: Boundaries<T>, color: Color) {
+        self.commands.push(Command::FillRect(bounds, color));
+    }
+
+    fn stroke_rect(&mut self, bounds: Boundaries<T>, width: T, color: Color) {
+        self.commands
+            .push(Command::StrokeRect(bounds, width, color));
+    }
+
+    fn fill_round_rect(&mut self, bounds: Boundaries<T>, radius: T, color: Color) {
+        self.commands
+            .push(Command::FillRoundRect(bounds, radius, color));
+    }
+
+    fn line(&mut self, from: Position<T>, to: Position<T>, width: T, color: Color) {
+        self.commands.push(Command::Line(from, to, width, color));
+    }
+
+    fn fill_path(&mut self, path: &[PathCommand<T>], color: Color) {
+        self.commands.push(Command::FillPath(path.to_vec(), color));
+    }
+
+    fn stroke_path(&mut self, path: &[PathCommand<T>], width: T, color: Color) {
+        self.commands
+            .push(Command::StrokePath(path.to_vec(), width, color));
+    }
+
+    fn text(&mut self, pos: Position<T>, font: FontID, size: T, text: &str, color: Color) {
+        self.commands
+            .push(Command::Text(pos, font, size, text.to_string(), color));
+    }
+
+    fn image(&mut self, bounds: Boundaries<T>, image: ImageID) {
+        self.commands.push(Command::Image(bounds, image));
+    }
+}
+
+// -----------------
+// SERIALIZATION
+// -----------------
+
+fn write_path<T: fmt::Display>(f: &mut fmt::Formatter, path: &[PathCommand<T>]) -> fmt::Result {
+    for segment in path {
+        match segment {
+            PathCommand::MoveTo(p) => write!(f, " M {} {}", p.0, p.1)?,
+            PathCommand::LineTo(p) => write!(f, " L {} {}", p.0, p.1)?,
+            PathCommand::QuadTo(c, p) => write!(f, " Q {} {} {} {}", c.0, c.1, p.0, p.1)?,
+            PathCommand::CubicTo(c1, c2, p) => {
+                write!(f, " C {} {} {} {} {} {}", c1.0, c1.1, c2.0, c2.1, p.0, p.1)?
+            }
+            PathCommand::Close => f.write_str(" Z")?,
+        }
+    }
+
+    Ok(())
+}
+
+fn write_text(f: &mut fmt::Formatter, text: &str) -> fmt::Result {
+    f.write_str("\"")?;
+    for c in text.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\t' => f.write_str("\\t")?,
+            _ => write!(f, "{}", c)?,
+        }
+    }
+
+    f.write_str("\"")
+}
+
+impl<T: fmt::Display> fmt::Display for Command<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Command::FillRect(b, color) => {
+                write!(f, "fill_rect {} {} {} {} {}", b.0, b.1, b.2, b.3, color)
+            }
+            Command::StrokeRect(b, width, color) => write!(
+                f,
+                "stroke_rect {} {} {} {} {} {}",
+                b.0, b.1, b.2, b.3, width, color
+            ),
+            Command::FillRoundRect(b, radius, color) => write!(
+                f,
+                "round_rect {} {} {} {} {} {}",
+                b.0, b.1, b.2, b.3, radius, color
+            ),
+            Command::Line(from, to, width, color) => write!(
+                f,
+                "line {} {} {} {} {} {}",
+                from.0, from.1, to.0, to.1, width, color
+            ),
+            Command::FillPath(path, color) => {
+                write!(f, "fill_path {}", color)?;
+                write_path(f, path)
+            }
+            Command::StrokePath(path, width, color) => {
+                write!(f, "stroke_path {} {}", width, color)?;
+                write_path(f, path)
+            }
+            Command::Text(pos, font, size, text, color) => {
+                write!(f, "text {} {} {} {} {} ", pos.0, pos.1, font, size, color)?;
+                write_text(f, text)
+            }
+            Command::Image(b, image) => {
+                write!(f, "image {} {} {} {} {}", b.0, b.1, b.2, b.3, image)
+            }
+            Command::PushClip(b) => write!(f, "push_clip {} {} {} {}", b.0, b.1, b.2, b.3),
+            Command::PopClip => f.write_str("pop_clip"),
+        }
+    }
+}
+
+/// One command per line
+impl<T: fmt::Display> fmt::Display for DisplayList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for command in self.commands.iter() {
+            writeln!(f, "{}", command)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Splits a line into words, a quoted text is a single word without quotes
+struct Words<'a> {
+    line: &'a str,
+}
+
+impl<'a> Words<'a> {
+    fn next_word(&mut self) -> Option<&'a str> {
+        self.line = self.line.trim_start();
+        if self.line.is_empty() || self.line.starts_with('"') {
+            return None;
+        }
+
+        let end = self
+            .line
+            .find(char::is_whitespace)
+            .unwrap_or(self.line.len());
+        let (word, rest) = self.line.split_at(end);
+        self.line = rest;
+
+        Some(word)
+    }
+
+    fn quoted(&mut self) -> Option<String> {
+        let line = self.line.trim_start();
+        let mut chars = line.char_indices();
+        let mut text = String::new();
+
+        if chars.next()?.1 != '"' {
+            return None;
+        }
+
+        while let Some((n, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.line = &line[n + 1..];
+                    return Some(text);
+                }
+                '\\' => text.push(match chars.next()?.1 {
+                    'n' => '\n',
+                    't' => '\t',
+                    c => c,
+                }),
+                c => text.push(c),
+            }
+        }
+
+        None
+    }
+}
+
+struct LineParser<'a> {
+    words: Words<'a>,
+    line: usize,
+}
+
+impl<'a> LineParser<'a> {
+    fn error<R>(&self, message: &'static str) -> Result<R, ParseError> {
+        Err(ParseError {
+            line: self.line,
+            message,
+        })
+    }
+
+    fn word(&mut self) -> Result<&'a str, ParseError> {
+        match self.words.next_word() {
+            Some(word) => Ok(word),
+            None => self.error("missing argument"),
+        }
+    }
+
+    fn value<V: FromStr>(&mut self) -> Result<V, ParseError> {
+        match self.word()?.parse() {
+            Ok(value) => Ok(value),
+            Err(_) => self.error("invalid number"),
+        }
+    }
+
+    fn pos<V: FromStr>(&mut self) -> Result<(V, V), ParseError> {
+        Ok((self.value()?, self.value()?))
+    }
+
+    fn bounds<V: FromStr>(&mut self) -> Result<(V, V, V, V), ParseError> {
+        Ok((self.value()?, self.value()?, self.value()?, self.value()?))
+    }
+
+    fn color(&mut self) -> Result<Color, ParseError> {
+        let word = self.word()?;
+
+        match word
+            .strip_prefix('#')
+            .map(|hex| (hex.len(), u32::from_str_radix(hex, 16)))
+        {
+            Some((8, Ok(value))) => Ok(Color::hex(value)),
+            _ => self.error("invalid color"),
+        }
+    }
+
+    fn path<V: FromStr>(&mut self) -> Result<Vec<PathCommand<V>>, ParseError> {
+        let mut path = Vec::new();
+
+        while let Some(word) = self.words.next_word() {
+            path.push(match word {
+                "M" => PathCommand::MoveTo(self.pos()?),
+                "L" => PathCommand::LineTo(self.pos()?),
+                "Q" => PathCommand::QuadTo(self.pos()?, self.pos()?),
+                "C" => PathCommand::CubicTo(self.pos()?, self.pos()?, self.pos()?),
+                "Z" => PathCommand::Close,
+                _ => return self.error("invalid path segment"),
+            });
+        }
+
+        Ok(path)
+    }
+
+    fn command<V: FromStr>(&mut self, name: &str) -> Result<Command<V>, ParseError> {
+        Ok(match name {
+            "fill_rect" => Command::FillRect(self.bounds()?, self.color()?),
+            "stroke_rect" => Command::StrokeRect(self.bounds()?, self.value()?, self.color()?),
+            "round_rect" => Command::FillRoundRect(self.bounds()?, self.value()?, self.color()?),
+            "line" => Command::Line(self.pos()?, self.pos()?, self.value()?, self.color()?),
+            "fill_path" => {
+                let color = self.color()?;
+                Command::FillPath(self.path()?, color)
+            }
+            "stroke_path" => {
+                let (width, color) = (self.value()?, self.color()?);
+                Command::StrokePath(self.path()?, width, color)
+            }
+            "text" => {
+                let (pos, font, size, color) =
+                    (self.pos()?, self.value()?, self.value()?, self.color()?);
+
+                match self.words.quoted() {
+                    Some(text) => Command::Text(pos, font, size, text, color),
+                    None => return self.error("invalid text"),
+                }
+            }
+            "image" => Command::Image(self.bounds()?, self.value()?),
+            "push_clip" => Command::PushClip(self.bounds()?),
+            "pop_clip" => Command::PopClip,
+            _ => return self.error("unknown command"),
+        })
+    }
+}
+
+impl<T> DisplayList<T>
+where
+    T: Copy + Add<Output = T> + Sub<Output = T> + PartialOrd + Default + FromStr,
+{
+    /// Parse a display list from text, empty lines are ignored
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut list = DisplayList::new();
+
+        for (n, line) in text.lines().enumerate() {
+            let mut parser = LineParser {
+                words: Words { line },
+                line: n + 1,
+            };
+
+            if let Some(name) = parser.words.next_word() {
+                let command = parser.command(name)?;
+
+                if parser.words.line.trim().is_empty() {
+                    list.commands.push(command);
+                } else {
+                    return parser.error("unexpected argument");
+                }
+            }
+        }
+
+        Ok(list)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> DisplayList<f32> {
+        let mut list = DisplayList::new();
+        let path = [
+            PathCommand::MoveTo((0.0, 0.0)),
+            PathCommand::QuadTo((5.0, -5.0), (10.0, 0.0)),
+            PathCommand::CubicTo((12.0, 2.0), (12.0, 8.0), (10.0, 10.0)),
+            PathCommand::LineTo((0.0, 10.0)),
+            PathCommand::Close,
+        ];
+
+        list.fill_rect((0.0, 0.0, 100.0, 20.0), Color::rgb(32, 32, 32));
+        list.push_clip((2.0, 2.0, 96.0, 16.0));
+        list.stroke_rect((2.5, 2.5, 95.0, 15.0), 1.0, Color::BLACK);
+        list.fill_round_rect((4.0, 4.0, 20.0, 12.0), 3.0, Color::WHITE);
+        list.line((0.0, 19.5), (100.0, 19.5), 1.0, Color::rgba(255, 0, 0, 128));
+        list.fill_path(&path, Color::hex(0x336699FF));
+        list.stroke_path(&path, 2.0, Color::BLACK);
+        list.text((30.0, 16.0), 1, 12.0, "Say \"hi\"\t\\ ñ", Color::WHITE);
+        list.image((80.0, 4.0, 12.0, 12.0), 2);
+        list.pop_clip();
+
+        list
+    }
+
+    #[test]
+    fn replay_round_trip() {
+        let list = record();
+        let mut other = DisplayList::new();
+        list.replay(&mut other);
+
+        assert_eq!(list.commands().len(), 10);
+        assert_eq!(list.commands(), other.commands());
+        assert_eq!(other.clip(), None);
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let list = record();
+        let parsed = DisplayList::<f32>::parse(&list.to_string()).unwrap();
+
+        assert_eq!(list.commands(), parsed.commands());
+    }
+
+    fn error(text: &str) -> ParseError {
+        match DisplayList::<f32>::parse(text) {
+            Ok(_) => panic!("{:?} was parsed", text),
+            Err(err) => err,
+        }
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(error("pop_clip\n\nfill_rect 0 0 10").line, 3);
+
+        assert_eq!(error("pop_clip 1").message, "unexpected argument");
+
+        assert_eq!(error("blit 0 0").message, "unknown command");
+    }
+}
@@ -0,0 +1,914 @@
+//! Gesture recognizers built on top of pointer events
+//!
+//! A GestureRecognizer consumes MouseStates of one or more pointers and produces
+//! high-level Gestures. It only depends on the pointer IDs, positions and timestamps
+//! of the MouseStates, so it can be driven by synthetic input streams.
+//!
+//! Widgets can use a recognizer directly or be wrapped with Gestures for receive
+//! the gestures through GestureHandler
+
+use crate::damage::DamageRegion;
+use crate::group::GroupID;
+use crate::math::Scalar;
+use crate::state::{KeyState, MouseState, MouseType, PointerID, TextInput};
+use crate::text::SharedMeasure;
+use crate::widget::flags::{GRAB, UPDATE};
+use crate::widget::{Boundaries, Dimensions, Widget, WidgetInternal};
+
+use std::f32::consts::PI;
+use std::ops::{Add, Sub};
+
+pub type GestureMask = u8;
+
+/// Two-finger scale gesture
+pub const PINCH: GestureMask = 0b0001;
+/// Two-finger translation gesture
+pub const PAN: GestureMask = 0b0010;
+/// Two-finger rotation gesture
+pub const ROTATE: GestureMask = 0b0100;
+/// Single pointer held without moving
+pub const LONG_PRESS: GestureMask = 0b1000;
+/// Every gesture
+pub const ALL: GestureMask = 0b1111;
+
+/// Relative scale change required for recognize a pinch
+const PINCH_SLOP: f32 = 0.08;
+/// Angle change in radians required for recognize a rotation
+const ROTATE_SLOP: f32 = 0.12;
+
+/// Phase of a continuous gesture
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Phase {
+    Begin,
+    Update,
+    End,
+}
+
+/// A recognized gesture, positions are absolute
+///
+/// Values of continuous gestures are relative to the previous gesture of the same kind,
+/// the Begin phase carries the change accumulated since the fingers touched
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Gesture {
+    /// Scale factor around a center point
+    Pinch {
+        phase: Phase,
+        center: (f32, f32),
+        scale: f32,
+    },
+    /// Translation of the center point between the fingers
+    Pan { phase: Phase, delta: (f32, f32) },
+    /// Rotation in radians around a center point, positive is clockwise on screen
+    Rotate {
+        phase: Phase,
+        center: (f32, f32),
+        angle: f32,
+    },
+    /// A single pointer was held without moving
+    LongPress { pointer: PointerID, pos: (f32, f32) },
+}
+
+/// Receives the gestures of a widget wrapped by Gestures
+pub trait GestureHandler<T> {
+    /// Handle a gesture recognized from the pointers of the widget
+    fn handle_gesture(&mut self, internal: &mut WidgetInternal<T>, gesture: Gesture);
+}
+
+#[derive(Clone, Copy)]
+struct Touch {
+    id: PointerID,
+    start: (f32, f32),
+    pos: (f32, f32),
+    time: u32,
+}
+
+/// Two-finger reference values
+#[derive(Clone, Copy, Default)]
+struct Pair {
+    center: (f32, f32),
+    distance: f32,
+    angle: f32,
+}
+
+impl Pair {
+    fn new(a: (f32, f32), b: (f32, f32)) -> Self {
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+
+        Pair {
+            center: ((a.0 + b.0) * 0.5, (a.1 + b.1) * 0.5),
+            distance: (dx * dx + dy * dy).sqrt(),
+            angle: dy.atan2(dx),
+        }
+    }
+
+    fn scale(&self, from: &Pair) -> f32 {
+        if from.distance > 0.0 {
+            self.distance / from.distance
+        } else {
+            1.0
+        }
+    }
+
+    fn rotation(&self, from: &Pair) -> f32 {
+        let mut angle = self.angle - from.angle;
+        if angle > PI {
+            angle -= 2.0 * PI;
+        } else if angle <= -PI {
+            angle += 2.0 * PI;
+        }
+
+        angle
+    }
+
+    fn translation(&self, from: &Pair) -> (f32, f32) {
+        (self.center.0 - from.center.0, self.center.1 - from.center.1)
+    }
+}
+
+/// Recognizes gestures from pointer events
+pub struct GestureRecognizer {
+    /// Enabled gestures
+    mask: GestureMask,
+    /// Gestures that are in progress
+    active: GestureMask,
+    /// Pressed pointers
+    touches: Vec<Touch>,
+    /// Two-finger values when the second finger touched
+    start: Pair,
+    /// Two-finger values of the previous event
+    last: Pair,
+    /// Long press duration in milliseconds
+    lp_time: u32,
+    /// Maximum distance that a pointer can move, also used as pan threshold
+    slop: f32,
+    /// Long press already fired for the current touch
+    lp_fired: bool,
+}
+
+impl GestureRecognizer {
+    /// Creates a new recognizer with enabled gestures, long press defaults to 500ms and 8 units of slop
+    pub fn new(mask: GestureMask) -> Self {
+        GestureRecognizer {
+            mask,
+            active: 0,
+            touches: Vec::with_capacity(2),
+            start: Default::default(),
+            last: Default::default(),
+            lp_time: 500,
+            slop: 8.0,
+            lp_fired: false,
+        }
+    }
+
+    /// Change long press duration in milliseconds
+    pub fn set_long_press(&mut self, time: u32) {
+        self.lp_time = time;
+    }
+
+    /// Change maximum movement distance of long press and minimum distance of pan
+    pub fn set_slop(&mut self, slop: f32) {
+        self.slop = slop;
+    }
+
+    /// Check if a pointer is pressed on the recognizer
+    #[inline]
+    pub fn is_pressed(&self, pointer: PointerID) -> bool {
+        self.touches.iter().any(|touch| touch.id == pointer)
+    }
+
+    /// Check if any continuous gesture is in progress
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        self.active != 0
+    }
+
+    /// Count pressed pointers
+    #[inline]
+    pub fn touches(&self) -> usize {
+        self.touches.len()
+    }
+
+    /// Check if a long press can be still recognized, tick should be called until it's false
+    #[inline]
+    pub fn is_waiting(&self) -> bool {
+        self.mask & LONG_PRESS > 0 && !self.lp_fired && self.touches.len() == 1
+    }
+
+    /// Consume a mouse state and call a function for every recognized gesture
+    pub fn handle_mouse<T, F>(&mut self, mouse: &MouseState<T>, mut f: F)
+    where
+        T: Scalar + Default + Sub<Output = T>,
+        F: FnMut(Gesture),
+    {
+        let pos = mouse.absolute_pos();
+        let pos = (pos.0.to_f32(), pos.1.to_f32());
+        let pointer = mouse.pointer();
+        let time = mouse.time();
+
+        match mouse.m_type {
+            MouseType::Pressed(_) => {
+                if !self.is_pressed(pointer) {
+                    self.touches.push(Touch {
+                        id: pointer,
+                        start: pos,
+                        pos,
+                        time,
+                    });
+
+                    self.lp_fired = false;
+                    if self.touches.len() == 2 {
+                        self.start = Pair::new(self.touches[0].pos, self.touches[1].pos);
+                        self.last = self.start;
+                    }
+                }
+            }
+            MouseType::Released(_) if mouse.m_count == 0 => {
+                // Without a clock the long press is checked by the next event
+                self.tick(time, &mut f);
+                self.release(pointer, &mut f);
+                return;
+            }
+            _ => {
+                if let Some(touch) = self.touches.iter_mut().find(|touch| touch.id == pointer) {
+                    touch.pos = pos;
+                } else {
+                    return;
+                }
+
+                if self.touches.len() == 2 {
+                    self.pair(&mut f);
+                }
+            }
+        }
+
+        self.tick(time, f);
+    }
+
+    /// Check long press timeout, call it periodically if the pointers don't move
+    pub fn tick<F: FnMut(Gesture)>(&mut self, time: u32, mut f: F) {
+        if self.mask & LONG_PRESS == 0 || self.lp_fired || self.touches.len() != 1 {
+            return;
+        }
+
+        let touch = self.touches[0];
+        let (dx, dy) = (touch.pos.0 - touch.start.0, touch.pos.1 - touch.start.1);
+
+        if dx * dx + dy * dy > self.slop * self.slop {
+            // The pointer moved, this is not a long press anymore
+            self.lp_fired = true;
+        } else if time.wrapping_sub(touch.time) >= self.lp_time {
+            self.lp_fired = true;
+
+            f(Gesture::LongPress {
+                pointer: touch.id,
+                pos: touch.pos,
+            });
+        }
+    }
+
+    /// Release a pointer that is no longer available, ending the gestures
+    pub fn pointer_out<F: FnMut(Gesture)>(&mut self, pointer: PointerID, mut f: F) {
+        self.release(pointer, &mut f);
+    }
+
+    /// Release every pointer, ending the gestures
+    pub fn cancel<F: FnMut(Gesture)>(&mut self, mut f: F) {
+        while let Some(touch) = self.touches.last() {
+            let id = touch.id;
+            self.release(id, &mut f);
+        }
+    }
+
+    fn release<F: FnMut(Gesture)>(&mut self, pointer: PointerID, f: &mut F) {
+        if let Some(n) = self.touches.iter().position(|touch| touch.id == pointer) {
+            self.touches.remove(n);
+
+            // The pair is broken, end the gestures
+            if n < 2 && self.active != 0 {
+                let center = self.last.center;
+
+                if self.active & PINCH > 0 {
+                    f(Gesture::Pinch {
+                        phase: Phase::End,
+                        center,
+                        scale: 1.0,
+                    });
+                }
+                if self.active & PAN > 0 {
+                    f(Gesture::Pan {
+                        phase: Phase::End,
+                        delta: (0.0, 0.0),
+                    });
+                }
+                if self.active & ROTATE > 0 {
+                    f(Gesture::Rotate {
+                        phase: Phase::End,
+                        center,
+                        angle: 0.0,
+                    });
+                }
+
+                self.active = 0;
+            }
+
+            // Avoid a long press of the remaining finger
+            self.lp_fired = true;
+            if self.touches.len() >= 2 {
+                self.start = Pair::new(self.touches[0].pos, self.touches[1].pos);
+                self.last = self.start;
+            }
+        }
+    }
+
+    fn pair<F: FnMut(Gesture)>(&mut self, f: &mut F) {
+        let pair = Pair::new(self.touches[0].pos, self.touches[1].pos);
+        let center = pair.center;
+
+        if self.mask & PINCH > 0 {
+            if self.active & PINCH > 0 {
+                f(Gesture::Pinch {
+                    phase: Phase::Update,
+                    center,
+                    scale: pair.scale(&self.last),
+                });
+            } else if (pair.scale(&self.start) - 1.0).abs() > PINCH_SLOP {
+                self.active |= PINCH;
+                f(Gesture::Pinch {
+                    phase: Phase::Begin,
+                    center,
+                    scale: pair.scale(&self.start),
+                });
+            }
+        }
+
+        if self.mask & PAN > 0 {
+            if self.active & PAN > 0 {
+                f(Gesture::Pan {
+                    phase: Phase::Update,
+                    delta: pair.translation(&self.last),
+                });
+            } else {
+                let delta = pair.translation(&self.start);
+
+                if delta.0 * delta.0 + delta.1 * delta.1 > self.slop * self.slop {
+                    self.active |= PAN;
+                    f(Gesture::Pan {
+                        phase: Phase::Begin,
+                        delta,
+                    });
+                }
+            }
+        }
+
+        if self.mask & ROTATE > 0 {
+            if self.active & ROTATE > 0 {
+                f(Gesture::Rotate {
+                    phase: Phase::Update,
+                    center,
+                    angle: pair.rotation(&self.last),
+                });
+            } else if pair.rotation(&self.start).abs() > ROTATE_SLOP {
+                self.active |= ROTATE;
+                f(Gesture::Rotate {
+                    phase: Phase::Begin,
+                    center,
+                    angle: pair.rotation(&self.start),
+                });
+            }
+        }
+
+        self.last = pair;
+    }
+}
+
+/// Wraps a widget for receive gestures through GestureHandler
+///
+/// Mouse events are still forwarded to the widget while there is no two-finger
+/// gesture in progress. The widget keeps the GRAB flag while a pointer is pressed.
+///
+/// A long press is checked when the pointer moves or is released, with a clock
+/// the widget also keeps the UPDATE flag while a long press is waiting for it
+pub struct Gestures<W> {
+    widget: W,
+    recognizer: GestureRecognizer,
+    /// Current time in milliseconds, in the same timebase of MouseState::time
+    clock: Option<Box<dyn Fn() -> u32>>,
+    /// UPDATE was turned on for the long press
+    waiting: bool,
+}
+
+impl<W> Gestures<W> {
+    /// Wraps a widget with a recognizer of enabled gestures
+    pub fn new(widget: W, mask: GestureMask) -> Self {
+        Gestures {
+            widget,
+            recognizer: GestureRecognizer::new(mask),
+            clock: None,
+            waiting: false,
+        }
+    }
+
+    /// Set a clock for recognize a long press while the pointer doesn't move, it
+    /// returns milliseconds in the same timebase of MouseState::time
+    pub fn set_clock<F: Fn() -> u32 + 'static>(&mut self, clock: F) {
+        self.clock = Some(Box::new(clock));
+    }
+
+    /// Get a reference of the wrapped widget
+    #[inline]
+    pub fn widget(&self) -> &W {
+        &self.widget
+    }
+
+    /// Get a mutable reference of the wrapped widget
+    #[inline]
+    pub fn widget_mut(&mut self) -> &mut W {
+        &mut self.widget
+    }
+
+    /// Get a mutable reference of the recognizer, useful for change its parameters
+    #[inline]
+    pub fn recognizer(&mut self) -> &mut GestureRecognizer {
+        &mut self.recognizer
+    }
+
+    /// Request updates while a long press can be recognized with the clock
+    fn wait<T>(&mut self, internal: &mut WidgetInternal<T>) {
+        self.waiting = self.clock.is_some() && self.recognizer.is_waiting();
+        if self.waiting {
+            internal.on(UPDATE);
+        }
+    }
+}
+
+impl<T, CTX, W> Widget<T, CTX> for Gestures<W>
+where
+    T: Sized + Copy + Clone + Scalar,
+    T: Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+    CTX: Sized,
+    W: Widget<T, CTX> + GestureHandler<T>,
+{
+    fn draw(&mut self, internal: &WidgetInternal<T>, ctx: &mut CTX) -> bool {
+        self.widget.draw(internal, ctx)
+    }
+
+    /// Check the long press with the clock, the UPDATE flag is turned on while a
+    /// touch is waiting for it
+    fn update(&mut self, internal: &mut WidgetInternal<T>) {
+        if let Some(clock) = &self.clock {
+            let widget = &mut self.widget;
+            self.recognizer
+                .tick(clock(), |gesture| widget.handle_gesture(internal, gesture));
+        }
+
+        // The widget turns it on again if it wants more updates
+        if self.waiting {
+            internal.off(UPDATE);
+        }
+
+        self.widget.update(internal);
+        self.wait(internal);
+    }
+
+    fn layout(&mut self, internal: &mut WidgetInternal<T>, complete: bool) {
+        self.widget.layout(internal, complete);
+    }
+
+    fn measure(&mut self, internal: &mut WidgetInternal<T>, measure: &SharedMeasure<T>) {
+        self.widget.measure(internal, measure);
+    }
+
+    fn handle_signal(&mut self, internal: &mut WidgetInternal<T>, group: GroupID) {
+        self.widget.handle_signal(internal, group);
+    }
+
+    fn handle_mouse(&mut self, internal: &mut WidgetInternal<T>, mouse: &MouseState<T>) {
+        let widget = &mut self.widget;
+        self.recognizer
+            .handle_mouse(mouse, |gesture| widget.handle_gesture(internal, gesture));
+
+        if !self.recognizer.is_active() {
+            self.widget.handle_mouse(internal, mouse);
+        }
+
+        if self.recognizer.is_pressed(mouse.pointer()) {
+            internal.on(GRAB);
+        }
+
+        self.wait(internal);
+    }
+
+    fn handle_keys(&mut self, internal: &mut WidgetInternal<T>, key: KeyState) {
+        self.widget.handle_keys(internal, key);
+    }
+
+    fn handle_text(&mut self, internal: &mut WidgetInternal<T>, text: TextInput) {
+        self.widget.handle_text(internal, text);
+    }
+
+    fn step_focus(&mut self, internal: &mut WidgetInternal<T>, back: bool) -> bool {
+        self.widget.step_focus(internal, back)
+    }
+
+    fn hover_in(&mut self, internal: &mut WidgetInternal<T>) {
+        self.widget.hover_in(internal);
+    }
+
+    fn hover_out(&mut self, internal: &mut WidgetInternal<T>) {
+        let widget = &mut self.widget;
+        self.recognizer
+            .cancel(|gesture| widget.handle_gesture(internal, gesture));
+
+        self.widget.hover_out(internal);
+    }
+
+    fn pointer_out(&mut self, internal: &mut WidgetInternal<T>, pointer: PointerID) {
+        let widget = &mut self.widget;
+        self.recognizer
+            .pointer_out(pointer, |gesture| widget.handle_gesture(internal, gesture));
+
+        self.widget.pointer_out(internal, pointer);
+    }
+
+    fn focus_out(&mut self, internal: &mut WidgetInternal<T>) {
+        self.widget.focus_out(internal);
+    }
+
+    fn expose(&mut self, internal: &mut WidgetInternal<T>, area: Boundaries<T>) {
+        self.widget.expose(internal, area);
+    }
+
+    fn pending(&self) -> usize {
+        self.widget.pending()
+    }
+
+    fn damage(&mut self, internal: &WidgetInternal<T>, region: &mut DamageRegion<T>) {
+        self.widget.damage(internal, region);
+    }
+
+    fn min_dimensions(&self) -> Dimensions<T> {
+        self.widget.min_dimensions()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(
+        recognizer: &mut GestureRecognizer,
+        mouse: &mut MouseState<f32>,
+        m_type: MouseType,
+        pos: (f32, f32),
+        time: u32,
+    ) -> Vec<Gesture> {
+        let mut gestures = Vec::new();
+        mouse.set_type(m_type);
+        mouse.set_position(pos);
+        mouse.set_time(time);
+        recognizer.handle_mouse(mouse, |gesture| gestures.push(gesture));
+
+        gestures
+    }
+
+    /// Two fingers pressed at a and b
+    fn fingers(
+        mask: GestureMask,
+        a: (f32, f32),
+        b: (f32, f32),
+    ) -> (GestureRecognizer, MouseState<f32>, MouseState<f32>) {
+        let mut recognizer = GestureRecognizer::new(mask);
+        let (mut first, mut second) = (MouseState::new_pointer(1), MouseState::new_pointer(2));
+        event(&mut recognizer, &mut first, MouseType::Pressed(1), a, 0);
+        event(&mut recognizer, &mut second, MouseType::Pressed(1), b, 10);
+
+        (recognizer, first, second)
+    }
+
+    fn near(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn pinch() {
+        let (mut r, mut first, mut second) = fingers(PINCH, (0.0, 0.0), (100.0, 0.0));
+
+        // Under the slop
+        let out = event(
+            &mut r,
+            &mut second,
+            MouseType::CursorMoved,
+            (105.0, 0.0),
+            20,
+        );
+        assert!(out.is_empty());
+
+        let out = event(
+            &mut r,
+            &mut second,
+            MouseType::CursorMoved,
+            (150.0, 0.0),
+            30,
+        );
+        match out[..] {
+            [Gesture::Pinch {
+                phase: Phase::Begin,
+                center,
+                scale,
+            }] => {
+                assert!(near(scale, 1.5));
+                assert_eq!(center, (75.0, 0.0));
+            }
+            _ => panic!("{:?}", out),
+        }
+
+        let out = event(
+            &mut r,
+            &mut second,
+            MouseType::CursorMoved,
+            (300.0, 0.0),
+            40,
+        );
+        match out[..] {
+            [Gesture::Pinch {
+                phase: Phase::Update,
+                scale,
+                ..
+            }] => assert!(near(scale, 2.0)),
+            _ => panic!("{:?}", out),
+        }
+
+        let out = event(&mut r, &mut first, MouseType::Released(1), (0.0, 0.0), 50);
+        assert!(matches!(
+            out[..],
+            [Gesture::Pinch {
+                phase: Phase::End,
+                ..
+            }]
+        ));
+        assert!(!r.is_active());
+    }
+
+    #[test]
+    fn pan() {
+        let (mut r, mut first, mut second) = fingers(PAN, (0.0, 0.0), (100.0, 0.0));
+
+        // Only one finger moves half of the distance of the center
+        let out = event(&mut r, &mut first, MouseType::CursorMoved, (0.0, 10.0), 20);
+        assert!(out.is_empty());
+
+        let out = event(
+            &mut r,
+            &mut second,
+            MouseType::CursorMoved,
+            (100.0, 10.0),
+            30,
+        );
+        assert_eq!(
+            out,
+            vec![Gesture::Pan {
+                phase: Phase::Begin,
+                delta: (0.0, 10.0)
+            }]
+        );
+
+        event(&mut r, &mut first, MouseType::CursorMoved, (20.0, 10.0), 40);
+        let out = event(
+            &mut r,
+            &mut second,
+            MouseType::CursorMoved,
+            (120.0, 10.0),
+            50,
+        );
+        assert_eq!(
+            out,
+            vec![Gesture::Pan {
+                phase: Phase::Update,
+                delta: (10.0, 0.0)
+            }]
+        );
+
+        let mut ended = Vec::new();
+        r.cancel(|gesture| ended.push(gesture));
+        assert_eq!(
+            ended,
+            vec![Gesture::Pan {
+                phase: Phase::End,
+                delta: (0.0, 0.0)
+            }]
+        );
+        assert_eq!(r.touches(), 0);
+    }
+
+    #[test]
+    fn rotate() {
+        let (mut r, _, mut second) = fingers(ROTATE, (0.0, 0.0), (100.0, 0.0));
+
+        let out = event(
+            &mut r,
+            &mut second,
+            MouseType::CursorMoved,
+            (0.0, 100.0),
+            20,
+        );
+        match out[..] {
+            [Gesture::Rotate {
+                phase: Phase::Begin,
+                angle,
+                ..
+            }] => assert!(near(angle, PI * 0.5)),
+            _ => panic!("{:?}", out),
+        }
+
+        // Crossing the half turn keeps the shortest angle
+        let out = event(
+            &mut r,
+            &mut second,
+            MouseType::CursorMoved,
+            (-100.0, -1.0),
+            30,
+        );
+        match out[..] {
+            [Gesture::Rotate {
+                phase: Phase::Update,
+                angle,
+                ..
+            }] => assert!(angle > 0.0 && angle < PI * 0.6),
+            _ => panic!("{:?}", out),
+        }
+
+        let mut ended = Vec::new();
+        r.pointer_out(2, |gesture| ended.push(gesture));
+        assert!(matches!(
+            ended[..],
+            [Gesture::Rotate {
+                phase: Phase::End,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn long_press() {
+        let mut r = GestureRecognizer::new(ALL);
+        let mut mouse = MouseState::new_pointer(1);
+
+        let out = event(&mut r, &mut mouse, MouseType::Pressed(1), (10.0, 10.0), 100);
+        assert!(out.is_empty());
+        assert!(r.is_waiting());
+
+        // Small movements are allowed
+        let out = event(
+            &mut r,
+            &mut mouse,
+            MouseType::CursorMoved,
+            (13.0, 12.0),
+            300,
+        );
+        assert!(out.is_empty());
+
+        let mut out = Vec::new();
+        r.tick(599, |gesture| out.push(gesture));
+        assert!(out.is_empty());
+
+        r.tick(600, |gesture| out.push(gesture));
+        assert_eq!(
+            out,
+            vec![Gesture::LongPress {
+                pointer: 1,
+                pos: (13.0, 12.0)
+            }]
+        );
+        assert!(!r.is_waiting());
+
+        // It fires only once
+        out.clear();
+        r.tick(2000, |gesture| out.push(gesture));
+        assert!(out.is_empty());
+
+        // Moving further than the slop cancels it
+        event(
+            &mut r,
+            &mut mouse,
+            MouseType::Released(1),
+            (13.0, 12.0),
+            2000,
+        );
+        event(
+            &mut r,
+            &mut mouse,
+            MouseType::Pressed(1),
+            (10.0, 10.0),
+            3000,
+        );
+        event(
+            &mut r,
+            &mut mouse,
+            MouseType::CursorMoved,
+            (30.0, 10.0),
+            3100,
+        );
+        r.tick(4000, |gesture| out.push(gesture));
+        assert!(out.is_empty());
+
+        // A second finger avoids it
+        let mut second = MouseState::new_pointer(2);
+        event(
+            &mut r,
+            &mut mouse,
+            MouseType::Released(1),
+            (30.0, 10.0),
+            4000,
+        );
+        event(
+            &mut r,
+            &mut mouse,
+            MouseType::Pressed(1),
+            (10.0, 10.0),
+            5000,
+        );
+        event(
+            &mut r,
+            &mut second,
+            MouseType::Pressed(1),
+            (50.0, 10.0),
+            5010,
+        );
+        r.tick(6000, |gesture| out.push(gesture));
+        assert!(out.is_empty());
+    }
+
+    struct Held(Vec<Gesture>);
+
+    impl Widget<f32, ()> for Held {
+        fn draw(&mut self, _: &WidgetInternal<f32>, _: &mut ()) -> bool {
+            false
+        }
+    }
+
+    impl GestureHandler<f32> for Held {
+        fn handle_gesture(&mut self, _: &mut WidgetInternal<f32>, gesture: Gesture) {
+            self.0.push(gesture);
+        }
+    }
+
+    fn press(
+        gestures: &mut Gestures<Held>,
+        internal: &mut WidgetInternal<f32>,
+        m_type: MouseType,
+        time: u32,
+    ) {
+        let mut mouse = MouseState::new();
+        mouse.set_type(m_type);
+        mouse.set_position((10.0, 10.0));
+        mouse.set_time(time);
+
+        Widget::<f32, ()>::handle_mouse(gestures, internal, &mouse);
+    }
+
+    #[test]
+    fn long_press_on_update() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        let now = Rc::new(Cell::new(100));
+        let clock = now.clone();
+        let mut gestures = Gestures::new(Held(Vec::new()), LONG_PRESS);
+        gestures.set_clock(move || clock.get());
+
+        let mut internal = WidgetInternal::new(0, crate::group::Group::Any);
+        press(&mut gestures, &mut internal, MouseType::Pressed(1), 100);
+        assert!(internal.check(UPDATE));
+
+        now.set(599);
+        Widget::<f32, ()>::update(&mut gestures, &mut internal);
+        assert!(internal.check(UPDATE));
+        assert!(gestures.widget().0.is_empty());
+
+        now.set(600);
+        Widget::<f32, ()>::update(&mut gestures, &mut internal);
+        assert!(!internal.check(UPDATE));
+        assert!(matches!(
+            gestures.widget().0[..],
+            [Gesture::LongPress { pointer: 0, .. }]
+        ));
+    }
+
+    #[test]
+    fn long_press_without_clock() {
+        let mut gestures = Gestures::new(Held(Vec::new()), LONG_PRESS);
+        let mut internal = WidgetInternal::new(0, crate::group::Group::Any);
+
+        // Nothing can wake the widget, so it doesn't wait for updates
+        press(&mut gestures, &mut internal, MouseType::Pressed(1), 100);
+        assert!(!internal.check(UPDATE));
+
+        // The release is the next event after the timeout
+        press(&mut gestures, &mut internal, MouseType::Released(1), 700);
+        assert!(matches!(
+            gestures.widget().0[..],
+            [Gesture::LongPress { pointer: 0, .. }]
+        ));
+        assert!(!gestures.recognizer().is_pressed(0));
+    }
+}
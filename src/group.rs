@@ -1,5 +1,6 @@
 //! Communication between widgets, it's based on Groups by IDs (an usize) that
 //! WidgetInternal will store and dispatched by a Container
+use std::cell::RefCell;
 use std::collections::VecDeque;
 
 pub type GroupID = usize;
@@ -10,33 +11,24 @@ pub enum GroupEvent {
     Layout,
 }
 
-static mut EVENT_QUEUE: Option<VecDeque<GroupEvent>> = None;
+thread_local! {
+    /// Event Queue of the thread, widgets of a Root live in one thread
+    static EVENT_QUEUE: RefCell<VecDeque<GroupEvent>> = RefCell::new(VecDeque::with_capacity(4));
+}
 
 /// Push a GroupEvent to the Event Queue
 pub fn push_event(event: GroupEvent) {
-    unsafe {
-        if let Some(ref mut queue) = EVENT_QUEUE {
-            if !queue.contains(&event) {
-                queue.push_back(event);
-            }
-        } else {
-            let mut queue: VecDeque<GroupEvent> = VecDeque::with_capacity(4);
+    EVENT_QUEUE.with(|queue| {
+        let mut queue = queue.borrow_mut();
+        if !queue.contains(&event) {
             queue.push_back(event);
-
-            EVENT_QUEUE = Some(queue);
         }
-    }
+    });
 }
 
 /// Pop a GroupEvent from the Event Queue
 pub fn next_event() -> Option<GroupEvent> {
-    unsafe {
-        if let Some(ref mut queue) = EVENT_QUEUE {
-            queue.pop_front()
-        } else {
-            None
-        }
-    }
+    EVENT_QUEUE.with(|queue| queue.borrow_mut().pop_front())
 }
 
 /// A Group with options to store ID/s
@@ -48,10 +40,7 @@ pub enum Group {
 
 impl Group {
     pub fn is_any(&self) -> bool {
-        match *self {
-            Group::Any => true,
-            _ => false,
-        }
+        matches!(*self, Group::Any)
     }
 
     pub fn check_id(&self, id: GroupID) -> bool {
@@ -1,16 +1,40 @@
 #![allow(dead_code)]
 
+pub mod clip;
+pub mod color;
+pub mod damage;
+pub mod display;
+pub mod gesture;
 pub mod group;
+pub mod math;
+pub mod paint;
+#[cfg(any(feature = "raster", feature = "svg"))]
+pub mod png;
 pub mod proxy;
+#[cfg(feature = "raster")]
+pub mod raster;
+pub mod scroll;
 pub mod state;
+#[cfg(feature = "svg")]
+pub mod svg;
+pub mod text;
+#[cfg(feature = "tui")]
+pub mod tui;
 pub mod widget;
+#[cfg(feature = "widgets")]
+pub mod widgets;
 
 mod container;
+mod root;
 pub use crate::container::Container;
+pub use crate::root::Root;
 
 use crate::widget::{Dimensions, WidgetInternal};
 
 /// Decorator Trait for a Container
+///
+/// The boundaries of the Container are already pushed as clip rectangle when
+/// before and after are called
 pub trait Decorator<T, CTX: Sized> {
     /// Prepares the decorator before draw the widgets
     fn before(&mut self, internal: &WidgetInternal<T>, ctx: &mut CTX);
@@ -30,7 +54,8 @@ pub trait Layout<T> {
 
 pub mod empty {
     //! Empty Implementations
-    use crate::widget::{Dimensions, WidgetInternal};
+    use crate::scroll::ScrollDecorator;
+    use crate::widget::{Boundaries, Dimensions, WidgetInternal};
     use crate::{Decorator, Layout};
 
     /// Layout with empty implementation
@@ -52,4 +77,8 @@ pub mod empty {
         fn after(&mut self, _: &WidgetInternal<T>, _: &mut CTX) {}
         fn update(&mut self, _: &WidgetInternal<T>) {}
     }
+
+    impl<T, CTX: Sized> ScrollDecorator<T, CTX> for EmptyDecorator {
+        fn bar(&mut self, _: Boundaries<T>, _: Boundaries<T>, _: bool, _: &mut CTX) {}
+    }
 }
@@ -0,0 +1,54 @@
+//! Numeric conversions for generic coordinate types
+//!
+//! Gestures, scrolling and transforms need floating point math, Scalar converts
+//! the generic T of the widgets from and to f32
+
+/// Conversion between a coordinate type and f32
+pub trait Scalar: Sized + Copy + Clone {
+    /// Converts the value into f32
+    fn to_f32(self) -> f32;
+    /// Converts a f32 into the value, integers are rounded
+    fn from_f32(value: f32) -> Self;
+}
+
+macro_rules! scalar_integer {
+    ($($t:ty),*) => {$(
+        impl Scalar for $t {
+            #[inline]
+            fn to_f32(self) -> f32 {
+                self as f32
+            }
+
+            #[inline]
+            fn from_f32(value: f32) -> Self {
+                value.round() as $t
+            }
+        }
+    )*};
+}
+
+scalar_integer!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl Scalar for f32 {
+    #[inline]
+    fn to_f32(self) -> f32 {
+        self
+    }
+
+    #[inline]
+    fn from_f32(value: f32) -> Self {
+        value
+    }
+}
+
+impl Scalar for f64 {
+    #[inline]
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+
+    #[inline]
+    fn from_f32(value: f32) -> Self {
+        value as f64
+    }
+}
@@ -0,0 +1,224 @@
+//! Drawing commands for backend-agnostic widgets
+//!
+//! Painter is the drawing-command trait that a CTX implements for a renderer.
+//! Widgets and Decorators that only use Painter can be drawn by any backend
+
+use crate::clip::Clip;
+use crate::math::Scalar;
+use crate::widget::{Boundaries, Position};
+
+use std::fmt;
+
+/// Identifier of a font provided by the backend
+pub type FontID = u32;
+/// Identifier of an image provided by the backend
+pub type ImageID = u32;
+
+/// RGBA Color with 8 bits per channel, not premultiplied
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Color {
+    pub const TRANSPARENT: Color = Color::rgba(0, 0, 0, 0);
+    pub const BLACK: Color = Color::rgb(0, 0, 0);
+    pub const WHITE: Color = Color::rgb(255, 255, 255);
+
+    /// Creates a new color with alpha
+    #[inline]
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Color { r, g, b, a }
+    }
+
+    /// Creates a new opaque color
+    #[inline]
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Color { r, g, b, a: 255 }
+    }
+
+    /// Creates a new color from a 0xRRGGBBAA number
+    #[inline]
+    pub const fn hex(value: u32) -> Self {
+        Color {
+            r: (value >> 24) as u8,
+            g: (value >> 16) as u8,
+            b: (value >> 8) as u8,
+            a: value as u8,
+        }
+    }
+
+    /// Get the color as a 0xRRGGBBAA number
+    #[inline]
+    pub const fn to_hex(self) -> u32 {
+        (self.r as u32) << 24 | (self.g as u32) << 16 | (self.b as u32) << 8 | self.a as u32
+    }
+
+    /// Replace the alpha channel
+    #[inline]
+    pub const fn with_alpha(self, a: u8) -> Self {
+        Color { a, ..self }
+    }
+}
+
+/// Formats the color as #rrggbbaa
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{:08x}", self.to_hex())
+    }
+}
+
+/// A segment of a path
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PathCommand<T> {
+    /// Starts a new subpath
+    MoveTo(Position<T>),
+    LineTo(Position<T>),
+    /// Quadratic bezier with a control point
+    QuadTo(Position<T>, Position<T>),
+    /// Cubic bezier with two control points
+    CubicTo(Position<T>, Position<T>, Position<T>),
+    /// Closes the current subpath
+    Close,
+}
+
+/// Drawing-command Trait for a CTX
+///
+/// Positions are absolute, like the boundaries of WidgetInternal. The clip rectangles
+/// pushed with Clip must be applied to every command
+pub trait Painter<T>: Clip<T> {
+    /// Fill a rectangle
+    fn fill_rect(&mut self, bounds: Boundaries<T>, color: Color);
+    /// Stroke the inside border of a rectangle
+    fn stroke_rect(&mut self, bounds: Boundaries<T>, width: T, color: Color);
+    /// Fill a rectangle with rounded corners
+    fn fill_round_rect(&mut self, bounds: Boundaries<T>, radius: T, color: Color);
+    /// Stroke a line between two points
+    fn line(&mut self, from: Position<T>, to: Position<T>, width: T, color: Color);
+    /// Fill a path using nonzero winding rule, subpaths are closed implicitly
+    fn fill_path(&mut self, path: &[PathCommand<T>], color: Color);
+    /// Stroke a path
+    fn stroke_path(&mut self, path: &[PathCommand<T>], width: T, color: Color);
+    /// Draw a text run, the position is the left point of the baseline
+    fn text(&mut self, pos: Position<T>, font: FontID, size: T, text: &str, color: Color);
+    /// Draw an image scaled to boundaries
+    fn image(&mut self, bounds: Boundaries<T>, image: ImageID);
+}
+
+/// Point of a flattened path in floating point
+pub type Point = (f32, f32);
+
+/// A flattened subpath
+pub struct Polyline {
+    pub points: Vec<Point>,
+    pub closed: bool,
+}
+
+#[inline]
+fn point<T: Scalar>(p: Position<T>) -> Point {
+    (p.0.to_f32(), p.1.to_f32())
+}
+
+#[inline]
+fn distance(a: Point, b: Point) -> f32 {
+    ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt()
+}
+
+/// Segments for a curve depending of the length of its control polygon
+pub(crate) fn segments(length: f32) -> usize {
+    ((length / 3.0).ceil() as usize).clamp(2, 64)
+}
+
+/// Convert path commands into polylines, useful for backends without curves
+pub fn flatten<T: Scalar>(path: &[PathCommand<T>]) -> Vec<Polyline> {
+    let mut lines: Vec<Polyline> = Vec::new();
+    let mut current = Polyline {
+        points: Vec::new(),
+        closed: false,
+    };
+    let mut last = (0.0, 0.0);
+
+    for command in path {
+        // A drawing command without MoveTo starts from the last point
+        if current.points.is_empty() {
+            if let PathCommand::LineTo(_) | PathCommand::QuadTo(..) | PathCommand::CubicTo(..) =
+                command
+            {
+                current.points.push(last);
+            }
+        }
+
+        match *command {
+            PathCommand::MoveTo(p) => {
+                if current.points.len() > 1 {
+                    lines.push(current);
+                }
+
+                last = point(p);
+                current = Polyline {
+                    points: vec![last],
+                    closed: false,
+                };
+            }
+            PathCommand::LineTo(p) => {
+                last = point(p);
+                current.points.push(last);
+            }
+            PathCommand::QuadTo(c, p) => {
+                let (c, p) = (point(c), point(p));
+                let n = segments(distance(last, c) + distance(c, p));
+
+                for i in 1..=n {
+                    let t = i as f32 / n as f32;
+                    let u = 1.0 - t;
+                    current.points.push((
+                        u * u * last.0 + 2.0 * u * t * c.0 + t * t * p.0,
+                        u * u * last.1 + 2.0 * u * t * c.1 + t * t * p.1,
+                    ));
+                }
+
+                last = p;
+            }
+            PathCommand::CubicTo(c1, c2, p) => {
+                let (c1, c2, p) = (point(c1), point(c2), point(p));
+                let n = segments(distance(last, c1) + distance(c1, c2) + distance(c2, p));
+
+                for i in 1..=n {
+                    let t = i as f32 / n as f32;
+                    let u = 1.0 - t;
+                    let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+                    current.points.push((
+                        a * last.0 + b * c1.0 + c * c2.0 + d * p.0,
+                        a * last.1 + b * c1.1 + c * c2.1 + d * p.1,
+                    ));
+                }
+
+                last = p;
+            }
+            PathCommand::Close => {
+                if let Some(&first) = current.points.first() {
+                    last = first;
+                }
+
+                current.closed = true;
+                if current.points.len() > 1 {
+                    lines.push(current);
+                }
+
+                current = Polyline {
+                    points: Vec::new(),
+                    closed: false,
+                };
+            }
+        }
+    }
+
+    if current.points.len() > 1 {
+        lines.push(current);
+    }
+
+    lines
+}
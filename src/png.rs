@@ -0,0 +1,844 @@
+//! Minimal PNG encoder and decoder for RGBA images
+//!
+//! Images are compressed with LZ77 and fixed Huffman codes, good enough for
+//! screenshots of user interfaces without any dependency. The decoder reads
+//! non-interlaced 8 bit images, which is enough for load reference images
+
+use std::fmt;
+
+/// PNG file signature
+const SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// Order of the code length codes of a dynamic Huffman block
+const CLEN_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Maximum width and height of a PNG image
+const MAX_SIZE: usize = 0x7fff_ffff;
+
+const WINDOW: usize = 32768;
+const MAX_MATCH: usize = 258;
+const MAX_CHAIN: usize = 64;
+const HASH_BITS: usize = 15;
+
+/// Error of a malformed or unsupported PNG file
+#[derive(Debug)]
+pub struct DecodeError {
+    pub message: &'static str,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid png: {}", self.message)
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+#[inline]
+fn error<R>(message: &'static str) -> Result<R, DecodeError> {
+    Err(DecodeError { message })
+}
+
+fn crc32(crc: u32, data: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+
+        a %= 65521;
+        b %= 65521;
+    }
+
+    b << 16 | a
+}
+
+/// Bit writer with LSB-first packing
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, count: u32) {
+        self.bits |= value << self.count;
+        self.count += count;
+
+        while self.count >= 8 {
+            self.bytes.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Huffman codes are packed starting from the most significant bit
+    fn write_code(&mut self, code: u32, count: u32) {
+        let mut reversed = 0;
+        for n in 0..count {
+            reversed |= (code >> n & 1) << (count - 1 - n);
+        }
+
+        self.write(reversed, count);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.bits as u8);
+        }
+
+        self.bytes
+    }
+}
+
+fn write_literal(w: &mut BitWriter, value: u16) {
+    let value = value as u32;
+    match value {
+        0..=143 => w.write_code(0x30 + value, 8),
+        144..=255 => w.write_code(0x190 + value - 144, 9),
+        256..=279 => w.write_code(value - 256, 7),
+        _ => w.write_code(0xc0 + value - 280, 8),
+    }
+}
+
+fn write_match(w: &mut BitWriter, length: usize, distance: usize) {
+    let l = LENGTH_BASE
+        .iter()
+        .rposition(|&base| base as usize <= length)
+        .unwrap();
+    write_literal(w, 257 + l as u16);
+    w.write(
+        (length - LENGTH_BASE[l] as usize) as u32,
+        LENGTH_EXTRA[l] as u32,
+    );
+
+    let d = DIST_BASE
+        .iter()
+        .rposition(|&base| base as usize <= distance)
+        .unwrap();
+    w.write_code(d as u32, 5);
+    w.write(
+        (distance - DIST_BASE[d] as usize) as u32,
+        DIST_EXTRA[d] as u32,
+    );
+}
+
+#[inline]
+fn hash(data: &[u8], n: usize) -> usize {
+    let value = (data[n] as usize) << 16 | (data[n + 1] as usize) << 8 | data[n + 2] as usize;
+    value.wrapping_mul(2_654_435_761) >> 7 & ((1 << HASH_BITS) - 1)
+}
+
+/// Compress data as a zlib stream with a single fixed Huffman block
+pub fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut w = BitWriter {
+        bytes: vec![0x78, 0x01],
+        bits: 0,
+        count: 0,
+    };
+
+    // BFINAL | BTYPE = 01
+    w.write(0b011, 3);
+
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut prev = vec![usize::MAX; WINDOW];
+    let mut n = 0;
+
+    let insert = |head: &mut Vec<usize>, prev: &mut Vec<usize>, n: usize| {
+        if n + 2 < data.len() {
+            let h = hash(data, n);
+            prev[n % WINDOW] = head[h];
+            head[h] = n;
+        }
+    };
+
+    while n < data.len() {
+        let (mut best_len, mut best_dist) = (0, 0);
+
+        if n + 2 < data.len() {
+            let mut candidate = head[hash(data, n)];
+            let max = MAX_MATCH.min(data.len() - n);
+            let mut chain = 0;
+
+            while candidate != usize::MAX && n - candidate <= WINDOW && chain < MAX_CHAIN {
+                let len = data[candidate..]
+                    .iter()
+                    .zip(data[n..n + max].iter())
+                    .take_while(|(a, b)| a == b)
+                    .count();
+
+                if len > best_len {
+                    best_len = len;
+                    best_dist = n - candidate;
+
+                    if len == max {
+                        break;
+                    }
+                }
+
+                let next = prev[candidate % WINDOW];
+                if next == usize::MAX || next >= candidate {
+                    break;
+                }
+
+                candidate = next;
+                chain += 1;
+            }
+        }
+
+        if best_len >= 3 {
+            write_match(&mut w, best_len, best_dist);
+            for m in n..n + best_len {
+                insert(&mut head, &mut prev, m);
+            }
+
+            n += best_len;
+        } else {
+            write_literal(&mut w, data[n] as u16);
+            insert(&mut head, &mut prev, n);
+
+            n += 1;
+        }
+    }
+
+    // End of block
+    write_literal(&mut w, 256);
+
+    let mut bytes = w.finish();
+    bytes.extend_from_slice(&adler32(data).to_be_bytes());
+
+    bytes
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Apply the PNG filter that gives the smallest sum of absolute values to every row
+fn filter(width: usize, height: usize, rgba: &[u8]) -> Vec<u8> {
+    let stride = width * 4;
+    let mut out = Vec::with_capacity((stride + 1) * height);
+    let mut row = vec![0u8; stride];
+    let zero = vec![0u8; stride];
+
+    for y in 0..height {
+        let cur = &rgba[y * stride..(y + 1) * stride];
+        let up = if y > 0 {
+            &rgba[(y - 1) * stride..y * stride]
+        } else {
+            &zero[..]
+        };
+
+        let mut best = (u64::MAX, 0u8);
+        for kind in 0..5u8 {
+            let mut sum = 0u64;
+            for x in 0..stride {
+                let a = if x >= 4 { cur[x - 4] } else { 0 };
+                let c = if x >= 4 { up[x - 4] } else { 0 };
+                let value = filter_byte(kind, cur[x], a, up[x], c);
+
+                sum += (value as i8).unsigned_abs() as u64;
+            }
+
+            if sum < best.0 {
+                best = (sum, kind);
+            }
+        }
+
+        for x in 0..stride {
+            let a = if x >= 4 { cur[x - 4] } else { 0 };
+            let c = if x >= 4 { up[x - 4] } else { 0 };
+            row[x] = filter_byte(best.1, cur[x], a, up[x], c);
+        }
+
+        out.push(best.1);
+        out.extend_from_slice(&row);
+    }
+
+    out
+}
+
+#[inline]
+fn filter_byte(kind: u8, x: u8, a: u8, b: u8, c: u8) -> u8 {
+    match kind {
+        1 => x.wrapping_sub(a),
+        2 => x.wrapping_sub(b),
+        3 => x.wrapping_sub(((a as u16 + b as u16) / 2) as u8),
+        4 => x.wrapping_sub(paeth(a, b, c)),
+        _ => x,
+    }
+}
+
+fn chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+
+    let crc = crc32(crc32(0, kind), data);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Encode RGBA pixels with 8 bits per channel as a PNG file
+///
+/// Panics if the buffer size doesn't match or a dimension is bigger than 2^31 - 1
+pub fn encode(width: usize, height: usize, rgba: &[u8]) -> Vec<u8> {
+    let size = width.checked_mul(height).and_then(|n| n.checked_mul(4));
+    assert_eq!(size, Some(rgba.len()), "invalid RGBA buffer size");
+    assert!(
+        width <= MAX_SIZE && height <= MAX_SIZE,
+        "image too big for png"
+    );
+
+    let mut out = SIGNATURE.to_vec();
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    // 8 bits, RGBA, deflate, adaptive filtering, no interlace
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    chunk(&mut out, b"IHDR", &header);
+    chunk(&mut out, b"IDAT", &deflate(&filter(width, height, rgba)));
+    chunk(&mut out, b"IEND", &[]);
+
+    out
+}
+
+/// Bit reader with LSB-first packing
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bits: u32,
+    count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn read(&mut self, count: u32) -> Result<u32, DecodeError> {
+        while self.count < count {
+            match self.data.get(self.pos) {
+                Some(&byte) => self.bits |= (byte as u32) << self.count,
+                None => return error("unexpected end of data"),
+            }
+
+            self.pos += 1;
+            self.count += 8;
+        }
+
+        let value = self.bits & ((1u64 << count) - 1) as u32;
+        self.bits >>= count;
+        self.count -= count;
+
+        Ok(value)
+    }
+
+    /// Discard the remaining bits of the current byte
+    fn align(&mut self) {
+        self.bits = 0;
+        self.count = 0;
+    }
+
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], DecodeError> {
+        match self.data.get(self.pos..self.pos + count) {
+            Some(bytes) => {
+                self.pos += count;
+                Ok(bytes)
+            }
+            None => error("unexpected end of data"),
+        }
+    }
+}
+
+/// Canonical Huffman code, decoded bit by bit
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; 16];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut offsets = [0u16; 16];
+        for len in 1..15 {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+
+        Huffman { counts, symbols }
+    }
+
+    fn decode(&self, r: &mut BitReader) -> Result<u16, DecodeError> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+
+        for len in 1..16 {
+            code |= r.read(1)? as i32;
+            let count = self.counts[len] as i32;
+
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+
+        error("invalid huffman code")
+    }
+}
+
+fn fixed_tables() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    lengths[..144].iter_mut().for_each(|l| *l = 8);
+    lengths[144..256].iter_mut().for_each(|l| *l = 9);
+    lengths[256..280].iter_mut().for_each(|l| *l = 7);
+    lengths[280..].iter_mut().for_each(|l| *l = 8);
+
+    (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+}
+
+fn dynamic_tables(r: &mut BitReader) -> Result<(Huffman, Huffman), DecodeError> {
+    let hlit = r.read(5)? as usize + 257;
+    let hdist = r.read(5)? as usize + 1;
+    let hclen = r.read(4)? as usize + 4;
+
+    let mut clen = [0u8; 19];
+    for &n in CLEN_ORDER[..hclen].iter() {
+        clen[n] = r.read(3)? as u8;
+    }
+
+    let clen = Huffman::new(&clen);
+    let mut lengths = Vec::with_capacity(hlit + hdist);
+
+    while lengths.len() < hlit + hdist {
+        let (value, repeat) = match clen.decode(r)? {
+            16 => match lengths.last() {
+                Some(&prev) => (prev, 3 + r.read(2)?),
+                None => return error("repeat without previous length"),
+            },
+            17 => (0, 3 + r.read(3)?),
+            18 => (0, 11 + r.read(7)?),
+            len => (len as u8, 1),
+        };
+
+        lengths.extend(std::iter::repeat(value).take(repeat as usize));
+    }
+
+    if lengths.len() > hlit + hdist {
+        return error("too many code lengths");
+    }
+
+    Ok((
+        Huffman::new(&lengths[..hlit]),
+        Huffman::new(&lengths[hlit..]),
+    ))
+}
+
+fn inflate_block(
+    r: &mut BitReader,
+    out: &mut Vec<u8>,
+    lit: &Huffman,
+    dist: &Huffman,
+) -> Result<(), DecodeError> {
+    loop {
+        let symbol = lit.decode(r)? as usize;
+
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            257..=285 => {
+                let l = symbol - 257;
+                let length = LENGTH_BASE[l] as usize + r.read(LENGTH_EXTRA[l] as u32)? as usize;
+
+                let d = dist.decode(r)? as usize;
+                if d >= 30 {
+                    return error("invalid distance code");
+                }
+
+                let distance = DIST_BASE[d] as usize + r.read(DIST_EXTRA[d] as u32)? as usize;
+                if distance > out.len() {
+                    return error("distance too far back");
+                }
+
+                let start = out.len() - distance;
+                for n in 0..length {
+                    out.push(out[start + n]);
+                }
+            }
+            _ => return error("invalid length code"),
+        }
+    }
+}
+
+/// Decompress a zlib stream
+pub fn inflate(data: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    if data.len() < 6 || data[0] & 0x0f != 8 || u16::from_be_bytes([data[0], data[1]]) % 31 != 0 {
+        return error("invalid zlib header");
+    }
+
+    let mut r = BitReader {
+        data: &data[2..],
+        pos: 0,
+        bits: 0,
+        count: 0,
+    };
+    let mut out = Vec::new();
+
+    loop {
+        let last = r.read(1)? == 1;
+
+        match r.read(2)? {
+            0 => {
+                r.align();
+                let header = r.bytes(4)?;
+                let len = u16::from_le_bytes([header[0], header[1]]);
+                let nlen = u16::from_le_bytes([header[2], header[3]]);
+
+                if len != !nlen {
+                    return error("invalid stored block length");
+                }
+
+                out.extend_from_slice(r.bytes(len as usize)?);
+            }
+            1 => {
+                let (lit, dist) = fixed_tables();
+                inflate_block(&mut r, &mut out, &lit, &dist)?;
+            }
+            2 => {
+                let (lit, dist) = dynamic_tables(&mut r)?;
+                inflate_block(&mut r, &mut out, &lit, &dist)?;
+            }
+            _ => return error("invalid block type"),
+        }
+
+        if last {
+            break;
+        }
+    }
+
+    r.align();
+    match r.bytes(4) {
+        Ok(sum) if u32::from_be_bytes([sum[0], sum[1], sum[2], sum[3]]) == adler32(&out) => Ok(out),
+        _ => error("invalid adler32 checksum"),
+    }
+}
+
+/// Reverse the filter of every row
+fn unfilter(width: usize, height: usize, bpp: usize, data: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    let stride = width.checked_mul(bpp);
+    let size = stride
+        .and_then(|stride| stride.checked_add(1))
+        .and_then(|row| row.checked_mul(height));
+
+    let stride = match (stride, size) {
+        (Some(stride), Some(size)) if data.len() >= size => stride,
+        (Some(_), Some(_)) => return error("image data too short"),
+        _ => return error("image too big"),
+    };
+
+    let mut out = vec![0u8; stride * height];
+    for y in 0..height {
+        let kind = data[y * (stride + 1)];
+        let row = &data[y * (stride + 1) + 1..(y + 1) * (stride + 1)];
+
+        if kind > 4 {
+            return error("invalid filter type");
+        }
+
+        for x in 0..stride {
+            let a = if x >= bpp {
+                out[y * stride + x - bpp]
+            } else {
+                0
+            };
+            let b = if y > 0 { out[(y - 1) * stride + x] } else { 0 };
+            let c = if x >= bpp && y > 0 {
+                out[(y - 1) * stride + x - bpp]
+            } else {
+                0
+            };
+
+            let predictor = match kind {
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                _ => 0,
+            };
+
+            out[y * stride + x] = row[x].wrapping_add(predictor);
+        }
+    }
+
+    Ok(out)
+}
+
+/// Decode a PNG file into width, height and RGBA pixels
+///
+/// Only 8 bit non-interlaced grayscale, RGB and their alpha variants are supported
+pub fn decode(data: &[u8]) -> Result<(usize, usize, Vec<u8>), DecodeError> {
+    if data.len() < 8 || data[..8] != SIGNATURE {
+        return error("missing signature");
+    }
+
+    let mut pos = 8;
+    let mut header: Option<&[u8]> = None;
+    let mut idat = Vec::new();
+
+    while pos + 12 <= data.len() {
+        let len = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]);
+        let end = match (pos + 12).checked_add(len as usize) {
+            Some(end) if end <= data.len() => end,
+            _ => return error("truncated chunk"),
+        };
+
+        let kind = &data[pos + 4..pos + 8];
+        let content = &data[pos + 8..end - 4];
+        let crc = &data[end - 4..end];
+
+        if crc32(crc32(0, kind), content) != u32::from_be_bytes([crc[0], crc[1], crc[2], crc[3]]) {
+            return error("invalid chunk checksum");
+        }
+
+        match kind {
+            b"IHDR" => header = Some(content),
+            b"IDAT" => idat.extend_from_slice(content),
+            b"IEND" => break,
+            _ => {}
+        }
+
+        pos = end;
+    }
+
+    let header = match header {
+        Some(header) if header.len() == 13 => header,
+        _ => return error("missing header"),
+    };
+
+    let width = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let height = u32::from_be_bytes([header[4], header[5], header[6], header[7]]) as usize;
+    if width == 0 || height == 0 || width > MAX_SIZE || height > MAX_SIZE {
+        return error("invalid image dimensions");
+    } else if width
+        .checked_mul(height)
+        .and_then(|n| n.checked_mul(4))
+        .is_none()
+    {
+        return error("image too big");
+    } else if header[8] != 8 || header[12] != 0 {
+        return error("unsupported bit depth or interlacing");
+    }
+
+    let channels = match header[9] {
+        0 => 1,
+        2 => 3,
+        4 => 2,
+        6 => 4,
+        _ => return error("unsupported color type"),
+    };
+
+    let pixels = unfilter(width, height, channels, &inflate(&idat)?)?;
+    let rgba = match channels {
+        4 => pixels,
+        3 => pixels
+            .chunks_exact(3)
+            .flat_map(|p| [p[0], p[1], p[2], 255])
+            .collect(),
+        2 => pixels
+            .chunks_exact(2)
+            .flat_map(|p| [p[0], p[0], p[0], p[1]])
+            .collect(),
+        _ => pixels.iter().flat_map(|&p| [p, p, p, 255]).collect(),
+    };
+
+    Ok((width, height, rgba))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header() {
+        let png = encode(3, 2, &[255; 24]);
+
+        assert_eq!(png[..8], SIGNATURE);
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(png[16..24], [0, 0, 0, 3, 0, 0, 0, 2]);
+        assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+    }
+
+    /// PNG file with every row filtered with the same kind
+    fn filtered(width: usize, height: usize, kind: u8, rgba: &[u8]) -> Vec<u8> {
+        let stride = width * 4;
+        let mut data = Vec::new();
+
+        for y in 0..height {
+            let row = |y: usize, x: usize| rgba[y * stride + x];
+
+            data.push(kind);
+            for x in 0..stride {
+                let a = if x >= 4 { row(y, x - 4) } else { 0 };
+                let b = if y > 0 { row(y - 1, x) } else { 0 };
+                let c = if x >= 4 && y > 0 {
+                    row(y - 1, x - 4)
+                } else {
+                    0
+                };
+
+                data.push(filter_byte(kind, row(y, x), a, b, c));
+            }
+        }
+
+        let mut header = Vec::new();
+        header.extend_from_slice(&(width as u32).to_be_bytes());
+        header.extend_from_slice(&(height as u32).to_be_bytes());
+        header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+        let mut out = SIGNATURE.to_vec();
+        chunk(&mut out, b"IHDR", &header);
+        chunk(&mut out, b"IDAT", &deflate(&data));
+        chunk(&mut out, b"IEND", &[]);
+
+        out
+    }
+
+    /// Gradient with noise, so every filter has something to predict
+    fn image(width: usize, height: usize) -> Vec<u8> {
+        (0..width * height * 4)
+            .map(|n| {
+                let (x, y) = (n / 4 % width, n / 4 / width);
+                (x * 7 + y * 13 + (n.wrapping_mul(2_654_435_761) >> 13) % 5) as u8
+            })
+            .collect()
+    }
+
+    fn message<R>(result: Result<R, DecodeError>) -> &'static str {
+        match result {
+            Ok(_) => panic!("decoded an invalid png"),
+            Err(err) => err.message,
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let rgba = image(17, 9);
+
+        for kind in 0..5 {
+            let (width, height, pixels) = decode(&filtered(17, 9, kind, &rgba)).unwrap();
+            assert_eq!((width, height), (17, 9));
+            assert!(pixels == rgba, "filter type {}", kind);
+        }
+
+        let (width, height, pixels) = decode(&encode(17, 9, &rgba)).unwrap();
+        assert_eq!((width, height), (17, 9));
+        assert!(pixels == rgba);
+    }
+
+    #[test]
+    fn truncated() {
+        let png = encode(5, 4, &image(5, 4));
+
+        for len in 0..png.len() - 12 {
+            assert!(decode(&png[..len]).is_err(), "decoded {} bytes", len);
+        }
+
+        let mut bad = png.clone();
+        bad[png.len() - 20] ^= 1;
+        assert_eq!(message(decode(&bad)), "invalid chunk checksum");
+    }
+
+    #[test]
+    fn oversized_header() {
+        let png = |width: u32, height: u32| {
+            let mut header = Vec::new();
+            header.extend_from_slice(&width.to_be_bytes());
+            header.extend_from_slice(&height.to_be_bytes());
+            header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+            let mut out = SIGNATURE.to_vec();
+            chunk(&mut out, b"IHDR", &header);
+            chunk(&mut out, b"IDAT", &deflate(&[0; 16]));
+            chunk(&mut out, b"IEND", &[]);
+
+            decode(&out)
+        };
+
+        assert_eq!(message(png(0x8000_0000, 1)), "invalid image dimensions");
+        assert_eq!(message(png(0, 1)), "invalid image dimensions");
+        assert_eq!(message(png(0x7fff_ffff, 2)), "image data too short");
+
+        // The sizes only overflow on 32 bits
+        let big = message(png(0x7fff_ffff, 0x7fff_ffff));
+        assert!(big == "image too big" || big == "image data too short");
+        assert_eq!(
+            message(unfilter(usize::MAX / 2, 2, 4, &[])),
+            "image too big"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid RGBA buffer size")]
+    fn overflowing_size() {
+        encode(usize::MAX / 2, 3, &[]);
+    }
+}
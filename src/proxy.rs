@@ -21,13 +21,21 @@ impl<T> RefProxy<T> {
     }
 
     #[inline]
+    #[allow(clippy::mut_from_ref)]
     /// Returns a mutable reference
+    ///
+    /// # Safety
+    /// The pointed data must be alive and not borrowed elsewhere
     pub unsafe fn read_write(&self) -> &mut T {
         &mut *self.ptr
     }
 
     #[inline]
+    #[allow(clippy::mut_from_ref)]
     /// Returns a mutable reference and push a signal id into queue
+    ///
+    /// # Safety
+    /// The pointed data must be alive and not borrowed elsewhere
     pub unsafe fn rw_push(&self, id: GroupID) -> &mut T {
         push_event(Signal(id));
 
@@ -46,6 +54,9 @@ pub trait Proxy<T> {
 }
 
 /// Cast an opaque pointer into a mutable reference of T type, use with caution!
+///
+/// # Safety
+/// The opaque pointer must point to an alive value of T type
 #[inline]
 pub unsafe fn cast_opaque<'a, T>(opaque: Opaque) -> &'a mut T {
     &mut *(opaque as *mut T)
@@ -0,0 +1,238 @@
+//! Glyph Bitmap Distribution Format loader
+
+use super::{BitmapFont, BitmapGlyph, FontError};
+
+/// Bounding box of a glyph: width, height, left and bottom offsets
+type BBox = (usize, usize, i32, i32);
+
+/// Glyph being read between STARTCHAR and ENDCHAR
+struct Char {
+    encoding: Option<char>,
+    advance: Option<i32>,
+    bbox: Option<BBox>,
+    rows: Vec<Vec<u8>>,
+}
+
+fn numbers<'a, I>(words: I) -> Result<Vec<i32>, FontError>
+where
+    I: Iterator<Item = &'a str>,
+{
+    words
+        .map(|w| w.parse().map_err(|_| FontError::new("bad number")))
+        .collect()
+}
+
+fn bbox(values: &[i32]) -> Result<BBox, FontError> {
+    match *values {
+        [w, h, x, y] if w >= 0 && h >= 0 => Ok((w as usize, h as usize, x, y)),
+        _ => Err(FontError::new("bad bounding box")),
+    }
+}
+
+fn hex(row: &str) -> Result<Vec<u8>, FontError> {
+    let digits = row.as_bytes();
+    if digits.len() % 2 != 0 {
+        return Err(FontError::new("bad bitmap row"));
+    }
+
+    digits
+        .chunks_exact(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or(FontError::new("bad bitmap row"))
+        })
+        .collect()
+}
+
+pub(super) fn parse(data: &[u8]) -> Result<BitmapFont, FontError> {
+    let text = String::from_utf8_lossy(data);
+    let mut lines = text.lines().map(str::trim);
+
+    match lines.next() {
+        Some(line) if line.starts_with("STARTFONT") => {}
+        _ => return Err(FontError::new("missing STARTFONT")),
+    }
+
+    let mut font_box: Option<BBox> = None;
+    let (mut ascent, mut descent) = (None, None);
+    let mut default = None;
+
+    let mut glyphs = Vec::new();
+    let mut current: Option<Char> = None;
+    let mut bitmap = false;
+
+    for line in lines {
+        let mut words = line.split_whitespace();
+        let keyword = match words.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+
+        if bitmap {
+            if keyword != "ENDCHAR" {
+                if let Some(c) = current.as_mut() {
+                    c.rows.push(hex(keyword)?);
+                }
+
+                continue;
+            }
+
+            bitmap = false;
+        }
+
+        match keyword {
+            "FONTBOUNDINGBOX" => font_box = Some(bbox(&numbers(words)?)?),
+            "FONT_ASCENT" => ascent = numbers(words)?.first().copied(),
+            "FONT_DESCENT" => descent = numbers(words)?.first().copied(),
+            "DEFAULT_CHAR" => {
+                default = numbers(words)?
+                    .first()
+                    .and_then(|&n| char::from_u32(n as u32));
+            }
+            "STARTCHAR" => {
+                current = Some(Char {
+                    encoding: None,
+                    advance: None,
+                    bbox: None,
+                    rows: Vec::new(),
+                });
+            }
+            "ENCODING" => {
+                if let Some(c) = current.as_mut() {
+                    // Negative encodings are glyphs without a character
+                    let n = numbers(words.take(1))?;
+                    c.encoding = n.first().and_then(|&n| char::from_u32(n as u32));
+                }
+            }
+            "DWIDTH" => {
+                if let Some(c) = current.as_mut() {
+                    c.advance = numbers(words.take(1))?.first().copied();
+                }
+            }
+            "BBX" => {
+                if let Some(c) = current.as_mut() {
+                    c.bbox = Some(bbox(&numbers(words)?)?);
+                }
+            }
+            "BITMAP" => bitmap = current.is_some(),
+            "ENDCHAR" => {
+                if let Some(c) = current.take() {
+                    glyphs.push(c);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let font_box = font_box.ok_or(FontError::new("missing FONTBOUNDINGBOX"))?;
+    let ascent = ascent.unwrap_or(font_box.1 as i32 + font_box.3);
+    let descent = descent.unwrap_or(-font_box.3);
+
+    let mut font = BitmapFont::new(ascent, descent);
+    for c in glyphs {
+        let encoding = match c.encoding {
+            Some(encoding) => encoding,
+            None => continue,
+        };
+
+        let (width, height, left, bottom) = c.bbox.unwrap_or(font_box);
+        let mut bits = vec![0; width * height];
+
+        // Rows are padded to whole bytes, the first pixel is the high bit
+        for (y, row) in c.rows.iter().take(height).enumerate() {
+            for x in 0..width.min(row.len() * 8) {
+                bits[y * width + x] = (row[x / 8] >> (7 - x % 8)) & 1;
+            }
+        }
+
+        font.insert(
+            encoding,
+            BitmapGlyph {
+                width,
+                height,
+                left,
+                top: bottom + height as i32,
+                advance: c.advance.unwrap_or(font_box.0 as i32),
+                bits,
+            },
+        );
+    }
+
+    match default {
+        Some(c) => font.set_default(c),
+        None if font.get('?').is_some() => font.set_default('?'),
+        None => {}
+    }
+
+    Ok(font)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FONT: &str = "STARTFONT 2.1
+FONTBOUNDINGBOX 6 8 0 -2
+FONT_ASCENT 6
+FONT_DESCENT 2
+CHARS 3
+STARTCHAR A
+ENCODING 65
+DWIDTH 7 0
+BBX 5 2 1 3
+BITMAP
+88
+F8
+ENDCHAR
+STARTCHAR unmapped
+ENCODING -1
+BITMAP
+ENDCHAR
+STARTCHAR question
+ENCODING 63
+BBX 2 1 0 0
+BITMAP
+C0
+ENDCHAR
+ENDFONT
+";
+
+    fn error(data: &str) -> &'static str {
+        match parse(data.as_bytes()) {
+            Ok(_) => panic!("the font was parsed"),
+            Err(err) => err.message,
+        }
+    }
+
+    #[test]
+    fn glyphs() {
+        let font = parse(FONT.as_bytes()).ok().unwrap();
+        assert_eq!((font.ascent(), font.descent(), font.len()), (6, 2, 2));
+
+        let glyph = font.get('A').unwrap();
+        assert_eq!((glyph.width, glyph.height), (5, 2));
+        assert_eq!((glyph.left, glyph.top, glyph.advance), (1, 5, 7));
+        assert_eq!(glyph.bits, [1, 0, 0, 0, 1, 1, 1, 1, 1, 1]);
+
+        // Without DWIDTH the advance is the width of the font, '?' is the default
+        let glyph = font.get('z').unwrap();
+        assert_eq!((glyph.advance, glyph.bits.as_slice()), (6, &[1, 1][..]));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(error("FONT x"), "missing STARTFONT");
+        assert_eq!(error("STARTFONT 2.1\nENDFONT"), "missing FONTBOUNDINGBOX");
+        assert_eq!(
+            error("STARTFONT 2.1\nFONTBOUNDINGBOX 6 -8 0 0"),
+            "bad bounding box"
+        );
+        assert_eq!(error("STARTFONT 2.1\nFONT_ASCENT x"), "bad number");
+        assert_eq!(
+            error("STARTFONT 2.1\nSTARTCHAR a\nBITMAP\nF\nENDCHAR"),
+            "bad bitmap row"
+        );
+    }
+}
@@ -0,0 +1,304 @@
+//! Embedded 5x7 font, ASCII and Latin-1
+//!
+//! The glyphs are drawn in a cell of 6x11 pixels, the two top rows are reserved
+//! for the accents of capital letters and the two bottom rows for descenders.
+//! Accented letters are composed with the base letter and a mark
+
+use super::{BitmapFont, BitmapGlyph};
+
+const WIDTH: usize = 5;
+const HEIGHT: usize = 11;
+const ASCENT: i32 = 9;
+const DESCENT: i32 = 2;
+const ADVANCE: i32 = 6;
+
+/// Rows of a glyph from the top of capital letters to the bottom of descenders,
+/// the five bits of each row are the pixels from left to right
+const GLYPHS: [(char, [u8; 9]); 139] = [
+    (' ', [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+    ('!', [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04, 0x00, 0x00]),
+    ('"', [0x0a, 0x0a, 0x0a, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+    ('#', [0x0a, 0x0a, 0x1f, 0x0a, 0x1f, 0x0a, 0x0a, 0x00, 0x00]),
+    ('$', [0x04, 0x0f, 0x14, 0x0e, 0x05, 0x1e, 0x04, 0x00, 0x00]),
+    ('%', [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03, 0x00, 0x00]),
+    ('&', [0x0c, 0x12, 0x14, 0x08, 0x15, 0x12, 0x0d, 0x00, 0x00]),
+    ('\'', [0x04, 0x04, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+    ('(', [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02, 0x00, 0x00]),
+    (')', [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08, 0x00, 0x00]),
+    ('*', [0x00, 0x04, 0x15, 0x0e, 0x15, 0x04, 0x00, 0x00, 0x00]),
+    ('+', [0x00, 0x04, 0x04, 0x1f, 0x04, 0x04, 0x00, 0x00, 0x00]),
+    (',', [0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x04, 0x08, 0x00]),
+    ('-', [0x00, 0x00, 0x00, 0x1f, 0x00, 0x00, 0x00, 0x00, 0x00]),
+    ('.', [0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x0c, 0x00, 0x00]),
+    ('/', [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00, 0x00, 0x00]),
+    ('0', [0x0e, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0e, 0x00, 0x00]),
+    ('1', [0x04, 0x0c, 0x04, 0x04, 0x04, 0x04, 0x0e, 0x00, 0x00]),
+    ('2', [0x0e, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1f, 0x00, 0x00]),
+    ('3', [0x1f, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0e, 0x00, 0x00]),
+    ('4', [0x02, 0x06, 0x0a, 0x12, 0x1f, 0x02, 0x02, 0x00, 0x00]),
+    ('5', [0x1f, 0x10, 0x1e, 0x01, 0x01, 0x11, 0x0e, 0x00, 0x00]),
+    ('6', [0x06, 0x08, 0x10, 0x1e, 0x11, 0x11, 0x0e, 0x00, 0x00]),
+    ('7', [0x1f, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08, 0x00, 0x00]),
+    ('8', [0x0e, 0x11, 0x11, 0x0e, 0x11, 0x11, 0x0e, 0x00, 0x00]),
+    ('9', [0x0e, 0x11, 0x11, 0x0f, 0x01, 0x02, 0x0c, 0x00, 0x00]),
+    (':', [0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x0c, 0x00, 0x00, 0x00]),
+    (';', [0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x04, 0x08, 0x00, 0x00]),
+    ('<', [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02, 0x00, 0x00]),
+    ('=', [0x00, 0x00, 0x1f, 0x00, 0x1f, 0x00, 0x00, 0x00, 0x00]),
+    ('>', [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08, 0x00, 0x00]),
+    ('?', [0x0e, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04, 0x00, 0x00]),
+    ('@', [0x0e, 0x11, 0x01, 0x0d, 0x15, 0x15, 0x0e, 0x00, 0x00]),
+    ('A', [0x0e, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11, 0x00, 0x00]),
+    ('B', [0x1e, 0x11, 0x11, 0x1e, 0x11, 0x11, 0x1e, 0x00, 0x00]),
+    ('C', [0x0e, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0e, 0x00, 0x00]),
+    ('D', [0x1c, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1c, 0x00, 0x00]),
+    ('E', [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x1f, 0x00, 0x00]),
+    ('F', [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x10, 0x00, 0x00]),
+    ('G', [0x0e, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0f, 0x00, 0x00]),
+    ('H', [0x11, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11, 0x00, 0x00]),
+    ('I', [0x0e, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e, 0x00, 0x00]),
+    ('J', [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0c, 0x00, 0x00]),
+    ('K', [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11, 0x00, 0x00]),
+    ('L', [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1f, 0x00, 0x00]),
+    ('M', [0x11, 0x1b, 0x15, 0x15, 0x11, 0x11, 0x11, 0x00, 0x00]),
+    ('N', [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11, 0x00, 0x00]),
+    ('O', [0x0e, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e, 0x00, 0x00]),
+    ('P', [0x1e, 0x11, 0x11, 0x1e, 0x10, 0x10, 0x10, 0x00, 0x00]),
+    ('Q', [0x0e, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0d, 0x00, 0x00]),
+    ('R', [0x1e, 0x11, 0x11, 0x1e, 0x14, 0x12, 0x11, 0x00, 0x00]),
+    ('S', [0x0f, 0x10, 0x10, 0x0e, 0x01, 0x01, 0x1e, 0x00, 0x00]),
+    ('T', [0x1f, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x00]),
+    ('U', [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e, 0x00, 0x00]),
+    ('V', [0x11, 0x11, 0x11, 0x11, 0x11, 0x0a, 0x04, 0x00, 0x00]),
+    ('W', [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0a, 0x00, 0x00]),
+    ('X', [0x11, 0x11, 0x0a, 0x04, 0x0a, 0x11, 0x11, 0x00, 0x00]),
+    ('Y', [0x11, 0x11, 0x11, 0x0a, 0x04, 0x04, 0x04, 0x00, 0x00]),
+    ('Z', [0x1f, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1f, 0x00, 0x00]),
+    ('[', [0x0e, 0x08, 0x08, 0x08, 0x08, 0x08, 0x0e, 0x00, 0x00]),
+    ('\\', [0x00, 0x10, 0x08, 0x04, 0x02, 0x01, 0x00, 0x00, 0x00]),
+    (']', [0x0e, 0x02, 0x02, 0x02, 0x02, 0x02, 0x0e, 0x00, 0x00]),
+    ('^', [0x04, 0x0a, 0x11, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+    ('_', [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1f, 0x00, 0x00]),
+    ('`', [0x08, 0x04, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+    ('a', [0x00, 0x00, 0x0e, 0x01, 0x0f, 0x11, 0x0f, 0x00, 0x00]),
+    ('b', [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x1e, 0x00, 0x00]),
+    ('c', [0x00, 0x00, 0x0e, 0x10, 0x10, 0x11, 0x0e, 0x00, 0x00]),
+    ('d', [0x01, 0x01, 0x0d, 0x13, 0x11, 0x11, 0x0f, 0x00, 0x00]),
+    ('e', [0x00, 0x00, 0x0e, 0x11, 0x1f, 0x10, 0x0e, 0x00, 0x00]),
+    ('f', [0x06, 0x09, 0x08, 0x1c, 0x08, 0x08, 0x08, 0x00, 0x00]),
+    ('g', [0x00, 0x00, 0x0f, 0x11, 0x11, 0x11, 0x0f, 0x01, 0x0e]),
+    ('h', [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x11, 0x00, 0x00]),
+    ('i', [0x04, 0x00, 0x0c, 0x04, 0x04, 0x04, 0x0e, 0x00, 0x00]),
+    ('j', [0x02, 0x00, 0x06, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0c]),
+    ('k', [0x10, 0x10, 0x12, 0x14, 0x18, 0x14, 0x12, 0x00, 0x00]),
+    ('l', [0x0c, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e, 0x00, 0x00]),
+    ('m', [0x00, 0x00, 0x1a, 0x15, 0x15, 0x15, 0x15, 0x00, 0x00]),
+    ('n', [0x00, 0x00, 0x16, 0x19, 0x11, 0x11, 0x11, 0x00, 0x00]),
+    ('o', [0x00, 0x00, 0x0e, 0x11, 0x11, 0x11, 0x0e, 0x00, 0x00]),
+    ('p', [0x00, 0x00, 0x1e, 0x11, 0x11, 0x11, 0x1e, 0x10, 0x10]),
+    ('q', [0x00, 0x00, 0x0f, 0x11, 0x11, 0x11, 0x0f, 0x01, 0x01]),
+    ('r', [0x00, 0x00, 0x16, 0x19, 0x10, 0x10, 0x10, 0x00, 0x00]),
+    ('s', [0x00, 0x00, 0x0f, 0x10, 0x0e, 0x01, 0x1e, 0x00, 0x00]),
+    ('t', [0x08, 0x08, 0x1c, 0x08, 0x08, 0x09, 0x06, 0x00, 0x00]),
+    ('u', [0x00, 0x00, 0x11, 0x11, 0x11, 0x13, 0x0d, 0x00, 0x00]),
+    ('v', [0x00, 0x00, 0x11, 0x11, 0x11, 0x0a, 0x04, 0x00, 0x00]),
+    ('w', [0x00, 0x00, 0x11, 0x11, 0x15, 0x15, 0x0a, 0x00, 0x00]),
+    ('x', [0x00, 0x00, 0x11, 0x0a, 0x04, 0x0a, 0x11, 0x00, 0x00]),
+    ('y', [0x00, 0x00, 0x11, 0x11, 0x11, 0x11, 0x0f, 0x01, 0x0e]),
+    ('z', [0x00, 0x00, 0x1f, 0x02, 0x04, 0x08, 0x1f, 0x00, 0x00]),
+    ('{', [0x02, 0x04, 0x04, 0x08, 0x04, 0x04, 0x02, 0x00, 0x00]),
+    ('|', [0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x00]),
+    ('}', [0x08, 0x04, 0x04, 0x02, 0x04, 0x04, 0x08, 0x00, 0x00]),
+    ('~', [0x00, 0x00, 0x08, 0x15, 0x02, 0x00, 0x00, 0x00, 0x00]),
+    (
+        '\u{a0}',
+        [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    ),
+    ('¡', [0x04, 0x00, 0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x00]),
+    ('¢', [0x04, 0x0f, 0x14, 0x14, 0x14, 0x0f, 0x04, 0x00, 0x00]),
+    ('£', [0x06, 0x09, 0x08, 0x1c, 0x08, 0x09, 0x16, 0x00, 0x00]),
+    ('¤', [0x00, 0x11, 0x0e, 0x0a, 0x0e, 0x11, 0x00, 0x00, 0x00]),
+    ('¥', [0x11, 0x0a, 0x1f, 0x04, 0x1f, 0x04, 0x04, 0x00, 0x00]),
+    ('¦', [0x04, 0x04, 0x04, 0x00, 0x04, 0x04, 0x04, 0x00, 0x00]),
+    ('§', [0x0e, 0x10, 0x0c, 0x0a, 0x06, 0x01, 0x0e, 0x00, 0x00]),
+    ('¨', [0x0a, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+    ('©', [0x0e, 0x11, 0x17, 0x15, 0x17, 0x11, 0x0e, 0x00, 0x00]),
+    ('ª', [0x0e, 0x01, 0x0f, 0x11, 0x0f, 0x00, 0x1f, 0x00, 0x00]),
+    ('«', [0x00, 0x05, 0x0a, 0x14, 0x0a, 0x05, 0x00, 0x00, 0x00]),
+    ('¬', [0x00, 0x00, 0x1f, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00]),
+    (
+        '\u{ad}',
+        [0x00, 0x00, 0x00, 0x0e, 0x00, 0x00, 0x00, 0x00, 0x00],
+    ),
+    ('®', [0x0e, 0x11, 0x19, 0x15, 0x19, 0x15, 0x0e, 0x00, 0x00]),
+    ('¯', [0x1f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+    ('°', [0x0c, 0x12, 0x12, 0x0c, 0x00, 0x00, 0x00, 0x00, 0x00]),
+    ('±', [0x04, 0x04, 0x1f, 0x04, 0x04, 0x00, 0x1f, 0x00, 0x00]),
+    ('²', [0x0c, 0x12, 0x04, 0x08, 0x1e, 0x00, 0x00, 0x00, 0x00]),
+    ('³', [0x1c, 0x02, 0x0c, 0x02, 0x1c, 0x00, 0x00, 0x00, 0x00]),
+    ('´', [0x02, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+    ('µ', [0x00, 0x00, 0x11, 0x11, 0x11, 0x13, 0x1d, 0x10, 0x10]),
+    ('¶', [0x0f, 0x1d, 0x1d, 0x0d, 0x05, 0x05, 0x05, 0x00, 0x00]),
+    ('·', [0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00]),
+    ('¸', [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x0c]),
+    ('¹', [0x08, 0x18, 0x08, 0x08, 0x1c, 0x00, 0x00, 0x00, 0x00]),
+    ('º', [0x0e, 0x11, 0x11, 0x0e, 0x00, 0x1f, 0x00, 0x00, 0x00]),
+    ('»', [0x00, 0x14, 0x0a, 0x05, 0x0a, 0x14, 0x00, 0x00, 0x00]),
+    ('¼', [0x10, 0x11, 0x12, 0x04, 0x0a, 0x17, 0x02, 0x00, 0x00]),
+    ('½', [0x10, 0x11, 0x12, 0x04, 0x0b, 0x11, 0x03, 0x00, 0x00]),
+    ('¾', [0x18, 0x09, 0x1a, 0x04, 0x0a, 0x17, 0x02, 0x00, 0x00]),
+    ('¿', [0x04, 0x00, 0x04, 0x08, 0x10, 0x11, 0x0e, 0x00, 0x00]),
+    ('Æ', [0x0f, 0x14, 0x14, 0x1f, 0x14, 0x14, 0x17, 0x00, 0x00]),
+    ('Ð', [0x1c, 0x12, 0x11, 0x1d, 0x11, 0x12, 0x1c, 0x00, 0x00]),
+    ('×', [0x00, 0x11, 0x0a, 0x04, 0x0a, 0x11, 0x00, 0x00, 0x00]),
+    ('Ø', [0x0e, 0x13, 0x15, 0x15, 0x15, 0x19, 0x0e, 0x00, 0x00]),
+    ('Þ', [0x10, 0x1e, 0x11, 0x11, 0x1e, 0x10, 0x10, 0x00, 0x00]),
+    ('ß', [0x0c, 0x12, 0x12, 0x14, 0x12, 0x12, 0x16, 0x00, 0x00]),
+    ('æ', [0x00, 0x00, 0x1a, 0x05, 0x0f, 0x14, 0x0b, 0x00, 0x00]),
+    ('ð', [0x0a, 0x04, 0x0a, 0x01, 0x0f, 0x11, 0x0e, 0x00, 0x00]),
+    ('÷', [0x00, 0x04, 0x00, 0x1f, 0x00, 0x04, 0x00, 0x00, 0x00]),
+    ('ø', [0x00, 0x00, 0x0e, 0x13, 0x15, 0x19, 0x0e, 0x00, 0x00]),
+    ('þ', [0x10, 0x10, 0x1e, 0x11, 0x11, 0x11, 0x1e, 0x10, 0x10]),
+    ('ı', [0x00, 0x00, 0x0c, 0x04, 0x04, 0x04, 0x0e, 0x00, 0x00]),
+];
+
+/// Marks of composed letters
+#[derive(Clone, Copy)]
+enum Mark {
+    Grave,
+    Acute,
+    Circumflex,
+    Tilde,
+    Diaeresis,
+    Ring,
+    Cedilla,
+}
+
+impl Mark {
+    /// Rows of the mark, the third row is only drawn on small letters
+    fn rows(self) -> [u8; 3] {
+        match self {
+            Mark::Grave => [0x08, 0x04, 0x00],
+            Mark::Acute => [0x02, 0x04, 0x00],
+            Mark::Circumflex => [0x04, 0x0a, 0x00],
+            Mark::Tilde => [0x0d, 0x16, 0x00],
+            Mark::Diaeresis => [0x0a, 0x00, 0x00],
+            Mark::Ring => [0x0e, 0x0a, 0x0e],
+            Mark::Cedilla => [0x04, 0x0c, 0x00],
+        }
+    }
+}
+
+/// Accented letters of Latin-1, small i uses the dotless i
+const COMPOSED: [(char, char, Mark); 53] = [
+    ('À', 'A', Mark::Grave),
+    ('Á', 'A', Mark::Acute),
+    ('Â', 'A', Mark::Circumflex),
+    ('Ã', 'A', Mark::Tilde),
+    ('Ä', 'A', Mark::Diaeresis),
+    ('Å', 'A', Mark::Ring),
+    ('Ç', 'C', Mark::Cedilla),
+    ('È', 'E', Mark::Grave),
+    ('É', 'E', Mark::Acute),
+    ('Ê', 'E', Mark::Circumflex),
+    ('Ë', 'E', Mark::Diaeresis),
+    ('Ì', 'I', Mark::Grave),
+    ('Í', 'I', Mark::Acute),
+    ('Î', 'I', Mark::Circumflex),
+    ('Ï', 'I', Mark::Diaeresis),
+    ('Ñ', 'N', Mark::Tilde),
+    ('Ò', 'O', Mark::Grave),
+    ('Ó', 'O', Mark::Acute),
+    ('Ô', 'O', Mark::Circumflex),
+    ('Õ', 'O', Mark::Tilde),
+    ('Ö', 'O', Mark::Diaeresis),
+    ('Ù', 'U', Mark::Grave),
+    ('Ú', 'U', Mark::Acute),
+    ('Û', 'U', Mark::Circumflex),
+    ('Ü', 'U', Mark::Diaeresis),
+    ('Ý', 'Y', Mark::Acute),
+    ('à', 'a', Mark::Grave),
+    ('á', 'a', Mark::Acute),
+    ('â', 'a', Mark::Circumflex),
+    ('ã', 'a', Mark::Tilde),
+    ('ä', 'a', Mark::Diaeresis),
+    ('å', 'a', Mark::Ring),
+    ('ç', 'c', Mark::Cedilla),
+    ('è', 'e', Mark::Grave),
+    ('é', 'e', Mark::Acute),
+    ('ê', 'e', Mark::Circumflex),
+    ('ë', 'e', Mark::Diaeresis),
+    ('ì', 'ı', Mark::Grave),
+    ('í', 'ı', Mark::Acute),
+    ('î', 'ı', Mark::Circumflex),
+    ('ï', 'ı', Mark::Diaeresis),
+    ('ñ', 'n', Mark::Tilde),
+    ('ò', 'o', Mark::Grave),
+    ('ó', 'o', Mark::Acute),
+    ('ô', 'o', Mark::Circumflex),
+    ('õ', 'o', Mark::Tilde),
+    ('ö', 'o', Mark::Diaeresis),
+    ('ù', 'u', Mark::Grave),
+    ('ú', 'u', Mark::Acute),
+    ('û', 'u', Mark::Circumflex),
+    ('ü', 'u', Mark::Diaeresis),
+    ('ý', 'y', Mark::Acute),
+    ('ÿ', 'y', Mark::Diaeresis),
+];
+
+/// Rows of the whole cell of a glyph
+fn cell(rows: &[u8; 9]) -> [u8; HEIGHT] {
+    let mut cell = [0; HEIGHT];
+    cell[2..].copy_from_slice(rows);
+
+    cell
+}
+
+fn glyph(cell: &[u8; HEIGHT]) -> BitmapGlyph {
+    let bits = cell
+        .iter()
+        .flat_map(|row| (0..WIDTH).map(move |x| (row >> (WIDTH - 1 - x)) & 1))
+        .collect();
+
+    BitmapGlyph {
+        width: WIDTH,
+        height: HEIGHT,
+        left: 0,
+        top: ASCENT,
+        advance: ADVANCE,
+        bits,
+    }
+}
+
+pub(super) fn font() -> BitmapFont {
+    let mut font = BitmapFont::new(ASCENT, DESCENT);
+    for (c, rows) in GLYPHS.iter() {
+        font.insert(*c, glyph(&cell(rows)));
+    }
+
+    for &(c, base, mark) in COMPOSED.iter() {
+        let rows = match GLYPHS.iter().find(|g| g.0 == base) {
+            Some(g) => &g.1,
+            None => continue,
+        };
+
+        let mut cell = cell(rows);
+        let marks = mark.rows();
+
+        // Capital marks use the reserved rows, small marks the ascender rows
+        let (start, count) = match mark {
+            Mark::Cedilla => (HEIGHT - 2, 2),
+            _ if base.is_uppercase() => (0, 2),
+            _ => (1, 3),
+        };
+
+        for (row, bits) in cell[start..start + count].iter_mut().zip(marks.iter()) {
+            *row |= bits;
+        }
+
+        font.insert(c, glyph(&cell));
+    }
+
+    font.set_default('?');
+    font
+}
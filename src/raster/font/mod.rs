@@ -0,0 +1,297 @@
+//! Bitmap fonts for the rasterizer
+//!
+//! BitmapFont is both a GlyphSource for a Framebuffer and a TextMeasure for a Root,
+//! so the text is measured with the same pixels that are drawn. The embedded font
+//! doesn't need external files, BDF and PSF fonts can be loaded too
+//!
+//! Bitmap fonts are only scaled by integer factors, the factor is the size of the
+//! text divided by the height of the font, at least one
+
+mod bdf;
+mod builtin;
+mod psf;
+
+use super::{Glyph, GlyphSource};
+use crate::math::Scalar;
+use crate::paint::FontID;
+use crate::text::TextMeasure;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+/// Error of a font that couldn't be loaded
+#[derive(Debug)]
+pub struct FontError {
+    pub message: &'static str,
+}
+
+impl FontError {
+    pub(crate) fn new(message: &'static str) -> Self {
+        FontError { message }
+    }
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid font: {}", self.message)
+    }
+}
+
+impl Error for FontError {}
+
+/// Glyph of a bitmap font, in pixels of the font
+#[derive(Clone)]
+pub struct BitmapGlyph {
+    pub width: usize,
+    pub height: usize,
+    /// Horizontal distance from the pen to the left of the bitmap
+    pub left: i32,
+    /// Vertical distance from the baseline to the top of the bitmap
+    pub top: i32,
+    /// Horizontal distance to the next pen position
+    pub advance: i32,
+    /// One byte per pixel, zero is empty
+    pub bits: Vec<u8>,
+}
+
+/// Font of bitmap glyphs
+#[derive(Clone)]
+pub struct BitmapFont {
+    glyphs: HashMap<char, BitmapGlyph>,
+    ascent: i32,
+    descent: i32,
+    /// Glyph used for the characters that the font doesn't have
+    default: Option<char>,
+}
+
+impl BitmapFont {
+    /// Creates an empty font with the distances of the baseline to the top and
+    /// the bottom of a line
+    pub fn new(ascent: i32, descent: i32) -> Self {
+        BitmapFont {
+            glyphs: HashMap::new(),
+            ascent,
+            descent,
+            default: None,
+        }
+    }
+
+    /// Embedded 5x7 font with ASCII and Latin-1, a line has 11 pixels of height
+    pub fn builtin() -> Self {
+        builtin::font()
+    }
+
+    /// Load a font of the Glyph Bitmap Distribution Format
+    pub fn from_bdf(data: &[u8]) -> Result<Self, FontError> {
+        bdf::parse(data)
+    }
+
+    /// Load a PC Screen Font, version 1 or 2
+    ///
+    /// PSF doesn't have a baseline, the descent is a quarter of the height. Fonts
+    /// without unicode table map their glyphs to the codepoints of their indices
+    pub fn from_psf(data: &[u8]) -> Result<Self, FontError> {
+        psf::parse(data)
+    }
+
+    /// Add or replace the glyph of a character
+    pub fn insert(&mut self, c: char, glyph: BitmapGlyph) {
+        self.glyphs.insert(c, glyph);
+    }
+
+    /// Set the character drawn instead of the characters that the font doesn't have
+    pub fn set_default(&mut self, c: char) {
+        self.default = Some(c);
+    }
+
+    /// Glyph of a character, control characters don't have glyphs
+    pub fn get(&self, c: char) -> Option<&BitmapGlyph> {
+        if c.is_control() {
+            return None;
+        }
+
+        self.glyphs
+            .get(&c)
+            .or_else(|| self.glyphs.get(&self.default?))
+    }
+
+    /// Count of glyphs
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty()
+    }
+
+    /// Distance from the baseline to the top of a line, in pixels of the font
+    #[inline]
+    pub fn ascent(&self) -> i32 {
+        self.ascent
+    }
+
+    /// Distance from the baseline to the bottom of a line, in pixels of the font
+    #[inline]
+    pub fn descent(&self) -> i32 {
+        self.descent
+    }
+
+    /// Height of a line, in pixels of the font
+    #[inline]
+    pub fn height(&self) -> i32 {
+        self.ascent + self.descent
+    }
+
+    /// Integer scale used for draw text at a size
+    pub fn scale(&self, size: f32) -> i32 {
+        let height = self.height().max(1) as f32;
+        ((size / height).round() as i32).max(1)
+    }
+
+    /// Advance of a text at a scale, in pixels
+    fn advance_scaled(&self, scale: i32, text: &str) -> i32 {
+        text.chars()
+            .filter_map(|c| self.get(c))
+            .map(|g| g.advance * scale)
+            .sum()
+    }
+}
+
+impl GlyphSource for BitmapFont {
+    fn glyph(&self, c: char, size: f32) -> Option<Glyph> {
+        let glyph = self.get(c)?;
+        let scale = self.scale(size) as usize;
+        let width = glyph.width * scale;
+
+        // Nearest neighbour upscaling
+        let mut mask = Vec::with_capacity(width * glyph.height * scale);
+        for row in glyph.bits.chunks_exact(glyph.width.max(1)) {
+            let start = mask.len();
+            for &bit in row {
+                let value = if bit != 0 { 255 } else { 0 };
+                mask.extend(std::iter::repeat(value).take(scale));
+            }
+
+            for _ in 1..scale {
+                mask.extend_from_within(start..start + width);
+            }
+        }
+
+        Some(Glyph {
+            width,
+            height: glyph.height * scale,
+            left: glyph.left * scale as i32,
+            top: glyph.top * scale as i32,
+            advance: (glyph.advance * scale as i32) as f32,
+            mask,
+        })
+    }
+}
+
+/// Measure in pixels, the FontID is ignored
+impl<T: Scalar> TextMeasure<T> for BitmapFont {
+    fn advance(&self, _: FontID, size: T, text: &str) -> T {
+        let scale = self.scale(size.to_f32());
+        T::from_f32(self.advance_scaled(scale, text) as f32)
+    }
+
+    fn ascent(&self, _: FontID, size: T) -> T {
+        T::from_f32((self.ascent * self.scale(size.to_f32())) as f32)
+    }
+
+    fn descent(&self, _: FontID, size: T) -> T {
+        T::from_f32((self.descent * self.scale(size.to_f32())) as f32)
+    }
+
+    fn glyph_positions(&self, _: FontID, size: T, text: &str, positions: &mut Vec<T>) {
+        let scale = self.scale(size.to_f32());
+        let mut pen = 0;
+
+        positions.clear();
+        for c in text.chars() {
+            positions.push(T::from_f32(pen as f32));
+            if let Some(glyph) = self.get(c) {
+                pen += glyph.advance * scale;
+            }
+        }
+
+        positions.push(T::from_f32(pen as f32));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Font with a glyph of 2x2 pixels for 'a' and a line of 4 pixels
+    fn font() -> BitmapFont {
+        let mut font = BitmapFont::new(3, 1);
+        font.insert(
+            'a',
+            BitmapGlyph {
+                width: 2,
+                height: 2,
+                left: 0,
+                top: 2,
+                advance: 3,
+                bits: vec![1, 0, 0, 1],
+            },
+        );
+
+        font
+    }
+
+    #[test]
+    fn scale() {
+        let font = font();
+        assert_eq!(
+            (font.scale(1.0), font.scale(4.0), font.scale(9.0)),
+            (1, 1, 2)
+        );
+
+        // The mask is upscaled by the nearest pixel
+        let glyph = font.glyph('a', 8.0).unwrap();
+        assert_eq!(
+            (glyph.width, glyph.height, glyph.top, glyph.advance),
+            (4, 4, 4, 6.0)
+        );
+        assert_eq!(
+            glyph.mask,
+            [255, 255, 0, 0, 255, 255, 0, 0, 0, 0, 255, 255, 0, 0, 255, 255]
+        );
+    }
+
+    #[test]
+    fn measure() {
+        let mut font = font();
+        let measure: &dyn TextMeasure<f32> = &font;
+        assert_eq!(measure.advance(0, 8.0, "aba"), 12.0);
+        assert_eq!(
+            (measure.ascent(0, 8.0), measure.descent(0, 8.0)),
+            (6.0, 2.0)
+        );
+
+        let mut positions = Vec::new();
+        measure.glyph_positions(0, 4.0, "ab\na", &mut positions);
+        assert_eq!(positions, [0.0, 3.0, 3.0, 3.0, 6.0]);
+
+        // The default glyph is drawn for the missing chars but not for controls
+        font.set_default('a');
+        assert!(font.get('b').is_some());
+        assert!(font.get('\n').is_none());
+    }
+
+    #[test]
+    fn builtin() {
+        let font = BitmapFont::builtin();
+        assert_eq!(font.height(), 11);
+        assert!(font.get('A').is_some() && font.get('é').is_some());
+        assert_eq!(
+            font.get('\u{4e00}').map(|g| g.advance),
+            font.get('?').map(|g| g.advance)
+        );
+    }
+}
@@ -0,0 +1,215 @@
+//! PC Screen Font loader, version 1 and 2
+
+use super::{BitmapFont, BitmapGlyph, FontError};
+
+const PSF1_MAGIC: [u8; 2] = [0x36, 0x04];
+const PSF2_MAGIC: [u8; 4] = [0x72, 0xb5, 0x4a, 0x86];
+
+// PSF1 modes
+const PSF1_MODE512: u8 = 0x01;
+const PSF1_MODEHASTAB: u8 = 0x02;
+const PSF1_MODESEQ: u8 = 0x04;
+// PSF2 flags
+const PSF2_HAS_UNICODE_TABLE: u32 = 0x01;
+
+/// Glyphs of a PSF file before mapping them to characters
+struct Glyphs<'a> {
+    width: usize,
+    height: usize,
+    count: usize,
+    /// Bytes of a glyph, rows are padded to whole bytes
+    size: usize,
+    data: &'a [u8],
+}
+
+impl Glyphs<'_> {
+    fn glyph(&self, index: usize) -> BitmapGlyph {
+        let (width, height) = (self.width, self.height);
+        let stride = width.div_ceil(8);
+        let data = &self.data[index * self.size..];
+
+        let mut bits = vec![0; width * height];
+        for y in 0..height {
+            for x in 0..width {
+                bits[y * width + x] = (data[y * stride + x / 8] >> (7 - x % 8)) & 1;
+            }
+        }
+
+        // PSF doesn't have a baseline
+        let descent = height as i32 / 4;
+        BitmapGlyph {
+            width,
+            height,
+            left: 0,
+            top: height as i32 - descent,
+            advance: width as i32,
+            bits,
+        }
+    }
+}
+
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+/// Characters of every glyph from a unicode table, sequences are skipped
+fn unicode_table(table: &[u8], count: usize, utf16: bool) -> Vec<Vec<char>> {
+    let mut chars = vec![Vec::new(); count];
+    let mut index = 0;
+    let mut sequence = false;
+
+    if utf16 {
+        for pair in table.chunks_exact(2) {
+            if index >= count {
+                break;
+            }
+
+            match u16::from_le_bytes([pair[0], pair[1]]) {
+                0xffff => {
+                    index += 1;
+                    sequence = false;
+                }
+                0xfffe => sequence = true,
+                n if !sequence => chars[index].extend(char::from_u32(n as u32)),
+                _ => {}
+            }
+        }
+    } else {
+        for entry in table.split(|&b| b == 0xff) {
+            if index >= count {
+                break;
+            }
+
+            // Sequences start after 0xfe
+            let single = entry.split(|&b| b == 0xfe).next().unwrap_or(&[]);
+            if let Ok(text) = std::str::from_utf8(single) {
+                chars[index].extend(text.chars());
+            }
+
+            index += 1;
+        }
+    }
+
+    chars
+}
+
+pub(super) fn parse(data: &[u8]) -> Result<BitmapFont, FontError> {
+    let (glyphs, table, utf16) = if data.starts_with(&PSF1_MAGIC) && data.len() >= 4 {
+        let (mode, height) = (data[2], data[3] as usize);
+        let count = if mode & PSF1_MODE512 != 0 { 512 } else { 256 };
+        let has_table = mode & (PSF1_MODEHASTAB | PSF1_MODESEQ) != 0;
+
+        let glyphs = Glyphs {
+            width: 8,
+            height,
+            count,
+            size: height,
+            data: &data[4..],
+        };
+
+        (glyphs, has_table, true)
+    } else if data.starts_with(&PSF2_MAGIC) && data.len() >= 32 {
+        let header = u32_at(data, 8) as usize;
+        let flags = u32_at(data, 12);
+        let count = u32_at(data, 16) as usize;
+        let size = u32_at(data, 20) as usize;
+        let height = u32_at(data, 24) as usize;
+        let width = u32_at(data, 28) as usize;
+
+        // Bytes of the rows of a glyph, a huge width or height can overflow
+        let rows = width.div_ceil(8).checked_mul(height);
+        if header > data.len() || rows.map_or(true, |rows| size < rows) {
+            return Err(FontError::new("bad header"));
+        }
+
+        let glyphs = Glyphs {
+            width,
+            height,
+            count,
+            size,
+            data: &data[header..],
+        };
+
+        (glyphs, flags & PSF2_HAS_UNICODE_TABLE != 0, false)
+    } else {
+        return Err(FontError::new("bad magic"));
+    };
+
+    let length = glyphs.count.checked_mul(glyphs.size);
+    let length = match length {
+        Some(length) if length <= glyphs.data.len() => length,
+        _ => return Err(FontError::new("truncated glyphs")),
+    };
+
+    let chars = if table {
+        unicode_table(&glyphs.data[length..], glyphs.count, utf16)
+    } else {
+        (0..glyphs.count as u32)
+            .map(|n| char::from_u32(n).into_iter().collect())
+            .collect()
+    };
+
+    let height = glyphs.height as i32;
+    let mut font = BitmapFont::new(height - height / 4, height / 4);
+    for (index, chars) in chars.iter().enumerate() {
+        for &c in chars {
+            font.insert(c, glyphs.glyph(index));
+        }
+    }
+
+    if font.get('?').is_some() {
+        font.set_default('?');
+    }
+
+    Ok(font)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn psf2(count: u32, size: u32, height: u32, width: u32, glyphs: &[u8]) -> Vec<u8> {
+        let mut data = PSF2_MAGIC.to_vec();
+        for value in [0, 32, 0, count, size, height, width] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+
+        data.extend_from_slice(glyphs);
+        data
+    }
+
+    fn error(data: &[u8]) -> &'static str {
+        match parse(data) {
+            Ok(_) => panic!("the font was parsed"),
+            Err(err) => err.message,
+        }
+    }
+
+    #[test]
+    fn glyphs() {
+        let mut glyphs = vec![0; 66 * 2];
+        glyphs[65 * 2] = 0b1000_0001;
+
+        let font = parse(&psf2(66, 2, 2, 8, &glyphs)).ok().unwrap();
+        let glyph = font.get('A').unwrap();
+        assert_eq!((glyph.width, glyph.height), (8, 2));
+        assert_eq!(&glyph.bits[..8], [1, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(font.len(), 66);
+    }
+
+    #[test]
+    fn bad_headers() {
+        assert_eq!(error(&psf2(1, 1, 2, 8, &[0; 2])), "bad header");
+        assert_eq!(
+            error(&psf2(1, 2, u32::MAX, u32::MAX, &[0; 2])),
+            "bad header"
+        );
+        assert_eq!(error(&psf2(2, 2, 2, 8, &[0; 2])), "truncated glyphs");
+        assert_eq!(error(&[0; 32]), "bad magic");
+    }
+}
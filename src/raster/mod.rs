@@ -0,0 +1,533 @@
+//! CPU Rasterizer for headless rendering
+//!
+//! Framebuffer is a CTX that implements Painter into a RGBA buffer, useful for
+//! screenshots, tests and platforms without a GPU. Shapes are anti-aliased with
+//! exact horizontal coverage and vertical subsampling. Fonts and images are
+//! registered in the Framebuffer and referenced by their FontID and ImageID
+
+pub mod font;
+mod path;
+pub mod snapshot;
+
+use crate::clip::{Clip, ClipStack};
+use crate::math::Scalar;
+use crate::paint::{flatten, Color, FontID, ImageID, Painter, PathCommand, Point};
+use crate::png;
+use crate::widget::{Boundaries, Position};
+
+use self::path::Edges;
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Alpha mask of a glyph
+pub struct Glyph {
+    pub width: usize,
+    pub height: usize,
+    /// Horizontal distance from the pen to the left of the mask
+    pub left: i32,
+    /// Vertical distance from the baseline to the top of the mask
+    pub top: i32,
+    /// Horizontal distance to the next pen position
+    pub advance: f32,
+    /// Coverage values, one byte per pixel
+    pub mask: Vec<u8>,
+}
+
+/// Glyph provider of a font registered in a Framebuffer
+pub trait GlyphSource {
+    /// Rasterize a character at a pixel size, None if the font doesn't have it
+    fn glyph(&self, c: char, size: f32) -> Option<Glyph>;
+}
+
+/// RGBA image with 8 bits per channel, not premultiplied
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    /// Creates an image from RGBA pixels
+    pub fn new(width: usize, height: usize, pixels: Vec<u8>) -> Self {
+        assert_eq!(pixels.len(), width * height * 4, "invalid RGBA buffer size");
+
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    #[inline]
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    #[inline]
+    fn pixel(&self, x: usize, y: usize) -> Color {
+        let i = (y * self.width + x) * 4;
+        let p = &self.pixels[i..i + 4];
+
+        Color::rgba(p[0], p[1], p[2], p[3])
+    }
+}
+
+/// RGBA Framebuffer CTX
+pub struct Framebuffer {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+    clip: ClipStack<f32>,
+    // Registered resources
+    images: Vec<Image>,
+    fonts: Vec<Box<dyn GlyphSource>>,
+    // Scanline buffers
+    cov: Vec<f32>,
+    crossings: Vec<(f32, i32)>,
+}
+
+/// Length of the overlap of a pixel with a range
+#[inline]
+fn overlap(p: i32, a: f32, b: f32) -> f32 {
+    let p = p as f32;
+    ((p + 1.0).min(b) - p.max(a)).max(0.0)
+}
+
+#[inline]
+fn rect<T: Scalar>(bounds: Boundaries<T>) -> (f32, f32, f32, f32) {
+    let (x, y) = (bounds.0.to_f32(), bounds.1.to_f32());
+    (x, y, x + bounds.2.to_f32(), y + bounds.3.to_f32())
+}
+
+impl Framebuffer {
+    /// Creates a transparent Framebuffer
+    pub fn new(width: usize, height: usize) -> Self {
+        Framebuffer {
+            width,
+            height,
+            pixels: vec![0; width * height * 4],
+            clip: ClipStack::new(),
+            images: Vec::new(),
+            fonts: Vec::new(),
+            cov: Vec::new(),
+            crossings: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Get RGBA pixels, rows are from top to bottom
+    #[inline]
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Get a pixel color, None if it's outside
+    pub fn pixel(&self, x: usize, y: usize) -> Option<Color> {
+        if x < self.width && y < self.height {
+            let i = (y * self.width + x) * 4;
+            let p = &self.pixels[i..i + 4];
+
+            Some(Color::rgba(p[0], p[1], p[2], p[3]))
+        } else {
+            None
+        }
+    }
+
+    /// Resize the framebuffer, the pixels are cleared
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+        self.pixels = vec![0; width * height * 4];
+    }
+
+    /// Fill every pixel with a color, ignoring the clip
+    pub fn clear(&mut self, color: Color) {
+        for p in self.pixels.chunks_exact_mut(4) {
+            p.copy_from_slice(&[color.r, color.g, color.b, color.a]);
+        }
+    }
+
+    /// Register an image for draw it with Painter::image
+    pub fn add_image(&mut self, image: Image) -> ImageID {
+        self.images.push(image);
+        (self.images.len() - 1) as ImageID
+    }
+
+    /// Register a font for draw text with Painter::text
+    pub fn add_font(&mut self, font: Box<dyn GlyphSource>) -> FontID {
+        self.fonts.push(font);
+        (self.fonts.len() - 1) as FontID
+    }
+
+    /// Encode the pixels as a PNG file
+    pub fn to_png(&self) -> Vec<u8> {
+        png::encode(self.width, self.height, &self.pixels)
+    }
+
+    /// Encode the pixels as a binary PPM file, alpha is discarded
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut out = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        for p in self.pixels.chunks_exact(4) {
+            out.extend_from_slice(&p[..3]);
+        }
+
+        out
+    }
+
+    /// Write the pixels to a PNG file
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_png())
+    }
+
+    /// Write the pixels to a PPM file
+    pub fn save_ppm<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_ppm())
+    }
+
+    /// Visible area as (x1, y1, x2, y2) in floating point
+    fn visible(&self) -> (f32, f32, f32, f32) {
+        let (w, h) = (self.width as f32, self.height as f32);
+        match self.clip.clip() {
+            Some(c) => (
+                c.0.max(0.0),
+                c.1.max(0.0),
+                (c.0 + c.2).min(w),
+                (c.1 + c.3).min(h),
+            ),
+            None => (0.0, 0.0, w, h),
+        }
+    }
+
+    /// Pixel range that touches an area and the visible area
+    fn pixel_range(&self, area: (f32, f32, f32, f32)) -> Option<(i32, i32, i32, i32)> {
+        let v = self.visible();
+        let x1 = area.0.max(v.0).floor() as i32;
+        let y1 = area.1.max(v.1).floor() as i32;
+        let x2 = area.2.min(v.2).ceil() as i32;
+        let y2 = area.3.min(v.3).ceil() as i32;
+
+        if x2 > x1 && y2 > y1 {
+            Some((x1, y1, x2, y2))
+        } else {
+            None
+        }
+    }
+
+    /// Blend a color over a pixel with a coverage, the clip is applied here
+    fn blend(&mut self, x: i32, y: i32, color: Color, coverage: f32) {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return;
+        }
+
+        let v = self.visible();
+        let coverage = coverage.min(1.0) * overlap(x, v.0, v.2) * overlap(y, v.1, v.3);
+        let sa = color.a as f32 / 255.0 * coverage;
+        if sa <= 0.0 {
+            return;
+        }
+
+        let i = (y as usize * self.width + x as usize) * 4;
+        let p = &mut self.pixels[i..i + 4];
+
+        let da = p[3] as f32 / 255.0 * (1.0 - sa);
+        let oa = sa + da;
+        let mix = |s: u8, d: u8| ((s as f32 * sa + d as f32 * da) / oa).round() as u8;
+
+        p[0] = mix(color.r, p[0]);
+        p[1] = mix(color.g, p[1]);
+        p[2] = mix(color.b, p[2]);
+        p[3] = (oa * 255.0).round() as u8;
+    }
+
+    /// Fill a rectangle in floating point with exact coverage
+    fn fill_area(&mut self, area: (f32, f32, f32, f32), color: Color) {
+        if let Some((x1, y1, x2, y2)) = self.pixel_range(area) {
+            for y in y1..y2 {
+                let cy = overlap(y, area.1, area.3);
+                for x in x1..x2 {
+                    self.blend(x, y, color, cy * overlap(x, area.0, area.2));
+                }
+            }
+        }
+    }
+
+    /// Fill edges with the nonzero winding rule
+    fn fill_edges(&mut self, edges: &Edges, color: Color) {
+        if edges.is_empty() {
+            return;
+        }
+
+        if let Some((x1, y1, x2, y2)) = self.pixel_range(edges.bounds()) {
+            let width = (x2 - x1) as usize;
+            let mut cov = std::mem::take(&mut self.cov);
+            let mut crossings = std::mem::take(&mut self.crossings);
+            cov.resize(width, 0.0);
+
+            for y in y1..y2 {
+                cov[..width].iter_mut().for_each(|c| *c = 0.0);
+                edges.coverage(x1, y, &mut cov[..width], &mut crossings);
+
+                for (n, &c) in cov[..width].iter().enumerate() {
+                    if c > 0.0 {
+                        self.blend(x1 + n as i32, y, color, c);
+                    }
+                }
+            }
+
+            self.cov = cov;
+            self.crossings = crossings;
+        }
+    }
+
+    /// Draw an alpha mask with the top left corner at x and y
+    pub fn mask(&mut self, x: i32, y: i32, width: usize, mask: &[u8], color: Color) {
+        for (n, &alpha) in mask.iter().enumerate() {
+            if alpha > 0 {
+                let (mx, my) = ((n % width) as i32, (n / width) as i32);
+                self.blend(x + mx, y + my, color, alpha as f32 / 255.0);
+            }
+        }
+    }
+}
+
+impl<T: Scalar> Clip<T> for Framebuffer {
+    fn push_clip(&mut self, bounds: Boundaries<T>) {
+        self.clip.push_clip((
+            bounds.0.to_f32(),
+            bounds.1.to_f32(),
+            bounds.2.to_f32(),
+            bounds.3.to_f32(),
+        ));
+    }
+
+    fn pop_clip(&mut self) {
+        self.clip.pop_clip();
+    }
+
+    fn clip(&self) -> Option<Boundaries<T>> {
+        self.clip.clip().map(|c| {
+            (
+                T::from_f32(c.0),
+                T::from_f32(c.1),
+                T::from_f32(c.2),
+                T::from_f32(c.3),
+            )
+        })
+    }
+}
+
+impl<T: Scalar> Painter<T> for Framebuffer {
+    fn fill_rect(&mut self, bounds: Boundaries<T>, color: Color) {
+        self.fill_area(rect(bounds), color);
+    }
+
+    fn stroke_rect(&mut self, bounds: Boundaries<T>, width: T, color: Color) {
+        let (x1, y1, x2, y2) = rect(bounds);
+        let w = width.to_f32().min((x2 - x1) * 0.5).min((y2 - y1) * 0.5);
+        if w <= 0.0 {
+            return;
+        }
+
+        self.fill_area((x1, y1, x2, y1 + w), color);
+        self.fill_area((x1, y2 - w, x2, y2), color);
+        self.fill_area((x1, y1 + w, x1 + w, y2 - w), color);
+        self.fill_area((x2 - w, y1 + w, x2, y2 - w), color);
+    }
+
+    fn fill_round_rect(&mut self, bounds: Boundaries<T>, radius: T, color: Color) {
+        let (x1, y1, x2, y2) = rect(bounds);
+        let r = radius.to_f32().min((x2 - x1) * 0.5).min((y2 - y1) * 0.5);
+        if r <= 0.0 {
+            self.fill_area((x1, y1, x2, y2), color);
+            return;
+        }
+
+        // Corner centers, clockwise from top left, with their start angles
+        let corners = [
+            (x1 + r, y1 + r, 180.0f32),
+            (x2 - r, y1 + r, 270.0),
+            (x2 - r, y2 - r, 0.0),
+            (x1 + r, y2 - r, 90.0),
+        ];
+
+        let steps = ((r * 0.75).ceil() as usize).clamp(2, 32);
+        let mut points: Vec<Point> = Vec::with_capacity(4 * (steps + 1));
+        for &(cx, cy, start) in corners.iter() {
+            for i in 0..=steps {
+                let angle = (start + 90.0 * i as f32 / steps as f32).to_radians();
+                points.push((cx + angle.cos() * r, cy + angle.sin() * r));
+            }
+        }
+
+        let mut edges = Edges::new();
+        edges.polygon(&points);
+        self.fill_edges(&edges, color);
+    }
+
+    fn line(&mut self, from: Position<T>, to: Position<T>, width: T, color: Color) {
+        let mut edges = Edges::new();
+        path::segment(
+            &mut edges,
+            (from.0.to_f32(), from.1.to_f32()),
+            (to.0.to_f32(), to.1.to_f32()),
+            width.to_f32(),
+        );
+
+        self.fill_edges(&edges, color);
+    }
+
+    fn fill_path(&mut self, commands: &[PathCommand<T>], color: Color) {
+        let mut edges = Edges::new();
+        for line in flatten(commands) {
+            edges.polygon(&line.points);
+        }
+
+        self.fill_edges(&edges, color);
+    }
+
+    fn stroke_path(&mut self, commands: &[PathCommand<T>], width: T, color: Color) {
+        let mut edges = Edges::new();
+        path::stroke(&mut edges, &flatten(commands), width.to_f32());
+
+        self.fill_edges(&edges, color);
+    }
+
+    fn text(&mut self, pos: Position<T>, font: FontID, size: T, text: &str, color: Color) {
+        let (mut pen, baseline) = (pos.0.to_f32(), pos.1.to_f32().round() as i32);
+        let size = size.to_f32();
+
+        for c in text.chars() {
+            let glyph = match self.fonts.get(font as usize) {
+                Some(source) => source.glyph(c, size),
+                None => return,
+            };
+
+            if let Some(glyph) = glyph {
+                let x = pen.round() as i32 + glyph.left;
+                self.mask(x, baseline - glyph.top, glyph.width, &glyph.mask, color);
+
+                pen += glyph.advance;
+            }
+        }
+    }
+
+    fn image(&mut self, bounds: Boundaries<T>, image: ImageID) {
+        let area = rect(bounds);
+        let range = self.pixel_range(area);
+
+        if let (Some((x1, y1, x2, y2)), Some(img)) = (range, self.images.get(image as usize)) {
+            if img.width == 0 || img.height == 0 {
+                return;
+            }
+
+            // Nearest neighbour sampling, the borders use area coverage
+            let sx = img.width as f32 / (area.2 - area.0);
+            let sy = img.height as f32 / (area.3 - area.1);
+
+            let mut samples = Vec::with_capacity(((x2 - x1) * (y2 - y1)) as usize);
+            for y in y1..y2 {
+                let iy = ((y as f32 + 0.5 - area.1) * sy).max(0.0) as usize;
+                for x in x1..x2 {
+                    let ix = ((x as f32 + 0.5 - area.0) * sx).max(0.0) as usize;
+                    let coverage = overlap(x, area.0, area.2) * overlap(y, area.1, area.3);
+
+                    let color = img.pixel(ix.min(img.width - 1), iy.min(img.height - 1));
+                    samples.push((x, y, color, coverage));
+                }
+            }
+
+            for (x, y, color, coverage) in samples {
+                self.blend(x, y, color, coverage);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Color = Color::rgb(255, 0, 0);
+
+    #[test]
+    fn fill_coverage() {
+        let mut fb = Framebuffer::new(4, 2);
+        Painter::<f32>::fill_rect(&mut fb, (1.0, 0.0, 0.5, 2.0), RED);
+
+        assert_eq!(fb.pixel(0, 0), Some(Color::TRANSPARENT));
+        assert_eq!(fb.pixel(1, 1), Some(Color::rgba(255, 0, 0, 128)));
+        assert_eq!(fb.pixel(4, 0), None);
+
+        // Over an opaque pixel the color is mixed
+        fb.clear(Color::WHITE);
+        Painter::<f32>::fill_rect(&mut fb, (1.0, 0.0, 0.5, 2.0), RED);
+        assert_eq!(fb.pixel(1, 0), Some(Color::rgb(255, 128, 128)));
+    }
+
+    #[test]
+    fn clip() {
+        let mut fb = Framebuffer::new(4, 4);
+        Clip::<f32>::push_clip(&mut fb, (0.0, 0.0, 2.0, 4.0));
+        Painter::<f32>::fill_rect(&mut fb, (0.0, 0.0, 4.0, 4.0), RED);
+        Clip::<f32>::pop_clip(&mut fb);
+
+        assert_eq!(fb.pixel(1, 3), Some(RED));
+        assert_eq!(fb.pixel(2, 0), Some(Color::TRANSPARENT));
+    }
+
+    #[test]
+    fn path() {
+        use PathCommand::*;
+
+        // Both squares turn the same way, so the overlap isn't a hole
+        let square = |x: f32, y: f32| {
+            vec![
+                MoveTo((x, y)),
+                LineTo((x + 4.0, y)),
+                LineTo((x + 4.0, y + 4.0)),
+                LineTo((x, y + 4.0)),
+                Close,
+            ]
+        };
+        let mut commands = square(0.0, 0.0);
+        commands.extend(square(2.0, 2.0));
+
+        let mut fb = Framebuffer::new(8, 8);
+        fb.fill_path(&commands, RED);
+
+        assert_eq!(fb.pixel(0, 0), Some(RED));
+        assert_eq!(fb.pixel(3, 3), Some(RED));
+        assert_eq!(fb.pixel(5, 5), Some(RED));
+        assert_eq!(fb.pixel(5, 0), Some(Color::TRANSPARENT));
+        assert_eq!(fb.pixel(0, 5), Some(Color::TRANSPARENT));
+    }
+
+    #[test]
+    fn ppm() {
+        let mut fb = Framebuffer::new(2, 1);
+        fb.clear(RED);
+
+        assert_eq!(fb.to_ppm(), b"P6\n2 1\n255\n\xff\0\0\xff\0\0");
+    }
+}
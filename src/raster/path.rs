@@ -0,0 +1,212 @@
+//! Path flattening and scanline coverage
+
+use crate::paint::{segments, Polyline};
+
+/// Sub-scanlines per pixel row for vertical anti-aliasing
+const SUBSAMPLES: usize = 5;
+
+use crate::paint::Point;
+
+/// Polygon edge ordered from top to bottom
+pub struct Edge {
+    x0: f32,
+    y0: f32,
+    x1: f32,
+    y1: f32,
+    winding: i32,
+}
+
+/// A list of edges filled with the nonzero winding rule
+pub struct Edges {
+    edges: Vec<Edge>,
+    min: Point,
+    max: Point,
+}
+
+impl Edges {
+    pub fn new() -> Self {
+        Edges {
+            edges: Vec::new(),
+            min: (f32::MAX, f32::MAX),
+            max: (f32::MIN, f32::MIN),
+        }
+    }
+
+    pub fn line(&mut self, a: Point, b: Point) {
+        if a.1 == b.1 {
+            return;
+        }
+
+        let (top, bottom, winding) = if a.1 < b.1 { (a, b, 1) } else { (b, a, -1) };
+        self.edges.push(Edge {
+            x0: top.0,
+            y0: top.1,
+            x1: bottom.0,
+            y1: bottom.1,
+            winding,
+        });
+
+        self.min = (self.min.0.min(a.0.min(b.0)), self.min.1.min(top.1));
+        self.max = (self.max.0.max(a.0.max(b.0)), self.max.1.max(bottom.1));
+    }
+
+    /// Add a closed polygon
+    pub fn polygon(&mut self, points: &[Point]) {
+        for (n, &a) in points.iter().enumerate() {
+            self.line(a, points[(n + 1) % points.len()]);
+        }
+    }
+
+    /// Add a polygon forcing counter-clockwise winding, so overlapping
+    /// polygons are merged instead of cancelled
+    pub fn polygon_ccw(&mut self, points: &mut [Point]) {
+        if area(points) < 0.0 {
+            points.reverse();
+        }
+
+        self.polygon(points);
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+
+    /// Bounding box as (x1, y1, x2, y2)
+    #[inline]
+    pub fn bounds(&self) -> (f32, f32, f32, f32) {
+        (self.min.0, self.min.1, self.max.0, self.max.1)
+    }
+
+    /// Accumulate the coverage of a pixel row into cov, the first
+    /// element of cov is the pixel at x
+    pub fn coverage(&self, x: i32, y: i32, cov: &mut [f32], crossings: &mut Vec<(f32, i32)>) {
+        let weight = 1.0 / SUBSAMPLES as f32;
+
+        for s in 0..SUBSAMPLES {
+            let sy = y as f32 + (s as f32 + 0.5) * weight;
+
+            crossings.clear();
+            crossings.extend(
+                self.edges
+                    .iter()
+                    .filter(|e| e.y0 <= sy && sy < e.y1)
+                    .map(|e| {
+                        let t = (sy - e.y0) / (e.y1 - e.y0);
+                        (e.x0 + (e.x1 - e.x0) * t - x as f32, e.winding)
+                    }),
+            );
+            crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            let mut winding = 0;
+            for pair in crossings.windows(2) {
+                winding += pair[0].1;
+                if winding != 0 {
+                    span(cov, pair[0].0, pair[1].0, weight);
+                }
+            }
+        }
+    }
+}
+
+impl Default for Edges {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Add horizontal coverage between a and b with fractional ends
+fn span(cov: &mut [f32], a: f32, b: f32, weight: f32) {
+    let len = cov.len() as f32;
+    let (a, b) = (a.max(0.0).min(len), b.max(0.0).min(len));
+    if b <= a {
+        return;
+    }
+
+    let (ia, ib) = (a as usize, b as usize);
+    if ia == ib {
+        cov[ia] += (b - a) * weight;
+        return;
+    }
+
+    cov[ia] += (ia as f32 + 1.0 - a) * weight;
+    for c in cov[ia + 1..ib].iter_mut() {
+        *c += weight;
+    }
+
+    if ib < cov.len() {
+        cov[ib] += (b - ib as f32) * weight;
+    }
+}
+
+/// Signed area of a polygon, positive if counter-clockwise in screen space
+fn area(points: &[Point]) -> f32 {
+    let mut sum = 0.0;
+    for (n, a) in points.iter().enumerate() {
+        let b = points[(n + 1) % points.len()];
+        sum += a.0 * b.1 - b.0 * a.1;
+    }
+
+    sum * 0.5
+}
+
+#[inline]
+fn distance(a: Point, b: Point) -> f32 {
+    ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt()
+}
+
+/// Edges of a thick line with butt caps
+pub fn segment(edges: &mut Edges, a: Point, b: Point, width: f32) {
+    let len = distance(a, b);
+    if len == 0.0 {
+        return;
+    }
+
+    let half = width * 0.5;
+    let (nx, ny) = (-(b.1 - a.1) / len * half, (b.0 - a.0) / len * half);
+
+    edges.polygon_ccw(&mut [
+        (a.0 + nx, a.1 + ny),
+        (b.0 + nx, b.1 + ny),
+        (b.0 - nx, b.1 - ny),
+        (a.0 - nx, a.1 - ny),
+    ]);
+}
+
+/// Edges of a circle approximated with a polygon
+pub fn circle(edges: &mut Edges, center: Point, radius: f32) {
+    let n = segments(radius * std::f32::consts::PI * 2.0).max(8);
+    let mut points: Vec<Point> = (0..n)
+        .map(|i| {
+            let angle = i as f32 / n as f32 * std::f32::consts::PI * 2.0;
+            (
+                center.0 + angle.cos() * radius,
+                center.1 + angle.sin() * radius,
+            )
+        })
+        .collect();
+
+    edges.polygon_ccw(&mut points);
+}
+
+/// Edges of stroked polylines, the joins are rounded
+pub fn stroke(edges: &mut Edges, lines: &[Polyline], width: f32) {
+    for line in lines {
+        let points = &line.points;
+
+        for pair in points.windows(2) {
+            segment(edges, pair[0], pair[1], width);
+        }
+
+        if line.closed {
+            segment(edges, points[points.len() - 1], points[0], width);
+            circle(edges, points[0], width * 0.5);
+        }
+
+        if points.len() > 2 {
+            for &p in &points[1..points.len() - 1] {
+                circle(edges, p, width * 0.5);
+            }
+        }
+    }
+}
@@ -0,0 +1,516 @@
+//! Golden-image snapshot testing
+//!
+//! Snapshot drives a widget tree through Root with scripted input, renders it in
+//! a Framebuffer and compares the result against a reference PNG. If the images
+//! don't match, the actual and a diff image are written next to the reference.
+//! Set the TRGUI_BLESS environment variable for write the references instead
+//!
+//! The Framebuffer is kept between renders like a window backend does, so only
+//! the widgets that request a draw are painted over the previous frame. Use
+//! set_full_redraw for paint every frame from scratch
+//!
+//! The embedded BitmapFont is registered as FontID 0 and measures the text of
+//! the tree, so the text is the same on every machine
+
+use super::font::BitmapFont;
+use super::Framebuffer;
+use crate::math::Scalar;
+use crate::paint::Color;
+use crate::png::{self, DecodeError};
+use crate::state::{KeyState, MouseState, MouseType, TextInput};
+use crate::widget::{Position, Widget};
+use crate::Root;
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::ops::{Add, Sub};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Environment variable that writes the references instead of comparing them
+pub const BLESS_VAR: &str = "TRGUI_BLESS";
+
+/// Allowed differences between an image and its reference
+#[derive(Clone, Copy, Debug)]
+pub struct Tolerance {
+    /// Maximum difference of a channel before the pixel is different
+    pub channel: u8,
+    /// Maximum count of different pixels
+    pub pixels: usize,
+}
+
+impl Tolerance {
+    pub const EXACT: Tolerance = Tolerance {
+        channel: 0,
+        pixels: 0,
+    };
+}
+
+/// Small channel differences are allowed for floating point rounding
+impl Default for Tolerance {
+    fn default() -> Self {
+        Tolerance {
+            channel: 2,
+            pixels: 0,
+        }
+    }
+}
+
+/// Failure of a snapshot comparison
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    Decode(DecodeError),
+    /// The reference doesn't exist, the actual image was written in the path
+    Missing {
+        actual: PathBuf,
+    },
+    /// The reference has other dimensions
+    Size {
+        expected: (usize, usize),
+        actual: (usize, usize),
+    },
+    /// Too many different pixels, the diff image was written in the path
+    Mismatch {
+        pixels: usize,
+        max_channel: u8,
+        diff: PathBuf,
+    },
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::Io(err) => write!(f, "{}", err),
+            SnapshotError::Decode(err) => write!(f, "{}", err),
+            SnapshotError::Missing { actual } => write!(
+                f,
+                "missing reference, actual image written to {} (set {} for accept it)",
+                actual.display(),
+                BLESS_VAR
+            ),
+            SnapshotError::Size { expected, actual } => write!(
+                f,
+                "expected {}x{} image, got {}x{}",
+                expected.0, expected.1, actual.0, actual.1
+            ),
+            SnapshotError::Mismatch {
+                pixels,
+                max_channel,
+                diff,
+            } => write!(
+                f,
+                "{} different pixels (max channel difference {}), diff written to {}",
+                pixels,
+                max_channel,
+                diff.display()
+            ),
+        }
+    }
+}
+
+impl Error for SnapshotError {}
+
+impl From<io::Error> for SnapshotError {
+    fn from(err: io::Error) -> Self {
+        SnapshotError::Io(err)
+    }
+}
+
+impl From<DecodeError> for SnapshotError {
+    fn from(err: DecodeError) -> Self {
+        SnapshotError::Decode(err)
+    }
+}
+
+#[inline]
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    path.with_extension(suffix)
+}
+
+fn bless() -> bool {
+    match std::env::var(BLESS_VAR) {
+        Ok(value) => !value.is_empty() && value != "0",
+        Err(_) => false,
+    }
+}
+
+/// Compare a Framebuffer against a reference PNG
+///
+/// On failure the actual image is written as name.actual.png and, if the
+/// dimensions match, the differences as name.diff.png
+pub fn compare<P: AsRef<Path>>(
+    fb: &Framebuffer,
+    path: P,
+    tolerance: Tolerance,
+) -> Result<(), SnapshotError> {
+    let path = path.as_ref();
+    let (actual, diff) = (sibling(path, "actual.png"), sibling(path, "diff.png"));
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    if bless() {
+        return Ok(fb.save_png(path)?);
+    }
+
+    let data = match fs::read(path) {
+        Ok(data) => data,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            fb.save_png(&actual)?;
+            return Err(SnapshotError::Missing { actual });
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    let (width, height, reference) = png::decode(&data)?;
+    if (width, height) != (fb.width(), fb.height()) {
+        fb.save_png(&actual)?;
+        return Err(SnapshotError::Size {
+            expected: (width, height),
+            actual: (fb.width(), fb.height()),
+        });
+    }
+
+    // Different pixels are red, the others are a faded version of the actual
+    let mut image = Vec::with_capacity(reference.len());
+    let (mut pixels, mut max_channel) = (0, 0);
+
+    for (a, b) in fb.pixels().chunks_exact(4).zip(reference.chunks_exact(4)) {
+        let channel = a.iter().zip(b).map(|(a, b)| a.abs_diff(*b)).max().unwrap();
+        max_channel = max_channel.max(channel);
+
+        if channel > tolerance.channel {
+            image.extend_from_slice(&[255, 0, 0, 255]);
+            pixels += 1;
+        } else {
+            let luma = (a[0] as u32 * 3 + a[1] as u32 * 6 + a[2] as u32) / 10;
+            let faded = (255 - (255 - luma) * a[3] as u32 / 255 / 4) as u8;
+            image.extend_from_slice(&[faded, faded, faded, 255]);
+        }
+    }
+
+    if pixels > tolerance.pixels {
+        fb.save_png(&actual)?;
+        fs::write(&diff, png::encode(width, height, &image))?;
+
+        return Err(SnapshotError::Mismatch {
+            pixels,
+            max_channel,
+            diff,
+        });
+    }
+
+    // Remove leftovers of a previous failure
+    let _ = fs::remove_file(actual);
+    let _ = fs::remove_file(diff);
+
+    Ok(())
+}
+
+/// Headless driver of a widget tree for snapshot tests
+pub struct Snapshot<T> {
+    root: Root<T, Framebuffer>,
+    fb: Framebuffer,
+    mouse: MouseState<T>,
+    background: Color,
+    tolerance: Tolerance,
+    clear: bool,
+    full: bool,
+}
+
+impl<T> Snapshot<T>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+{
+    /// Creates a Snapshot with a white background
+    pub fn new(widget: Box<dyn Widget<T, Framebuffer>>, width: usize, height: usize) -> Self {
+        let dim = (T::from_f32(width as f32), T::from_f32(height as f32));
+        let (mut root, mut fb) = (Root::new(widget, dim), Framebuffer::new(width, height));
+
+        let font = BitmapFont::builtin();
+        root.set_measure(Rc::new(font.clone()));
+        fb.add_font(Box::new(font));
+
+        Snapshot {
+            root,
+            fb,
+            mouse: MouseState::new(),
+            background: Color::WHITE,
+            tolerance: Tolerance::default(),
+            clear: true,
+            full: false,
+        }
+    }
+
+    /// Set the color painted before the first frame
+    pub fn set_background(&mut self, color: Color) {
+        self.background = color;
+        self.clear = true;
+    }
+
+    /// Set the tolerance used by compare
+    pub fn set_tolerance(&mut self, tolerance: Tolerance) {
+        self.tolerance = tolerance;
+    }
+
+    /// Clear and draw the whole tree in every render, instead of drawing over
+    /// the previous frame
+    pub fn set_full_redraw(&mut self, full: bool) {
+        self.full = full;
+    }
+
+    /// Get the Root of the tree
+    #[inline]
+    pub fn root(&mut self) -> &mut Root<T, Framebuffer> {
+        &mut self.root
+    }
+
+    /// Get the Framebuffer, useful for register fonts and images
+    #[inline]
+    pub fn framebuffer(&mut self) -> &mut Framebuffer {
+        &mut self.fb
+    }
+
+    /// Resize the window, the next frame is painted from scratch
+    pub fn resize(&mut self, width: usize, height: usize) {
+        let dim = (T::from_f32(width as f32), T::from_f32(height as f32));
+
+        self.fb.resize(width, height);
+        self.root.resize(dim);
+        self.clear = true;
+    }
+
+    // -- Scripted Input --
+
+    /// Send a raw mouse state, useful for other pointers
+    pub fn mouse(&mut self, mouse: &MouseState<T>) -> &mut Self {
+        self.root.handle_mouse(mouse);
+        self
+    }
+
+    fn send(&mut self, m_type: MouseType) -> &mut Self {
+        self.mouse.set_type(m_type);
+        self.root.handle_mouse(&self.mouse);
+
+        self
+    }
+
+    /// Move the primary pointer
+    pub fn move_to(&mut self, pos: Position<T>) -> &mut Self {
+        self.mouse.set_position(pos);
+        self.send(MouseType::CursorMoved)
+    }
+
+    /// Press a button of the primary pointer
+    pub fn press(&mut self, button: u8) -> &mut Self {
+        self.send(MouseType::Pressed(button))
+    }
+
+    /// Release a button of the primary pointer
+    pub fn release(&mut self, button: u8) -> &mut Self {
+        self.send(MouseType::Released(button))
+    }
+
+    /// Move, press and release a button
+    pub fn click(&mut self, pos: Position<T>, button: u8) -> &mut Self {
+        self.move_to(pos).press(button).release(button)
+    }
+
+    /// Scroll the wheel of the primary pointer
+    pub fn wheel(&mut self, delta: f32) -> &mut Self {
+        self.send(MouseType::Wheel(delta))
+    }
+
+    /// Set keyboard modifiers of the primary pointer
+    pub fn modifiers(&mut self, mods: u16) -> &mut Self {
+        self.mouse.set_modifiers(mods);
+        self
+    }
+
+    /// Advance the timestamp of the primary pointer in milliseconds
+    pub fn wait(&mut self, ms: u32) -> &mut Self {
+        let time = self.mouse.time().wrapping_add(ms);
+        self.mouse.set_time(time);
+
+        self
+    }
+
+    /// Press and release a key
+    pub fn key(&mut self, code: u32, mods: u16) -> &mut Self {
+        self.root.handle_keys(KeyState::Pressed(code, mods));
+        self.root.handle_keys(KeyState::Released(code, mods));
+
+        self
+    }
+
+    /// Send a raw key state
+    pub fn key_state(&mut self, key: KeyState) -> &mut Self {
+        self.root.handle_keys(key);
+        self
+    }
+
+    /// Send text of an input method
+    pub fn text(&mut self, text: TextInput) -> &mut Self {
+        self.root.handle_text(text);
+        self
+    }
+
+    /// Step the focus to the next or previous widget
+    pub fn step_focus(&mut self, back: bool) -> &mut Self {
+        self.root.step_focus(back);
+        self
+    }
+
+    /// Move the pointer out of the window
+    pub fn hover_out(&mut self) -> &mut Self {
+        self.root.hover_out();
+        self
+    }
+
+    // -- Rendering --
+
+    /// Update and draw the tree over the previous frame
+    pub fn render(&mut self) -> &Framebuffer {
+        self.root.update();
+
+        if self.full {
+            self.root.redraw();
+            self.clear = true;
+        }
+
+        if std::mem::replace(&mut self.clear, false) {
+            self.fb.clear(self.background);
+        }
+
+        if self.root.need_draw() {
+            self.root.draw(&mut self.fb);
+        }
+
+        &self.fb
+    }
+
+    /// Render and compare against a reference PNG
+    pub fn compare<P: AsRef<Path>>(&mut self, path: P) -> Result<(), SnapshotError> {
+        self.render();
+        compare(&self.fb, path, self.tolerance)
+    }
+
+    /// Render and compare against a reference PNG, panics if they don't match
+    pub fn assert<P: AsRef<Path>>(&mut self, path: P) {
+        if let Err(err) = self.compare(path.as_ref()) {
+            panic!("snapshot {}: {}", path.as_ref().display(), err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paint::Painter;
+    use crate::widget::flags::DRAW;
+    use crate::widget::WidgetInternal;
+
+    /// Button like widget for drive the snapshots
+    struct Swatch {
+        pressed: bool,
+    }
+
+    impl Widget<f32, Framebuffer> for Swatch {
+        fn draw(&mut self, internal: &WidgetInternal<f32>, ctx: &mut Framebuffer) -> bool {
+            let (x, y, w, h) = internal.boundaries_abs();
+            let color = if self.pressed {
+                Color::rgb(51, 102, 204)
+            } else {
+                Color::rgb(221, 221, 221)
+            };
+
+            ctx.fill_rect((x + 4.0, y + 4.0, w - 8.0, h - 8.0), color);
+            ctx.stroke_rect((x + 4.0, y + 4.0, w - 8.0, h - 8.0), 1.0, Color::BLACK);
+            ctx.text((x + 8.0, y + 15.0), 0, 8.0, "OK", Color::BLACK);
+
+            false
+        }
+
+        fn handle_mouse(&mut self, internal: &mut WidgetInternal<f32>, mouse: &MouseState<f32>) {
+            if let MouseType::Released(1) = mouse.m_type {
+                self.pressed = !self.pressed;
+                internal.on(DRAW);
+            }
+        }
+    }
+
+    fn reference(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src/raster/snapshots")
+            .join(name)
+    }
+
+    #[test]
+    fn swatch() {
+        let mut snap = Snapshot::new(Box::new(Swatch { pressed: false }), 48, 24);
+        snap.set_tolerance(Tolerance::EXACT);
+        snap.assert(reference("swatch.png"));
+
+        snap.click((24.0, 12.0), 1);
+        snap.assert(reference("swatch_pressed.png"));
+    }
+
+    #[test]
+    fn mismatch_writes_diff() {
+        if bless() {
+            return;
+        }
+
+        let dir = std::env::temp_dir().join(format!("trgui-snapshot-{}", std::process::id()));
+        let path = dir.join("square.png");
+
+        let mut fb = Framebuffer::new(4, 4);
+        fb.clear(Color::WHITE);
+        fs::create_dir_all(&dir).unwrap();
+        fb.save_png(&path).unwrap();
+        assert!(compare(&fb, &path, Tolerance::EXACT).is_ok());
+
+        Painter::<f32>::fill_rect(&mut fb, (1.0, 2.0, 1.0, 1.0), Color::BLACK);
+        match compare(&fb, &path, Tolerance::EXACT) {
+            Err(SnapshotError::Mismatch {
+                pixels,
+                max_channel,
+                diff,
+            }) => {
+                assert_eq!((pixels, max_channel), (1, 255));
+                assert_eq!(diff, dir.join("square.diff.png"));
+            }
+            other => panic!("expected a mismatch, got {:?}", other),
+        }
+
+        // The different pixel is red, the others are faded
+        let (width, height, image) =
+            png::decode(&fs::read(dir.join("square.diff.png")).unwrap()).unwrap();
+        assert_eq!((width, height), (4, 4));
+        for (i, pixel) in image.chunks_exact(4).enumerate() {
+            if i == 2 * 4 + 1 {
+                assert_eq!(pixel, [255, 0, 0, 255]);
+            } else {
+                assert_eq!(pixel, [255, 255, 255, 255]);
+            }
+        }
+
+        let actual = fs::read(dir.join("square.actual.png")).unwrap();
+        assert_eq!(png::decode(&actual).unwrap().2, fb.pixels());
+
+        // A tolerance accepts the pixel and removes the leftovers
+        let tolerance = Tolerance {
+            channel: 0,
+            pixels: 1,
+        };
+        assert!(compare(&fb, &path, tolerance).is_ok());
+        assert!(!dir.join("square.diff.png").exists());
+        assert!(!dir.join("square.actual.png").exists());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
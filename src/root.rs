@@ -0,0 +1,217 @@
+//! The root of a widget tree, it connects the tree with a window backend
+//!
+//! Root owns the top widget and its WidgetInternal, consumes the Group events and
+//! calls update, layout and draw only when the flags of the tree request it.
+//! After drawing, Root collects the damaged areas of the frame
+
+use crate::damage::DamageRegion;
+use crate::group::{next_event, Group, GroupEvent};
+use crate::state::{Cursor, KeyState, MouseState, PointerID, TextInput};
+use crate::text::SharedMeasure;
+use crate::widget::flags::*;
+use crate::widget::{Dimensions, Widget, WidgetInternal};
+
+use std::ops::{Add, Sub};
+
+/// Top of a widget tree
+pub struct Root<T, CTX: Sized> {
+    widget: Box<dyn Widget<T, CTX>>,
+    internal: WidgetInternal<T>,
+    /// The whole window needs to be painted
+    full: bool,
+    /// Widgets that requested another frame
+    pending: usize,
+    /// Text measurement of the backend
+    measure: Option<SharedMeasure<T>>,
+}
+
+impl<T: Sized + Copy + Clone, CTX: Sized> Root<T, CTX>
+where
+    T: Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+{
+    /// Creates a new Root with the top widget and the dimensions of the window
+    pub fn new(widget: Box<dyn Widget<T, CTX>>, dim: Dimensions<T>) -> Self {
+        let mut internal = WidgetInternal::new_with(
+            (Default::default(), Default::default()),
+            dim,
+            WIDGET,
+            Group::Any,
+        );
+        internal.set_min_dimensions(widget.min_dimensions());
+
+        let mut root = Root {
+            widget,
+            internal,
+            full: true,
+            pending: 0,
+            measure: None,
+        };
+        root.relayout();
+
+        root
+    }
+
+    /// Change dimensions of the window, the tree is layout again
+    pub fn resize(&mut self, dim: Dimensions<T>) {
+        self.internal.set_dimensions(dim);
+        self.relayout();
+    }
+
+    /// Set the text measurement of the backend, the tree is measured and layout again
+    pub fn set_measure(&mut self, measure: SharedMeasure<T>) {
+        self.measure = Some(measure);
+        self.relayout();
+    }
+
+    /// Layout and draw the whole tree again, useful when the window contents are lost
+    pub fn redraw(&mut self) {
+        self.relayout();
+    }
+
+    /// Get a reference of the WidgetInternal of the top widget
+    #[inline]
+    pub fn internal(&self) -> &WidgetInternal<T> {
+        &self.internal
+    }
+
+    /// Get the cursor shape requested by the widget under the primary pointer
+    #[inline]
+    pub fn cursor(&self) -> Cursor {
+        self.internal.cursor()
+    }
+
+    /// Check if the tree needs to be drawn
+    #[inline]
+    pub fn need_draw(&self) -> bool {
+        self.internal.check(DRAW)
+    }
+
+    /// Count of widgets that requested another frame on the last draw
+    #[inline]
+    pub fn pending(&self) -> usize {
+        self.pending
+    }
+
+    /// Check if the tree needs to be updated
+    #[inline]
+    pub fn need_update(&self) -> bool {
+        self.internal.check(UPDATE)
+    }
+
+    /// Send a mouse state to the tree
+    pub fn handle_mouse(&mut self, mouse: &MouseState<T>) {
+        self.widget.handle_mouse(&mut self.internal, mouse);
+        self.dispatch();
+    }
+
+    /// Notify that a pointer is no longer available, like a lifted finger
+    pub fn pointer_out(&mut self, pointer: PointerID) {
+        self.widget.pointer_out(&mut self.internal, pointer);
+        self.dispatch();
+    }
+
+    /// Notify that every pointer left the window
+    pub fn hover_out(&mut self) {
+        self.widget.hover_out(&mut self.internal);
+        self.internal.off(HOVER | GRAB);
+        self.dispatch();
+    }
+
+    /// Send a key state to the focused widget
+    pub fn handle_keys(&mut self, key: KeyState) {
+        self.widget.handle_keys(&mut self.internal, key);
+        self.dispatch();
+    }
+
+    /// Send text of an input method to the focused widget
+    pub fn handle_text(&mut self, text: TextInput) {
+        self.widget.handle_text(&mut self.internal, text);
+        self.dispatch();
+    }
+
+    /// Step the focus to the next or previous widget, returns false if the focus reached the end
+    pub fn step_focus(&mut self, back: bool) -> bool {
+        let focus = self.widget.step_focus(&mut self.internal, back);
+        self.dispatch();
+
+        focus
+    }
+
+    /// Remove the focus of the tree
+    pub fn focus_out(&mut self) {
+        self.widget.focus_out(&mut self.internal);
+        self.internal.off(FOCUS);
+        self.dispatch();
+    }
+
+    /// Consume the Group events, update and layout the tree if it's requested
+    pub fn update(&mut self) {
+        if self.internal.check(UPDATE) {
+            self.widget.update(&mut self.internal);
+        }
+
+        self.dispatch();
+    }
+
+    /// Draw the tree if it's requested and return the painted area
+    ///
+    /// After a complete layout, the area is the whole window. Use need_draw and
+    /// pending for check if the tree wants to be drawn again
+    pub fn draw(&mut self, ctx: &mut CTX) -> DamageRegion<T> {
+        let mut region = DamageRegion::new();
+
+        if self.internal.check(DRAW) {
+            let draw = self.widget.draw(&self.internal, ctx);
+            self.internal.turn(DRAW, draw);
+
+            self.pending = if draw {
+                self.widget.pending().max(1)
+            } else {
+                0
+            };
+
+            if std::mem::replace(&mut self.full, false) {
+                let mut ignore = DamageRegion::new();
+                self.widget.damage(&self.internal, &mut ignore);
+
+                region.add(self.internal.boundaries_abs());
+            } else {
+                self.widget.damage(&self.internal, &mut region);
+            }
+        }
+
+        region
+    }
+
+    /// Consume the Group events and do a partial layout if it's requested
+    fn dispatch(&mut self) {
+        while let Some(event) = next_event() {
+            match event {
+                GroupEvent::Signal(id) => self.widget.handle_signal(&mut self.internal, id),
+                GroupEvent::Layout => self.internal.on(PREV_LAYOUT),
+            }
+        }
+
+        if self.internal.check(PREV_LAYOUT) {
+            self.relayout();
+        } else if self.internal.check(LAYOUT) {
+            self.widget.layout(&mut self.internal, false);
+            self.internal.off(LAYOUT);
+        }
+    }
+
+    /// Complete layout of the tree
+    fn relayout(&mut self) {
+        if let Some(measure) = &self.measure {
+            self.widget.measure(&mut self.internal, measure);
+        }
+
+        self.internal
+            .set_min_dimensions(self.widget.min_dimensions());
+        self.widget.layout(&mut self.internal, true);
+
+        self.internal.off(LAYOUT | PREV_LAYOUT);
+        self.internal.on(DRAW);
+        self.full = true;
+    }
+}
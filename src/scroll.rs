@@ -0,0 +1,746 @@
+//! A viewport over a widget that is bigger than its area
+//!
+//! ScrollView positions the content with a pivot displaced by the scroll offset,
+//! so the content is laid out as usual and only the visible part is drawn and hovered
+
+use crate::clip::{intersect, overlaps, Clip};
+use crate::damage::DamageRegion;
+use crate::group::{Group, GroupID};
+use crate::math::Scalar;
+use crate::state::{KeyState, MouseState, MouseType, PointerID, TextInput};
+use crate::text::SharedMeasure;
+use crate::widget::flags::*;
+use crate::widget::{Boundaries, Dimensions, Position, Widget, WidgetInternal};
+
+use std::ops::{Add, Sub};
+
+pub type ScrollFlags = u8;
+
+/// Horizontal scrollbar
+pub const SCROLL_H: ScrollFlags = 0b01;
+/// Vertical scrollbar
+pub const SCROLL_V: ScrollFlags = 0b10;
+
+/// Decorator Trait for the scrollbars of a ScrollView
+pub trait ScrollDecorator<T, CTX: Sized> {
+    /// Draw a scrollbar, thumb is the draggable part of the track
+    fn bar(&mut self, track: Boundaries<T>, thumb: Boundaries<T>, vertical: bool, ctx: &mut CTX);
+}
+
+enum Drag<T> {
+    /// Dragging a scrollbar thumb, stores the cursor coordinate and the offset when pressed
+    Thumb {
+        pointer: PointerID,
+        vertical: bool,
+        start: T,
+        offset: T,
+    },
+    /// Dragging the content, stores the cursor position and the offset when pressed
+    Content {
+        pointer: PointerID,
+        start: Position<T>,
+        offset: Position<T>,
+    },
+}
+
+/// Scrollable viewport over a widget, usually a Container
+pub struct ScrollView<T, CTX: Sized, DE: ScrollDecorator<T, CTX>> {
+    content: Box<dyn Widget<T, CTX>>,
+    content_i: WidgetInternal<T>,
+    content_dim: Dimensions<T>,
+    offset: Position<T>,
+    bars: ScrollFlags,
+    thickness: T,
+    step: f32,
+    button: u8,
+    drag: Option<Drag<T>>,
+    /// Pointers inside the viewport
+    hovers: Vec<PointerID>,
+    drawn: bool,
+    decorator: DE,
+}
+
+#[inline]
+fn clamp<T: PartialOrd + Default>(value: T, max: T) -> T {
+    if value < T::default() {
+        T::default()
+    } else if value > max {
+        max
+    } else {
+        value
+    }
+}
+
+#[inline]
+fn max<T: PartialOrd>(a: T, b: T) -> T {
+    if a > b {
+        a
+    } else {
+        b
+    }
+}
+
+impl<T: Sized + Copy + Clone + Scalar, CTX: Sized, DE> ScrollView<T, CTX, DE>
+where
+    T: Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+    DE: ScrollDecorator<T, CTX>,
+{
+    /// Creates a new ScrollView with the content, its dimensions and the visible scrollbars
+    ///
+    /// The content is never smaller than its minimum dimensions or the viewport
+    pub fn new(
+        content: Box<dyn Widget<T, CTX>>,
+        content_dim: Dimensions<T>,
+        bars: ScrollFlags,
+        decorator: DE,
+    ) -> Self {
+        let mut content_i = WidgetInternal::new(WIDGET_S, Group::Any);
+        content_i.set_min_dimensions(content.min_dimensions());
+
+        ScrollView {
+            content,
+            content_i,
+            content_dim,
+            offset: (Default::default(), Default::default()),
+            bars,
+            thickness: T::from_f32(12.0),
+            step: 32.0,
+            button: 2,
+            drag: None,
+            hovers: Vec::new(),
+            drawn: false,
+            decorator,
+        }
+    }
+
+    /// Change the thickness of the scrollbars, requires a layout
+    pub fn set_thickness(&mut self, thickness: T) {
+        self.thickness = thickness;
+    }
+
+    /// Change the distance scrolled by a step of the mouse wheel
+    pub fn set_step(&mut self, step: f32) {
+        self.step = step;
+    }
+
+    /// Change the mouse button that drags the content, 0 disables content dragging
+    pub fn set_drag_button(&mut self, button: u8) {
+        self.button = button;
+    }
+
+    /// Change the dimensions of the content, requires a layout
+    pub fn set_content_dimensions(&mut self, dim: Dimensions<T>) {
+        self.content_dim = dim;
+    }
+
+    /// Get the scroll offset
+    #[inline]
+    pub fn offset(&self) -> Position<T> {
+        self.offset
+    }
+
+    /// Get the dimensions of the content
+    #[inline]
+    pub fn content_dimensions(&self) -> Dimensions<T> {
+        self.content_i.dimensions()
+    }
+
+    /// Change the scroll offset, it's clamped to the scrollable range
+    pub fn scroll_to(&mut self, internal: &mut WidgetInternal<T>, offset: Position<T>) {
+        let range = self.range(internal);
+        let offset = (clamp(offset.0, range.0), clamp(offset.1, range.1));
+
+        if offset != self.offset {
+            self.offset = offset;
+            self.place(internal, true);
+
+            internal.on(DRAW);
+        }
+    }
+
+    /// Get the visible area of the content with absolute position
+    pub fn viewport(&self, internal: &WidgetInternal<T>) -> Boundaries<T> {
+        let (x, y, mut w, mut h) = internal.boundaries_abs();
+
+        if self.bars & SCROLL_V > 0 {
+            w = sub(w, self.thickness);
+        }
+        if self.bars & SCROLL_H > 0 {
+            h = sub(h, self.thickness);
+        }
+
+        (x, y, w, h)
+    }
+
+    /// Maximum scroll offset
+    fn range(&self, internal: &WidgetInternal<T>) -> Position<T> {
+        let (_, _, w, h) = self.viewport(internal);
+        let (c_w, c_h) = self.content_i.dimensions();
+
+        (sub(c_w, w), sub(c_h, h))
+    }
+
+    /// Track and thumb of a scrollbar
+    fn bar(&self, internal: &WidgetInternal<T>, vertical: bool) -> (Boundaries<T>, Boundaries<T>) {
+        let (x, y, w, h) = self.viewport(internal);
+        let (c_w, c_h) = self.content_i.dimensions();
+
+        let (track, view, content, offset) = if vertical {
+            ((x + w, y, self.thickness, h), h, c_h, self.offset.1)
+        } else {
+            ((x, y + h, w, self.thickness), w, c_w, self.offset.0)
+        };
+
+        let t_len = if vertical { track.3 } else { track.2 }.to_f32();
+        let (view, content) = (view.to_f32(), content.to_f32());
+
+        let len = if content > 0.0 {
+            (t_len * view / content)
+                .max(self.thickness.to_f32())
+                .min(t_len)
+        } else {
+            t_len
+        };
+        let pos = if content > view {
+            (t_len - len) * offset.to_f32() / (content - view)
+        } else {
+            0.0
+        };
+
+        let thumb = if vertical {
+            (
+                track.0,
+                track.1 + T::from_f32(pos),
+                track.2,
+                T::from_f32(len),
+            )
+        } else {
+            (
+                track.0 + T::from_f32(pos),
+                track.1,
+                T::from_f32(len),
+                track.3,
+            )
+        };
+
+        (track, thumb)
+    }
+
+    /// Apply the pivot to the content and layout it
+    fn place(&mut self, internal: &mut WidgetInternal<T>, complete: bool) {
+        let (x, y) = internal.absolute_pos();
+        self.content_i
+            .set_pivot((x - self.offset.0, y - self.offset.1));
+
+        let backup = self.content_i.flags;
+        self.content.layout(&mut self.content_i, complete);
+
+        // DRAW | UPDATE & LAYOUT | PREV_LAYOUT
+        self.content_i.turn(DRAW, self.content_i.check(VISIBLE));
+        internal.on(self.content_i.drain(DRAW | UPDATE, LAYOUT | PREV_LAYOUT));
+
+        // HOVER | GRAB | FOCUS
+        self.content_i.replace(0b11100000, backup);
+    }
+
+    /// Propagate the flags of the content, a layout is requested if the content needs it
+    fn drain(&mut self, internal: &mut WidgetInternal<T>) {
+        internal.on(self.content_i.val(DRAW | UPDATE | LAYOUT));
+
+        if self.content_i.check(PREV_LAYOUT) {
+            internal.on(LAYOUT);
+        }
+    }
+
+    fn drag_to(&mut self, internal: &mut WidgetInternal<T>, mouse: &MouseState<T>) {
+        let pos = mouse.absolute_pos();
+
+        match self.drag {
+            Some(Drag::Thumb {
+                vertical,
+                start,
+                offset,
+                ..
+            }) => {
+                let (track, thumb) = self.bar(internal, vertical);
+                let range = self.range(internal);
+
+                let (t_len, len, delta, range) = if vertical {
+                    (track.3, thumb.3, pos.1.to_f32() - start.to_f32(), range.1)
+                } else {
+                    (track.2, thumb.2, pos.0.to_f32() - start.to_f32(), range.0)
+                };
+
+                let free = (t_len - len).to_f32();
+                if free > 0.0 {
+                    let value = offset.to_f32() + delta * range.to_f32() / free;
+                    let value = T::from_f32(value.max(0.0));
+
+                    if vertical {
+                        self.scroll_to(internal, (self.offset.0, value));
+                    } else {
+                        self.scroll_to(internal, (value, self.offset.1));
+                    }
+                }
+            }
+            Some(Drag::Content { start, offset, .. }) => {
+                let x = offset.0.to_f32() - (pos.0.to_f32() - start.0.to_f32());
+                let y = offset.1.to_f32() - (pos.1.to_f32() - start.1.to_f32());
+
+                self.scroll_to(internal, (T::from_f32(x.max(0.0)), T::from_f32(y.max(0.0))));
+            }
+            None => {}
+        }
+    }
+
+    /// Call hover_in on the content when the first pointer enters the viewport and
+    /// hover_out when the last pointer leaves it, returns true if it was called
+    fn hover_content(&mut self, pointer: PointerID, inside: bool) -> bool {
+        let index = self.hovers.iter().position(|&p| p == pointer);
+
+        match (index, inside) {
+            (None, true) => {
+                self.hovers.push(pointer);
+                if self.hovers.len() > 1 {
+                    return false;
+                }
+
+                self.content_i.on(HOVER);
+                self.content.hover_in(&mut self.content_i);
+            }
+            (Some(n), false) => {
+                self.hovers.swap_remove(n);
+                if !self.hovers.is_empty() {
+                    return false;
+                }
+
+                self.content.hover_out(&mut self.content_i);
+                self.content_i.off(HOVER);
+            }
+            _ => return false,
+        }
+
+        true
+    }
+
+    fn drag_pointer(&self) -> Option<PointerID> {
+        match self.drag {
+            Some(Drag::Thumb { pointer, .. }) | Some(Drag::Content { pointer, .. }) => {
+                Some(pointer)
+            }
+            None => None,
+        }
+    }
+
+    /// Start dragging if the pointer pressed a thumb or the drag button
+    fn drag_start(&mut self, internal: &mut WidgetInternal<T>, mouse: &MouseState<T>) -> bool {
+        let pos = mouse.absolute_pos();
+        let pointer = mouse.pointer();
+
+        if let MouseType::Pressed(button) = mouse.m_type {
+            for &vertical in [true, false].iter() {
+                let mask = if vertical { SCROLL_V } else { SCROLL_H };
+                if self.bars & mask == 0 {
+                    continue;
+                }
+
+                let (track, thumb) = self.bar(internal, vertical);
+                if contains(track, pos) {
+                    if !contains(thumb, pos) {
+                        // Jump the thumb to the cursor before dragging
+                        let range = self.range(internal);
+                        let (p, t, t_len, len, range) = if vertical {
+                            (pos.1, track.1, track.3, thumb.3, range.1)
+                        } else {
+                            (pos.0, track.0, track.2, thumb.2, range.0)
+                        };
+
+                        let free = (t_len - len).to_f32();
+                        if free > 0.0 {
+                            let rel = (p.to_f32() - t.to_f32() - len.to_f32() * 0.5) / free;
+                            let value = T::from_f32(rel.clamp(0.0, 1.0) * range.to_f32());
+
+                            let offset = if vertical {
+                                (self.offset.0, value)
+                            } else {
+                                (value, self.offset.1)
+                            };
+
+                            self.scroll_to(internal, offset);
+                        }
+                    }
+
+                    self.drag = Some(Drag::Thumb {
+                        pointer,
+                        vertical,
+                        start: if vertical { pos.1 } else { pos.0 },
+                        offset: if vertical {
+                            self.offset.1
+                        } else {
+                            self.offset.0
+                        },
+                    });
+
+                    return true;
+                }
+            }
+
+            if self.button > 0 && button == self.button {
+                self.drag = Some(Drag::Content {
+                    pointer,
+                    start: pos,
+                    offset: self.offset,
+                });
+
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+#[inline]
+fn contains<T: Add<Output = T> + PartialOrd + Copy>(b: Boundaries<T>, p: Position<T>) -> bool {
+    p.0 >= b.0 && p.0 <= b.0 + b.2 && p.1 >= b.1 && p.1 <= b.1 + b.3
+}
+
+impl<T: Sized + Copy + Clone + Scalar, CTX, DE> Widget<T, CTX> for ScrollView<T, CTX, DE>
+where
+    T: Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+    CTX: Sized + Clip<T>,
+    DE: ScrollDecorator<T, CTX>,
+{
+    /// Draw the content clipped by the viewport if it's requested and the scrollbars
+    fn draw(&mut self, internal: &WidgetInternal<T>, ctx: &mut CTX) -> bool {
+        let mut draw = false;
+
+        // DRAW | VISIBLE
+        if self.content_i.check(0b00001010) {
+            ctx.push_clip(self.viewport(internal));
+            draw = self.content.draw(&self.content_i, ctx);
+            ctx.pop_clip();
+
+            self.content_i.turn(DRAW, draw);
+            self.drawn = true;
+        }
+
+        if self.bars & SCROLL_V > 0 {
+            let (track, thumb) = self.bar(internal, true);
+            self.decorator.bar(track, thumb, true, ctx);
+        }
+        if self.bars & SCROLL_H > 0 {
+            let (track, thumb) = self.bar(internal, false);
+            self.decorator.bar(track, thumb, false, ctx);
+        }
+
+        draw
+    }
+
+    fn update(&mut self, internal: &mut WidgetInternal<T>) {
+        if self.content_i.check(UPDATE) {
+            self.content.update(&mut self.content_i);
+            self.drain(internal);
+        }
+
+        internal.turn(UPDATE, self.content_i.check(UPDATE));
+    }
+
+    /// Calculate the content dimensions, clamp the offset and layout the content
+    fn layout(&mut self, internal: &mut WidgetInternal<T>, complete: bool) {
+        let (_, _, w, h) = self.viewport(internal);
+        let (min_w, min_h) = self.content.min_dimensions();
+        let dim = (
+            max(max(self.content_dim.0, min_w), w),
+            max(max(self.content_dim.1, min_h), h),
+        );
+
+        let changed = dim != self.content_i.dimensions();
+        self.content_i.set_min_dimensions((min_w, min_h));
+        self.content_i.set_dimensions(dim);
+
+        let range = self.range(internal);
+        self.offset = (clamp(self.offset.0, range.0), clamp(self.offset.1, range.1));
+
+        self.place(internal, complete || changed);
+    }
+
+    fn measure(&mut self, _: &mut WidgetInternal<T>, measure: &SharedMeasure<T>) {
+        self.content.measure(&mut self.content_i, measure);
+    }
+
+    fn handle_signal(&mut self, internal: &mut WidgetInternal<T>, group: GroupID) {
+        self.content.handle_signal(&mut self.content_i, group);
+        self.drain(internal);
+    }
+
+    /// Scroll with the wheel or dragging, otherwise send the mouse to the content
+    /// if the cursor is inside the viewport. The wheel is sent to the content
+    /// when the offset doesn't change, so a nested ScrollView scrolls when this one
+    /// reaches the end
+    fn handle_mouse(&mut self, internal: &mut WidgetInternal<T>, mouse: &MouseState<T>) {
+        let pointer = mouse.pointer();
+
+        if self.drag_pointer() == Some(pointer) {
+            if mouse.m_count == 0 {
+                self.drag = None;
+                internal.off(GRAB);
+            } else {
+                self.drag_to(internal, mouse);
+            }
+
+            return;
+        }
+
+        if let MouseType::Wheel(delta) = mouse.m_type {
+            let range = self.range(internal);
+            let step = T::from_f32((delta * self.step).abs());
+
+            // Scroll horizontally if there is nothing to scroll vertically
+            let offset = if range.1 > T::default() {
+                let y = self.offset.1;
+                (
+                    self.offset.0,
+                    if delta > 0.0 { sub(y, step) } else { y + step },
+                )
+            } else {
+                let x = self.offset.0;
+                (
+                    if delta > 0.0 { sub(x, step) } else { x + step },
+                    self.offset.1,
+                )
+            };
+
+            // The content receives the wheel if the view can't scroll more
+            let old = self.offset;
+            self.scroll_to(internal, offset);
+            if self.offset != old {
+                return;
+            }
+        }
+
+        let grab = internal.check(GRAB);
+        let inside = contains(self.viewport(internal), mouse.absolute_pos());
+        // A grabbed content keeps the hover until the button is released
+        let changed = self.hover_content(pointer, inside || grab);
+
+        if grab || inside {
+            self.content_i.turn(GRAB, grab);
+            self.content_i.turn(HOVER, inside);
+
+            self.content.handle_mouse(&mut self.content_i, mouse);
+            self.drain(internal);
+
+            if self.content_i.check(GRAB) {
+                internal.on(GRAB);
+                return;
+            }
+
+            internal.off(GRAB);
+        } else {
+            if !changed {
+                self.content.pointer_out(&mut self.content_i, pointer);
+            }
+
+            self.drain(internal);
+        }
+
+        if self.drag_start(internal, mouse) {
+            self.content.pointer_out(&mut self.content_i, pointer);
+            self.drain(internal);
+
+            internal.on(GRAB | DRAW);
+        }
+    }
+
+    fn handle_keys(&mut self, internal: &mut WidgetInternal<T>, key: KeyState) {
+        self.content.handle_keys(&mut self.content_i, key);
+        self.drain(internal);
+    }
+
+    fn handle_text(&mut self, internal: &mut WidgetInternal<T>, text: TextInput) {
+        self.content.handle_text(&mut self.content_i, text);
+        self.drain(internal);
+    }
+
+    fn step_focus(&mut self, internal: &mut WidgetInternal<T>, back: bool) -> bool {
+        let focus = self.content.step_focus(&mut self.content_i, back);
+        self.content_i.turn(FOCUS, focus);
+        self.drain(internal);
+
+        focus
+    }
+
+    /// The content is hovered when a pointer enters the viewport, handle_mouse
+    /// calls hover_in of the content because the pointer can be over a scrollbar
+    fn hover_in(&mut self, _: &mut WidgetInternal<T>) {}
+
+    fn hover_out(&mut self, internal: &mut WidgetInternal<T>) {
+        self.drag = None;
+
+        // The content was already left if no pointer is inside the viewport
+        if !self.hovers.is_empty() || self.content_i.check(GRAB) {
+            self.hovers.clear();
+            self.content.hover_out(&mut self.content_i);
+        }
+
+        self.content_i.off(HOVER | GRAB);
+        self.drain(internal);
+    }
+
+    fn pointer_out(&mut self, internal: &mut WidgetInternal<T>, pointer: PointerID) {
+        if self.drag_pointer() == Some(pointer) {
+            self.drag = None;
+        }
+
+        if !self.hover_content(pointer, false) {
+            self.content.pointer_out(&mut self.content_i, pointer);
+        }
+
+        self.drain(internal);
+    }
+
+    fn focus_out(&mut self, internal: &mut WidgetInternal<T>) {
+        self.content.focus_out(&mut self.content_i);
+        self.content_i.off(FOCUS);
+        self.drain(internal);
+    }
+
+    /// Expose the content if the area is inside the viewport, the scrollbars are
+    /// always drawn again
+    fn expose(&mut self, internal: &mut WidgetInternal<T>, area: Boundaries<T>) {
+        if overlaps(area, self.viewport(internal)) {
+            self.content.expose(&mut self.content_i, area);
+            self.drain(internal);
+        }
+
+        internal.on(DRAW);
+    }
+
+    fn pending(&self) -> usize {
+        if self.content_i.check(DRAW) {
+            self.content.pending().max(1)
+        } else {
+            0
+        }
+    }
+
+    /// Add the scrollbars and the damage of the content clipped by the viewport
+    fn damage(&mut self, internal: &WidgetInternal<T>, region: &mut DamageRegion<T>) {
+        if std::mem::replace(&mut self.drawn, false) {
+            let viewport = self.viewport(internal);
+            let mut content = DamageRegion::new();
+            self.content.damage(&self.content_i, &mut content);
+
+            for &rect in content.rects() {
+                region.add(intersect(rect, viewport));
+            }
+        }
+
+        if self.bars & SCROLL_V > 0 {
+            region.add(self.bar(internal, true).0);
+        }
+        if self.bars & SCROLL_H > 0 {
+            region.add(self.bar(internal, false).0);
+        }
+    }
+
+    /// The content is scrollable, only the scrollbars are required
+    fn min_dimensions(&self) -> Dimensions<T> {
+        let zero = T::default();
+
+        (
+            if self.bars & SCROLL_V > 0 {
+                self.thickness
+            } else {
+                zero
+            },
+            if self.bars & SCROLL_H > 0 {
+                self.thickness
+            } else {
+                zero
+            },
+        )
+    }
+}
+
+/// Subtraction that stops at zero, useful for unsigned types
+#[inline]
+fn sub<T: Sub<Output = T> + PartialOrd + Default>(a: T, b: T) -> T {
+    if b > a {
+        T::default()
+    } else {
+        a - b
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Bars;
+
+    impl ScrollDecorator<f32, ()> for Bars {
+        fn bar(&mut self, _: Boundaries<f32>, _: Boundaries<f32>, _: bool, _: &mut ()) {}
+    }
+
+    struct Content;
+
+    impl Widget<f32, ()> for Content {
+        fn draw(&mut self, _: &WidgetInternal<f32>, _: &mut ()) -> bool {
+            false
+        }
+    }
+
+    fn event(
+        view: &mut ScrollView<f32, (), Bars>,
+        internal: &mut WidgetInternal<f32>,
+        mouse: &mut MouseState<f32>,
+        m_type: MouseType,
+        pos: Position<f32>,
+    ) {
+        mouse.set_type(m_type);
+        mouse.set_position(pos);
+        Widget::<f32, ()>::handle_mouse(view, internal, mouse);
+    }
+
+    #[test]
+    fn track_click() {
+        let mut view = ScrollView::new(Box::new(Content), (100.0, 400.0), SCROLL_V, Bars);
+        let mut internal =
+            WidgetInternal::new_with((0.0, 0.0), (100.0, 100.0), WIDGET_S, Group::Any);
+        Widget::<f32, ()>::layout(&mut view, &mut internal, true);
+
+        // The thumb is 25 units long, its center jumps to the middle of the track
+        let mut mouse = MouseState::new();
+        event(
+            &mut view,
+            &mut internal,
+            &mut mouse,
+            MouseType::Pressed(1),
+            (94.0, 50.0),
+        );
+        assert_eq!(view.offset(), (0.0, 150.0));
+        assert_eq!(view.content_i.absolute_pos(), (0.0, -150.0));
+        assert!(internal.check(GRAB | DRAW));
+
+        // Dragging continues from the new offset
+        event(
+            &mut view,
+            &mut internal,
+            &mut mouse,
+            MouseType::CursorMoved,
+            (94.0, 65.0),
+        );
+        assert_eq!(view.content_i.absolute_pos(), (0.0, -210.0));
+
+        event(
+            &mut view,
+            &mut internal,
+            &mut mouse,
+            MouseType::Released(1),
+            (94.0, 65.0),
+        );
+        assert!(!internal.check(GRAB));
+    }
+}
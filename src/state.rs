@@ -5,6 +5,11 @@
 use crate::widget::Position;
 use std::ops::Sub;
 
+/// Identifier of a pointer, every finger of a touch screen has its own ID
+pub type PointerID = usize;
+/// Pointer ID of the mouse or the first touch
+pub const PRIMARY_POINTER: PointerID = 0;
+
 /// Generic Mouse Buttons State
 pub enum MouseType {
     CursorMoved,
@@ -15,16 +20,45 @@ pub enum MouseType {
     Nothing,
 }
 
+/// Generic Cursor Shape that a widget can request
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Cursor {
+    Default,
+    Pointer,
+    Text,
+    Crosshair,
+    Move,
+    Grab,
+    Grabbing,
+    Wait,
+    NotAllowed,
+    /// Horizontal resize arrows
+    ResizeH,
+    /// Vertical resize arrows
+    ResizeV,
+    /// Diagonal resize arrows from top-right to bottom-left
+    ResizeNESW,
+    /// Diagonal resize arrows from top-left to bottom-right
+    ResizeNWSE,
+    Hidden,
+    /// Cursor defined by the window backend
+    Custom(u32),
+}
+
 /// Generic Mouse State
 pub struct MouseState<T> {
     /// Mouse event type
     pub m_type: MouseType,
     /// Click counter
     pub m_count: u8,
+    /// Pointer ID
+    m_pointer: PointerID,
+    /// Event timestamp in milliseconds
+    m_time: u32,
     /// Mouse coordinates
     m_position: Position<T>,
-    /// Tablet Pressure Level
-    t_pressure: f32,
+    /// Tablet Pressure Level, None if the device doesn't have it
+    t_pressure: Option<f32>,
     /// Keyboard Modifiers as bitflags
     k_modifiers: u16,
 }
@@ -35,6 +69,19 @@ pub enum KeyState {
     Released(u32, u16),
 }
 
+/// Text from an input method
+///
+/// Printable keys are already text, a backend sends the text of an input method
+/// instead of the keys that compose it
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TextInput<'a> {
+    /// Finished text, it replaces the text being composed
+    Commit(&'a str),
+    /// Text being composed and the byte position of its cursor, an empty text
+    /// cancels the composition
+    Preedit(&'a str, Option<usize>),
+}
+
 impl<T> MouseState<T>
 where
     T: Sized + Copy + Clone + Default + Sub<Output = T>,
@@ -44,12 +91,24 @@ where
         MouseState {
             m_type: MouseType::Nothing,
             m_position: (Default::default(), Default::default()),
-            t_pressure: 0.0,
+            t_pressure: None,
             k_modifiers: 0,
             m_count: 0,
+            m_pointer: PRIMARY_POINTER,
+            m_time: 0,
         }
     }
 
+    /// Creates a new Mouse State for a pointer ID, useful for touch screens
+    ///
+    /// Every pointer should have its own Mouse State for keep the click counter separated
+    pub fn new_pointer(pointer: PointerID) -> Self {
+        let mut state = Self::new();
+        state.m_pointer = pointer;
+
+        state
+    }
+
     /// Set cursor position
     pub fn set_position(&mut self, position: Position<T>) {
         self.m_position = position;
@@ -59,21 +118,22 @@ where
     pub fn set_type(&mut self, m_type: MouseType) {
         match m_type {
             MouseType::Pressed(_) => self.m_count += 1,
-            MouseType::Released(_) => {
-                // Avoid a CVE by overflow
-                if self.m_count > 0 {
-                    self.m_count -= 1
-                }
-            }
+            // Avoid a CVE by overflow
+            MouseType::Released(_) if self.m_count > 0 => self.m_count -= 1,
             _ => {}
         };
 
         self.m_type = m_type;
     }
 
-    /// Set tablet pressure (optional)
+    /// Set event timestamp in milliseconds (optional), required by gestures
+    pub fn set_time(&mut self, time: u32) {
+        self.m_time = time;
+    }
+
+    /// Set tablet pressure (optional), 0 is a valid pressure after this
     pub fn set_pressure(&mut self, pressure: f32) {
-        self.t_pressure = pressure;
+        self.t_pressure = Some(pressure);
     }
 
     /// Get Absolute position of the cursor
@@ -89,9 +149,27 @@ where
     }
 
     #[inline]
-    /// Get tablet pressure value
+    /// Get pointer ID
+    pub fn pointer(&self) -> PointerID {
+        self.m_pointer
+    }
+
+    #[inline]
+    /// Get event timestamp in milliseconds
+    pub fn time(&self) -> u32 {
+        self.m_time
+    }
+
+    #[inline]
+    /// Get tablet pressure value, it's 0 if the pressure was never set
     pub fn tablet_pressure(&self) -> f32 {
-        self.t_pressure
+        self.t_pressure.unwrap_or(0.0)
+    }
+
+    #[inline]
+    /// Check if the pressure was set, a mouse doesn't have pressure
+    pub fn has_pressure(&self) -> bool {
+        self.t_pressure.is_some()
     }
 
     // Modifiers
@@ -107,6 +185,15 @@ where
     }
 }
 
+impl<T> Default for MouseState<T>
+where
+    T: Sized + Copy + Clone + Default + Sub<Output = T>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl KeyState {
     /// Check if there is pressed modifiers using a bitflags mask
     #[inline]
@@ -116,3 +203,49 @@ impl KeyState {
         }
     }
 }
+
+/// Key codes and modifiers for KeyState
+///
+/// Printable keys use the Unicode codepoint of the character, so a key code can be
+/// converted with char::from_u32. Other keys use codes outside the Unicode range
+pub mod keys {
+    // Modifiers
+    pub const SHIFT: u16 = 0b0001;
+    pub const CTRL: u16 = 0b0010;
+    pub const ALT: u16 = 0b0100;
+    pub const SUPER: u16 = 0b1000;
+
+    // Control characters
+    pub const BACKSPACE: u32 = 0x08;
+    pub const TAB: u32 = 0x09;
+    pub const ENTER: u32 = 0x0D;
+    pub const ESCAPE: u32 = 0x1B;
+    pub const SPACE: u32 = 0x20;
+    pub const DELETE: u32 = 0x7F;
+
+    /// First code of the keys without a character
+    const SPECIAL: u32 = 0x11_0000;
+
+    pub const UP: u32 = SPECIAL;
+    pub const DOWN: u32 = SPECIAL + 1;
+    pub const LEFT: u32 = SPECIAL + 2;
+    pub const RIGHT: u32 = SPECIAL + 3;
+    pub const HOME: u32 = SPECIAL + 4;
+    pub const END: u32 = SPECIAL + 5;
+    pub const PAGE_UP: u32 = SPECIAL + 6;
+    pub const PAGE_DOWN: u32 = SPECIAL + 7;
+    pub const INSERT: u32 = SPECIAL + 8;
+
+    /// Function keys from F1 to F12
+    pub const fn function(n: u32) -> u32 {
+        SPECIAL + 0x10 + n
+    }
+
+    /// Get the character of a printable key code
+    pub fn to_char(code: u32) -> Option<char> {
+        match char::from_u32(code) {
+            Some(c) if !c.is_control() => Some(c),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,510 @@
+//! SVG export of a frame
+//!
+//! SvgDocument is a CTX that records the Painter commands as SVG elements, clip
+//! rectangles become clipPath groups and images are embedded as PNG data URIs.
+//! Call Root::redraw before Root::draw for export the whole frame
+
+use crate::clip::{Clip, ClipStack};
+use crate::math::Scalar;
+use crate::paint::{Color, FontID, ImageID, Painter, PathCommand};
+use crate::png;
+use crate::widget::{Boundaries, Position};
+
+use std::fmt::{self, Write};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64[(n >> (18 - i * 6) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+
+    out
+}
+
+/// Escape text for XML content and attributes
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// Number with at most two decimals and without trailing zeros
+struct Num(f32);
+
+impl fmt::Display for Num {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = (self.0 * 100.0).round() / 100.0;
+        if value.fract() == 0.0 {
+            write!(f, "{}", value as i64)
+        } else {
+            let text = format!("{:.2}", value);
+            f.write_str(text.trim_end_matches('0'))
+        }
+    }
+}
+
+/// Paint attribute of a color, the alpha is written as opacity
+struct Paint(&'static str, Color);
+
+impl fmt::Display for Paint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Paint(name, c) = *self;
+        write!(f, "{}=\"#{:02x}{:02x}{:02x}\"", name, c.r, c.g, c.b)?;
+
+        if c.a < 255 {
+            write!(f, " {}-opacity=\"{}\"", name, Num(c.a as f32 / 255.0))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Image embedded as a PNG data URI
+struct SvgImage {
+    uri: String,
+}
+
+/// SVG document CTX
+pub struct SvgDocument {
+    width: f32,
+    height: f32,
+    defs: String,
+    body: String,
+    clip: ClipStack<f32>,
+    /// Count of clipPath elements
+    clips: usize,
+    // Registered resources
+    fonts: Vec<String>,
+    images: Vec<SvgImage>,
+}
+
+#[inline]
+fn rect<T: Scalar>(bounds: Boundaries<T>) -> (Num, Num, Num, Num) {
+    (
+        Num(bounds.0.to_f32()),
+        Num(bounds.1.to_f32()),
+        Num(bounds.2.to_f32()),
+        Num(bounds.3.to_f32()),
+    )
+}
+
+#[inline]
+fn pos<T: Scalar>(p: Position<T>) -> (Num, Num) {
+    (Num(p.0.to_f32()), Num(p.1.to_f32()))
+}
+
+/// Path data of the commands
+fn path_data<T: Scalar>(path: &[PathCommand<T>]) -> String {
+    let mut d = String::new();
+    for command in path {
+        if !d.is_empty() {
+            d.push(' ');
+        }
+
+        let _ = match *command {
+            PathCommand::MoveTo(p) => {
+                let (x, y) = pos(p);
+                write!(d, "M{} {}", x, y)
+            }
+            PathCommand::LineTo(p) => {
+                let (x, y) = pos(p);
+                write!(d, "L{} {}", x, y)
+            }
+            PathCommand::QuadTo(c, p) => {
+                let ((cx, cy), (x, y)) = (pos(c), pos(p));
+                write!(d, "Q{} {} {} {}", cx, cy, x, y)
+            }
+            PathCommand::CubicTo(c1, c2, p) => {
+                let ((x1, y1), (x2, y2), (x, y)) = (pos(c1), pos(c2), pos(p));
+                write!(d, "C{} {} {} {} {} {}", x1, y1, x2, y2, x, y)
+            }
+            PathCommand::Close => write!(d, "Z"),
+        };
+    }
+
+    d
+}
+
+impl SvgDocument {
+    /// Creates an empty document
+    pub fn new(width: f32, height: f32) -> Self {
+        SvgDocument {
+            width,
+            height,
+            defs: String::new(),
+            body: String::new(),
+            clip: ClipStack::new(),
+            clips: 0,
+            fonts: Vec::new(),
+            images: Vec::new(),
+        }
+    }
+
+    /// Remove every element, registered fonts and images are kept
+    pub fn clear(&mut self) {
+        self.defs.clear();
+        self.body.clear();
+        self.clip.clear();
+        self.clips = 0;
+    }
+
+    /// Register a font family for draw text with Painter::text
+    pub fn add_font(&mut self, family: &str) -> FontID {
+        self.fonts.push(escape(family));
+        (self.fonts.len() - 1) as FontID
+    }
+
+    /// Register RGBA pixels for draw them with Painter::image
+    pub fn add_image(&mut self, width: usize, height: usize, rgba: &[u8]) -> ImageID {
+        let uri = format!(
+            "data:image/png;base64,{}",
+            base64(&png::encode(width, height, rgba))
+        );
+        self.images.push(SvgImage { uri });
+
+        (self.images.len() - 1) as ImageID
+    }
+
+    /// Write the document to a file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    #[inline]
+    fn element(&mut self, args: fmt::Arguments) {
+        let _ = self.body.write_fmt(args);
+        self.body.push('\n');
+    }
+}
+
+/// Writes the standalone SVG document, unclosed clips are closed
+impl fmt::Display for SvgDocument {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (w, h) = (Num(self.width), Num(self.height));
+
+        writeln!(f, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(
+            f,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\" \
+             width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">",
+            w, h, w, h
+        )?;
+
+        if !self.defs.is_empty() {
+            write!(f, "<defs>\n{}</defs>\n", self.defs)?;
+        }
+
+        f.write_str(&self.body)?;
+        for _ in 0..self.clip.len() {
+            writeln!(f, "</g>")?;
+        }
+
+        writeln!(f, "</svg>")
+    }
+}
+
+impl<T: Scalar> Clip<T> for SvgDocument {
+    fn push_clip(&mut self, bounds: Boundaries<T>) {
+        self.clip.push_clip((
+            bounds.0.to_f32(),
+            bounds.1.to_f32(),
+            bounds.2.to_f32(),
+            bounds.3.to_f32(),
+        ));
+
+        // The clip is already intersected with the previous
+        let (c, id) = (self.clip.clip().unwrap(), self.clips);
+        let (x, y, w, h) = (Num(c.0), Num(c.1), Num(c.2), Num(c.3));
+        let _ = writeln!(
+            self.defs,
+            "<clipPath id=\"clip{}\"><rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/></clipPath>",
+            id, x, y, w, h
+        );
+
+        self.element(format_args!("<g clip-path=\"url(#clip{})\">", id));
+        self.clips += 1;
+    }
+
+    fn pop_clip(&mut self) {
+        if !self.clip.is_empty() {
+            self.clip.pop_clip();
+            self.element(format_args!("</g>"));
+        }
+    }
+
+    fn clip(&self) -> Option<Boundaries<T>> {
+        self.clip.clip().map(|c| {
+            (
+                T::from_f32(c.0),
+                T::from_f32(c.1),
+                T::from_f32(c.2),
+                T::from_f32(c.3),
+            )
+        })
+    }
+}
+
+impl<T: Scalar> Painter<T> for SvgDocument {
+    fn fill_rect(&mut self, bounds: Boundaries<T>, color: Color) {
+        let (x, y, w, h) = rect(bounds);
+        self.element(format_args!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" {}/>",
+            x,
+            y,
+            w,
+            h,
+            Paint("fill", color)
+        ));
+    }
+
+    fn stroke_rect(&mut self, bounds: Boundaries<T>, width: T, color: Color) {
+        // SVG strokes are centered, move the rectangle inside
+        let half = width.to_f32() * 0.5;
+        let (x, y) = (bounds.0.to_f32() + half, bounds.1.to_f32() + half);
+        let (w, h) = (
+            bounds.2.to_f32() - half * 2.0,
+            bounds.3.to_f32() - half * 2.0,
+        );
+
+        self.element(format_args!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"none\" {} stroke-width=\"{}\"/>",
+            Num(x),
+            Num(y),
+            Num(w.max(0.0)),
+            Num(h.max(0.0)),
+            Paint("stroke", color),
+            Num(width.to_f32())
+        ));
+    }
+
+    fn fill_round_rect(&mut self, bounds: Boundaries<T>, radius: T, color: Color) {
+        let (x, y, w, h) = rect(bounds);
+        let r = Num(radius.to_f32());
+        self.element(format_args!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"{}\" ry=\"{}\" {}/>",
+            x,
+            y,
+            w,
+            h,
+            r,
+            r,
+            Paint("fill", color)
+        ));
+    }
+
+    fn line(&mut self, from: Position<T>, to: Position<T>, width: T, color: Color) {
+        let ((x1, y1), (x2, y2)) = (pos(from), pos(to));
+        self.element(format_args!(
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" {} stroke-width=\"{}\"/>",
+            x1,
+            y1,
+            x2,
+            y2,
+            Paint("stroke", color),
+            Num(width.to_f32())
+        ));
+    }
+
+    fn fill_path(&mut self, path: &[PathCommand<T>], color: Color) {
+        self.element(format_args!(
+            "<path d=\"{}\" fill-rule=\"nonzero\" {}/>",
+            path_data(path),
+            Paint("fill", color)
+        ));
+    }
+
+    fn stroke_path(&mut self, path: &[PathCommand<T>], width: T, color: Color) {
+        self.element(format_args!(
+            "<path d=\"{}\" fill=\"none\" {} stroke-width=\"{}\" stroke-linejoin=\"round\"/>",
+            path_data(path),
+            Paint("stroke", color),
+            Num(width.to_f32())
+        ));
+    }
+
+    fn text(&mut self, pos: Position<T>, font: FontID, size: T, text: &str, color: Color) {
+        let family = match self.fonts.get(font as usize) {
+            Some(family) => family.clone(),
+            None => String::from("sans-serif"),
+        };
+
+        let (x, y) = self::pos(pos);
+        self.element(format_args!(
+            "<text x=\"{}\" y=\"{}\" font-family=\"{}\" font-size=\"{}\" {} xml:space=\"preserve\">{}</text>",
+            x,
+            y,
+            family,
+            Num(size.to_f32()),
+            Paint("fill", color),
+            escape(text)
+        ));
+    }
+
+    fn image(&mut self, bounds: Boundaries<T>, image: ImageID) {
+        if let Some(img) = self.images.get(image as usize) {
+            let (x, y, w, h) = rect(bounds);
+            let uri = img.uri.clone();
+
+            self.element(format_args!(
+                "<image x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" preserveAspectRatio=\"none\" xlink:href=\"{}\"/>",
+                x, y, w, h, uri
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encoding() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(
+            escape("<a href='x'>\"&\"</a>"),
+            "&lt;a href=&apos;x&apos;&gt;&quot;&amp;&quot;&lt;/a&gt;"
+        );
+
+        let nums: Vec<_> = [1.0, 0.5, 1.234, -2.5, 0.999]
+            .iter()
+            .map(|&n| Num(n).to_string())
+            .collect();
+        assert_eq!(nums, ["1", "0.5", "1.23", "-2.5", "1"]);
+        assert_eq!(
+            Paint("fill", Color::rgb(255, 0, 16)).to_string(),
+            "fill=\"#ff0010\""
+        );
+        assert_eq!(
+            Paint("stroke", Color::rgba(0, 0, 0, 51)).to_string(),
+            "stroke=\"#000000\" stroke-opacity=\"0.2\""
+        );
+    }
+
+    #[test]
+    fn elements() {
+        let mut svg = SvgDocument::new(100.0, 50.0);
+        let font = svg.add_font("Noto <Sans>");
+
+        Painter::<f32>::fill_rect(&mut svg, (0.0, 0.0, 100.0, 50.0), Color::rgb(255, 255, 255));
+        Painter::<f32>::stroke_rect(&mut svg, (10.0, 10.0, 20.0, 10.0), 2.0, Color::rgb(0, 0, 0));
+        Painter::<f32>::text(
+            &mut svg,
+            (5.0, 40.5),
+            font,
+            11.0,
+            "a < b",
+            Color::rgb(0, 0, 0),
+        );
+        Painter::<f32>::text(&mut svg, (5.0, 40.5), 3, 11.0, "", Color::rgb(0, 0, 0));
+
+        let path = [
+            PathCommand::MoveTo((0.0, 0.0)),
+            PathCommand::QuadTo((5.0, 0.0), (5.0, 5.0)),
+            PathCommand::CubicTo((5.0, 6.0), (6.0, 7.0), (7.0, 7.0)),
+            PathCommand::LineTo((0.0, 7.0)),
+            PathCommand::Close,
+        ];
+        assert_eq!(path_data(&path), "M0 0 Q5 0 5 5 C5 6 6 7 7 7 L0 7 Z");
+
+        let text = svg.to_string();
+        let lines: Vec<_> = text.lines().skip(2).collect();
+        assert_eq!(
+            lines,
+            [
+                "<rect x=\"0\" y=\"0\" width=\"100\" height=\"50\" fill=\"#ffffff\"/>",
+                "<rect x=\"11\" y=\"11\" width=\"18\" height=\"8\" fill=\"none\" stroke=\"#000000\" stroke-width=\"2\"/>",
+                "<text x=\"5\" y=\"40.5\" font-family=\"Noto &lt;Sans&gt;\" font-size=\"11\" fill=\"#000000\" xml:space=\"preserve\">a &lt; b</text>",
+                "<text x=\"5\" y=\"40.5\" font-family=\"sans-serif\" font-size=\"11\" fill=\"#000000\" xml:space=\"preserve\"></text>",
+                "</svg>",
+            ]
+        );
+        assert!(text.starts_with("<?xml"));
+        assert!(text.contains("width=\"100\" height=\"50\" viewBox=\"0 0 100 50\""));
+    }
+
+    #[test]
+    fn clips() {
+        let mut svg = SvgDocument::new(100.0, 100.0);
+        let red = Color::rgb(255, 0, 0);
+
+        // The second clip is intersected with the first, the last one isn't closed
+        Clip::<f32>::push_clip(&mut svg, (0.0, 0.0, 50.0, 50.0));
+        Clip::<f32>::push_clip(&mut svg, (25.0, 25.0, 50.0, 50.0));
+        assert_eq!(Clip::<f32>::clip(&svg), Some((25.0, 25.0, 25.0, 25.0)));
+        Painter::<f32>::fill_rect(&mut svg, (0.0, 0.0, 100.0, 100.0), red);
+        Clip::<f32>::pop_clip(&mut svg);
+
+        let text = svg.to_string();
+        let lines: Vec<_> = text.lines().skip(2).collect();
+        assert_eq!(
+            lines,
+            [
+                "<defs>",
+                "<clipPath id=\"clip0\"><rect x=\"0\" y=\"0\" width=\"50\" height=\"50\"/></clipPath>",
+                "<clipPath id=\"clip1\"><rect x=\"25\" y=\"25\" width=\"25\" height=\"25\"/></clipPath>",
+                "</defs>",
+                "<g clip-path=\"url(#clip0)\">",
+                "<g clip-path=\"url(#clip1)\">",
+                "<rect x=\"0\" y=\"0\" width=\"100\" height=\"100\" fill=\"#ff0000\"/>",
+                "</g>",
+                "</g>",
+                "</svg>",
+            ]
+        );
+
+        // Popping without clips doesn't close a group
+        svg.clear();
+        Clip::<f32>::pop_clip(&mut svg);
+        assert_eq!(svg.to_string().lines().count(), 3);
+    }
+
+    #[test]
+    fn images() {
+        let mut svg = SvgDocument::new(10.0, 10.0);
+        let image = svg.add_image(1, 1, &[255, 0, 0, 255]);
+
+        Painter::<f32>::image(&mut svg, (1.0, 2.0, 8.0, 4.0), image);
+        Painter::<f32>::image(&mut svg, (0.0, 0.0, 8.0, 4.0), image + 1);
+
+        let text = svg.to_string();
+        let images: Vec<_> = text
+            .lines()
+            .filter(|line| line.starts_with("<image"))
+            .collect();
+        assert_eq!(images.len(), 1);
+        assert!(images[0].starts_with("<image x=\"1\" y=\"2\" width=\"8\" height=\"4\""));
+        // The PNG signature
+        assert!(images[0].contains("xlink:href=\"data:image/png;base64,iVBORw0KGgo"));
+    }
+}
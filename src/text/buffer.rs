@@ -0,0 +1,660 @@
+//! Editable text model
+//!
+//! TextBuffer keeps a text with a caret, a selection and the undo history. It
+//! doesn't know about fonts or widgets, positions are byte indices that are
+//! always at char boundaries
+
+use std::ops::Range;
+
+/// Kind of an edit, only edits of the same kind are merged
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Insert,
+    Backspace,
+    Delete,
+    Other,
+}
+
+/// A replacement of a range of the text, enough for undo and redo it
+struct Edit {
+    at: usize,
+    removed: String,
+    inserted: String,
+    kind: Kind,
+    /// Caret and anchor before the edit
+    before: (usize, usize),
+}
+
+/// Class of a char for word navigation
+#[derive(PartialEq)]
+enum Class {
+    Space,
+    Word,
+    Punct,
+}
+
+fn class(c: char) -> Class {
+    if c.is_whitespace() {
+        Class::Space
+    } else if c.is_alphanumeric() || c == '_' {
+        Class::Word
+    } else {
+        Class::Punct
+    }
+}
+
+/// Text with caret, selection, undo and redo
+///
+/// The selection is the range between the anchor and the caret, both are the
+/// same when nothing is selected. Consecutive typing and deleting are merged
+/// into a single undo step until the caret is moved
+pub struct TextBuffer {
+    text: String,
+    caret: usize,
+    anchor: usize,
+    multiline: bool,
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    /// The last edit can be extended
+    merge: bool,
+    /// Text being composed by an input method and its cursor
+    preedit: String,
+    preedit_cursor: Option<usize>,
+}
+
+impl TextBuffer {
+    /// Creates a buffer with the caret at the end, a single line buffer removes
+    /// the line breaks of the inserted text
+    pub fn new(text: &str, multiline: bool) -> Self {
+        let mut buffer = TextBuffer {
+            text: String::new(),
+            caret: 0,
+            anchor: 0,
+            multiline,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            merge: false,
+            preedit: String::new(),
+            preedit_cursor: None,
+        };
+        buffer.set_text(text);
+
+        buffer
+    }
+
+    #[inline]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    #[inline]
+    pub fn is_multiline(&self) -> bool {
+        self.multiline
+    }
+
+    /// Replace the whole text and clear the history, the caret goes to the end
+    pub fn set_text(&mut self, text: &str) {
+        self.text = self.filter(text);
+        self.caret = self.text.len();
+        self.anchor = self.caret;
+
+        self.undo.clear();
+        self.redo.clear();
+        self.merge = false;
+    }
+
+    // -- Caret and Selection --
+
+    #[inline]
+    pub fn caret(&self) -> usize {
+        self.caret
+    }
+
+    #[inline]
+    pub fn anchor(&self) -> usize {
+        self.anchor
+    }
+
+    /// Selected range, it's empty if nothing is selected
+    pub fn selection(&self) -> Range<usize> {
+        self.caret.min(self.anchor)..self.caret.max(self.anchor)
+    }
+
+    #[inline]
+    pub fn has_selection(&self) -> bool {
+        self.caret != self.anchor
+    }
+
+    pub fn selected_text(&self) -> &str {
+        &self.text[self.selection()]
+    }
+
+    /// Move the caret to a position, the anchor stays if it's selecting
+    pub fn set_caret(&mut self, pos: usize, select: bool) {
+        let mut pos = pos.min(self.text.len());
+        while !self.text.is_char_boundary(pos) {
+            pos -= 1;
+        }
+
+        self.caret = pos;
+        if !select {
+            self.anchor = pos;
+        }
+
+        self.merge = false;
+    }
+
+    pub fn select_all(&mut self) {
+        self.anchor = 0;
+        self.caret = self.text.len();
+        self.merge = false;
+    }
+
+    // -- Positions --
+
+    /// Position of the previous char
+    pub fn prev_char(&self, pos: usize) -> usize {
+        self.text[..pos]
+            .char_indices()
+            .next_back()
+            .map_or(0, |(n, _)| n)
+    }
+
+    /// Position of the next char
+    pub fn next_char(&self, pos: usize) -> usize {
+        self.text[pos..]
+            .chars()
+            .next()
+            .map_or(pos, |c| pos + c.len_utf8())
+    }
+
+    /// Start of the word before a position, spaces are skipped
+    pub fn prev_word(&self, pos: usize) -> usize {
+        let mut chars = self.text[..pos].char_indices().rev().peekable();
+        while chars.next_if(|&(_, c)| class(c) == Class::Space).is_some() {}
+
+        let mut start = chars.peek().map_or(0, |&(n, _)| n + 1);
+        if let Some(&(_, first)) = chars.peek() {
+            let first = class(first);
+            while let Some((n, _)) = chars.next_if(|&(_, c)| class(c) == first) {
+                start = n;
+            }
+        }
+
+        start
+    }
+
+    /// End of the word after a position, spaces are skipped
+    pub fn next_word(&self, pos: usize) -> usize {
+        let mut chars = self.text[pos..].char_indices().peekable();
+        while chars.next_if(|&(_, c)| class(c) == Class::Space).is_some() {}
+
+        let mut end = chars.peek().map_or(self.text.len() - pos, |&(n, _)| n);
+        if let Some(&(_, first)) = chars.peek() {
+            let first = class(first);
+            while let Some((n, c)) = chars.next_if(|&(_, c)| class(c) == first) {
+                end = n + c.len_utf8();
+            }
+        }
+
+        pos + end
+    }
+
+    /// Start of the line of a position
+    pub fn line_start(&self, pos: usize) -> usize {
+        self.text[..pos].rfind('\n').map_or(0, |n| n + 1)
+    }
+
+    /// End of the line of a position, before the line break
+    pub fn line_end(&self, pos: usize) -> usize {
+        self.text[pos..]
+            .find('\n')
+            .map_or(self.text.len(), |n| pos + n)
+    }
+
+    // -- Movement --
+
+    /// Move the caret to a position, without selecting it collapses the selection
+    /// to the side of the movement
+    fn move_to(&mut self, pos: usize, select: bool, forward: bool) {
+        if !select && self.has_selection() {
+            let range = self.selection();
+            let pos = if forward { range.end } else { range.start };
+
+            return self.set_caret(pos, false);
+        }
+
+        self.set_caret(pos, select);
+    }
+
+    pub fn move_left(&mut self, select: bool) {
+        self.move_to(self.prev_char(self.caret), select, false);
+    }
+
+    pub fn move_right(&mut self, select: bool) {
+        self.move_to(self.next_char(self.caret), select, true);
+    }
+
+    pub fn word_left(&mut self, select: bool) {
+        self.set_caret(self.prev_word(self.caret), select);
+    }
+
+    pub fn word_right(&mut self, select: bool) {
+        self.set_caret(self.next_word(self.caret), select);
+    }
+
+    pub fn home(&mut self, select: bool) {
+        self.set_caret(self.line_start(self.caret), select);
+    }
+
+    pub fn end(&mut self, select: bool) {
+        self.set_caret(self.line_end(self.caret), select);
+    }
+
+    // -- Editing --
+
+    /// Remove line breaks from a single line text
+    fn filter(&self, text: &str) -> String {
+        if self.multiline {
+            text.replace("\r\n", "\n")
+        } else {
+            text.chars().filter(|&c| c != '\n' && c != '\r').collect()
+        }
+    }
+
+    /// Replace a range and record the edit, the caret goes to the end of the inserted text
+    fn replace(&mut self, range: Range<usize>, text: &str, kind: Kind) {
+        let before = (self.caret, self.anchor);
+        let removed = self.text[range.clone()].to_string();
+        self.text.replace_range(range.clone(), text);
+
+        self.caret = range.start + text.len();
+        self.anchor = self.caret;
+        self.redo.clear();
+
+        // Extend the last edit if it's next to this one
+        if self.merge && kind != Kind::Other {
+            if let Some(last) = self.undo.last_mut().filter(|last| last.kind == kind) {
+                let merged = match kind {
+                    Kind::Insert if removed.is_empty() => {
+                        let end = last.at + last.inserted.len() == range.start;
+                        // A space after a word starts a new step
+                        let word = !text.starts_with(' ') || last.inserted.ends_with(' ');
+
+                        end && word && {
+                            last.inserted.push_str(text);
+                            true
+                        }
+                    }
+                    Kind::Backspace if range.end == last.at => {
+                        last.removed.insert_str(0, &removed);
+                        last.at = range.start;
+                        true
+                    }
+                    Kind::Delete if range.start == last.at => {
+                        last.removed.push_str(&removed);
+                        true
+                    }
+                    _ => false,
+                };
+
+                if merged {
+                    return;
+                }
+            }
+        }
+
+        self.undo.push(Edit {
+            at: range.start,
+            removed,
+            inserted: text.to_string(),
+            kind,
+            before,
+        });
+        self.merge = kind != Kind::Other;
+    }
+
+    /// Insert a text replacing the selection, returns false if nothing changed
+    pub fn insert(&mut self, text: &str) -> bool {
+        let text = self.filter(text);
+        if text.is_empty() && !self.has_selection() {
+            return false;
+        }
+
+        let kind = if self.has_selection() {
+            Kind::Other
+        } else {
+            Kind::Insert
+        };
+
+        self.replace(self.selection(), &text, kind);
+        true
+    }
+
+    /// Remove the selection, returns false if nothing was selected
+    pub fn delete_selection(&mut self) -> bool {
+        if !self.has_selection() {
+            return false;
+        }
+
+        self.replace(self.selection(), "", Kind::Other);
+        true
+    }
+
+    /// Remove the selection or the char or word before the caret
+    pub fn backspace(&mut self, word: bool) -> bool {
+        if self.delete_selection() {
+            return true;
+        }
+
+        let start = if word {
+            self.prev_word(self.caret)
+        } else {
+            self.prev_char(self.caret)
+        };
+
+        if start == self.caret {
+            return false;
+        }
+
+        self.replace(start..self.caret, "", Kind::Backspace);
+        true
+    }
+
+    /// Remove the selection or the char or word after the caret
+    pub fn delete(&mut self, word: bool) -> bool {
+        if self.delete_selection() {
+            return true;
+        }
+
+        let end = if word {
+            self.next_word(self.caret)
+        } else {
+            self.next_char(self.caret)
+        };
+
+        if end == self.caret {
+            return false;
+        }
+
+        self.replace(self.caret..end, "", Kind::Delete);
+        true
+    }
+
+    // -- History --
+
+    #[inline]
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    #[inline]
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Revert the last edit, the caret and the selection are restored
+    pub fn undo(&mut self) -> bool {
+        match self.undo.pop() {
+            Some(edit) => {
+                let range = edit.at..edit.at + edit.inserted.len();
+                self.text.replace_range(range, &edit.removed);
+                (self.caret, self.anchor) = edit.before;
+
+                self.redo.push(edit);
+                self.merge = false;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Apply again the last reverted edit
+    pub fn redo(&mut self) -> bool {
+        match self.redo.pop() {
+            Some(edit) => {
+                let range = edit.at..edit.at + edit.removed.len();
+                self.text.replace_range(range, &edit.inserted);
+                self.caret = edit.at + edit.inserted.len();
+                self.anchor = self.caret;
+
+                self.undo.push(edit);
+                self.merge = false;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // -- Input Method --
+
+    /// Text being composed, it's shown at the caret but it isn't part of the text
+    #[inline]
+    pub fn preedit(&self) -> &str {
+        &self.preedit
+    }
+
+    /// Cursor of the text being composed
+    #[inline]
+    pub fn preedit_cursor(&self) -> Option<usize> {
+        self.preedit_cursor
+    }
+
+    /// Replace the text being composed, an empty text cancels the composition.
+    /// A cursor inside a char is moved to the start of the char
+    pub fn set_preedit(&mut self, text: &str, cursor: Option<usize>) {
+        self.preedit = self.filter(text);
+        self.preedit_cursor = cursor.map(|c| {
+            let mut c = c.min(self.preedit.len());
+            while !self.preedit.is_char_boundary(c) {
+                c -= 1;
+            }
+
+            c
+        });
+    }
+
+    /// Insert a composed text and end the composition
+    pub fn commit(&mut self, text: &str) -> bool {
+        self.preedit.clear();
+        self.preedit_cursor = None;
+
+        self.insert(text)
+    }
+}
+
+impl Default for TextBuffer {
+    fn default() -> Self {
+        Self::new("", false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TextBuffer;
+
+    #[test]
+    fn insert_and_delete() {
+        let mut buffer = TextBuffer::new("", false);
+        assert!(buffer.insert("hello"));
+        assert!(buffer.insert(" world"));
+        assert_eq!(buffer.text(), "hello world");
+        assert_eq!(buffer.caret(), 11);
+
+        assert!(buffer.backspace(false));
+        assert_eq!(buffer.text(), "hello worl");
+
+        buffer.set_caret(0, false);
+        assert!(buffer.delete(false));
+        assert_eq!(buffer.text(), "ello worl");
+        assert!(!buffer.backspace(false));
+
+        assert!(buffer.delete(true));
+        assert_eq!(buffer.text(), " worl");
+
+        buffer.set_caret(5, false);
+        assert!(buffer.backspace(true));
+        assert_eq!(buffer.text(), " ");
+        assert!(!buffer.insert(""));
+    }
+
+    #[test]
+    fn line_breaks() {
+        let mut single = TextBuffer::new("a\nb", false);
+        assert_eq!(single.text(), "ab");
+        single.insert("c\r\nd");
+        assert_eq!(single.text(), "abcd");
+
+        let mut multi = TextBuffer::new("a\r\nb", true);
+        assert_eq!(multi.text(), "a\nb");
+        assert_eq!(multi.line_start(3), 2);
+        assert_eq!(multi.line_end(0), 1);
+
+        multi.home(false);
+        assert_eq!(multi.caret(), 2);
+    }
+
+    #[test]
+    fn char_and_word_motion() {
+        // 'é' is 2 bytes and '日' is 3 bytes
+        let mut buffer = TextBuffer::new("é日x", false);
+        assert_eq!(buffer.caret(), 6);
+
+        buffer.move_left(false);
+        assert_eq!(buffer.caret(), 5);
+        buffer.move_left(false);
+        assert_eq!(buffer.caret(), 2);
+        buffer.move_left(false);
+        assert_eq!(buffer.caret(), 0);
+        buffer.move_left(false);
+        assert_eq!(buffer.caret(), 0);
+
+        buffer.move_right(false);
+        assert_eq!(buffer.caret(), 2);
+
+        // Inside a char is moved to its start
+        buffer.set_caret(4, false);
+        assert_eq!(buffer.caret(), 2);
+
+        let mut buffer = TextBuffer::new("foo_bar, baz  qux", false);
+        buffer.word_left(false);
+        assert_eq!(buffer.caret(), 14);
+        buffer.word_left(false);
+        assert_eq!(buffer.caret(), 9);
+        buffer.word_left(false);
+        assert_eq!(buffer.caret(), 7);
+        buffer.word_left(false);
+        assert_eq!(buffer.caret(), 0);
+
+        buffer.word_right(false);
+        assert_eq!(buffer.caret(), 7);
+        buffer.word_right(false);
+        assert_eq!(buffer.caret(), 8);
+        buffer.word_right(false);
+        assert_eq!(buffer.caret(), 12);
+        buffer.end(false);
+        assert_eq!(buffer.caret(), 17);
+    }
+
+    #[test]
+    fn selection() {
+        let mut buffer = TextBuffer::new("abcdef", false);
+        buffer.set_caret(1, false);
+        buffer.move_right(true);
+        buffer.move_right(true);
+        assert_eq!(buffer.selection(), 1..3);
+        assert_eq!(buffer.selected_text(), "bc");
+
+        // Moving without selecting collapses to the side of the movement
+        buffer.move_left(false);
+        assert_eq!(buffer.caret(), 1);
+        assert!(!buffer.has_selection());
+
+        buffer.set_caret(4, true);
+        buffer.set_caret(2, true);
+        assert_eq!(buffer.selection(), 1..2);
+
+        buffer.select_all();
+        assert_eq!(buffer.selected_text(), "abcdef");
+        assert!(buffer.insert("x"));
+        assert_eq!(buffer.text(), "x");
+
+        buffer.select_all();
+        assert!(buffer.backspace(false));
+        assert_eq!(buffer.text(), "");
+        assert!(!buffer.delete_selection());
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut buffer = TextBuffer::new("", false);
+        for c in "one two".chars() {
+            buffer.insert(&c.to_string());
+        }
+
+        // The space starts a new step
+        assert!(buffer.undo());
+        assert_eq!(buffer.text(), "one");
+        assert!(buffer.undo());
+        assert_eq!(buffer.text(), "");
+        assert!(!buffer.undo());
+
+        assert!(buffer.redo());
+        assert_eq!(buffer.text(), "one");
+        assert_eq!(buffer.caret(), 3);
+        assert!(buffer.redo());
+        assert_eq!(buffer.text(), "one two");
+        assert!(!buffer.redo());
+
+        // Backspaces are merged and restore the caret
+        buffer.backspace(false);
+        buffer.backspace(false);
+        assert_eq!(buffer.text(), "one t");
+        assert!(buffer.undo());
+        assert_eq!(buffer.text(), "one two");
+        assert_eq!(buffer.caret(), 7);
+
+        // A new edit clears the redo
+        buffer.undo();
+        buffer.insert("!");
+        assert!(!buffer.can_redo());
+
+        // A selection is restored by undo
+        buffer.set_text("abc");
+        buffer.set_caret(0, true);
+        buffer.insert("x");
+        buffer.undo();
+        assert_eq!(buffer.text(), "abc");
+        assert_eq!((buffer.caret(), buffer.anchor()), (0, 3));
+    }
+
+    #[test]
+    fn preedit_and_commit() {
+        let mut buffer = TextBuffer::new("ab", false);
+        buffer.set_caret(1, false);
+
+        buffer.set_preedit("にほ", Some(3));
+        assert_eq!(buffer.preedit(), "にほ");
+        assert_eq!(buffer.preedit_cursor(), Some(3));
+        // The composition isn't part of the text
+        assert_eq!(buffer.text(), "ab");
+
+        // Inside a char and out of the text
+        buffer.set_preedit("にほ", Some(4));
+        assert_eq!(buffer.preedit_cursor(), Some(3));
+        buffer.set_preedit("にほ", Some(20));
+        assert_eq!(buffer.preedit_cursor(), Some(6));
+
+        assert!(buffer.commit("日本"));
+        assert_eq!(buffer.text(), "a日本b");
+        assert_eq!(buffer.caret(), 7);
+        assert_eq!(buffer.preedit(), "");
+        assert_eq!(buffer.preedit_cursor(), None);
+
+        buffer.set_preedit("x", None);
+        buffer.set_preedit("", None);
+        assert_eq!(buffer.preedit(), "");
+        assert!(!buffer.commit(""));
+        assert_eq!(buffer.text(), "a日本b");
+    }
+}
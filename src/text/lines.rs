@@ -0,0 +1,275 @@
+//! Line breaking and alignment of text
+//!
+//! Lines are byte ranges of the original text, so widgets can draw them with
+//! Painter::text without allocating a string per line
+
+use super::TextMeasure;
+use crate::math::Scalar;
+use crate::paint::FontID;
+use crate::widget::{Boundaries, Dimensions, Position};
+
+use std::ops::Range;
+
+/// Horizontal alignment of lines
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// A line of a text
+#[derive(Clone, PartialEq, Debug)]
+pub struct Line<T> {
+    /// Byte range in the text, without the line break and the trailing spaces
+    pub range: Range<usize>,
+    /// Advance of the line
+    pub width: T,
+}
+
+/// Break a paragraph into lines, None width only trims the spaces
+fn paragraph<T: Scalar>(
+    measure: &dyn TextMeasure<T>,
+    font: FontID,
+    size: T,
+    text: &str,
+    offset: usize,
+    max_width: Option<f32>,
+    lines: &mut Vec<Line<T>>,
+) {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut positions = Vec::with_capacity(chars.len() + 1);
+    measure.glyph_positions(font, size, text, &mut positions);
+
+    let pos: Vec<f32> = positions.into_iter().map(Scalar::to_f32).collect();
+    let byte = |n: usize| offset + chars.get(n).map_or(text.len(), |c| c.0);
+    let n = chars.len();
+
+    // Line without trailing spaces
+    let mut push = |start: usize, mut end: usize| {
+        while end > start && chars[end - 1].1.is_whitespace() {
+            end -= 1;
+        }
+
+        lines.push(Line {
+            range: byte(start)..byte(end),
+            width: T::from_f32(pos[end] - pos[start]),
+        });
+    };
+
+    if n == 0 {
+        return push(0, 0);
+    }
+
+    let max = match max_width {
+        Some(max) => max,
+        None => return push(0, n),
+    };
+
+    let mut start = 0;
+    while start < n {
+        let base = pos[start];
+        let (mut i, mut space) = (start, None);
+
+        while i < n {
+            // A line has at least one character
+            if i > start && pos[i + 1] - base > max {
+                break;
+            }
+
+            if chars[i].1.is_whitespace() {
+                space = Some(i);
+            }
+
+            i += 1;
+        }
+
+        start = match space {
+            _ if i == n => {
+                push(start, n);
+                n
+            }
+            Some(space) if space > start => {
+                push(start, space);
+                space + 1
+            }
+            // A word longer than the line is broken
+            _ => {
+                push(start, i);
+                i
+            }
+        };
+
+        // Spaces at the start of a wrapped line are skipped
+        while start < n && chars[start].1.is_whitespace() {
+            start += 1;
+        }
+    }
+}
+
+/// Break a text into lines with word wrapping, lines are also broken at '\n'
+///
+/// Without a width, the text is only broken at '\n'. Words longer than the width
+/// are broken between characters
+pub fn wrap<T: Scalar>(
+    measure: &dyn TextMeasure<T>,
+    font: FontID,
+    size: T,
+    text: &str,
+    width: Option<T>,
+) -> Vec<Line<T>> {
+    let mut lines = Vec::new();
+    let max = width.map(Scalar::to_f32);
+    let mut offset = 0;
+
+    for para in text.split('\n') {
+        let trimmed = para.strip_suffix('\r').unwrap_or(para);
+        paragraph(measure, font, size, trimmed, offset, max, &mut lines);
+
+        offset += para.len() + 1;
+    }
+
+    lines
+}
+
+/// Shorten a text with an ellipsis until it fits in a width
+pub fn ellipsis<T: Scalar>(
+    measure: &dyn TextMeasure<T>,
+    font: FontID,
+    size: T,
+    text: &str,
+    width: T,
+) -> String {
+    let max = width.to_f32();
+    if measure.advance(font, size, text).to_f32() <= max {
+        return text.to_string();
+    }
+
+    let dots = measure.advance(font, size, "…").to_f32();
+    let mut positions = Vec::new();
+    measure.glyph_positions(font, size, text, &mut positions);
+
+    // Longest prefix that fits with the ellipsis
+    let end = text
+        .char_indices()
+        .map(|(n, _)| n)
+        .zip(positions.iter())
+        .take_while(|(_, p)| p.to_f32() + dots <= max)
+        .last();
+
+    match end {
+        Some((end, _)) => format!("{}…", text[..end].trim_end()),
+        None => String::new(),
+    }
+}
+
+/// Horizontal offset of a line inside a width
+pub fn align_offset<T: Scalar>(align: Align, line: T, width: T) -> T {
+    let free = width.to_f32() - line.to_f32();
+    T::from_f32(match align {
+        Align::Left => 0.0,
+        Align::Center => free * 0.5,
+        Align::Right => free,
+    })
+}
+
+/// Height of a line, the sum of ascent and descent
+pub fn line_height<T: Scalar>(measure: &dyn TextMeasure<T>, font: FontID, size: T) -> T {
+    let ascent = measure.ascent(font, size).to_f32();
+    T::from_f32(ascent + measure.descent(font, size).to_f32())
+}
+
+/// Dimensions of a block of lines, useful for min_dimensions
+pub fn dimensions<T: Scalar>(
+    measure: &dyn TextMeasure<T>,
+    font: FontID,
+    size: T,
+    lines: &[Line<T>],
+) -> Dimensions<T> {
+    let width = lines.iter().map(|l| l.width.to_f32()).fold(0.0, f32::max);
+    let height = line_height(measure, font, size).to_f32() * lines.len() as f32;
+
+    (T::from_f32(width), T::from_f32(height))
+}
+
+/// Baseline positions of the lines inside boundaries, for Painter::text
+pub fn baselines<T: Scalar>(
+    measure: &dyn TextMeasure<T>,
+    font: FontID,
+    size: T,
+    lines: &[Line<T>],
+    bounds: Boundaries<T>,
+    align: Align,
+) -> Vec<Position<T>> {
+    let ascent = measure.ascent(font, size).to_f32();
+    let height = line_height(measure, font, size).to_f32();
+    let (x, y) = (bounds.0.to_f32(), bounds.1.to_f32());
+
+    lines
+        .iter()
+        .enumerate()
+        .map(|(n, line)| {
+            let offset = align_offset(align, line.width, bounds.2).to_f32();
+            (
+                T::from_f32(x + offset),
+                T::from_f32(y + ascent + height * n as f32),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::Monospace;
+
+    /// Every char is 1 wide
+    const MONO: Monospace = Monospace {
+        advance: 1.0,
+        ascent: 0.8,
+        descent: 0.2,
+    };
+
+    fn texts(text: &str, width: Option<f32>) -> Vec<&str> {
+        wrap(&MONO, 0, 1.0, text, width)
+            .into_iter()
+            .map(|line| &text[line.range])
+            .collect()
+    }
+
+    #[test]
+    fn long_words() {
+        assert_eq!(texts("abcdefghij", Some(4.0)), ["abcd", "efgh", "ij"]);
+        assert_eq!(texts("hi abcdefgh", Some(4.0)), ["hi", "abcd", "efgh"]);
+        assert_eq!(texts("ab   cd ef", Some(5.0)), ["ab", "cd ef"]);
+        // A line has at least one character
+        assert_eq!(texts("abc", Some(0.5)), ["a", "b", "c"]);
+
+        let lines = wrap(&MONO, 0, 1.0, "abcdef", Some(4.0));
+        assert_eq!(lines[1].range, 4..6);
+        assert_eq!(lines[1].width, 2.0);
+    }
+
+    #[test]
+    fn empty_lines() {
+        assert_eq!(texts("", None), [""]);
+        assert_eq!(texts("a\n\nb", Some(4.0)), ["a", "", "b"]);
+        assert_eq!(texts("a\r\n", None), ["a", ""]);
+        assert_eq!(texts("   \nb", Some(2.0)), ["", "b"]);
+
+        let lines = wrap(&MONO, 0, 1.0, "a\n\nb", None);
+        assert_eq!(lines[1].range, 2..2);
+        assert_eq!(lines[1].width, 0.0);
+        assert_eq!(dimensions(&MONO, 0, 1.0, &lines), (1.0, 3.0));
+    }
+
+    #[test]
+    fn ellipsis_width() {
+        assert_eq!(ellipsis(&MONO, 0, 1.0, "hello", 5.0), "hello");
+        assert_eq!(ellipsis(&MONO, 0, 1.0, "hello world", 7.0), "hello…");
+        assert_eq!(ellipsis(&MONO, 0, 1.0, "hello", 1.0), "…");
+        // The ellipsis alone is wider than the width
+        assert_eq!(ellipsis(&MONO, 0, 1.0, "hello", 0.5), "");
+        assert_eq!(ellipsis(&MONO, 0, 1.0, "", 0.0), "");
+    }
+}
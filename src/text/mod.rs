@@ -0,0 +1,95 @@
+//! Text measurement for widgets
+//!
+//! A backend provides a TextMeasure for its fonts. Root calls Widget::measure with
+//! it before every complete layout, so widgets with text can store the sizes they
+//! need and answer min_dimensions. The lines module breaks and aligns text using
+//! the same measurements
+
+pub mod buffer;
+pub mod lines;
+
+use crate::math::Scalar;
+use crate::paint::FontID;
+
+use std::rc::Rc;
+
+/// Text measurement of a backend
+///
+/// Distances use the same units than widget boundaries, sizes are the same of
+/// Painter::text
+pub trait TextMeasure<T: Copy> {
+    /// Horizontal distance from the start to the end of a text run
+    fn advance(&self, font: FontID, size: T, text: &str) -> T;
+    /// Distance from the baseline to the top of the line, positive upwards
+    fn ascent(&self, font: FontID, size: T) -> T;
+    /// Distance from the baseline to the bottom of the line, positive downwards
+    fn descent(&self, font: FontID, size: T) -> T;
+
+    /// Horizontal position of every character from the start of the text, the
+    /// last position is the advance of the whole text
+    fn glyph_positions(&self, font: FontID, size: T, text: &str, positions: &mut Vec<T>) {
+        positions.clear();
+        positions.extend(
+            text.char_indices()
+                .map(|(n, _)| n)
+                .chain(Some(text.len()))
+                .map(|end| self.advance(font, size, &text[..end])),
+        );
+    }
+}
+
+/// TextMeasure shared by the Root with the widgets
+///
+/// Widgets that measure text after the layout, like text editors, keep a clone
+pub type SharedMeasure<T> = Rc<dyn TextMeasure<T>>;
+
+/// Measure of fonts where every character has the same advance
+///
+/// The metrics are ratios of the size, a terminal with cells uses
+/// Monospace::new(1.0, 1.0, 0.0) and a size of 1
+#[derive(Clone, Copy, Debug)]
+pub struct Monospace {
+    advance: f32,
+    ascent: f32,
+    descent: f32,
+}
+
+impl Monospace {
+    pub fn new(advance: f32, ascent: f32, descent: f32) -> Self {
+        Monospace {
+            advance,
+            ascent,
+            descent,
+        }
+    }
+}
+
+impl<T: Scalar> TextMeasure<T> for Monospace {
+    fn advance(&self, _: FontID, size: T, text: &str) -> T {
+        let count = text.chars().filter(|c| !c.is_control()).count();
+        T::from_f32(count as f32 * self.advance * size.to_f32())
+    }
+
+    fn ascent(&self, _: FontID, size: T) -> T {
+        T::from_f32(self.ascent * size.to_f32())
+    }
+
+    fn descent(&self, _: FontID, size: T) -> T {
+        T::from_f32(self.descent * size.to_f32())
+    }
+
+    fn glyph_positions(&self, _: FontID, size: T, text: &str, positions: &mut Vec<T>) {
+        let advance = self.advance * size.to_f32();
+        let mut count = 0;
+
+        positions.clear();
+        for c in text.chars() {
+            positions.push(T::from_f32(count as f32 * advance));
+            if !c.is_control() {
+                count += 1;
+            }
+        }
+
+        positions.push(T::from_f32(count as f32 * advance));
+    }
+}
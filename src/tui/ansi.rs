@@ -0,0 +1,223 @@
+//! ANSI escape-sequence renderer
+
+use super::{char_width, Cell, CellBuffer, CellStyle, CONTINUATION};
+use super::{BOLD, DIM, ITALIC, REVERSE, UNDERLINE};
+use crate::paint::Color;
+
+use std::io::{self, Write};
+
+/// Switch to the alternate screen, hide the cursor and enable SGR mouse reports
+pub fn enter<W: Write>(out: &mut W) -> io::Result<()> {
+    out.write_all(b"\x1b[?1049h\x1b[?25l\x1b[?1003h\x1b[?1006h\x1b[2J")?;
+    out.flush()
+}
+
+/// Restore the terminal changed by enter
+pub fn leave<W: Write>(out: &mut W) -> io::Result<()> {
+    out.write_all(b"\x1b[0m\x1b[?1006l\x1b[?1003l\x1b[?25h\x1b[?1049l")?;
+    out.flush()
+}
+
+/// Pen of the terminal used for write a cell
+#[derive(Clone, Copy, PartialEq)]
+struct Pen {
+    fg: Color,
+    bg: Color,
+    style: CellStyle,
+}
+
+impl Pen {
+    /// Reset the attributes and set the pen
+    fn write(self, out: &mut Vec<u8>) {
+        out.extend_from_slice(b"\x1b[0");
+
+        let styles = [
+            (BOLD, 1),
+            (DIM, 2),
+            (ITALIC, 3),
+            (UNDERLINE, 4),
+            (REVERSE, 7),
+        ];
+        for &(flag, code) in styles.iter() {
+            if self.style & flag != 0 {
+                let _ = write!(out, ";{}", code);
+            }
+        }
+
+        if self.fg.a > 0 {
+            let _ = write!(out, ";38;2;{};{};{}", self.fg.r, self.fg.g, self.fg.b);
+        }
+
+        if self.bg.a > 0 {
+            let _ = write!(out, ";48;2;{};{};{}", self.bg.r, self.bg.g, self.bg.b);
+        }
+
+        out.push(b'm');
+    }
+}
+
+/// Renderer that writes only the cells changed since the previous frame
+pub struct AnsiRenderer {
+    front: Vec<Cell>,
+    width: usize,
+    height: usize,
+    full: bool,
+}
+
+impl AnsiRenderer {
+    /// Creates a renderer, the first frame writes every cell
+    pub fn new() -> Self {
+        AnsiRenderer {
+            front: Vec::new(),
+            width: 0,
+            height: 0,
+            full: true,
+        }
+    }
+
+    /// Write every cell in the next frame, useful when the screen was changed by others
+    pub fn invalidate(&mut self) {
+        self.full = true;
+    }
+
+    /// Write the changed cells of a buffer and return how many cells were written
+    pub fn render<W: Write>(&mut self, buffer: &CellBuffer, out: &mut W) -> io::Result<usize> {
+        let (width, height) = (buffer.width(), buffer.height());
+        let mut bytes = Vec::new();
+
+        if self.full || (width, height) != (self.width, self.height) {
+            bytes.extend_from_slice(b"\x1b[0m\x1b[2J");
+
+            self.front = vec![Cell::default(); width * height];
+            self.width = width;
+            self.height = height;
+            self.full = false;
+        }
+
+        // Cursor position and pen of the terminal, None if they are unknown
+        let mut cursor: Option<(usize, usize)> = None;
+        let mut pen: Option<Pen> = None;
+        let mut count = 0;
+
+        for (n, (cell, front)) in buffer.cells().iter().zip(self.front.iter_mut()).enumerate() {
+            // A cleared screen is filled with default cells
+            if cell == front {
+                continue;
+            }
+
+            // The wide character at the left already covered the cell
+            if cell.ch == CONTINUATION {
+                *front = *cell;
+                continue;
+            }
+
+            let (x, y) = (n % width, n / width);
+            if cursor != Some((x, y)) {
+                let _ = write!(bytes, "\x1b[{};{}H", y + 1, x + 1);
+            }
+
+            let next = Pen {
+                fg: cell.fg,
+                bg: cell.bg,
+                style: cell.style,
+            };
+
+            if pen != Some(next) {
+                next.write(&mut bytes);
+                pen = Some(next);
+            }
+
+            let mut utf8 = [0u8; 4];
+            bytes.extend_from_slice(cell.ch.encode_utf8(&mut utf8).as_bytes());
+
+            // The cursor doesn't move after the last column
+            let next = x + char_width(cell.ch).max(1);
+            cursor = if next < width { Some((next, y)) } else { None };
+            *front = *cell;
+            count += 1;
+        }
+
+        if pen.is_some() {
+            bytes.extend_from_slice(b"\x1b[0m");
+        }
+
+        if !bytes.is_empty() {
+            out.write_all(&bytes)?;
+            out.flush()?;
+        }
+
+        Ok(count)
+    }
+}
+
+impl Default for AnsiRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(renderer: &mut AnsiRenderer, buffer: &CellBuffer) -> (usize, String) {
+        let mut out = Vec::new();
+        let count = renderer.render(buffer, &mut out).unwrap();
+
+        (count, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn diff() {
+        let mut renderer = AnsiRenderer::new();
+        let mut buffer = CellBuffer::new(4, 2);
+        let red = Color::rgb(255, 0, 0);
+
+        // The first frame clears the screen, blank cells are not written
+        buffer.print(1, 0, "ab", red, BOLD);
+        assert_eq!(
+            render(&mut renderer, &buffer),
+            (
+                2,
+                "\x1b[0m\x1b[2J\x1b[1;2H\x1b[0;1;38;2;255;0;0mab\x1b[0m".to_string()
+            )
+        );
+        assert_eq!(render(&mut renderer, &buffer), (0, String::new()));
+
+        // Only the changed cell moves the cursor
+        buffer.print(2, 0, "c", red, BOLD);
+        buffer.print(3, 1, "d", Color::TRANSPARENT, 0);
+        assert_eq!(
+            render(&mut renderer, &buffer),
+            (
+                2,
+                "\x1b[1;3H\x1b[0;1;38;2;255;0;0mc\x1b[2;4H\x1b[0md\x1b[0m".to_string()
+            )
+        );
+
+        renderer.invalidate();
+        assert_eq!(render(&mut renderer, &buffer).0, 3);
+    }
+
+    #[test]
+    fn wide_chars() {
+        let mut renderer = AnsiRenderer::new();
+        let mut buffer = CellBuffer::new(4, 1);
+
+        buffer.print(0, 0, "\u{4e16}a", Color::TRANSPARENT, 0);
+        assert_eq!(
+            render(&mut renderer, &buffer),
+            (
+                2,
+                "\x1b[0m\x1b[2J\x1b[1;1H\x1b[0m\u{4e16}a\x1b[0m".to_string()
+            )
+        );
+
+        // Breaking the wide character writes both halves
+        buffer.print(1, 0, "b", Color::TRANSPARENT, 0);
+        assert_eq!(
+            render(&mut renderer, &buffer),
+            (2, "\x1b[1;1H\x1b[0m b\x1b[0m".to_string())
+        );
+    }
+}
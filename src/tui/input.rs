@@ -0,0 +1,401 @@
+//! Terminal input decoder
+
+use crate::math::Scalar;
+use crate::state::keys::{self, ALT, CTRL, SHIFT, SUPER};
+use crate::state::{KeyState, MouseState, MouseType};
+
+use std::ops::Sub;
+
+/// Event decoded from terminal input
+pub enum TermEvent<'a, T> {
+    Mouse(&'a MouseState<T>),
+    Key(KeyState),
+}
+
+/// Result of parsing the start of the input
+enum Parsed {
+    /// The sequence needs more bytes
+    Incomplete,
+    /// Ignore an unknown sequence of a length
+    Skip(usize),
+    /// Length, key code and modifiers
+    Key(usize, u32, u16),
+    /// Length, SGR button code, cell column, cell row and if it's a press
+    Mouse(usize, u32, u32, u32, bool),
+}
+
+/// Modifiers of a CSI parameter, the value is 1 + bitflags
+fn csi_mods(value: Option<u32>) -> u16 {
+    let bits = value.unwrap_or(1).saturating_sub(1);
+    let mut mods = 0;
+
+    if bits & 1 != 0 {
+        mods |= SHIFT;
+    }
+    if bits & 2 != 0 {
+        mods |= ALT;
+    }
+    if bits & 4 != 0 {
+        mods |= CTRL;
+    }
+    if bits & 8 != 0 {
+        mods |= SUPER;
+    }
+
+    mods
+}
+
+/// Key of the final byte of a CSI or SS3 sequence
+fn final_key(byte: u8) -> Option<u32> {
+    match byte {
+        b'A' => Some(keys::UP),
+        b'B' => Some(keys::DOWN),
+        b'C' => Some(keys::RIGHT),
+        b'D' => Some(keys::LEFT),
+        b'H' => Some(keys::HOME),
+        b'F' => Some(keys::END),
+        b'P'..=b'S' => Some(keys::function((byte - b'P') as u32 + 1)),
+        _ => None,
+    }
+}
+
+/// Key of a CSI sequence with ~ as final byte
+fn tilde_key(n: u32) -> Option<u32> {
+    match n {
+        1 | 7 => Some(keys::HOME),
+        2 => Some(keys::INSERT),
+        3 => Some(keys::DELETE),
+        4 | 8 => Some(keys::END),
+        5 => Some(keys::PAGE_UP),
+        6 => Some(keys::PAGE_DOWN),
+        11..=15 => Some(keys::function(n - 10)),
+        17..=21 => Some(keys::function(n - 11)),
+        23 | 24 => Some(keys::function(n - 12)),
+        _ => None,
+    }
+}
+
+fn parse_csi(buf: &[u8]) -> Parsed {
+    // Parameters and intermediate bytes until the final byte
+    let end = match buf[2..].iter().position(|b| (0x40..=0x7e).contains(b)) {
+        Some(n) => n + 2,
+        None if buf.len() > 32 => return Parsed::Skip(buf.len()),
+        None => return Parsed::Incomplete,
+    };
+
+    let (params, last) = (&buf[2..end], buf[end]);
+    let len = end + 1;
+
+    // SGR mouse report: ESC [ < button ; column ; row M/m
+    if let Some(b'<') = params.first() {
+        let values: Vec<u32> = params[1..]
+            .split(|&b| b == b';')
+            .filter_map(|p| std::str::from_utf8(p).ok()?.parse().ok())
+            .collect();
+
+        return match (values.as_slice(), last) {
+            (&[b, x, y], b'M') => Parsed::Mouse(len, b, x, y, true),
+            (&[b, x, y], b'm') => Parsed::Mouse(len, b, x, y, false),
+            _ => Parsed::Skip(len),
+        };
+    }
+
+    let mut values = params.split(|&b| b == b';').map(|p| {
+        std::str::from_utf8(p)
+            .ok()
+            .and_then(|p| p.parse::<u32>().ok())
+    });
+    let first = values.next().flatten();
+    let mods = csi_mods(values.next().flatten());
+
+    let code = match last {
+        b'~' => first.and_then(tilde_key),
+        b'Z' => return Parsed::Key(len, keys::TAB, SHIFT),
+        byte => final_key(byte),
+    };
+
+    match code {
+        Some(code) => Parsed::Key(len, code, mods),
+        None => Parsed::Skip(len),
+    }
+}
+
+fn parse(buf: &[u8]) -> Parsed {
+    let byte = buf[0];
+
+    match byte {
+        0x1b => match buf.get(1) {
+            None => Parsed::Incomplete,
+            Some(b'[') => parse_csi(buf),
+            Some(b'O') => match buf.get(2) {
+                None => Parsed::Incomplete,
+                Some(&last) => match final_key(last) {
+                    Some(code) => Parsed::Key(3, code, 0),
+                    None => Parsed::Skip(3),
+                },
+            },
+            Some(0x1b) => Parsed::Key(1, keys::ESCAPE, 0),
+            // A key prefixed with ESC is pressed with Alt
+            Some(_) => match parse(&buf[1..]) {
+                Parsed::Key(len, code, mods) => Parsed::Key(len + 1, code, mods | ALT),
+                Parsed::Incomplete => Parsed::Incomplete,
+                _ => Parsed::Key(1, keys::ESCAPE, 0),
+            },
+        },
+        b'\r' | b'\n' => Parsed::Key(1, keys::ENTER, 0),
+        b'\t' => Parsed::Key(1, keys::TAB, 0),
+        0x7f | 0x08 => Parsed::Key(1, keys::BACKSPACE, 0),
+        0x00 => Parsed::Key(1, keys::SPACE, CTRL),
+        // Ctrl + letter
+        0x01..=0x1a => Parsed::Key(1, (b'a' + byte - 1) as u32, CTRL),
+        0x1c..=0x1f => Parsed::Key(1, (b'\\' + byte - 0x1c) as u32, CTRL),
+        0x20..=0x7e => Parsed::Key(1, byte as u32, 0),
+        _ => {
+            let len = match byte {
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                0xf0..=0xf7 => 4,
+                _ => return Parsed::Skip(1),
+            };
+
+            if buf.len() < len {
+                return Parsed::Incomplete;
+            }
+
+            match std::str::from_utf8(&buf[..len]) {
+                Ok(s) => Parsed::Key(len, s.chars().next().unwrap() as u32, 0),
+                Err(_) => Parsed::Skip(1),
+            }
+        }
+    }
+}
+
+/// Decoder of terminal bytes, keys and SGR mouse reports
+///
+/// Incomplete sequences are kept until the next bytes arrive. A lone ESC can't be
+/// told apart from the start of a sequence, call flush after a short timeout
+pub struct InputDecoder<T> {
+    mouse: MouseState<T>,
+    pending: Vec<u8>,
+}
+
+impl<T> InputDecoder<T>
+where
+    T: Scalar + Default + Sub<Output = T>,
+{
+    /// Creates a new decoder
+    pub fn new() -> Self {
+        InputDecoder {
+            mouse: MouseState::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Check if there are bytes waiting for the rest of a sequence
+    #[inline]
+    pub fn is_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Decode bytes read from the terminal, a key is reported as pressed and released
+    pub fn decode<F>(&mut self, bytes: &[u8], mut f: F)
+    where
+        F: FnMut(TermEvent<T>),
+    {
+        self.pending.extend_from_slice(bytes);
+        let mut start = 0;
+
+        while start < self.pending.len() {
+            let len = match parse(&self.pending[start..]) {
+                Parsed::Incomplete => break,
+                Parsed::Skip(len) => len,
+                Parsed::Key(len, code, mods) => {
+                    f(TermEvent::Key(KeyState::Pressed(code, mods)));
+                    f(TermEvent::Key(KeyState::Released(code, mods)));
+
+                    len
+                }
+                Parsed::Mouse(len, b, x, y, press) => {
+                    self.mouse(b, x, y, press, &mut f);
+                    len
+                }
+            };
+
+            start += len;
+        }
+
+        self.pending.drain(..start);
+    }
+
+    /// Decode the pending bytes as they are, a lone ESC becomes the Escape key and
+    /// other incomplete sequences are discarded
+    pub fn flush<F>(&mut self, mut f: F)
+    where
+        F: FnMut(TermEvent<T>),
+    {
+        let pending = std::mem::take(&mut self.pending);
+        if pending.first() == Some(&0x1b) {
+            f(TermEvent::Key(KeyState::Pressed(keys::ESCAPE, 0)));
+            f(TermEvent::Key(KeyState::Released(keys::ESCAPE, 0)));
+
+            self.decode(&pending[1..], f);
+        }
+    }
+
+    /// Update the MouseState with a SGR report, buttons are numbered from 1 as
+    /// left, middle and right, the wheel is positive upwards
+    fn mouse<F>(&mut self, b: u32, x: u32, y: u32, press: bool, f: &mut F)
+    where
+        F: FnMut(TermEvent<T>),
+    {
+        let mut mods = 0;
+        if b & 4 != 0 {
+            mods |= SHIFT;
+        }
+        if b & 8 != 0 {
+            mods |= ALT;
+        }
+        if b & 16 != 0 {
+            mods |= CTRL;
+        }
+
+        let pos = (
+            T::from_f32(x.saturating_sub(1) as f32),
+            T::from_f32(y.saturating_sub(1) as f32),
+        );
+
+        let m_type = match b & !(4 | 8 | 16) {
+            64 => MouseType::Wheel(1.0),
+            65 => MouseType::Wheel(-1.0),
+            // Horizontal wheel
+            66 | 67 => return,
+            b if b & 32 != 0 => MouseType::CursorMoved,
+            // Release without button of legacy reports
+            b if b & 3 == 3 => return,
+            b if press => MouseType::Pressed((b & 3) as u8 + 1),
+            b => MouseType::Released((b & 3) as u8 + 1),
+        };
+
+        self.mouse.set_modifiers(mods);
+        self.mouse.set_position(pos);
+        self.mouse.set_type(m_type);
+
+        f(TermEvent::Mouse(&self.mouse));
+    }
+}
+
+impl<T> Default for InputDecoder<T>
+where
+    T: Scalar + Default + Sub<Output = T>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pressed keys and mouse events as text
+    fn decode(decoder: &mut InputDecoder<f32>, bytes: &[u8]) -> Vec<String> {
+        let mut events = Vec::new();
+        decoder.decode(bytes, |event| match event {
+            TermEvent::Key(KeyState::Pressed(code, mods)) => {
+                events.push(format!("key {} {}", code, mods))
+            }
+            TermEvent::Key(KeyState::Released(..)) => {}
+            TermEvent::Mouse(m) => {
+                let pos = m.absolute_pos();
+                let m_type = match m.m_type {
+                    MouseType::Pressed(b) => format!("press {}", b),
+                    MouseType::Released(b) => format!("release {}", b),
+                    MouseType::CursorMoved => "move".to_string(),
+                    MouseType::Wheel(delta) => format!("wheel {}", delta),
+                    _ => "other".to_string(),
+                };
+
+                events.push(format!("{} {} {}", m_type, pos.0, pos.1))
+            }
+        });
+
+        events
+    }
+
+    fn key(code: u32, mods: u16) -> String {
+        format!("key {} {}", code, mods)
+    }
+
+    #[test]
+    fn keys() {
+        let mut decoder = InputDecoder::new();
+
+        assert_eq!(
+            decode(&mut decoder, b"\x1b[A\x1bOQ"),
+            [key(keys::UP, 0), key(keys::function(2), 0)]
+        );
+        assert_eq!(
+            decode(&mut decoder, b"\x1b[1;5C\x1b[3;2~\x1b[Z"),
+            [
+                key(keys::RIGHT, CTRL),
+                key(keys::DELETE, SHIFT),
+                key(keys::TAB, SHIFT)
+            ]
+        );
+        assert_eq!(
+            decode(&mut decoder, b"\x1b[15;7~\x01"),
+            [key(keys::function(5), ALT | CTRL), key('a' as u32, CTRL)]
+        );
+    }
+
+    #[test]
+    fn mouse() {
+        let mut decoder = InputDecoder::new();
+
+        assert_eq!(
+            decode(&mut decoder, b"\x1b[<0;3;2M\x1b[<32;4;2M"),
+            ["press 1 2 1", "move 3 1"]
+        );
+        assert_eq!(
+            decode(&mut decoder, b"\x1b[<0;5;3m\x1b[<65;5;3M"),
+            ["release 1 4 2", "wheel -1 4 2"]
+        );
+
+        // A report split across reads
+        assert!(decode(&mut decoder, b"\x1b[<2;1").is_empty());
+        assert!(decoder.is_pending());
+        assert_eq!(decode(&mut decoder, b";1M"), ["press 3 0 0"]);
+    }
+
+    #[test]
+    fn escape() {
+        let mut decoder = InputDecoder::new();
+
+        // A lone ESC waits for a flush
+        assert!(decode(&mut decoder, b"\x1b").is_empty());
+        let mut flushed = Vec::new();
+        decoder.flush(|event| {
+            if let TermEvent::Key(KeyState::Pressed(code, mods)) = event {
+                flushed.push(key(code, mods));
+            }
+        });
+        assert_eq!(flushed, [key(keys::ESCAPE, 0)]);
+        assert!(!decoder.is_pending());
+
+        // ESC followed by a key is the key with Alt
+        assert_eq!(
+            decode(&mut decoder, b"\x1bx\x1b\x1b"),
+            [key('x' as u32, ALT), key(keys::ESCAPE, 0)]
+        );
+    }
+
+    #[test]
+    fn split_utf8() {
+        let mut decoder = InputDecoder::new();
+        let bytes = "\u{e9}\u{4e16}".as_bytes();
+
+        assert_eq!(decode(&mut decoder, &bytes[..3]), [key(0xe9, 0)]);
+        assert!(decoder.is_pending());
+        assert_eq!(decode(&mut decoder, &bytes[3..]), [key(0x4e16, 0)]);
+    }
+}
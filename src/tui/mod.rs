@@ -0,0 +1,613 @@
+//! Text-mode backend for terminals
+//!
+//! CellBuffer is a CTX where every unit of T is a character cell, usually with
+//! T = u16. It implements Painter with box-drawing characters and half blocks,
+//! AnsiRenderer writes only the changed cells to the terminal and InputDecoder
+//! translates the terminal bytes into MouseState and KeyState
+//!
+//! Blank cells have the default colors of the terminal, stored as zero alpha, and
+//! painting a color with zero alpha leaves the cell color. A wide character uses
+//! two cells, the second cell has the CONTINUATION char. Putting the terminal in
+//! raw mode is left to the application
+
+mod ansi;
+mod input;
+
+pub use self::ansi::{enter, leave, AnsiRenderer};
+pub use self::input::{InputDecoder, TermEvent};
+
+use crate::clip::{Clip, ClipStack};
+use crate::math::Scalar;
+use crate::paint::{flatten, Color, FontID, ImageID, Painter, PathCommand, Point};
+use crate::widget::{Boundaries, Position};
+
+/// Text attributes of a cell as bitflags
+pub type CellStyle = u8;
+
+pub const BOLD: CellStyle = 0b00001;
+pub const DIM: CellStyle = 0b00010;
+pub const ITALIC: CellStyle = 0b00100;
+pub const UNDERLINE: CellStyle = 0b01000;
+pub const REVERSE: CellStyle = 0b10000;
+
+/// Char of the cell covered by the wide character at its left
+pub const CONTINUATION: char = '\0';
+
+/// A character cell of the terminal
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Cell {
+    pub ch: char,
+    pub fg: Color,
+    pub bg: Color,
+    pub style: CellStyle,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Cell {
+            ch: ' ',
+            fg: Color::TRANSPARENT,
+            bg: Color::TRANSPARENT,
+            style: 0,
+        }
+    }
+}
+
+/// Line style of box-drawing characters
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BoxStyle {
+    Light,
+    Heavy,
+    Double,
+    /// Light lines with rounded corners
+    Rounded,
+}
+
+// Directions of a box-drawing character
+const UP: u8 = 0b0001;
+const DOWN: u8 = 0b0010;
+const LEFT: u8 = 0b0100;
+const RIGHT: u8 = 0b1000;
+
+/// Light box-drawing characters indexed by their directions
+const LIGHT: [char; 16] = [
+    ' ', '│', '│', '│', '─', '┘', '┐', '┤', '─', '└', '┌', '├', '─', '┴', '┬', '┼',
+];
+
+/// Directions of a light box-drawing character, for merge crossing lines
+fn light_mask(ch: char) -> u8 {
+    match ch {
+        '╭' => DOWN | RIGHT,
+        '╮' => DOWN | LEFT,
+        '╰' => UP | RIGHT,
+        '╯' => UP | LEFT,
+        ' ' => 0,
+        ch => match LIGHT.iter().rposition(|&c| c == ch) {
+            Some(mask) => mask as u8,
+            None => 0,
+        },
+    }
+}
+
+impl BoxStyle {
+    /// Character of a piece with some directions
+    fn piece(self, mask: u8) -> char {
+        let (h, v, corners) = match self {
+            BoxStyle::Light => return LIGHT[mask as usize],
+            BoxStyle::Rounded => match mask {
+                m if m == DOWN | RIGHT => return '╭',
+                m if m == DOWN | LEFT => return '╮',
+                m if m == UP | RIGHT => return '╰',
+                m if m == UP | LEFT => return '╯',
+                m => return LIGHT[m as usize],
+            },
+            BoxStyle::Heavy => ('━', '┃', ['┏', '┓', '┗', '┛']),
+            BoxStyle::Double => ('═', '║', ['╔', '╗', '╚', '╝']),
+        };
+
+        match mask {
+            m if m == DOWN | RIGHT => corners[0],
+            m if m == DOWN | LEFT => corners[1],
+            m if m == UP | RIGHT => corners[2],
+            m if m == UP | LEFT => corners[3],
+            m if m & (UP | DOWN) != 0 => v,
+            _ => h,
+        }
+    }
+}
+
+/// RGBA image sampled by the cells
+struct CellImage {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+/// Buffer of character cells CTX
+pub struct CellBuffer {
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+    clip: ClipStack<f32>,
+    images: Vec<CellImage>,
+}
+
+#[inline]
+fn cells<T: Scalar>(bounds: Boundaries<T>) -> (i32, i32, i32, i32) {
+    let (x, y) = (bounds.0.to_f32(), bounds.1.to_f32());
+    (
+        x.round() as i32,
+        y.round() as i32,
+        (x + bounds.2.to_f32()).round() as i32,
+        (y + bounds.3.to_f32()).round() as i32,
+    )
+}
+
+/// Columns used by a character, 2 for wide east asian characters and emoji and
+/// 0 for combining marks and other zero width characters
+pub fn char_width(ch: char) -> usize {
+    match ch as u32 {
+        0x0300..=0x036f | 0x200b..=0x200f | 0xfe00..=0xfe0f | 0xfe20..=0xfe2f => 0,
+        0x1100..=0x115f
+        | 0x2e80..=0x303e
+        | 0x3041..=0x33ff
+        | 0x3400..=0x4dbf
+        | 0x4e00..=0x9fff
+        | 0xa000..=0xa4cf
+        | 0xac00..=0xd7a3
+        | 0xf900..=0xfaff
+        | 0xfe30..=0xfe4f
+        | 0xff00..=0xff60
+        | 0xffe0..=0xffe6
+        | 0x1f300..=0x1f64f
+        | 0x1f900..=0x1f9ff
+        | 0x20000..=0x3fffd => 2,
+        _ => 1,
+    }
+}
+
+/// Blend a color over a cell color, zero alpha leaves the cell color and the
+/// default color of the terminal is replaced
+fn blend(dst: Color, src: Color) -> Color {
+    if src.a == 0 {
+        return dst;
+    } else if src.a == 255 || dst.a == 0 {
+        return src;
+    }
+
+    let a = src.a as u32;
+    let mix = |s: u8, d: u8| ((s as u32 * a + d as u32 * (255 - a)) / 255) as u8;
+
+    Color::rgb(mix(src.r, dst.r), mix(src.g, dst.g), mix(src.b, dst.b))
+}
+
+impl CellBuffer {
+    /// Creates a buffer filled with blank cells
+    pub fn new(width: usize, height: usize) -> Self {
+        CellBuffer {
+            width,
+            height,
+            cells: vec![Cell::default(); width * height],
+            clip: ClipStack::new(),
+            images: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Get cells, rows are from top to bottom
+    #[inline]
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    /// Get a cell, None if it's outside
+    pub fn cell(&self, x: usize, y: usize) -> Option<&Cell> {
+        if x < self.width && y < self.height {
+            Some(&self.cells[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    /// Resize the buffer, the cells are cleared
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+        self.cells = vec![Cell::default(); width * height];
+    }
+
+    /// Replace every cell with a blank cell, ignoring the clip
+    pub fn clear(&mut self) {
+        self.cells.iter_mut().for_each(|c| *c = Cell::default());
+    }
+
+    /// Register RGBA pixels for draw them with Painter::image
+    pub fn add_image(&mut self, width: usize, height: usize, rgba: &[u8]) -> ImageID {
+        assert_eq!(rgba.len(), width * height * 4, "invalid RGBA buffer size");
+        self.images.push(CellImage {
+            width,
+            height,
+            pixels: rgba.to_vec(),
+        });
+
+        (self.images.len() - 1) as ImageID
+    }
+
+    /// Get a mutable cell if it's inside the buffer and the clip, a wide character
+    /// that uses the cell is replaced with spaces because the cell will change
+    fn visible(&mut self, x: i32, y: i32) -> Option<&mut Cell> {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return None;
+        }
+
+        if let Some(c) = self.clip.clip() {
+            let (x, y) = (x as f32, y as f32);
+            if x < c.0 || y < c.1 || x + 1.0 > c.0 + c.2 || y + 1.0 > c.1 + c.3 {
+                return None;
+            }
+        }
+
+        let (x, n) = (x as usize, y as usize * self.width + x as usize);
+        if self.cells[n].ch == CONTINUATION && x > 0 {
+            self.cells[n - 1].ch = ' ';
+        } else if char_width(self.cells[n].ch) == 2 && x + 1 < self.width {
+            self.cells[n + 1].ch = ' ';
+        }
+
+        Some(&mut self.cells[n])
+    }
+
+    /// Replace a cell, the clip is applied
+    pub fn set(&mut self, x: i32, y: i32, cell: Cell) {
+        if let Some(c) = self.visible(x, y) {
+            *c = cell;
+        }
+    }
+
+    /// Write a text in a row and return the columns used. A wide character uses two
+    /// columns, it's written as a space if both aren't visible, and zero width
+    /// characters are skipped
+    pub fn print(&mut self, x: i32, y: i32, text: &str, fg: Color, style: CellStyle) -> usize {
+        let mut column = x;
+        for ch in text.chars().filter(|c| !c.is_control()) {
+            let width = char_width(ch);
+            let wide = width == 2 && self.visible(column + 1, y).is_some();
+
+            if width == 0 {
+                continue;
+            } else if let Some(c) = self.visible(column, y) {
+                c.ch = if width == 2 && !wide { ' ' } else { ch };
+                c.fg = fg;
+                c.style = style;
+            }
+
+            if let (true, Some(c)) = (wide, self.visible(column + 1, y)) {
+                c.ch = CONTINUATION;
+                c.fg = fg;
+                c.style = style;
+            }
+
+            column += width as i32;
+        }
+
+        (column - x) as usize
+    }
+
+    /// Fill cells with a character and colors
+    pub fn fill(&mut self, bounds: Boundaries<i32>, ch: char, fg: Color, bg: Color) {
+        for y in bounds.1..bounds.1 + bounds.3 {
+            for x in bounds.0..bounds.0 + bounds.2 {
+                if let Some(c) = self.visible(x, y) {
+                    *c = Cell {
+                        ch,
+                        fg,
+                        bg: blend(c.bg, bg),
+                        style: 0,
+                    };
+                }
+            }
+        }
+    }
+
+    /// Put a box-drawing piece, light pieces are merged with crossing lines
+    fn piece(&mut self, x: i32, y: i32, mask: u8, style: BoxStyle, fg: Color) {
+        if let Some(c) = self.visible(x, y) {
+            c.ch = match style {
+                BoxStyle::Light | BoxStyle::Rounded => {
+                    let merged = mask | light_mask(c.ch);
+                    if merged == mask {
+                        style.piece(mask)
+                    } else {
+                        LIGHT[merged as usize]
+                    }
+                }
+                _ => style.piece(mask),
+            };
+
+            c.fg = fg;
+        }
+    }
+
+    /// Draw a horizontal line of a length
+    pub fn hline(&mut self, x: i32, y: i32, len: i32, style: BoxStyle, fg: Color) {
+        for n in x..x + len {
+            self.piece(n, y, LEFT | RIGHT, style, fg);
+        }
+    }
+
+    /// Draw a vertical line of a length
+    pub fn vline(&mut self, x: i32, y: i32, len: i32, style: BoxStyle, fg: Color) {
+        for n in y..y + len {
+            self.piece(x, n, UP | DOWN, style, fg);
+        }
+    }
+
+    /// Draw the border of a box with box-drawing characters
+    pub fn draw_box(&mut self, bounds: Boundaries<i32>, style: BoxStyle, fg: Color) {
+        let (x1, y1) = (bounds.0, bounds.1);
+        let (x2, y2) = (x1 + bounds.2 - 1, y1 + bounds.3 - 1);
+        if x2 <= x1 || y2 <= y1 {
+            return;
+        }
+
+        self.hline(x1 + 1, y1, x2 - x1 - 1, style, fg);
+        self.hline(x1 + 1, y2, x2 - x1 - 1, style, fg);
+        self.vline(x1, y1 + 1, y2 - y1 - 1, style, fg);
+        self.vline(x2, y1 + 1, y2 - y1 - 1, style, fg);
+
+        self.piece(x1, y1, DOWN | RIGHT, style, fg);
+        self.piece(x2, y1, DOWN | LEFT, style, fg);
+        self.piece(x1, y2, UP | RIGHT, style, fg);
+        self.piece(x2, y2, UP | LEFT, style, fg);
+    }
+
+    /// Draw a line between cells with the character that follows its slope
+    fn segment(&mut self, a: Point, b: Point, style: BoxStyle, fg: Color) {
+        let (x1, y1) = (a.0.floor() as i32, a.1.floor() as i32);
+        let (x2, y2) = (b.0.floor() as i32, b.1.floor() as i32);
+        let (dx, dy) = (x2 - x1, y2 - y1);
+
+        if dy == 0 {
+            return self.hline(x1.min(x2), y1, dx.abs() + 1, style, fg);
+        } else if dx == 0 {
+            return self.vline(x1, y1.min(y2), dy.abs() + 1, style, fg);
+        }
+
+        let ch = match (dx.abs() * 2 <= dy.abs(), dy.abs() * 2 <= dx.abs()) {
+            (true, _) => style.piece(UP | DOWN),
+            (_, true) => style.piece(LEFT | RIGHT),
+            _ if (dx > 0) == (dy > 0) => '╲',
+            _ => '╱',
+        };
+
+        // Bresenham
+        let steps = dx.abs().max(dy.abs());
+        for n in 0..=steps {
+            let x = x1 + (dx * n + dx.signum() * steps / 2) / steps;
+            let y = y1 + (dy * n + dy.signum() * steps / 2) / steps;
+
+            if let Some(c) = self.visible(x, y) {
+                c.ch = ch;
+                c.fg = fg;
+            }
+        }
+    }
+}
+
+impl<T: Scalar> Clip<T> for CellBuffer {
+    fn push_clip(&mut self, bounds: Boundaries<T>) {
+        self.clip.push_clip((
+            bounds.0.to_f32(),
+            bounds.1.to_f32(),
+            bounds.2.to_f32(),
+            bounds.3.to_f32(),
+        ));
+    }
+
+    fn pop_clip(&mut self) {
+        self.clip.pop_clip();
+    }
+
+    fn clip(&self) -> Option<Boundaries<T>> {
+        self.clip.clip().map(|c| {
+            (
+                T::from_f32(c.0),
+                T::from_f32(c.1),
+                T::from_f32(c.2),
+                T::from_f32(c.3),
+            )
+        })
+    }
+}
+
+/// Shapes are drawn with cells: fills change the background, strokes and lines
+/// use box-drawing characters, text is written in the row above the baseline and
+/// images use half blocks for double vertical resolution
+impl<T: Scalar> Painter<T> for CellBuffer {
+    fn fill_rect(&mut self, bounds: Boundaries<T>, color: Color) {
+        let (x1, y1, x2, y2) = cells(bounds);
+        self.fill((x1, y1, x2 - x1, y2 - y1), ' ', Color::TRANSPARENT, color);
+    }
+
+    fn stroke_rect(&mut self, bounds: Boundaries<T>, width: T, color: Color) {
+        let (x1, y1, x2, y2) = cells(bounds);
+        let style = if width.to_f32() > 1.0 {
+            BoxStyle::Heavy
+        } else {
+            BoxStyle::Light
+        };
+
+        self.draw_box((x1, y1, x2 - x1, y2 - y1), style, color);
+    }
+
+    fn fill_round_rect(&mut self, bounds: Boundaries<T>, _: T, color: Color) {
+        self.fill_rect(bounds, color);
+    }
+
+    fn line(&mut self, from: Position<T>, to: Position<T>, width: T, color: Color) {
+        let style = if width.to_f32() > 1.0 {
+            BoxStyle::Heavy
+        } else {
+            BoxStyle::Light
+        };
+
+        self.segment(
+            (from.0.to_f32(), from.1.to_f32()),
+            (to.0.to_f32(), to.1.to_f32()),
+            style,
+            color,
+        );
+    }
+
+    fn fill_path(&mut self, path: &[PathCommand<T>], color: Color) {
+        let lines = flatten(path);
+        let edges: Vec<(Point, Point)> = lines
+            .iter()
+            .flat_map(|line| {
+                let points = &line.points;
+                (0..points.len()).map(move |n| (points[n], points[(n + 1) % points.len()]))
+            })
+            .collect();
+
+        let (mut min, mut max) = ((f32::MAX, f32::MAX), (f32::MIN, f32::MIN));
+        for &(p, _) in edges.iter() {
+            min = (min.0.min(p.0), min.1.min(p.1));
+            max = (max.0.max(p.0), max.1.max(p.1));
+        }
+
+        // Fill the cells that have their center inside, nonzero winding rule
+        for y in min.1.floor() as i32..max.1.ceil() as i32 {
+            for x in min.0.floor() as i32..max.0.ceil() as i32 {
+                let (cx, cy) = (x as f32 + 0.5, y as f32 + 0.5);
+                let mut winding = 0;
+
+                for &(a, b) in edges.iter() {
+                    if (a.1 <= cy) != (b.1 <= cy) {
+                        let ix = a.0 + (cy - a.1) / (b.1 - a.1) * (b.0 - a.0);
+                        if ix > cx {
+                            winding += if b.1 > a.1 { 1 } else { -1 };
+                        }
+                    }
+                }
+
+                if winding != 0 {
+                    if let Some(c) = self.visible(x, y) {
+                        c.ch = ' ';
+                        c.bg = blend(c.bg, color);
+                    }
+                }
+            }
+        }
+    }
+
+    fn stroke_path(&mut self, path: &[PathCommand<T>], width: T, color: Color) {
+        for line in flatten(path) {
+            let points = &line.points;
+            let count = if line.closed {
+                points.len()
+            } else {
+                points.len() - 1
+            };
+
+            for n in 0..count {
+                let (a, b) = (points[n], points[(n + 1) % points.len()]);
+                Painter::<f32>::line(self, a, b, width.to_f32(), color);
+            }
+        }
+    }
+
+    fn text(&mut self, pos: Position<T>, _: FontID, _: T, text: &str, color: Color) {
+        let (x, y) = (pos.0.to_f32().round() as i32, pos.1.to_f32().round() as i32);
+        self.print(x, y - 1, text, color, 0);
+    }
+
+    fn image(&mut self, bounds: Boundaries<T>, image: ImageID) {
+        let img = match self.images.get(image as usize) {
+            Some(img) if img.width > 0 && img.height > 0 => img,
+            _ => return,
+        };
+
+        let (x1, y1, x2, y2) = cells(bounds);
+        let (w, h) = ((x2 - x1) as f32, (y2 - y1) as f32 * 2.0);
+
+        // Nearest neighbour samples, two per cell
+        let mut samples = Vec::new();
+        for y in y1..y2 {
+            for x in x1..x2 {
+                let ix = ((x - x1) as f32 + 0.5) / w * img.width as f32;
+                let sample = |half: f32| {
+                    let iy = (((y - y1) as f32 * 2.0 + half) / h * img.height as f32) as usize;
+                    let i =
+                        (iy.min(img.height - 1) * img.width + (ix as usize).min(img.width - 1)) * 4;
+                    let p = &img.pixels[i..i + 4];
+
+                    Color::rgba(p[0], p[1], p[2], p[3])
+                };
+
+                samples.push((x, y, sample(0.5), sample(1.5)));
+            }
+        }
+
+        for (x, y, top, bottom) in samples {
+            if let Some(c) = self.visible(x, y) {
+                c.ch = '▀';
+                c.fg = blend(c.bg, top);
+                c.bg = blend(c.bg, bottom);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(buffer: &CellBuffer, y: usize) -> String {
+        (0..buffer.width())
+            .map(|x| buffer.cell(x, y).unwrap().ch)
+            .collect()
+    }
+
+    #[test]
+    fn blend_alpha() {
+        let mut buffer = CellBuffer::new(2, 1);
+        let red = Color::rgb(255, 0, 0);
+
+        buffer.fill((0, 0, 2, 1), ' ', Color::TRANSPARENT, red);
+        Painter::<u16>::fill_rect(&mut buffer, (0, 0, 1, 1), Color::TRANSPARENT);
+        assert_eq!(buffer.cell(0, 0).unwrap().bg, red);
+
+        Painter::<u16>::fill_rect(&mut buffer, (1, 0, 1, 1), Color::rgba(0, 0, 255, 255));
+        assert_eq!(buffer.cell(1, 0).unwrap().bg, Color::rgb(0, 0, 255));
+    }
+
+    #[test]
+    fn wide_chars() {
+        let mut buffer = CellBuffer::new(5, 1);
+        let white = Color::rgb(255, 255, 255);
+
+        assert_eq!(buffer.print(0, 0, "a\u{4e16}e\u{301}", white, 0), 4);
+        assert_eq!(row(&buffer, 0), "a\u{4e16}\0e ");
+
+        // Overwriting a half of the wide character clears the other half
+        buffer.print(2, 0, "b", white, 0);
+        assert_eq!(row(&buffer, 0), "a be ");
+        buffer.print(1, 0, "\u{4e16}", white, 0);
+        buffer.print(1, 0, "c", white, 0);
+        assert_eq!(row(&buffer, 0), "ac e ");
+
+        // The last column has no room for the second half
+        assert_eq!(buffer.print(4, 0, "\u{4e16}", white, 0), 2);
+        assert_eq!(row(&buffer, 0), "ac e ");
+        assert_eq!(char_width('\u{1f600}'), 2);
+    }
+}
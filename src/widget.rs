@@ -1,7 +1,9 @@
 //! Structs and Traits for widgets
 
+use crate::damage::DamageRegion;
 use crate::group::{Group, GroupID};
-use crate::state::{KeyState, MouseState};
+use crate::state::{Cursor, KeyState, MouseState, PointerID, TextInput};
+use crate::text::SharedMeasure;
 use std::ops::{Add, Sub};
 
 pub type Position<T> = (T, T);
@@ -44,6 +46,9 @@ where
     fn update(&mut self, _: &mut WidgetInternal<T>) {}
     /// Update the layout of the widget.
     fn layout(&mut self, _: &mut WidgetInternal<T>, _: bool) {}
+    /// Measure the text of the widget, it's called before a complete layout and
+    /// min_dimensions is called after it.
+    fn measure(&mut self, _: &mut WidgetInternal<T>, _: &SharedMeasure<T>) {}
     /// Containers search for widgets that are members of the same Group and then
     /// call this function on found widgets.
     fn handle_signal(&mut self, _: &mut WidgetInternal<T>, _: GroupID) {}
@@ -52,6 +57,8 @@ where
     fn handle_mouse(&mut self, _: &mut WidgetInternal<T>, _: &MouseState<T>) {}
     /// Handle a keyboard state, it only be called if the widget is focused by a Container.
     fn handle_keys(&mut self, _: &mut WidgetInternal<T>, _: KeyState) {}
+    /// Handle text of an input method, it only be called if the widget is focused by a Container.
+    fn handle_text(&mut self, _: &mut WidgetInternal<T>, _: TextInput) {}
     /// Containers call this function for check if the widget should be focused or not by stepping.
     fn step_focus(&mut self, internal: &mut WidgetInternal<T>, _: bool) -> bool {
         let check = !internal.check(FOCUS);
@@ -59,14 +66,34 @@ where
 
         check
     }
+    /// When you hover the widget, the HOVER flag is already turned on
+    fn hover_in(&mut self, internal: &mut WidgetInternal<T>) {
+        internal.on(DRAW);
+    }
     /// When you unhover the widget
     fn hover_out(&mut self, internal: &mut WidgetInternal<T>) {
         internal.on(DRAW);
     }
+    /// When a pointer leaves the widget but other pointers are still on it
+    fn pointer_out(&mut self, _: &mut WidgetInternal<T>, _: PointerID) {}
     /// When you unfocus the widget
     fn focus_out(&mut self, internal: &mut WidgetInternal<T>) {
         internal.on(DRAW);
     }
+    /// An area over the widget was uncovered, like a closed popup, and it must be drawn
+    /// again. Containers call it on the widgets that overlap the area.
+    fn expose(&mut self, internal: &mut WidgetInternal<T>, _: Boundaries<T>) {
+        internal.on(DRAW);
+    }
+    /// Count of widgets that requested another frame on the last draw, Containers use it
+    /// for report the widgets of their lists. Zero means the widget itself.
+    fn pending(&self) -> usize {
+        0
+    }
+    /// Add the area painted by the last draw to the damage region, it's called after a frame.
+    fn damage(&mut self, internal: &WidgetInternal<T>, region: &mut DamageRegion<T>) {
+        region.add(internal.boundaries_abs());
+    }
     /// Get minimal Dimensions of the Widget.
     fn min_dimensions(&self) -> Dimensions<T> {
         (Default::default(), Default::default())
@@ -87,6 +114,8 @@ pub struct WidgetInternal<T> {
     p_pos: Position<T>,
     /// Absolute position
     pos: Position<T>,
+    /// Requested cursor shape
+    cursor: Cursor,
 }
 
 impl<T> WidgetInternal<T> {
@@ -133,7 +162,7 @@ impl<T> WidgetInternal<T> {
     /// Get numeric value of the requested flags and then off with other mask
     #[inline]
     pub fn drain(&mut self, mut get_mask: Flags, off_mask: Flags) -> Flags {
-        get_mask = get_mask & self.flags;
+        get_mask &= self.flags;
         self.flags &= !off_mask;
 
         get_mask
@@ -167,6 +196,17 @@ impl<T> WidgetInternal<T> {
     pub fn set_group(&mut self, group: Group) {
         self.group = group;
     }
+
+    #[inline]
+    /// Get the requested cursor shape
+    pub fn cursor(&self) -> Cursor {
+        self.cursor
+    }
+
+    /// Request a cursor shape, Containers use it when the widget is hovered or grabbed
+    pub fn set_cursor(&mut self, cursor: Cursor) {
+        self.cursor = cursor;
+    }
 }
 
 impl<T: Sized + Copy + Clone> WidgetInternal<T>
@@ -182,6 +222,7 @@ where
             min_dim: (Default::default(), Default::default()),
             pos: (Default::default(), Default::default()),
             p_pos: (Default::default(), Default::default()),
+            cursor: Cursor::Default,
         }
     }
 
@@ -194,6 +235,7 @@ where
             min_dim: (Default::default(), Default::default()),
             pos,
             p_pos: (Default::default(), Default::default()),
+            cursor: Cursor::Default,
         }
     }
 
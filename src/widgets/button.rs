@@ -0,0 +1,318 @@
+//! Push buttons
+
+use super::{activates, inset, padded, Press, Style, TextBlock};
+use crate::group::{push_event, GroupEvent, GroupID};
+use crate::math::Scalar;
+use crate::paint::{Color, Painter};
+use crate::state::{KeyState, MouseState, PointerID};
+use crate::text::lines::Align;
+use crate::text::SharedMeasure;
+use crate::widget::flags::*;
+use crate::widget::{Boundaries, Dimensions, Widget, WidgetInternal};
+
+use std::cell::Cell;
+use std::ops::{Add, Sub};
+use std::rc::Rc;
+
+/// Draw the face, the border and the centered text of a button
+fn draw_face<T, CTX>(
+    ctx: &mut CTX,
+    internal: &WidgetInternal<T>,
+    bounds: Boundaries<T>,
+    style: &Style<T>,
+    text: &TextBlock<T>,
+    (face, color): (Color, Color),
+) where
+    T: Scalar,
+    CTX: Painter<T>,
+{
+    ctx.fill_rect(bounds, style.window);
+    ctx.fill_round_rect(bounds, style.radius, style.outline_color(internal));
+    ctx.fill_round_rect(inset(bounds, style.border), style.radius, face);
+    text.draw(
+        ctx,
+        inset(bounds, style.padding),
+        Align::Center,
+        style,
+        color,
+    );
+}
+
+/// Face and text colors of a button that isn't checked
+fn colors<T: Scalar>(
+    internal: &WidgetInternal<T>,
+    style: &Style<T>,
+    press: &Press,
+) -> (Color, Color) {
+    let face = if !internal.check(ENABLED) {
+        style.button
+    } else if press.is_down() {
+        style.pressed
+    } else if internal.check(HOVER) {
+        style.hover
+    } else {
+        style.button
+    };
+
+    (face, style.text_color(internal))
+}
+
+/// Button that pushes a signal when it's clicked
+pub struct Button<T> {
+    text: TextBlock<T>,
+    style: Style<T>,
+    signal: GroupID,
+    press: Press,
+}
+
+impl<T: Scalar> Button<T> {
+    /// Creates a Button with a text and the signal ID pushed by clicks
+    pub fn new(text: &str, signal: GroupID) -> Self {
+        Button {
+            text: TextBlock::new(text),
+            style: Style::default(),
+            signal,
+            press: Press::new(),
+        }
+    }
+
+    pub fn set_style(&mut self, style: Style<T>) {
+        self.style = style;
+    }
+
+    /// Replace the text, the tree is measured and layout again
+    pub fn set_text(&mut self, text: &str) {
+        self.text.set_text(text);
+        push_event(GroupEvent::Layout);
+    }
+}
+
+impl<T, CTX> Widget<T, CTX> for Button<T>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+    CTX: Painter<T>,
+{
+    fn draw(&mut self, internal: &WidgetInternal<T>, ctx: &mut CTX) -> bool {
+        let colors = colors(internal, &self.style, &self.press);
+        draw_face(
+            ctx,
+            internal,
+            internal.boundaries_abs(),
+            &self.style,
+            &self.text,
+            colors,
+        );
+
+        false
+    }
+
+    fn measure(&mut self, _: &mut WidgetInternal<T>, measure: &SharedMeasure<T>) {
+        self.text
+            .measure(measure.as_ref(), self.style.font, self.style.font_size);
+    }
+
+    fn handle_mouse(&mut self, internal: &mut WidgetInternal<T>, mouse: &MouseState<T>) {
+        if self.press.mouse(internal, mouse) {
+            push_event(GroupEvent::Signal(self.signal));
+        }
+    }
+
+    fn handle_keys(&mut self, internal: &mut WidgetInternal<T>, key: KeyState) {
+        if activates(internal, key) {
+            push_event(GroupEvent::Signal(self.signal));
+        }
+    }
+
+    fn hover_out(&mut self, internal: &mut WidgetInternal<T>) {
+        self.press.cancel();
+        internal.on(DRAW);
+    }
+
+    fn pointer_out(&mut self, internal: &mut WidgetInternal<T>, pointer: PointerID) {
+        if self.press.pointer_out(pointer) {
+            internal.off(GRAB);
+            internal.on(DRAW);
+        }
+    }
+
+    fn min_dimensions(&self) -> Dimensions<T> {
+        padded(self.text.dimensions(), self.style.padding)
+    }
+}
+
+/// Button that switches a shared value when it's clicked
+///
+/// The signal is pushed after the value changes, add the button with the SIGNAL
+/// flag and the signal Group for draw it when other widgets change the value
+pub struct ToggleButton<T> {
+    text: TextBlock<T>,
+    style: Style<T>,
+    value: Rc<Cell<bool>>,
+    signal: GroupID,
+    press: Press,
+}
+
+impl<T: Scalar> ToggleButton<T> {
+    /// Creates a ToggleButton with a text, the shared value and the signal ID
+    pub fn new(text: &str, value: Rc<Cell<bool>>, signal: GroupID) -> Self {
+        ToggleButton {
+            text: TextBlock::new(text),
+            style: Style::default(),
+            value,
+            signal,
+            press: Press::new(),
+        }
+    }
+
+    pub fn set_style(&mut self, style: Style<T>) {
+        self.style = style;
+    }
+
+    #[inline]
+    pub fn value(&self) -> bool {
+        self.value.get()
+    }
+
+    fn toggle(&mut self, internal: &mut WidgetInternal<T>) {
+        self.value.set(!self.value.get());
+        internal.on(DRAW);
+
+        push_event(GroupEvent::Signal(self.signal));
+    }
+}
+
+impl<T, CTX> Widget<T, CTX> for ToggleButton<T>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+    CTX: Painter<T>,
+{
+    fn draw(&mut self, internal: &WidgetInternal<T>, ctx: &mut CTX) -> bool {
+        let style = &self.style;
+        let colors = if self.value.get() && !self.press.is_down() {
+            (style.accent, style.accent_text)
+        } else {
+            colors(internal, style, &self.press)
+        };
+
+        draw_face(
+            ctx,
+            internal,
+            internal.boundaries_abs(),
+            style,
+            &self.text,
+            colors,
+        );
+
+        false
+    }
+
+    fn measure(&mut self, _: &mut WidgetInternal<T>, measure: &SharedMeasure<T>) {
+        self.text
+            .measure(measure.as_ref(), self.style.font, self.style.font_size);
+    }
+
+    /// The shared value was changed by another widget
+    fn handle_signal(&mut self, internal: &mut WidgetInternal<T>, _: GroupID) {
+        internal.on(DRAW);
+    }
+
+    fn handle_mouse(&mut self, internal: &mut WidgetInternal<T>, mouse: &MouseState<T>) {
+        if self.press.mouse(internal, mouse) {
+            self.toggle(internal);
+        }
+    }
+
+    fn handle_keys(&mut self, internal: &mut WidgetInternal<T>, key: KeyState) {
+        if activates(internal, key) {
+            self.toggle(internal);
+        }
+    }
+
+    fn hover_out(&mut self, internal: &mut WidgetInternal<T>) {
+        self.press.cancel();
+        internal.on(DRAW);
+    }
+
+    fn pointer_out(&mut self, internal: &mut WidgetInternal<T>, pointer: PointerID) {
+        if self.press.pointer_out(pointer) {
+            internal.off(GRAB);
+            internal.on(DRAW);
+        }
+    }
+
+    fn min_dimensions(&self) -> Dimensions<T> {
+        padded(self.text.dimensions(), self.style.padding)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::DisplayList;
+    use crate::state::keys::SPACE;
+    use crate::state::MouseType;
+    use crate::widgets::tests::{signals, Harness};
+
+    #[test]
+    fn click() {
+        let mut h = Harness::new(Button::new("Ok", 7), (0.0, 0.0, 60.0, 20.0));
+
+        h.click((10.0, 10.0));
+        assert_eq!(signals(), [7]);
+        assert!(!h.internal.check(GRAB));
+
+        // Releasing outside cancels the click
+        h.mouse(0, MouseType::Pressed(1), (10.0, 10.0));
+        assert!(h.internal.check(GRAB | FOCUS));
+        assert!(h.widget.press.is_down());
+        h.mouse(0, MouseType::CursorMoved, (80.0, 10.0));
+        assert!(!h.widget.press.is_down());
+        h.mouse(0, MouseType::Released(1), (80.0, 10.0));
+        assert!(signals().is_empty());
+
+        h.key(SPACE, 0);
+        assert_eq!(signals(), [7]);
+
+        h.internal.off(ENABLED);
+        h.click((10.0, 10.0)).key(SPACE, 0);
+        assert!(signals().is_empty());
+    }
+
+    #[test]
+    fn other_pointers() {
+        let mut h = Harness::new(Button::new("Ok", 7), (0.0, 0.0, 60.0, 20.0));
+
+        // A second finger doesn't release or cancel the press of the first
+        h.mouse(1, MouseType::Pressed(1), (10.0, 10.0));
+        h.mouse(2, MouseType::Pressed(1), (20.0, 10.0));
+        h.mouse(2, MouseType::Released(1), (20.0, 10.0));
+        Widget::<f32, DisplayList<f32>>::pointer_out(&mut h.widget, &mut h.internal, 2);
+        assert!(signals().is_empty());
+        assert!(h.widget.press.is_down());
+
+        h.mouse(1, MouseType::Released(1), (10.0, 10.0));
+        assert_eq!(signals(), [7]);
+
+        // Only the finger that pressed it cancels it
+        h.mouse(1, MouseType::Pressed(1), (10.0, 10.0));
+        Widget::<f32, DisplayList<f32>>::pointer_out(&mut h.widget, &mut h.internal, 1);
+        assert!(!h.internal.check(GRAB));
+        h.mouse(1, MouseType::Released(1), (10.0, 10.0));
+        assert!(signals().is_empty());
+    }
+
+    #[test]
+    fn toggle() {
+        let value = Rc::new(Cell::new(false));
+        let button = ToggleButton::new("Bold", value.clone(), 3);
+        let mut h = Harness::new(button, (0.0, 0.0, 60.0, 20.0));
+
+        h.click((10.0, 10.0));
+        assert!(value.get() && h.widget.value());
+        assert_eq!(signals(), [3]);
+
+        h.click((10.0, 10.0));
+        assert!(!value.get());
+        assert_eq!(signals(), [3]);
+    }
+}
@@ -0,0 +1,571 @@
+//! Painting canvas
+//!
+//! A Canvas captures the strokes of a pen or a mouse in the coordinates of the
+//! document, through a view that can be zoomed, panned and rotated. The document
+//! is drawn by a CanvasSource, like the items of a ListView, and it receives every
+//! finished stroke for rasterize it
+
+use super::{mouse_pos, rect, Style};
+use crate::group::{push_event, GroupEvent, GroupID};
+use crate::math::Scalar;
+use crate::paint::{Color, Painter, PathCommand};
+use crate::state::keys::*;
+use crate::state::{Cursor, KeyState, MouseState, MouseType, PointerID};
+use crate::widget::flags::*;
+use crate::widget::{Boundaries, Dimensions, Widget, WidgetInternal};
+
+use std::cell::RefCell;
+use std::f32::consts::PI;
+use std::ops::{Add, Sub};
+use std::rc::Rc;
+
+const MIN_ZOOM: f32 = 1.0 / 32.0;
+const MAX_ZOOM: f32 = 64.0;
+/// Zoom of a step of the wheel or the keys
+const ZOOM_STEP: f32 = 1.25;
+/// Rotation of a step of the wheel or the keys
+const ANGLE_STEP: f32 = PI / 12.0;
+/// Pan of a key
+const PAN_STEP: f32 = 32.0;
+
+/// Point of a stroke in document coordinates
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sample {
+    pub pos: (f32, f32),
+    /// Pressure of the pen from 0 to 1, a mouse is always 1
+    pub pressure: f32,
+    /// Timestamp of the event in milliseconds
+    pub time: u32,
+}
+
+/// Samples captured from a press to a release
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stroke {
+    pub samples: Vec<Sample>,
+}
+
+/// Transform from the document to the canvas
+///
+/// A document point is scaled by the zoom, rotated counterclockwise by the angle
+/// and moved by the offset. The positions of the view are relative to the
+/// boundaries of the canvas
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CanvasView {
+    pub zoom: f32,
+    /// Angle in radians
+    pub angle: f32,
+    pub offset: (f32, f32),
+}
+
+impl Default for CanvasView {
+    fn default() -> Self {
+        CanvasView {
+            zoom: 1.0,
+            angle: 0.0,
+            offset: (0.0, 0.0),
+        }
+    }
+}
+
+impl CanvasView {
+    /// Position in the view of a document point
+    pub fn to_view(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let (sin, cos) = self.angle.sin_cos();
+        let (x, y) = (x * self.zoom, y * self.zoom);
+
+        (
+            x * cos + y * sin + self.offset.0,
+            y * cos - x * sin + self.offset.1,
+        )
+    }
+
+    /// Document point of a position in the view
+    pub fn to_document(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let (sin, cos) = self.angle.sin_cos();
+        let (x, y) = (x - self.offset.0, y - self.offset.1);
+
+        (
+            (x * cos - y * sin) / self.zoom,
+            (y * cos + x * sin) / self.zoom,
+        )
+    }
+
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        self.offset = (self.offset.0 + dx, self.offset.1 + dy);
+    }
+
+    /// Multiply the zoom keeping a position of the view over the same point
+    pub fn zoom_at(&mut self, pivot: (f32, f32), factor: f32) {
+        let point = self.to_document(pivot);
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.keep(point, pivot);
+    }
+
+    /// Rotate counterclockwise keeping a position of the view over the same point
+    pub fn rotate_at(&mut self, pivot: (f32, f32), angle: f32) {
+        let point = self.to_document(pivot);
+        self.angle = (self.angle + angle).rem_euclid(PI * 2.0);
+        self.keep(point, pivot);
+    }
+
+    /// Move the offset for that a document point is over a position
+    fn keep(&mut self, point: (f32, f32), pos: (f32, f32)) {
+        let now = self.to_view(point);
+        self.pan(pos.0 - now.0, pos.1 - now.1);
+    }
+}
+
+/// Document of a Canvas
+pub trait CanvasSource<T, CTX> {
+    /// Draw the document through the view in the absolute boundaries of the canvas,
+    /// with the stroke that is being captured
+    fn draw(
+        &mut self,
+        view: &CanvasView,
+        bounds: Boundaries<T>,
+        current: Option<&Stroke>,
+        ctx: &mut CTX,
+    );
+    /// Receive a finished stroke
+    fn stroke(&mut self, stroke: Stroke);
+}
+
+/// Strokes shared with the application as a CanvasSource
+///
+/// The paper is filled with a color and every stroke is drawn as lines with the
+/// width multiplied by the pressure
+pub struct StrokeList<T> {
+    strokes: Rc<RefCell<Vec<Stroke>>>,
+    size: (f32, f32),
+    paper: Color,
+    color: Color,
+    width: T,
+}
+
+impl<T: Scalar> StrokeList<T> {
+    /// Creates a StrokeList with the shared strokes and the size of the paper
+    pub fn new(strokes: Rc<RefCell<Vec<Stroke>>>, size: (f32, f32)) -> Self {
+        StrokeList {
+            strokes,
+            size,
+            paper: Color::WHITE,
+            color: Color::BLACK,
+            width: T::from_f32(3.0),
+        }
+    }
+
+    /// Color of the paper and of the strokes, and the width with full pressure
+    pub fn set_ink(&mut self, paper: Color, color: Color, width: T) {
+        self.paper = paper;
+        self.color = color;
+        self.width = width;
+    }
+
+    #[inline]
+    pub fn strokes(&self) -> &Rc<RefCell<Vec<Stroke>>> {
+        &self.strokes
+    }
+
+    fn draw_stroke<CTX: Painter<T>>(
+        &self,
+        view: &CanvasView,
+        origin: (f32, f32),
+        stroke: &Stroke,
+        ctx: &mut CTX,
+    ) {
+        let width = self.width.to_f32() * view.zoom;
+        let abs = |sample: &Sample| {
+            let (x, y) = view.to_view(sample.pos);
+            (T::from_f32(origin.0 + x), T::from_f32(origin.1 + y))
+        };
+
+        match stroke.samples.as_slice() {
+            [] => {}
+            [sample] => {
+                let size = (width * sample.pressure).max(1.0);
+                let (x, y) = abs(sample);
+                let dot = rect(x.to_f32() - size * 0.5, y.to_f32() - size * 0.5, size, size);
+                ctx.fill_round_rect(dot, T::from_f32(size * 0.5), self.color);
+            }
+            samples => {
+                for pair in samples.windows(2) {
+                    let pressure = (pair[0].pressure + pair[1].pressure) * 0.5;
+                    let width = T::from_f32((width * pressure).max(1.0));
+                    ctx.line(abs(&pair[0]), abs(&pair[1]), width, self.color);
+                }
+            }
+        }
+    }
+}
+
+impl<T, CTX> CanvasSource<T, CTX> for StrokeList<T>
+where
+    T: Scalar,
+    CTX: Painter<T>,
+{
+    fn draw(
+        &mut self,
+        view: &CanvasView,
+        bounds: Boundaries<T>,
+        current: Option<&Stroke>,
+        ctx: &mut CTX,
+    ) {
+        let origin = (bounds.0.to_f32(), bounds.1.to_f32());
+        let corner = |x: f32, y: f32| {
+            let (x, y) = view.to_view((x, y));
+            (T::from_f32(origin.0 + x), T::from_f32(origin.1 + y))
+        };
+
+        let (w, h) = self.size;
+        let paper = [
+            PathCommand::MoveTo(corner(0.0, 0.0)),
+            PathCommand::LineTo(corner(w, 0.0)),
+            PathCommand::LineTo(corner(w, h)),
+            PathCommand::LineTo(corner(0.0, h)),
+            PathCommand::Close,
+        ];
+        ctx.fill_path(&paper, self.paper);
+
+        for stroke in self.strokes.borrow().iter().chain(current) {
+            self.draw_stroke(view, origin, stroke, ctx);
+        }
+    }
+
+    fn stroke(&mut self, stroke: Stroke) {
+        self.strokes.borrow_mut().push(stroke);
+    }
+}
+
+/// Pointer action of a Canvas
+enum Action {
+    /// Capture of a stroke by a pointer
+    Stroke(PointerID, Stroke),
+    /// Pan with the last position
+    Pan((f32, f32)),
+    /// Rotation around the center with the last angle of the pointer
+    Rotate(f32),
+}
+
+/// Surface that captures strokes
+///
+/// The left button or the pen captures a stroke while the canvas is grabbed, the
+/// source receives it at the release and the signal is pushed. The middle button
+/// pans the view, with Shift it rotates it around the center. The wheel zooms at
+/// the pointer, with Shift it rotates. When it's focused the arrows pan, '+' and
+/// '-' zoom, '[' and ']' rotate and Home resets the view
+pub struct Canvas<T, CTX> {
+    source: Box<dyn CanvasSource<T, CTX>>,
+    signal: GroupID,
+    style: Style<T>,
+    view: CanvasView,
+    action: Option<Action>,
+}
+
+impl<T, CTX> Canvas<T, CTX>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+    CTX: Painter<T>,
+{
+    /// Creates a Canvas with the source of the document and the signal ID pushed
+    /// when a stroke is finished
+    pub fn new(source: Box<dyn CanvasSource<T, CTX>>, signal: GroupID) -> Self {
+        Canvas {
+            source,
+            signal,
+            style: Style::default(),
+            view: CanvasView::default(),
+            action: None,
+        }
+    }
+
+    pub fn set_style(&mut self, style: Style<T>) {
+        self.style = style;
+    }
+
+    #[inline]
+    pub fn view(&self) -> &CanvasView {
+        &self.view
+    }
+
+    /// Replace the view, draw the canvas with WidgetInternal::on(DRAW) after it
+    pub fn set_view(&mut self, view: CanvasView) {
+        self.view = view;
+    }
+
+    #[inline]
+    pub fn source(&mut self) -> &mut dyn CanvasSource<T, CTX> {
+        self.source.as_mut()
+    }
+
+    /// Check if a stroke is being captured
+    pub fn is_capturing(&self) -> bool {
+        matches!(self.action, Some(Action::Stroke(..)))
+    }
+
+    /// Sample of the mouse, a mouse without pressure has full pressure
+    fn sample(&self, mouse: &MouseState<T>, pos: (f32, f32)) -> Sample {
+        let pressure = if mouse.has_pressure() {
+            mouse.tablet_pressure().clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+
+        Sample {
+            pos: self.view.to_document(pos),
+            pressure,
+            time: mouse.time(),
+        }
+    }
+
+    /// Finish the stroke, the source receives it
+    fn finish(&mut self, internal: &mut WidgetInternal<T>) {
+        if let Some(Action::Stroke(_, stroke)) = self.action.take() {
+            self.source.stroke(stroke);
+            push_event(GroupEvent::Signal(self.signal));
+        }
+
+        internal.off(GRAB);
+        internal.on(DRAW);
+    }
+
+    /// Center of the canvas, the pivot of the keys and of the rotation
+    fn center(internal: &WidgetInternal<T>) -> (f32, f32) {
+        let (w, h) = internal.dimensions();
+        (w.to_f32() * 0.5, h.to_f32() * 0.5)
+    }
+}
+
+impl<T, CTX> Widget<T, CTX> for Canvas<T, CTX>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+    CTX: Painter<T>,
+{
+    fn draw(&mut self, internal: &WidgetInternal<T>, ctx: &mut CTX) -> bool {
+        let bounds = internal.boundaries_abs();
+        let current = match &self.action {
+            Some(Action::Stroke(_, stroke)) => Some(stroke),
+            _ => None,
+        };
+
+        ctx.fill_rect(bounds, self.style.window);
+        ctx.push_clip(bounds);
+        self.source.draw(&self.view, bounds, current, ctx);
+        ctx.pop_clip();
+
+        if internal.check(FOCUS) {
+            ctx.stroke_rect(bounds, self.style.border, self.style.focus);
+        }
+
+        false
+    }
+
+    /// The document was changed by the application
+    fn handle_signal(&mut self, internal: &mut WidgetInternal<T>, _: GroupID) {
+        internal.on(DRAW);
+    }
+
+    fn handle_mouse(&mut self, internal: &mut WidgetInternal<T>, mouse: &MouseState<T>) {
+        if !internal.check(ENABLED) {
+            return;
+        }
+
+        let bounds = internal.boundaries_abs();
+        let abs = mouse_pos(mouse);
+        let pos = (abs.0 - bounds.0.to_f32(), abs.1 - bounds.1.to_f32());
+        let center = Self::center(internal);
+        let pointer = mouse.pointer();
+
+        match mouse.m_type {
+            MouseType::Pressed(1) if self.action.is_none() => {
+                let stroke = Stroke {
+                    samples: vec![self.sample(mouse, pos)],
+                };
+
+                self.action = Some(Action::Stroke(pointer, stroke));
+                internal.on(GRAB | FOCUS | DRAW);
+            }
+            MouseType::Pressed(2) if self.action.is_none() => {
+                self.action = Some(if mouse.check_modifiers(SHIFT) {
+                    Action::Rotate((pos.1 - center.1).atan2(pos.0 - center.0))
+                } else {
+                    Action::Pan(pos)
+                });
+
+                internal.on(GRAB | FOCUS);
+            }
+            MouseType::CursorMoved => {
+                let sample = self.sample(mouse, pos);
+
+                match &mut self.action {
+                    Some(Action::Stroke(id, stroke)) if *id == pointer => {
+                        // Only new positions or pressures are samples
+                        let last = stroke.samples.last().copied();
+                        if last.map(|l| (l.pos, l.pressure)) != Some((sample.pos, sample.pressure))
+                        {
+                            stroke.samples.push(sample);
+                            internal.on(DRAW);
+                        }
+                    }
+                    Some(Action::Pan(last)) => {
+                        self.view.pan(pos.0 - last.0, pos.1 - last.1);
+                        *last = pos;
+                        internal.on(DRAW);
+                    }
+                    Some(Action::Rotate(last)) => {
+                        let angle = (pos.1 - center.1).atan2(pos.0 - center.0);
+                        // The view angle is counterclockwise and y goes down
+                        self.view.rotate_at(center, *last - angle);
+                        *last = angle;
+                        internal.on(DRAW);
+                    }
+                    _ => {}
+                }
+            }
+            MouseType::Released(1) => {
+                if matches!(&self.action, Some(Action::Stroke(id, _)) if *id == pointer) {
+                    self.finish(internal);
+                }
+            }
+            MouseType::Released(2) => {
+                if matches!(&self.action, Some(Action::Pan(_)) | Some(Action::Rotate(_))) {
+                    self.action = None;
+                    internal.off(GRAB);
+                }
+            }
+            MouseType::Wheel(delta) if delta != 0.0 => {
+                if mouse.check_modifiers(SHIFT) {
+                    self.view.rotate_at(center, ANGLE_STEP * delta.signum());
+                } else {
+                    self.view.zoom_at(pos, ZOOM_STEP.powf(delta.signum()));
+                }
+
+                internal.on(DRAW);
+            }
+            _ => {}
+        }
+
+        internal.set_cursor(match self.action {
+            Some(Action::Pan(_)) | Some(Action::Rotate(_)) => Cursor::Grabbing,
+            _ => Cursor::Crosshair,
+        });
+    }
+
+    fn handle_keys(&mut self, internal: &mut WidgetInternal<T>, key: KeyState) {
+        let code = match key {
+            KeyState::Pressed(code, _) if internal.check(ENABLED) => code,
+            _ => return,
+        };
+
+        let center = Self::center(internal);
+        let view = &mut self.view;
+
+        match code {
+            LEFT => view.pan(PAN_STEP, 0.0),
+            RIGHT => view.pan(-PAN_STEP, 0.0),
+            UP => view.pan(0.0, PAN_STEP),
+            DOWN => view.pan(0.0, -PAN_STEP),
+            HOME => *view = CanvasView::default(),
+            _ => match to_char(code) {
+                Some('+') | Some('=') => view.zoom_at(center, ZOOM_STEP),
+                Some('-') => view.zoom_at(center, 1.0 / ZOOM_STEP),
+                Some('[') => view.rotate_at(center, ANGLE_STEP),
+                Some(']') => view.rotate_at(center, -ANGLE_STEP),
+                _ => return,
+            },
+        }
+
+        internal.on(DRAW);
+    }
+
+    /// A stroke is finished when its pointer is gone
+    fn pointer_out(&mut self, internal: &mut WidgetInternal<T>, pointer: PointerID) {
+        match &self.action {
+            Some(Action::Stroke(id, _)) if *id == pointer => self.finish(internal),
+            Some(Action::Pan(_)) | Some(Action::Rotate(_)) => {
+                self.action = None;
+                internal.off(GRAB);
+            }
+            _ => {}
+        }
+    }
+
+    fn focus_out(&mut self, internal: &mut WidgetInternal<T>) {
+        internal.on(DRAW);
+    }
+
+    fn min_dimensions(&self) -> Dimensions<T> {
+        let size = self.style.font_size.to_f32() * 4.0;
+        (T::from_f32(size), T::from_f32(size))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn near(a: (f32, f32), b: (f32, f32)) -> bool {
+        (a.0 - b.0).abs() < 1e-3 && (a.1 - b.1).abs() < 1e-3
+    }
+
+    fn views() -> Vec<CanvasView> {
+        let mut views = Vec::new();
+        for &zoom in [MIN_ZOOM, 0.5, 1.0, 3.0, MAX_ZOOM].iter() {
+            for &angle in [0.0, 0.3, PI * 0.5, PI, 5.5].iter() {
+                for &offset in [(0.0, 0.0), (120.0, -45.5), (-3.0, 800.0)].iter() {
+                    views.push(CanvasView {
+                        zoom,
+                        angle,
+                        offset,
+                    });
+                }
+            }
+        }
+
+        views
+    }
+
+    const POINTS: [(f32, f32); 4] = [(0.0, 0.0), (10.0, 20.0), (-35.5, 7.25), (400.0, -300.0)];
+
+    #[test]
+    fn round_trip() {
+        for view in views() {
+            for &point in POINTS.iter() {
+                let back = view.to_document(view.to_view(point));
+                assert!(near(back, point), "{:?} {:?} {:?}", view, point, back);
+            }
+        }
+    }
+
+    #[test]
+    fn transform() {
+        let view = CanvasView {
+            zoom: 2.0,
+            angle: PI * 0.5,
+            offset: (100.0, 50.0),
+        };
+
+        // Counterclockwise on the screen, the x axis of the document goes up
+        assert!(near(view.to_view((10.0, 0.0)), (100.0, 30.0)));
+        assert!(near(view.to_view((0.0, 10.0)), (120.0, 50.0)));
+        assert!(near(view.to_document((100.0, 50.0)), (0.0, 0.0)));
+    }
+
+    #[test]
+    fn pivots() {
+        for view in views() {
+            for &pivot in [(0.0, 0.0), (64.0, 48.0), (-20.0, 300.0)].iter() {
+                let point = view.to_document(pivot);
+
+                let mut zoomed = view;
+                zoomed.zoom_at(pivot, ZOOM_STEP);
+                assert!(near(zoomed.to_view(point), pivot), "{:?}", zoomed);
+                assert!(zoomed.zoom <= MAX_ZOOM);
+
+                let mut rotated = view;
+                rotated.rotate_at(pivot, -ANGLE_STEP);
+                assert!(near(rotated.to_view(point), pivot), "{:?}", rotated);
+                assert!(rotated.angle >= 0.0 && rotated.angle < PI * 2.0);
+            }
+        }
+    }
+}
@@ -0,0 +1,398 @@
+//! Check boxes and radio buttons
+
+use super::{activates, Press, Style, TextBlock};
+use crate::group::{push_event, GroupEvent, GroupID};
+use crate::math::Scalar;
+use crate::paint::{Color, Painter, PathCommand};
+use crate::state::{KeyState, MouseState, PointerID};
+use crate::text::lines::Align;
+use crate::text::SharedMeasure;
+use crate::widget::flags::*;
+use crate::widget::{Boundaries, Dimensions, Widget, WidgetInternal};
+
+use std::cell::Cell;
+use std::ops::{Add, Sub};
+use std::rc::Rc;
+
+/// Mark on the left and text on the right, the mark side is the font size
+struct Indicator<T> {
+    text: TextBlock<T>,
+    style: Style<T>,
+    press: Press,
+}
+
+impl<T: Scalar> Indicator<T> {
+    fn new(text: &str) -> Self {
+        Indicator {
+            text: TextBlock::new(text),
+            style: Style::default(),
+            press: Press::new(),
+        }
+    }
+
+    /// Boundaries of the mark, centered vertically
+    fn mark(&self, bounds: Boundaries<T>) -> Boundaries<T> {
+        let (x, y, _, h) = bounds;
+        let side = self.style.font_size.to_f32();
+        let pad = self.style.padding.to_f32();
+
+        (
+            T::from_f32(x.to_f32() + pad),
+            T::from_f32(y.to_f32() + (h.to_f32() - side) * 0.5),
+            T::from_f32(side),
+            T::from_f32(side),
+        )
+    }
+
+    /// Draw the background and the text, returns the boundaries of the mark
+    fn draw<CTX: Painter<T>>(
+        &self,
+        internal: &WidgetInternal<T>,
+        bounds: Boundaries<T>,
+        ctx: &mut CTX,
+    ) -> Boundaries<T> {
+        let (x, y, w, h) = bounds;
+        let style = &self.style;
+        let mark = self.mark(bounds);
+
+        ctx.fill_rect((x, y, w, h), style.window);
+
+        // Text after the mark
+        let left = mark.0.to_f32() + mark.2.to_f32() + style.padding.to_f32();
+        let right = x.to_f32() + w.to_f32() - style.padding.to_f32();
+        let bounds = (
+            T::from_f32(left),
+            y,
+            T::from_f32((right - left).max(0.0)),
+            h,
+        );
+
+        let color = style.text_color(internal);
+        self.text.draw(ctx, bounds, Align::Left, style, color);
+
+        mark
+    }
+
+    fn min_dimensions(&self) -> Dimensions<T> {
+        let (w, h) = self.text.dimensions();
+        let side = self.style.font_size.to_f32();
+        let pad = self.style.padding.to_f32();
+
+        (
+            T::from_f32(side + w.to_f32() + pad * 3.0),
+            T::from_f32(side.max(h.to_f32()) + pad * 2.0),
+        )
+    }
+
+    /// Face color of the mark
+    fn face(&self, internal: &WidgetInternal<T>, checked: bool) -> Color {
+        let style = &self.style;
+        if checked && internal.check(ENABLED) {
+            style.accent
+        } else if self.press.is_down() {
+            style.pressed
+        } else if internal.check(HOVER) && internal.check(ENABLED) {
+            style.hover
+        } else {
+            style.button
+        }
+    }
+}
+
+/// Check box that switches a shared value when it's clicked
+///
+/// The signal is pushed after the value changes, add the check box with the SIGNAL
+/// flag and the signal Group for draw it when other widgets change the value
+pub struct CheckBox<T> {
+    indicator: Indicator<T>,
+    value: Rc<Cell<bool>>,
+    signal: GroupID,
+}
+
+impl<T: Scalar> CheckBox<T> {
+    /// Creates a CheckBox with a text, the shared value and the signal ID
+    pub fn new(text: &str, value: Rc<Cell<bool>>, signal: GroupID) -> Self {
+        CheckBox {
+            indicator: Indicator::new(text),
+            value,
+            signal,
+        }
+    }
+
+    pub fn set_style(&mut self, style: Style<T>) {
+        self.indicator.style = style;
+    }
+
+    #[inline]
+    pub fn value(&self) -> bool {
+        self.value.get()
+    }
+
+    fn toggle(&mut self, internal: &mut WidgetInternal<T>) {
+        self.value.set(!self.value.get());
+        internal.on(DRAW);
+
+        push_event(GroupEvent::Signal(self.signal));
+    }
+}
+
+impl<T, CTX> Widget<T, CTX> for CheckBox<T>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+    CTX: Painter<T>,
+{
+    fn draw(&mut self, internal: &WidgetInternal<T>, ctx: &mut CTX) -> bool {
+        let mark = self
+            .indicator
+            .draw(internal, internal.boundaries_abs(), ctx);
+        let style = &self.indicator.style;
+        let checked = self.value.get();
+
+        let border = style.border.to_f32();
+        let (x, y, side) = (mark.0.to_f32(), mark.1.to_f32(), mark.2.to_f32());
+        let inner = (
+            T::from_f32(x + border),
+            T::from_f32(y + border),
+            T::from_f32(side - border * 2.0),
+            T::from_f32(side - border * 2.0),
+        );
+
+        ctx.fill_round_rect(mark, style.radius, style.outline_color(internal));
+        ctx.fill_round_rect(inner, style.radius, self.indicator.face(internal, checked));
+
+        if checked {
+            let p = |px: f32, py: f32| (T::from_f32(x + side * px), T::from_f32(y + side * py));
+            let path = [
+                PathCommand::MoveTo(p(0.22, 0.52)),
+                PathCommand::LineTo(p(0.42, 0.72)),
+                PathCommand::LineTo(p(0.78, 0.3)),
+            ];
+
+            let width = T::from_f32((side / 7.0).max(1.5));
+            let color = if internal.check(ENABLED) {
+                style.accent_text
+            } else {
+                style.disabled
+            };
+
+            ctx.stroke_path(&path, width, color);
+        }
+
+        false
+    }
+
+    fn measure(&mut self, _: &mut WidgetInternal<T>, measure: &SharedMeasure<T>) {
+        let style = &self.indicator.style;
+        self.indicator
+            .text
+            .measure(measure.as_ref(), style.font, style.font_size);
+    }
+
+    /// The shared value was changed by another widget
+    fn handle_signal(&mut self, internal: &mut WidgetInternal<T>, _: GroupID) {
+        internal.on(DRAW);
+    }
+
+    fn handle_mouse(&mut self, internal: &mut WidgetInternal<T>, mouse: &MouseState<T>) {
+        if self.indicator.press.mouse(internal, mouse) {
+            self.toggle(internal);
+        }
+    }
+
+    fn handle_keys(&mut self, internal: &mut WidgetInternal<T>, key: KeyState) {
+        if activates(internal, key) {
+            self.toggle(internal);
+        }
+    }
+
+    fn hover_out(&mut self, internal: &mut WidgetInternal<T>) {
+        self.indicator.press.cancel();
+        internal.on(DRAW);
+    }
+
+    fn pointer_out(&mut self, internal: &mut WidgetInternal<T>, pointer: PointerID) {
+        if self.indicator.press.pointer_out(pointer) {
+            internal.off(GRAB);
+            internal.on(DRAW);
+        }
+    }
+
+    fn min_dimensions(&self) -> Dimensions<T> {
+        self.indicator.min_dimensions()
+    }
+}
+
+/// Radio button that selects its index in a value shared with other radio buttons
+///
+/// Add every radio button of the same value with the SIGNAL flag and the signal
+/// Group, so the previous selected button is drawn again
+pub struct RadioButton<T> {
+    indicator: Indicator<T>,
+    value: Rc<Cell<usize>>,
+    index: usize,
+    signal: GroupID,
+}
+
+impl<T: Scalar> RadioButton<T> {
+    /// Creates a RadioButton with a text, the shared value, the index selected by
+    /// the button and the signal ID
+    pub fn new(text: &str, value: Rc<Cell<usize>>, index: usize, signal: GroupID) -> Self {
+        RadioButton {
+            indicator: Indicator::new(text),
+            value,
+            index,
+            signal,
+        }
+    }
+
+    pub fn set_style(&mut self, style: Style<T>) {
+        self.indicator.style = style;
+    }
+
+    /// Check if the button is the selected of the shared value
+    #[inline]
+    pub fn is_selected(&self) -> bool {
+        self.value.get() == self.index
+    }
+
+    fn select(&mut self, internal: &mut WidgetInternal<T>) {
+        internal.on(DRAW);
+
+        if self.value.get() != self.index {
+            self.value.set(self.index);
+            push_event(GroupEvent::Signal(self.signal));
+        }
+    }
+}
+
+impl<T, CTX> Widget<T, CTX> for RadioButton<T>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+    CTX: Painter<T>,
+{
+    fn draw(&mut self, internal: &WidgetInternal<T>, ctx: &mut CTX) -> bool {
+        let mark = self
+            .indicator
+            .draw(internal, internal.boundaries_abs(), ctx);
+        let style = &self.indicator.style;
+        let selected = self.is_selected();
+
+        let (x, y, side) = (mark.0.to_f32(), mark.1.to_f32(), mark.2.to_f32());
+        let circle = |inset: f32| {
+            let d = (side - inset * 2.0).max(0.0);
+            (
+                (
+                    T::from_f32(x + inset),
+                    T::from_f32(y + inset),
+                    T::from_f32(d),
+                    T::from_f32(d),
+                ),
+                T::from_f32(d * 0.5),
+            )
+        };
+
+        let (outer, r) = circle(0.0);
+        ctx.fill_round_rect(outer, r, style.outline_color(internal));
+        let (inner, r) = circle(style.border.to_f32());
+        ctx.fill_round_rect(inner, r, self.indicator.face(internal, selected));
+
+        if selected {
+            let color = if internal.check(ENABLED) {
+                style.accent_text
+            } else {
+                style.disabled
+            };
+
+            let (dot, r) = circle(side * 0.3);
+            ctx.fill_round_rect(dot, r, color);
+        }
+
+        false
+    }
+
+    fn measure(&mut self, _: &mut WidgetInternal<T>, measure: &SharedMeasure<T>) {
+        let style = &self.indicator.style;
+        self.indicator
+            .text
+            .measure(measure.as_ref(), style.font, style.font_size);
+    }
+
+    /// Another radio button was selected
+    fn handle_signal(&mut self, internal: &mut WidgetInternal<T>, _: GroupID) {
+        internal.on(DRAW);
+    }
+
+    fn handle_mouse(&mut self, internal: &mut WidgetInternal<T>, mouse: &MouseState<T>) {
+        if self.indicator.press.mouse(internal, mouse) {
+            self.select(internal);
+        }
+    }
+
+    fn handle_keys(&mut self, internal: &mut WidgetInternal<T>, key: KeyState) {
+        if activates(internal, key) {
+            self.select(internal);
+        }
+    }
+
+    fn hover_out(&mut self, internal: &mut WidgetInternal<T>) {
+        self.indicator.press.cancel();
+        internal.on(DRAW);
+    }
+
+    fn pointer_out(&mut self, internal: &mut WidgetInternal<T>, pointer: PointerID) {
+        if self.indicator.press.pointer_out(pointer) {
+            internal.off(GRAB);
+            internal.on(DRAW);
+        }
+    }
+
+    fn min_dimensions(&self) -> Dimensions<T> {
+        self.indicator.min_dimensions()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::keys::ENTER;
+    use crate::widgets::tests::{signals, Harness};
+
+    #[test]
+    fn check_box() {
+        let value = Rc::new(Cell::new(false));
+        let mut h = Harness::new(
+            CheckBox::new("Wrap", value.clone(), 2),
+            (0.0, 0.0, 80.0, 20.0),
+        );
+
+        h.click((40.0, 10.0));
+        assert!(value.get());
+        assert_eq!(signals(), [2]);
+
+        h.key(ENTER, 0);
+        assert!(!value.get());
+        assert_eq!(signals(), [2]);
+    }
+
+    #[test]
+    fn radio_buttons() {
+        let value = Rc::new(Cell::new(0));
+        let mut first = Harness::new(
+            RadioButton::new("A", value.clone(), 0, 5),
+            (0.0, 0.0, 80.0, 20.0),
+        );
+        let mut second = Harness::new(
+            RadioButton::new("B", value.clone(), 1, 5),
+            (0.0, 20.0, 80.0, 20.0),
+        );
+
+        second.click((10.0, 30.0));
+        assert_eq!(value.get(), 1);
+        assert_eq!(signals(), [5]);
+
+        // Selecting the selected button again changes nothing
+        second.click((10.0, 30.0));
+        first.click((10.0, 10.0));
+        assert_eq!(value.get(), 0);
+    }
+}
@@ -0,0 +1,1118 @@
+//! Color pickers
+//!
+//! The pickers share a `Rc<Cell<Hsv>>`, so a ColorWheel, some ColorSliders, a
+//! HexField and a Palette can edit the same color. Each one pushes its signal when
+//! it changes the color, the others are drawn again if they are members of the
+//! signal Group and have the SIGNAL flag
+
+use super::{contains, inset, line, mouse_pos, rect, Style};
+use crate::color::{format_hex, parse_hex, Channel, Hsv, Rgb};
+use crate::group::{push_event, GroupEvent, GroupID};
+use crate::math::Scalar;
+use crate::paint::{Color, Painter, PathCommand};
+use crate::state::keys::*;
+use crate::state::{Cursor, KeyState, MouseState, MouseType, PointerID};
+use crate::text::{Monospace, SharedMeasure};
+use crate::widget::flags::*;
+use crate::widget::{Boundaries, Dimensions, Widget, WidgetInternal};
+
+use std::cell::{Cell, RefCell};
+use std::f32::consts::PI;
+use std::ops::{Add, Sub};
+use std::rc::Rc;
+
+/// Step of the keys and the wheel as a fraction of a channel
+const STEP: f32 = 0.05;
+/// Step with Shift
+const FINE: f32 = 0.01;
+/// Quads of the hue ring
+const SEGMENTS: usize = 90;
+
+const CHECKER_LIGHT: Color = Color::rgb(255, 255, 255);
+const CHECKER_DARK: Color = Color::rgb(204, 204, 204);
+
+/// Store a color in the shared value, the signal is pushed if it changed
+fn store<T>(value: &Cell<Hsv>, hsv: Hsv, signal: GroupID, internal: &mut WidgetInternal<T>) {
+    if hsv != value.get() {
+        value.set(hsv);
+        push_event(GroupEvent::Signal(signal));
+    }
+
+    internal.on(DRAW);
+}
+
+/// Step of a key or the wheel, Shift makes it fine
+#[inline]
+fn step(fine: bool) -> f32 {
+    if fine {
+        FINE
+    } else {
+        STEP
+    }
+}
+
+/// Squares for show the alpha of a color over them
+fn checker<T: Scalar, CTX: Painter<T>>(ctx: &mut CTX, bounds: Boundaries<T>, size: f32) {
+    let (x, y, w, h) = (
+        bounds.0.to_f32(),
+        bounds.1.to_f32(),
+        bounds.2.to_f32(),
+        bounds.3.to_f32(),
+    );
+    let size = size.max(2.0);
+
+    ctx.fill_rect(bounds, CHECKER_LIGHT);
+    ctx.push_clip(bounds);
+
+    let (columns, rows) = ((w / size).ceil() as usize, (h / size).ceil() as usize);
+    for row in 0..rows {
+        for column in (row % 2..columns).step_by(2) {
+            let cell = rect(x + column as f32 * size, y + row as f32 * size, size, size);
+            ctx.fill_rect(cell, CHECKER_DARK);
+        }
+    }
+
+    ctx.pop_clip();
+}
+
+/// Circle with a dark and a light ring for show a position over any color
+fn marker<T: Scalar, CTX: Painter<T>>(ctx: &mut CTX, (x, y): (f32, f32), size: f32, fill: Color) {
+    let outer = rect(x - size * 0.5, y - size * 0.5, size, size);
+    let radius = |bounds: Boundaries<T>| T::from_f32(bounds.2.to_f32() * 0.5);
+    let light = inset(outer, T::from_f32(1.0));
+    let inner = inset(outer, T::from_f32(2.5));
+
+    ctx.fill_round_rect(outer, radius(outer), Color::BLACK);
+    ctx.fill_round_rect(light, radius(light), Color::WHITE);
+    ctx.fill_round_rect(inner, radius(inner), fill);
+}
+
+/// Part of a ColorWheel dragged by the pointer
+#[derive(Clone, Copy, PartialEq)]
+enum Part {
+    Ring,
+    Square,
+}
+
+/// Hue ring with a square of saturation and value inside it
+///
+/// The hue is 0 at the right and it increases counterclockwise. In the square the
+/// saturation increases to the right and the value upwards. Pressing a part moves
+/// the color to the pointer and dragging follows it. When it's focused Left and
+/// Right change the saturation, Up and Down the value and PageUp and PageDown the
+/// hue, with Shift the steps are finer. The wheel changes the hue
+pub struct ColorWheel<T> {
+    value: Rc<Cell<Hsv>>,
+    signal: GroupID,
+    style: Style<T>,
+    drag: Option<Part>,
+}
+
+impl<T: Scalar> ColorWheel<T> {
+    /// Creates a ColorWheel with the shared color and the signal ID pushed when it
+    /// changes
+    pub fn new(value: Rc<Cell<Hsv>>, signal: GroupID) -> Self {
+        ColorWheel {
+            value,
+            signal,
+            style: Style::default(),
+            drag: None,
+        }
+    }
+
+    pub fn set_style(&mut self, style: Style<T>) {
+        self.style = style;
+    }
+
+    #[inline]
+    pub fn value(&self) -> Hsv {
+        self.value.get()
+    }
+
+    /// Center, outer and inner radius of the ring
+    fn ring(&self, bounds: Boundaries<T>) -> ((f32, f32), f32, f32) {
+        let (x, y, w, h) = inset(bounds, self.style.padding);
+        let (x, y, w, h) = (x.to_f32(), y.to_f32(), w.to_f32(), h.to_f32());
+        let outer = w.min(h) * 0.5;
+        let width = (outer * 0.18).max(self.style.font_size.to_f32() * 0.5);
+
+        ((x + w * 0.5, y + h * 0.5), outer, (outer - width).max(0.0))
+    }
+
+    /// Top left corner and side of the square
+    fn square(&self, bounds: Boundaries<T>) -> (f32, f32, f32) {
+        let ((cx, cy), _, inner) = self.ring(bounds);
+        let gap = self.style.padding.to_f32();
+        let side = ((inner - gap) * std::f32::consts::SQRT_2).max(0.0).floor();
+
+        (cx - side * 0.5, cy - side * 0.5, side)
+    }
+
+    fn part(&self, bounds: Boundaries<T>, (x, y): (f32, f32)) -> Option<Part> {
+        let ((cx, cy), outer, inner) = self.ring(bounds);
+        let distance = (x - cx).hypot(y - cy);
+        let (sx, sy, side) = self.square(bounds);
+
+        if distance >= inner && distance <= outer {
+            Some(Part::Ring)
+        } else if x >= sx && y >= sy && x <= sx + side && y <= sy + side {
+            Some(Part::Square)
+        } else {
+            None
+        }
+    }
+
+    /// Move the color of a part to a position
+    fn drag_to(
+        &mut self,
+        internal: &mut WidgetInternal<T>,
+        bounds: Boundaries<T>,
+        part: Part,
+        (x, y): (f32, f32),
+    ) {
+        let mut hsv = self.value.get();
+
+        match part {
+            Part::Ring => {
+                let ((cx, cy), _, _) = self.ring(bounds);
+                let angle = (cy - y).atan2(x - cx).to_degrees();
+                hsv.h = angle.rem_euclid(360.0) % 360.0;
+            }
+            Part::Square => {
+                let (sx, sy, side) = self.square(bounds);
+                let side = side.max(1.0);
+                hsv.s = ((x - sx) / side).clamp(0.0, 1.0);
+                hsv.v = (1.0 - (y - sy) / side).clamp(0.0, 1.0);
+            }
+        }
+
+        store(&self.value, hsv, self.signal, internal);
+    }
+
+    /// Add a step to a channel
+    fn add(&mut self, internal: &mut WidgetInternal<T>, channel: Channel, steps: f32) {
+        let hsv = self.value.get();
+        let value = hsv.channel(channel) + steps;
+
+        // The hue goes around
+        let value = if channel == Channel::Hue {
+            value.rem_euclid(1.0)
+        } else {
+            value
+        };
+
+        store(
+            &self.value,
+            hsv.with_channel(channel, value),
+            self.signal,
+            internal,
+        );
+    }
+}
+
+impl<T, CTX> Widget<T, CTX> for ColorWheel<T>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+    CTX: Painter<T>,
+{
+    fn draw(&mut self, internal: &WidgetInternal<T>, ctx: &mut CTX) -> bool {
+        let bounds = internal.boundaries_abs();
+        let style = &self.style;
+        let hsv = self.value.get();
+
+        ctx.fill_rect(bounds, style.window);
+
+        // Ring, the quads overlap a bit for hide the seams
+        let ((cx, cy), outer, inner) = self.ring(bounds);
+        let p = |angle: f32, radius: f32| {
+            (
+                T::from_f32(cx + angle.cos() * radius),
+                T::from_f32(cy - angle.sin() * radius),
+            )
+        };
+
+        let segment = PI * 2.0 / SEGMENTS as f32;
+        for n in 0..SEGMENTS {
+            let (a, b) = (segment * n as f32, segment * (n as f32 + 1.5));
+            let color = Hsv::new((n as f32 + 0.5) * 360.0 / SEGMENTS as f32, 1.0, 1.0, 1.0);
+            let quad = [
+                PathCommand::MoveTo(p(a, outer)),
+                PathCommand::LineTo(p(b, outer)),
+                PathCommand::LineTo(p(b, inner)),
+                PathCommand::LineTo(p(a, inner)),
+                PathCommand::Close,
+            ];
+
+            ctx.fill_path(&quad, color.to_color());
+        }
+
+        // Square with cells of about 3 pixels
+        let (sx, sy, side) = self.square(bounds);
+        let cells = ((side / 3.0) as usize).clamp(1, 64);
+        let edge = |n: usize| (side * n as f32 / cells as f32).round();
+
+        for row in 0..cells {
+            let (top, bottom) = (edge(row), edge(row + 1));
+            let v = 1.0 - (row as f32 + 0.5) / cells as f32;
+
+            for column in 0..cells {
+                let (left, right) = (edge(column), edge(column + 1));
+                let s = (column as f32 + 0.5) / cells as f32;
+                let color = Hsv::new(hsv.h, s, v, 1.0).to_color();
+
+                ctx.fill_rect(rect(sx + left, sy + top, right - left, bottom - top), color);
+            }
+        }
+
+        let size = style.font_size.to_f32() * 0.8;
+        let opaque = Hsv { a: 1.0, ..hsv };
+
+        // Markers of the hue and of the saturation and value
+        let angle = hsv.h.to_radians();
+        let middle = (outer + inner) * 0.5;
+        let ring = (cx + angle.cos() * middle, cy - angle.sin() * middle);
+        let pure = Hsv::new(hsv.h, 1.0, 1.0, 1.0).to_color();
+        marker(ctx, ring, (outer - inner).min(size * 1.5), pure);
+
+        let point = (sx + hsv.s * side, sy + (1.0 - hsv.v) * side);
+        marker(ctx, point, size, opaque.to_color());
+
+        if internal.check(FOCUS) {
+            let bounds = rect(cx - outer, cy - outer, outer * 2.0, outer * 2.0);
+            ctx.stroke_rect(bounds, style.border, style.focus);
+        }
+
+        false
+    }
+
+    /// The shared color was changed by another widget
+    fn handle_signal(&mut self, internal: &mut WidgetInternal<T>, _: GroupID) {
+        internal.on(DRAW);
+    }
+
+    fn handle_mouse(&mut self, internal: &mut WidgetInternal<T>, mouse: &MouseState<T>) {
+        if !internal.check(ENABLED) {
+            return;
+        }
+
+        let bounds = internal.boundaries_abs();
+        let pos = mouse_pos(mouse);
+
+        internal.set_cursor(match self.drag.or_else(|| self.part(bounds, pos)) {
+            Some(_) => Cursor::Crosshair,
+            None => Cursor::Default,
+        });
+
+        match mouse.m_type {
+            MouseType::Pressed(1) => {
+                internal.on(FOCUS | DRAW);
+
+                if let Some(part) = self.part(bounds, pos) {
+                    self.drag = Some(part);
+                    internal.on(GRAB);
+                    self.drag_to(internal, bounds, part, pos);
+                }
+            }
+            MouseType::Released(1) if self.drag.is_some() => {
+                self.drag = None;
+                internal.off(GRAB);
+                internal.on(DRAW);
+            }
+            MouseType::CursorMoved => {
+                if let Some(part) = self.drag {
+                    self.drag_to(internal, bounds, part, pos);
+                }
+            }
+            MouseType::Wheel(delta) if delta != 0.0 => {
+                let steps = delta.signum() * step(mouse.check_modifiers(SHIFT));
+                self.add(internal, Channel::Hue, steps);
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_keys(&mut self, internal: &mut WidgetInternal<T>, key: KeyState) {
+        let (code, mods) = match key {
+            KeyState::Pressed(code, mods) if internal.check(ENABLED) => (code, mods),
+            _ => return,
+        };
+
+        let step = step(mods & SHIFT != 0);
+        let (channel, steps) = match code {
+            LEFT => (Channel::Saturation, -step),
+            RIGHT => (Channel::Saturation, step),
+            DOWN => (Channel::Value, -step),
+            UP => (Channel::Value, step),
+            PAGE_DOWN => (Channel::Hue, -step),
+            PAGE_UP => (Channel::Hue, step),
+            _ => return,
+        };
+
+        self.add(internal, channel, steps);
+    }
+
+    fn pointer_out(&mut self, internal: &mut WidgetInternal<T>, _: PointerID) {
+        self.drag = None;
+        internal.off(GRAB);
+        internal.on(DRAW);
+    }
+
+    fn focus_out(&mut self, internal: &mut WidgetInternal<T>) {
+        internal.on(DRAW);
+    }
+
+    fn min_dimensions(&self) -> Dimensions<T> {
+        let side = self.style.font_size.to_f32() * 10.0 + self.style.padding.to_f32() * 2.0;
+        (T::from_f32(side), T::from_f32(side))
+    }
+}
+
+/// Horizontal bar with the gradient of a channel of the color
+///
+/// The gradient shows the color with the other channels of the current color, the
+/// hue bar shows the pure hues. Pressing the bar moves the channel to the pointer
+/// and dragging follows it. The wheel and the arrows change the channel, with
+/// Shift the steps are finer, and Home and End go to the limits
+pub struct ColorSlider<T> {
+    value: Rc<Cell<Hsv>>,
+    channel: Channel,
+    signal: GroupID,
+    style: Style<T>,
+    drag: bool,
+}
+
+impl<T: Scalar> ColorSlider<T> {
+    /// Creates a ColorSlider of a channel with the shared color and the signal ID
+    pub fn new(value: Rc<Cell<Hsv>>, channel: Channel, signal: GroupID) -> Self {
+        ColorSlider {
+            value,
+            channel,
+            signal,
+            style: Style::default(),
+            drag: false,
+        }
+    }
+
+    pub fn set_style(&mut self, style: Style<T>) {
+        self.style = style;
+    }
+
+    #[inline]
+    pub fn channel(&self) -> Channel {
+        self.channel
+    }
+
+    fn bar(&self, bounds: Boundaries<T>) -> Boundaries<T> {
+        inset(bounds, self.style.padding)
+    }
+
+    /// Color of the gradient at a fraction of the bar
+    fn color_at(&self, hsv: Hsv, fraction: f32) -> Color {
+        let hsv = match self.channel {
+            Channel::Hue => Hsv::new(fraction * 360.0, 1.0, 1.0, 1.0),
+            Channel::Alpha => hsv.with_channel(Channel::Alpha, fraction),
+            channel => Hsv { a: 1.0, ..hsv }.with_channel(channel, fraction),
+        };
+
+        hsv.to_color()
+    }
+
+    fn set(&mut self, internal: &mut WidgetInternal<T>, fraction: f32) {
+        let hsv = self.value.get().with_channel(self.channel, fraction);
+        store(&self.value, hsv, self.signal, internal);
+    }
+
+    fn set_x(&mut self, internal: &mut WidgetInternal<T>, bounds: Boundaries<T>, x: f32) {
+        let bar = self.bar(bounds);
+        let fraction = (x - bar.0.to_f32()) / bar.2.to_f32().max(1.0);
+        self.set(internal, fraction);
+    }
+}
+
+impl<T, CTX> Widget<T, CTX> for ColorSlider<T>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+    CTX: Painter<T>,
+{
+    fn draw(&mut self, internal: &WidgetInternal<T>, ctx: &mut CTX) -> bool {
+        let bounds = internal.boundaries_abs();
+        let style = &self.style;
+        let hsv = self.value.get();
+
+        ctx.fill_rect(bounds, style.window);
+
+        let bar = self.bar(bounds);
+        let (x, y, w, h) = (
+            bar.0.to_f32(),
+            bar.1.to_f32(),
+            bar.2.to_f32(),
+            bar.3.to_f32(),
+        );
+
+        if self.channel == Channel::Alpha {
+            checker(ctx, bar, h * 0.5);
+        }
+
+        // Strips of 2 pixels
+        let strips = ((w / 2.0).ceil() as usize).max(1);
+        for n in 0..strips {
+            let left = (w * n as f32 / strips as f32).round();
+            let right = (w * (n + 1) as f32 / strips as f32).round();
+            let color = self.color_at(hsv, (n as f32 + 0.5) / strips as f32);
+
+            ctx.fill_rect(rect(x + left, y, right - left, h), color);
+        }
+
+        ctx.stroke_rect(bar, style.border, style.outline_color(internal));
+
+        // Marker of the value
+        let cx = x + hsv.channel(self.channel) * w;
+        let width = (h * 0.4).max(4.0);
+        let pad = style.padding.to_f32();
+        let knob = rect(cx - width * 0.5, y - pad * 0.5, width, h + pad);
+        let radius = T::from_f32(2.0);
+
+        ctx.fill_round_rect(knob, radius, Color::BLACK);
+        ctx.fill_round_rect(inset(knob, T::from_f32(1.0)), radius, Color::WHITE);
+        ctx.fill_rect(
+            inset(knob, T::from_f32(2.5)),
+            self.color_at(hsv, hsv.channel(self.channel)),
+        );
+
+        false
+    }
+
+    /// The shared color was changed by another widget
+    fn handle_signal(&mut self, internal: &mut WidgetInternal<T>, _: GroupID) {
+        internal.on(DRAW);
+    }
+
+    fn handle_mouse(&mut self, internal: &mut WidgetInternal<T>, mouse: &MouseState<T>) {
+        if !internal.check(ENABLED) {
+            return;
+        }
+
+        let bounds = internal.boundaries_abs();
+        let x = mouse_pos(mouse).0;
+        match mouse.m_type {
+            MouseType::Pressed(1) => {
+                self.drag = true;
+                internal.on(GRAB | FOCUS | DRAW);
+                self.set_x(internal, bounds, x);
+            }
+            MouseType::Released(1) if self.drag => {
+                self.drag = false;
+                internal.off(GRAB);
+                internal.on(DRAW);
+            }
+            MouseType::CursorMoved if self.drag => self.set_x(internal, bounds, x),
+            MouseType::Wheel(delta) if delta != 0.0 => {
+                let value = self.value.get().channel(self.channel);
+                let steps = delta.signum() * step(mouse.check_modifiers(SHIFT));
+                self.set(internal, value + steps);
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_keys(&mut self, internal: &mut WidgetInternal<T>, key: KeyState) {
+        let (code, mods) = match key {
+            KeyState::Pressed(code, mods) if internal.check(ENABLED) => (code, mods),
+            _ => return,
+        };
+
+        let value = self.value.get().channel(self.channel);
+        let value = match code {
+            LEFT | DOWN => value - step(mods & SHIFT != 0),
+            RIGHT | UP => value + step(mods & SHIFT != 0),
+            HOME => 0.0,
+            END => 1.0,
+            _ => return,
+        };
+
+        self.set(internal, value);
+    }
+
+    fn pointer_out(&mut self, internal: &mut WidgetInternal<T>, _: PointerID) {
+        self.drag = false;
+        internal.off(GRAB);
+        internal.on(DRAW);
+    }
+
+    fn focus_out(&mut self, internal: &mut WidgetInternal<T>) {
+        internal.on(DRAW);
+    }
+
+    fn min_dimensions(&self) -> Dimensions<T> {
+        let size = self.style.font_size.to_f32();
+        let pad = self.style.padding.to_f32() * 2.0;
+
+        (T::from_f32(size * 8.0 + pad), T::from_f32(size + pad))
+    }
+}
+
+/// Field with a swatch and the hex text of the color
+///
+/// Typing hex digits replaces the text, Enter applies it and Escape cancels it. The
+/// text is applied too when the focus goes out. It accepts 3, 4, 6 or 8 digits, the
+/// alpha is only changed when the field shows it
+pub struct HexField<T> {
+    value: Rc<Cell<Hsv>>,
+    signal: GroupID,
+    style: Style<T>,
+    measure: SharedMeasure<T>,
+    alpha: bool,
+    typed: Option<String>,
+}
+
+impl<T: Scalar> HexField<T> {
+    /// Creates a HexField with the shared color and the signal ID
+    pub fn new(value: Rc<Cell<Hsv>>, signal: GroupID) -> Self {
+        HexField {
+            value,
+            signal,
+            style: Style::default(),
+            measure: Rc::new(Monospace::new(0.6, 0.8, 0.2)),
+            alpha: false,
+            typed: None,
+        }
+    }
+
+    pub fn set_style(&mut self, style: Style<T>) {
+        self.style = style;
+    }
+
+    /// Show and edit the alpha as the last two digits
+    pub fn set_alpha(&mut self, alpha: bool) {
+        self.alpha = alpha;
+    }
+
+    /// Typed text or the text of the color
+    fn text(&self) -> String {
+        match &self.typed {
+            Some(typed) => typed.clone(),
+            None => format_hex(self.value.get().to_color(), self.alpha),
+        }
+    }
+
+    /// Replace the color with the typed text if it's a color
+    fn apply(&mut self, internal: &mut WidgetInternal<T>) {
+        let color = match self.typed.take().as_deref().and_then(parse_hex) {
+            Some(color) => color,
+            None => return internal.on(DRAW),
+        };
+
+        let mut hsv = self.value.get();
+        let mut rgb = Rgb::from(color);
+        if !self.alpha {
+            rgb.a = hsv.a;
+        }
+
+        hsv.set_rgb(rgb);
+        store(&self.value, hsv, self.signal, internal);
+    }
+}
+
+impl<T, CTX> Widget<T, CTX> for HexField<T>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+    CTX: Painter<T>,
+{
+    fn draw(&mut self, internal: &WidgetInternal<T>, ctx: &mut CTX) -> bool {
+        let bounds = internal.boundaries_abs();
+        let style = &self.style;
+
+        ctx.fill_rect(bounds, style.window);
+        ctx.fill_round_rect(bounds, style.radius, style.outline_color(internal));
+
+        let inner = inset(bounds, style.border);
+        ctx.fill_round_rect(inner, style.radius, style.field);
+
+        let content = inset(inner, style.padding);
+        let (x, y, w, h) = (
+            content.0.to_f32(),
+            content.1.to_f32(),
+            content.2.to_f32(),
+            content.3.to_f32(),
+        );
+
+        // Swatch of the color over the squares of the alpha
+        let line = line(&self.measure, &self.style);
+        let swatch = rect(x, y + (h - line) * 0.5, line, line);
+        checker(ctx, swatch, line * 0.5);
+        ctx.fill_rect(swatch, self.value.get().to_color());
+        ctx.stroke_rect(swatch, style.border, style.outline);
+
+        let text = self.text();
+        let left = x + line + style.padding.to_f32();
+        let ascent = self.measure.ascent(style.font, style.font_size).to_f32();
+        let pos = (
+            T::from_f32(left),
+            T::from_f32(y + (h - line) * 0.5 + ascent),
+        );
+
+        ctx.push_clip(rect(left, y, (x + w - left).max(0.0), h));
+        ctx.text(
+            pos,
+            style.font,
+            style.font_size,
+            &text,
+            style.text_color(internal),
+        );
+
+        if self.typed.is_some() && internal.check(FOCUS) {
+            let end = left
+                + self
+                    .measure
+                    .advance(style.font, style.font_size, &text)
+                    .to_f32();
+            ctx.fill_rect(rect(end, y + (h - line) * 0.5, 1.0, line), style.text);
+        }
+        ctx.pop_clip();
+
+        false
+    }
+
+    fn measure(&mut self, _: &mut WidgetInternal<T>, measure: &SharedMeasure<T>) {
+        self.measure = measure.clone();
+    }
+
+    /// The shared color was changed by another widget
+    fn handle_signal(&mut self, internal: &mut WidgetInternal<T>, _: GroupID) {
+        internal.on(DRAW);
+    }
+
+    fn handle_mouse(&mut self, internal: &mut WidgetInternal<T>, mouse: &MouseState<T>) {
+        if !internal.check(ENABLED) {
+            return;
+        }
+
+        internal.set_cursor(Cursor::Text);
+        if let MouseType::Pressed(1) = mouse.m_type {
+            internal.on(FOCUS | DRAW);
+        }
+    }
+
+    fn handle_keys(&mut self, internal: &mut WidgetInternal<T>, key: KeyState) {
+        let (code, mods) = match key {
+            KeyState::Pressed(code, mods) if internal.check(ENABLED) => (code, mods),
+            _ => return,
+        };
+
+        let max = if self.alpha { 9 } else { 7 };
+        match code {
+            ENTER => self.apply(internal),
+            ESCAPE => self.typed = None,
+            BACKSPACE => {
+                if let Some(typed) = &mut self.typed {
+                    typed.pop();
+                }
+            }
+            _ => match to_char(code) {
+                Some(c)
+                    if mods & (CTRL | ALT | SUPER) == 0 && (c.is_ascii_hexdigit() || c == '#') =>
+                {
+                    let typed = self.typed.get_or_insert_with(String::new);
+                    if typed.len() < max {
+                        typed.push(c.to_ascii_uppercase());
+                    }
+                }
+                _ => return,
+            },
+        }
+
+        internal.on(DRAW);
+    }
+
+    fn pointer_out(&mut self, _: &mut WidgetInternal<T>, _: PointerID) {}
+
+    /// The typed text is applied
+    fn focus_out(&mut self, internal: &mut WidgetInternal<T>) {
+        self.apply(internal);
+        internal.on(DRAW);
+    }
+
+    fn min_dimensions(&self) -> Dimensions<T> {
+        let style = &self.style;
+        let text = if self.alpha { "#DDDDDDDD" } else { "#DDDDDD" };
+        let width = self
+            .measure
+            .advance(style.font, style.font_size, text)
+            .to_f32();
+        let line = line(&self.measure, &self.style);
+        let space = (style.border.to_f32() + style.padding.to_f32()) * 2.0;
+
+        (
+            T::from_f32(line + style.padding.to_f32() + width + space),
+            T::from_f32(line + space),
+        )
+    }
+}
+
+/// Grid of color swatches
+///
+/// Clicking a swatch replaces the shared color, the swatch of the current color has
+/// the focus color as outline. When it's focused the arrows move a cursor over the
+/// swatches and Space or Enter picks the color under it. The colors are shared with
+/// the application, push the signal after changing them
+pub struct Palette<T> {
+    colors: Rc<RefCell<Vec<Color>>>,
+    value: Rc<Cell<Hsv>>,
+    signal: GroupID,
+    style: Style<T>,
+    columns: usize,
+    hover: Option<usize>,
+    cursor: usize,
+}
+
+impl<T: Scalar> Palette<T> {
+    /// Creates a Palette with the shared colors, the number of columns, the shared
+    /// color and the signal ID
+    pub fn new(
+        colors: Rc<RefCell<Vec<Color>>>,
+        columns: usize,
+        value: Rc<Cell<Hsv>>,
+        signal: GroupID,
+    ) -> Self {
+        Palette {
+            colors,
+            value,
+            signal,
+            style: Style::default(),
+            columns: columns.max(1),
+            hover: None,
+            cursor: 0,
+        }
+    }
+
+    pub fn set_style(&mut self, style: Style<T>) {
+        self.style = style;
+    }
+
+    #[inline]
+    pub fn colors(&self) -> &Rc<RefCell<Vec<Color>>> {
+        &self.colors
+    }
+
+    /// Side of a swatch and the gap between them
+    fn metrics(&self) -> (f32, f32) {
+        let size = (self.style.font_size.to_f32() * 1.5).round();
+        (size, self.style.border.to_f32() * 3.0)
+    }
+
+    fn swatch(&self, bounds: Boundaries<T>, index: usize) -> Boundaries<T> {
+        let (size, gap) = self.metrics();
+        let pad = self.style.padding.to_f32();
+        let (column, row) = (index % self.columns, index / self.columns);
+
+        rect(
+            bounds.0.to_f32() + pad + column as f32 * (size + gap),
+            bounds.1.to_f32() + pad + row as f32 * (size + gap),
+            size,
+            size,
+        )
+    }
+
+    fn hit(&self, bounds: Boundaries<T>, pos: (f32, f32)) -> Option<usize> {
+        let count = self.colors.borrow().len();
+        (0..count).find(|&n| contains(self.swatch(bounds, n), pos))
+    }
+
+    /// Replace the shared color with a swatch, the hue is kept for grays
+    fn pick(&mut self, internal: &mut WidgetInternal<T>, index: usize) {
+        let color = match self.colors.borrow().get(index) {
+            Some(&color) => color,
+            None => return,
+        };
+
+        let mut hsv = self.value.get();
+        hsv.set_rgb(Rgb::from(color));
+        self.cursor = index;
+        store(&self.value, hsv, self.signal, internal);
+    }
+}
+
+impl<T, CTX> Widget<T, CTX> for Palette<T>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+    CTX: Painter<T>,
+{
+    fn draw(&mut self, internal: &WidgetInternal<T>, ctx: &mut CTX) -> bool {
+        let bounds = internal.boundaries_abs();
+        let style = &self.style;
+        let current = self.value.get().to_color();
+        let focused = internal.check(FOCUS);
+
+        ctx.fill_rect(bounds, style.window);
+
+        for (n, &color) in self.colors.borrow().iter().enumerate() {
+            let swatch = self.swatch(bounds, n);
+
+            if color.a < 255 {
+                checker(ctx, swatch, swatch.2.to_f32() * 0.25);
+            }
+            ctx.fill_rect(swatch, color);
+
+            let outline = if color == current {
+                style.focus
+            } else if self.hover == Some(n) {
+                style.text
+            } else {
+                style.outline
+            };
+            ctx.stroke_rect(swatch, style.border, outline);
+
+            if focused && self.cursor == n {
+                let border = T::from_f32(style.border.to_f32() * -2.0);
+                ctx.stroke_rect(inset(swatch, border), style.border, style.focus);
+            }
+        }
+
+        false
+    }
+
+    /// The shared color or the colors were changed
+    fn handle_signal(&mut self, internal: &mut WidgetInternal<T>, _: GroupID) {
+        internal.on(DRAW);
+    }
+
+    fn handle_mouse(&mut self, internal: &mut WidgetInternal<T>, mouse: &MouseState<T>) {
+        let hit = self.hit(internal.boundaries_abs(), mouse_pos(mouse));
+        if hit != self.hover {
+            self.hover = hit;
+            internal.on(DRAW);
+        }
+
+        if !internal.check(ENABLED) {
+            return;
+        }
+
+        if let MouseType::Pressed(1) = mouse.m_type {
+            internal.on(FOCUS | DRAW);
+
+            if let Some(index) = hit {
+                self.pick(internal, index);
+            }
+        }
+    }
+
+    fn handle_keys(&mut self, internal: &mut WidgetInternal<T>, key: KeyState) {
+        let code = match key {
+            KeyState::Pressed(code, _) if internal.check(ENABLED) => code,
+            _ => return,
+        };
+
+        let count = self.colors.borrow().len();
+        if count == 0 {
+            return;
+        }
+
+        let cursor = self.cursor.min(count - 1);
+        let columns = self.columns;
+        self.cursor = match code {
+            LEFT => cursor.saturating_sub(1),
+            RIGHT => (cursor + 1).min(count - 1),
+            UP if cursor >= columns => cursor - columns,
+            DOWN if cursor + columns < count => cursor + columns,
+            HOME => 0,
+            END => count - 1,
+            ENTER | SPACE => return self.pick(internal, cursor),
+            _ => return,
+        };
+
+        internal.on(DRAW);
+    }
+
+    fn hover_out(&mut self, internal: &mut WidgetInternal<T>) {
+        if self.hover.take().is_some() {
+            internal.on(DRAW);
+        }
+    }
+
+    fn focus_out(&mut self, internal: &mut WidgetInternal<T>) {
+        internal.on(DRAW);
+    }
+
+    fn min_dimensions(&self) -> Dimensions<T> {
+        let (size, gap) = self.metrics();
+        let count = self.colors.borrow().len();
+        let rows = count.div_ceil(self.columns);
+        let columns = self.columns.min(count);
+        let pad = self.style.padding.to_f32() * 2.0;
+        let length = |n: usize| (n as f32 * (size + gap) - gap).max(0.0) + pad;
+
+        (T::from_f32(length(columns)), T::from_f32(length(rows)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::DisplayList;
+    use crate::state::PRIMARY_POINTER;
+    use crate::widgets::tests::{signals, texts, Harness};
+
+    const COLOR: GroupID = 7;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    fn shared(hsv: Hsv) -> Rc<Cell<Hsv>> {
+        Rc::new(Cell::new(hsv))
+    }
+
+    #[test]
+    fn wheel() {
+        // The ring is from 37.7 to 46 around (50, 50), the square is 47 wide from 26.5
+        let value = shared(Hsv::new(180.0, 0.5, 0.5, 1.0));
+        let mut h = Harness::new(
+            ColorWheel::new(value.clone(), COLOR),
+            (0.0, 0.0, 100.0, 100.0),
+        );
+
+        h.mouse(PRIMARY_POINTER, MouseType::Pressed(1), (92.0, 50.0));
+        assert!(close(value.get().h, 0.0));
+        // The drag follows the pointer out of the ring
+        h.mouse(PRIMARY_POINTER, MouseType::CursorMoved, (50.0, 0.0));
+        assert!(close(value.get().h, 90.0));
+        h.mouse(PRIMARY_POINTER, MouseType::Released(1), (50.0, 0.0));
+        assert!(!h.internal.check(GRAB));
+        assert_eq!(signals(), [COLOR]);
+
+        h.mouse(PRIMARY_POINTER, MouseType::Pressed(1), (73.5, 26.5));
+        assert_eq!((value.get().s, value.get().v), (1.0, 1.0));
+        h.mouse(PRIMARY_POINTER, MouseType::CursorMoved, (0.0, 100.0));
+        assert_eq!((value.get().s, value.get().v), (0.0, 0.0));
+        assert!(close(value.get().h, 90.0));
+        h.mouse(PRIMARY_POINTER, MouseType::Released(1), (0.0, 100.0));
+
+        // Pressing out of the parts takes the focus without changing the color
+        signals();
+        h.mouse(PRIMARY_POINTER, MouseType::Pressed(1), (5.0, 5.0));
+        assert!(signals().is_empty());
+        assert!(h.internal.check(FOCUS));
+    }
+
+    #[test]
+    fn wheel_keys() {
+        let value = shared(Hsv::new(0.0, 0.5, 0.5, 1.0));
+        let mut h = Harness::new(
+            ColorWheel::new(value.clone(), COLOR),
+            (0.0, 0.0, 100.0, 100.0),
+        );
+
+        h.key(RIGHT, 0).key(DOWN, SHIFT);
+        assert!(close(value.get().s, 0.55));
+        assert!(close(value.get().v, 0.49));
+
+        // The hue goes around
+        h.key(PAGE_DOWN, 0);
+        assert!(close(value.get().h, 342.0));
+        h.mouse(PRIMARY_POINTER, MouseType::Wheel(1.0), (50.0, 50.0));
+        assert!(close(value.get().h, 0.0));
+        assert_eq!(signals(), [COLOR]);
+    }
+
+    #[test]
+    fn slider() {
+        // The bar is 100 wide from 4
+        let value = shared(Hsv::new(120.0, 1.0, 1.0, 1.0));
+        let mut h = Harness::new(
+            ColorSlider::new(value.clone(), Channel::Alpha, COLOR),
+            (0.0, 0.0, 108.0, 19.0),
+        );
+
+        h.mouse(PRIMARY_POINTER, MouseType::Pressed(1), (54.0, 10.0));
+        assert!(close(value.get().a, 0.5));
+        h.mouse(PRIMARY_POINTER, MouseType::CursorMoved, (200.0, 10.0));
+        assert_eq!(value.get().a, 1.0);
+        h.mouse(PRIMARY_POINTER, MouseType::Released(1), (200.0, 10.0));
+        assert_eq!(signals(), [COLOR]);
+
+        h.key(LEFT, SHIFT);
+        assert!(close(value.get().a, 0.99));
+        h.key(HOME, 0);
+        assert_eq!(value.get().a, 0.0);
+        h.mouse(PRIMARY_POINTER, MouseType::Wheel(1.0), (54.0, 10.0));
+        assert!(close(value.get().a, 0.05));
+
+        // The other channels are kept
+        assert!(close(value.get().h, 120.0));
+        assert_eq!((value.get().s, value.get().v), (1.0, 1.0));
+
+        // A RGB channel changes the hue
+        let mut h = Harness::new(
+            ColorSlider::new(value.clone(), Channel::Red, COLOR),
+            (0.0, 0.0, 108.0, 19.0),
+        );
+        h.key(END, 0);
+        assert!(close(value.get().h, 60.0));
+    }
+
+    #[test]
+    fn hex_field() {
+        let value = shared(Hsv::new(0.0, 0.0, 0.0, 0.5));
+        let mut h = Harness::new(HexField::new(value.clone(), COLOR), (0.0, 0.0, 120.0, 30.0));
+        assert_eq!(texts(&h.draw()), ["#000000"]);
+
+        // The alpha isn't changed without the alpha digits
+        h.click((50.0, 15.0));
+        for c in "#0f0".chars() {
+            h.key(c as u32, 0);
+        }
+        assert_eq!(texts(&h.draw()), ["#0F0"]);
+        assert!(signals().is_empty());
+        h.key(ENTER, 0);
+        assert!(close(value.get().h, 120.0));
+        assert_eq!(value.get().a, 0.5);
+        assert_eq!(signals(), [COLOR]);
+
+        // Escape cancels, a text that isn't a color is dropped
+        h.key('1' as u32, 0).key(ESCAPE, 0);
+        assert_eq!(texts(&h.draw()), ["#00FF00"]);
+        h.key('1' as u32, 0).key('2' as u32, 0).key(ENTER, 0);
+        assert_eq!(texts(&h.draw()), ["#00FF00"]);
+        assert!(signals().is_empty());
+
+        // The focus going out applies the text
+        h.widget.set_alpha(true);
+        for c in "0000ff80".chars() {
+            h.key(c as u32, 0);
+        }
+        h.key(BACKSPACE, 0).key('0' as u32, 0);
+        Widget::<f32, DisplayList<f32>>::focus_out(&mut h.widget, &mut h.internal);
+        assert!(close(value.get().h, 240.0));
+        assert!(close(value.get().a, 128.0 / 255.0));
+        assert_eq!(texts(&h.draw()), ["#0000FF80"]);
+    }
+
+    #[test]
+    fn palette() {
+        // Swatches of 17 with a gap of 3 from (4, 4)
+        let colors = vec![
+            Color::rgb(255, 0, 0),
+            Color::rgb(0, 255, 0),
+            Color::rgb(0, 0, 255),
+            Color::rgb(255, 255, 255),
+            Color::rgb(128, 128, 128),
+        ];
+        let value = shared(Hsv::default());
+        let palette = Palette::new(Rc::new(RefCell::new(colors)), 3, value.clone(), COLOR);
+        let mut h = Harness::new(palette, (0.0, 0.0, 100.0, 50.0));
+
+        h.click((30.0, 10.0));
+        assert_eq!(value.get().to_color(), Color::rgb(0, 255, 0));
+        assert_eq!(signals(), [COLOR]);
+
+        // A gap doesn't pick, the gray keeps the hue
+        h.click((22.0, 10.0));
+        assert!(signals().is_empty());
+        h.key(DOWN, 0).key(ENTER, 0);
+        assert_eq!(value.get().to_color(), Color::rgb(128, 128, 128));
+        assert!(close(value.get().h, 120.0));
+
+        h.key(UP, 0).key(HOME, 0).key(SPACE, 0);
+        assert_eq!(value.get().to_color(), Color::rgb(255, 0, 0));
+    }
+}
@@ -0,0 +1,818 @@
+//! Dropdown selects
+//!
+//! A ComboBox shows the selected option of a list of texts and opens a popup with
+//! the options below it. The popup is a scrolling list, so a select can have many
+//! options. An editable ComboBox has a TextEdit, the option with the same text as
+//! the typed one is selected
+
+use super::edit::TextEdit;
+use super::list::ItemScroll;
+use super::popup::{anchor_bounds, Popup, PopupAction, SharedPopups, Side};
+use super::{arrow, contains, inset, line, mouse_pos, rect, Direction, Style, TypeAhead};
+use crate::group::{push_event, Group, GroupEvent, GroupID};
+use crate::math::Scalar;
+use crate::paint::Painter;
+use crate::state::keys::*;
+use crate::state::{Cursor, KeyState, MouseState, MouseType, PointerID, TextInput};
+use crate::text::buffer::TextBuffer;
+use crate::text::{Monospace, SharedMeasure};
+use crate::widget::flags::*;
+use crate::widget::{Boundaries, Dimensions, Widget, WidgetInternal};
+
+use std::cell::{Cell, RefCell};
+use std::ops::{Add, Sub};
+use std::rc::Rc;
+
+/// Option of a navigation key, None if the key doesn't move
+fn step_key(current: Option<usize>, code: u32, count: usize, page: usize) -> Option<usize> {
+    let last = count.checked_sub(1)?;
+
+    let index = match (code, current) {
+        (UP, Some(n)) => n.saturating_sub(1),
+        (DOWN, Some(n)) => (n + 1).min(last),
+        (UP, None) | (END, _) => last,
+        (DOWN, None) | (HOME, _) => 0,
+        (PAGE_UP, n) => n.unwrap_or(0).saturating_sub(page),
+        (PAGE_DOWN, n) => (n.unwrap_or(0) + page).min(last),
+        _ => return None,
+    };
+
+    Some(index)
+}
+
+/// Options and selection shared by a ComboBox and its popup
+#[derive(Clone)]
+struct Choice {
+    options: Rc<RefCell<Vec<String>>>,
+    selected: Rc<Cell<Option<usize>>>,
+    /// Text of an editable ComboBox
+    buffer: Option<Rc<RefCell<TextBuffer>>>,
+    signal: GroupID,
+}
+
+impl Choice {
+    fn count(&self) -> usize {
+        self.options.borrow().len()
+    }
+
+    /// Select an option and replace the text of an editable ComboBox, the signal
+    /// is pushed if something changed
+    fn select(&self, index: usize) {
+        let options = self.options.borrow();
+        let option = match options.get(index) {
+            Some(option) => option,
+            None => return,
+        };
+
+        let mut changed = self.selected.replace(Some(index)) != Some(index);
+        if let Some(buffer) = &self.buffer {
+            let mut buffer = buffer.borrow_mut();
+            if buffer.text() != option {
+                buffer.set_text(option);
+                changed = true;
+            }
+
+            buffer.select_all();
+        }
+
+        if changed {
+            push_event(GroupEvent::Signal(self.signal));
+        }
+    }
+
+    /// Select the option with the text of the buffer, or nothing
+    fn select_text(&self) {
+        if let Some(buffer) = &self.buffer {
+            let text = buffer.borrow();
+            let found = self
+                .options
+                .borrow()
+                .iter()
+                .position(|option| option == text.text());
+
+            self.selected.set(found);
+        }
+    }
+}
+
+/// Scrolling list of the options below a ComboBox
+///
+/// The hovered option starts at the selected one. Releasing a button over an
+/// option selects it, so the popup can be pressed, dragged and released. Up, Down,
+/// Home, End and the pages move the hovered option, Enter and Space select it and
+/// typing goes to the options that start with the typed text
+struct ComboPopup<T> {
+    choice: Choice,
+    anchor: Boundaries<T>,
+    style: Style<T>,
+    measure: SharedMeasure<T>,
+    rows: usize,
+    /// Boundaries of the popup and the draw requests of the scroll
+    internal: WidgetInternal<T>,
+    scroll: ItemScroll,
+    hover: Option<usize>,
+    typed: TypeAhead,
+    /// Shared with the ComboBox, it's cleared when the popup is closed
+    open: Rc<Cell<bool>>,
+}
+
+impl<T> ComboPopup<T>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+{
+    fn item_height(&self) -> f32 {
+        line(&self.measure, &self.style) + self.style.padding.to_f32()
+    }
+
+    /// Boundaries of the options and of the scrollbar, the count of options could
+    /// change since the last event
+    fn areas(&mut self) -> (Boundaries<T>, Option<Boundaries<T>>) {
+        self.scroll.update(self.choice.count(), self.item_height());
+
+        let areas = self
+            .scroll
+            .areas(self.internal.boundaries_abs(), &self.style);
+        self.scroll.clamp(areas.0);
+
+        areas
+    }
+
+    /// Hover an option and scroll for show it
+    fn go(&mut self, area: Boundaries<T>, index: usize) {
+        self.hover = Some(index);
+        self.scroll.reveal(&mut self.internal, area, index);
+        self.internal.on(DRAW);
+    }
+
+    /// Select the hovered option and close
+    fn choose<CTX>(&mut self) -> PopupAction<T, CTX> {
+        if let Some(hover) = self.hover {
+            self.choice.select(hover);
+        }
+
+        PopupAction::CloseAll
+    }
+
+    /// Draw requests of the last event
+    fn action<CTX>(&mut self) -> PopupAction<T, CTX> {
+        if self.internal.drain(DRAW, DRAW) != 0 {
+            PopupAction::Draw
+        } else {
+            PopupAction::None
+        }
+    }
+}
+
+impl<T> Drop for ComboPopup<T> {
+    fn drop(&mut self) {
+        self.open.set(false);
+    }
+}
+
+impl<T, CTX> Popup<T, CTX> for ComboPopup<T>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+    CTX: Painter<T>,
+{
+    fn bounds(&self) -> Boundaries<T> {
+        self.internal.boundaries_abs()
+    }
+
+    /// The popup is as wide as the ComboBox or the widest option
+    fn place(&mut self, area: Boundaries<T>) {
+        let style = &self.style;
+        let (pad, border) = (style.padding.to_f32(), style.border.to_f32());
+        let options = self.choice.options.borrow();
+
+        let widest = options.iter().fold(0.0f32, |w, option| {
+            let advance = self.measure.advance(style.font, style.font_size, option);
+            w.max(advance.to_f32())
+        });
+
+        let rows = options.len().clamp(1, self.rows.max(1));
+        let dim = (
+            (widest + pad * 4.0 + border * 2.0).max(self.anchor.2.to_f32()),
+            rows as f32 * self.item_height() + border * 2.0,
+        );
+        drop(options);
+
+        let bounds = anchor_bounds(self.anchor, Side::Below, dim, area);
+        self.internal.set_boundaries(bounds);
+
+        let (area, _) = self.areas();
+        if let Some(hover) = self.hover {
+            self.scroll.reveal(&mut self.internal, area, hover);
+        }
+    }
+
+    fn draw(&mut self, ctx: &mut CTX) {
+        let (area, bar) = self.areas();
+        let bounds = self.internal.boundaries_abs();
+        let style = &self.style;
+
+        ctx.fill_rect(bounds, style.outline);
+        ctx.fill_rect(inset(bounds, style.border), style.field);
+
+        let options = self.choice.options.borrow();
+        let selected = self.choice.selected.get();
+        let height = self.scroll.height() as f32;
+        let ascent = self.measure.ascent(style.font, style.font_size).to_f32();
+        let (x, w) = (area.0.to_f32(), area.2.to_f32());
+        let pad = style.padding.to_f32();
+
+        ctx.push_clip(area);
+        for n in self.scroll.visible(area) {
+            let y = self.scroll.item_y(area, n);
+            let item = rect(x, y, w, height);
+
+            let color = if self.hover == Some(n) {
+                ctx.fill_rect(item, style.accent);
+                style.accent_text
+            } else if selected == Some(n) {
+                ctx.fill_rect(item, style.selection);
+                style.text
+            } else {
+                style.text
+            };
+
+            let pos = (T::from_f32(x + pad), T::from_f32(y + pad * 0.5 + ascent));
+            ctx.text(pos, style.font, style.font_size, &options[n], color);
+        }
+        ctx.pop_clip();
+
+        if let Some(bar) = bar {
+            self.scroll.draw(ctx, style, area, bar);
+        }
+    }
+
+    fn handle_mouse(&mut self, mouse: &MouseState<T>) -> PopupAction<T, CTX> {
+        let (area, bar) = self.areas();
+        let pos = mouse_pos(mouse);
+        let option = Some(area)
+            .filter(|&area| contains(area, pos))
+            .and_then(|area| self.scroll.index_at(area, pos.1));
+
+        match mouse.m_type {
+            MouseType::Pressed(1) => {
+                if let Some(bar) = bar.filter(|&bar| contains(bar, pos)) {
+                    self.scroll.press(&mut self.internal, area, bar, pos.1);
+                }
+            }
+            MouseType::Released(1) => {
+                if self.scroll.release() {
+                    self.internal.on(DRAW);
+                } else if option.is_some() {
+                    self.hover = option;
+                    return self.choose();
+                }
+            }
+            MouseType::CursorMoved => {
+                let dragged = self.scroll.drag(&mut self.internal, area, bar, pos.1);
+                if !dragged && option.is_some() && option != self.hover {
+                    self.hover = option;
+                    self.internal.on(DRAW);
+                }
+            }
+            MouseType::Wheel(delta) => self.scroll.wheel(&mut self.internal, area, delta),
+            _ => {}
+        }
+
+        self.action()
+    }
+
+    fn handle_keys(&mut self, key: &KeyState) -> PopupAction<T, CTX> {
+        let (code, mods) = match *key {
+            KeyState::Pressed(code, mods) => (code, mods),
+            _ => return PopupAction::None,
+        };
+
+        let (area, _) = self.areas();
+        match code {
+            ESCAPE => return PopupAction::Close,
+            UP if mods & ALT != 0 => return PopupAction::Close,
+            ENTER => return self.choose(),
+            SPACE if self.typed.is_empty() => return self.choose(),
+            _ => {}
+        }
+
+        let page = self.scroll.page(area);
+        if let Some(index) = step_key(self.hover, code, self.choice.count(), page) {
+            self.typed.clear();
+            self.go(area, index);
+            return self.action();
+        }
+
+        match to_char(code) {
+            Some(c) if mods & (CTRL | ALT | SUPER) == 0 => {
+                let options = self.choice.options.clone();
+                let options = options.borrow();
+                let start = self.hover.unwrap_or(0);
+                let found = self.typed.push(options.len(), start, c, |n| &options[n]);
+
+                if let Some(index) = found {
+                    self.go(area, index);
+                }
+
+                self.action()
+            }
+            _ => PopupAction::Ignore,
+        }
+    }
+
+    /// A dragged thumb is released
+    fn hover_out(&mut self) -> bool {
+        self.scroll.release()
+    }
+
+    fn measure(&mut self, measure: &SharedMeasure<T>) {
+        self.measure = measure.clone();
+    }
+}
+
+/// TextEdit of an editable ComboBox with its own boundaries
+struct Field<T> {
+    edit: TextEdit<T>,
+    internal: WidgetInternal<T>,
+}
+
+/// Button with the selected option that opens a list of options
+///
+/// A press, Alt+Down or F4 open the popup, and Enter or Space too if it isn't
+/// editable. While it's closed, Up, Down, Home, End and typing change the selected
+/// option without open it. The signal is pushed when the selected option changes,
+/// add the combo box with SIGNAL and a Group of the signal for draw it when the
+/// application changes the selection
+///
+/// The popups must be shared with a PopupLayer over the widget
+pub struct ComboBox<T, CTX> {
+    choice: Choice,
+    popups: SharedPopups<T, CTX>,
+    style: Style<T>,
+    measure: SharedMeasure<T>,
+    rows: usize,
+    /// The popup of this combo box is open
+    open: Rc<Cell<bool>>,
+    typed: TypeAhead,
+    field: Option<Field<T>>,
+}
+
+impl<T, CTX> ComboBox<T, CTX>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default + 'static,
+    CTX: Painter<T>,
+{
+    /// Creates a ComboBox of shared options, the index of the selected option and
+    /// the signal ID pushed when it changes
+    pub fn new(
+        options: Rc<RefCell<Vec<String>>>,
+        selected: Rc<Cell<Option<usize>>>,
+        popups: SharedPopups<T, CTX>,
+        signal: GroupID,
+    ) -> Self {
+        ComboBox {
+            choice: Choice {
+                options,
+                selected,
+                buffer: None,
+                signal,
+            },
+            popups,
+            style: Style::default(),
+            measure: Rc::new(Monospace::new(0.6, 0.8, 0.2)),
+            rows: 8,
+            open: Rc::new(Cell::new(false)),
+            typed: TypeAhead::default(),
+            field: None,
+        }
+    }
+
+    pub fn set_style(&mut self, style: Style<T>) {
+        if let Some(field) = &mut self.field {
+            field.edit.set_style(style.clone());
+        }
+
+        self.style = style;
+    }
+
+    /// Maximum count of options visible in the popup, 8 by default
+    pub fn set_rows(&mut self, rows: usize) {
+        self.rows = rows.max(1);
+    }
+
+    /// Make the text editable, it's kept in a single line buffer. Selecting an
+    /// option replaces the text, and editing it selects the option with the same
+    /// text. Edits push the signal too
+    pub fn set_editable(&mut self, buffer: Rc<RefCell<TextBuffer>>) {
+        let mut edit = TextEdit::new(buffer.clone(), self.choice.signal);
+        edit.set_style(self.style.clone());
+
+        self.choice.buffer = Some(buffer);
+        self.choice.select_text();
+        self.field = Some(Field {
+            edit,
+            internal: WidgetInternal::new(WIDGET, Group::Any),
+        });
+    }
+
+    #[inline]
+    pub fn options(&self) -> &Rc<RefCell<Vec<String>>> {
+        &self.choice.options
+    }
+
+    #[inline]
+    pub fn selected(&self) -> Option<usize> {
+        self.choice.selected.get()
+    }
+
+    #[inline]
+    pub fn is_open(&self) -> bool {
+        self.open.get()
+    }
+
+    fn button_width(&self) -> f32 {
+        self.style.font_size.to_f32() + self.style.padding.to_f32() * 2.0
+    }
+
+    /// Boundaries of the text and the arrow button
+    fn parts(&self, bounds: Boundaries<T>) -> (Boundaries<T>, Boundaries<T>) {
+        let (x, y, w, h) = (
+            bounds.0.to_f32(),
+            bounds.1.to_f32(),
+            bounds.2.to_f32(),
+            bounds.3.to_f32(),
+        );
+        let bw = self.button_width().min(w);
+
+        (rect(x, y, w - bw, h), rect(x + w - bw, y, bw, h))
+    }
+
+    /// Call the TextEdit with the flags of the combo box in the boundaries of the
+    /// field, the flags that it changes are kept. None if it isn't editable
+    fn with_field<R>(
+        &mut self,
+        internal: &mut WidgetInternal<T>,
+        f: impl FnOnce(&mut dyn Widget<T, CTX>, &mut WidgetInternal<T>) -> R,
+    ) -> Option<R> {
+        let (bounds, _) = self.parts(internal.boundaries_abs());
+        let field = self.field.as_mut()?;
+
+        field.internal.set_boundaries(bounds);
+        field.internal.flags = internal.flags;
+        let result = f(&mut field.edit, &mut field.internal);
+
+        internal.flags = field.internal.flags;
+        internal.set_cursor(field.internal.cursor());
+        Some(result)
+    }
+
+    fn open_popup(&mut self, internal: &mut WidgetInternal<T>) {
+        let popup = ComboPopup {
+            choice: self.choice.clone(),
+            anchor: internal.boundaries_abs(),
+            style: self.style.clone(),
+            measure: self.measure.clone(),
+            rows: self.rows,
+            internal: WidgetInternal::new(WIDGET, Group::Any),
+            scroll: ItemScroll::new(),
+            hover: self.choice.selected.get(),
+            typed: TypeAhead::default(),
+            open: self.open.clone(),
+        };
+
+        self.typed.clear();
+        self.popups.borrow_mut().open(Box::new(popup), None);
+        self.open.set(true);
+        internal.on(FOCUS | DRAW);
+    }
+
+    fn select(&mut self, internal: &mut WidgetInternal<T>, index: usize) {
+        self.choice.select(index);
+        self.with_field(internal, |edit, internal| edit.handle_signal(internal, 0));
+        internal.on(DRAW);
+    }
+}
+
+impl<T, CTX> Widget<T, CTX> for ComboBox<T, CTX>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default + 'static,
+    CTX: Painter<T>,
+{
+    fn draw(&mut self, internal: &WidgetInternal<T>, ctx: &mut CTX) -> bool {
+        let bounds = internal.boundaries_abs();
+        let (text, button) = self.parts(bounds);
+        let style = &self.style;
+
+        let face = if self.open.get() {
+            style.pressed
+        } else if internal.check(HOVER) && internal.check(ENABLED) {
+            style.hover
+        } else {
+            style.button
+        };
+
+        ctx.fill_rect(bounds, style.window);
+        let color = style.text_color(internal);
+
+        match &mut self.field {
+            Some(field) => {
+                field.internal.set_boundaries(text);
+                field.internal.flags = internal.flags;
+                field.edit.draw(&field.internal, ctx);
+
+                ctx.fill_round_rect(button, style.radius, style.outline_color(internal));
+                ctx.fill_round_rect(inset(button, style.border), style.radius, face);
+            }
+            None => {
+                ctx.fill_round_rect(bounds, style.radius, style.outline_color(internal));
+                ctx.fill_round_rect(inset(bounds, style.border), style.radius, face);
+
+                let space = T::from_f32(style.border.to_f32() + style.padding.to_f32());
+                let area = inset(text, space);
+                let options = self.choice.options.borrow();
+
+                if let Some(option) = self.choice.selected.get().and_then(|n| options.get(n)) {
+                    let ascent = self.measure.ascent(style.font, style.font_size).to_f32();
+                    let (y, h) = (area.1.to_f32(), area.3.to_f32());
+                    let baseline = y + (h - line(&self.measure, style)) * 0.5 + ascent;
+                    let pos = (area.0, T::from_f32(baseline));
+
+                    ctx.push_clip(area);
+                    ctx.text(pos, style.font, style.font_size, option, color);
+                    ctx.pop_clip();
+                }
+            }
+        }
+
+        let size = style.font_size.to_f32() * 0.6;
+        ctx.fill_path(&arrow(button, size, Direction::Down), color);
+
+        false
+    }
+
+    fn measure(&mut self, internal: &mut WidgetInternal<T>, measure: &SharedMeasure<T>) {
+        self.measure = measure.clone();
+        self.with_field(internal, |edit, internal| edit.measure(internal, measure));
+    }
+
+    /// The selection or the options were changed
+    fn handle_signal(&mut self, internal: &mut WidgetInternal<T>, group: GroupID) {
+        self.with_field(internal, |edit, internal| {
+            edit.handle_signal(internal, group)
+        });
+        internal.on(DRAW);
+    }
+
+    fn handle_mouse(&mut self, internal: &mut WidgetInternal<T>, mouse: &MouseState<T>) {
+        if !internal.check(ENABLED) {
+            return;
+        }
+
+        let bounds = internal.boundaries_abs();
+        let (text, _) = self.parts(bounds);
+        let on_text = contains(text, mouse_pos(mouse));
+
+        // The field keeps the mouse while it selects text
+        if let Some(field) = &self.field {
+            if on_text || field.internal.check(GRAB) {
+                self.with_field(internal, |edit, internal| {
+                    edit.handle_mouse(internal, mouse)
+                });
+                self.choice.select_text();
+                return;
+            }
+        }
+
+        internal.set_cursor(Cursor::Default);
+        if let MouseType::Pressed(1) = mouse.m_type {
+            self.open_popup(internal);
+        }
+    }
+
+    fn handle_keys(&mut self, internal: &mut WidgetInternal<T>, key: KeyState) {
+        let (code, mods) = match key {
+            KeyState::Pressed(code, mods) if internal.check(ENABLED) => (code, mods),
+            _ => return,
+        };
+
+        let editable = self.field.is_some();
+        let open = match code {
+            DOWN if mods & ALT != 0 => true,
+            ENTER | SPACE => !editable,
+            _ => code == function(4),
+        };
+
+        if open {
+            self.open_popup(internal);
+            return;
+        }
+
+        // An editable combo box only changes the option with Up and Down
+        let count = self.choice.count();
+        let current = self.choice.selected.get();
+        let step = match code {
+            UP | DOWN => step_key(current, code, count, 1),
+            _ if editable => None,
+            _ => step_key(current, code, count, self.rows),
+        };
+
+        if let Some(index) = step {
+            self.typed.clear();
+            self.select(internal, index);
+            return;
+        }
+
+        if editable {
+            self.with_field(internal, |edit, internal| edit.handle_keys(internal, key));
+            self.choice.select_text();
+            return;
+        }
+
+        match to_char(code) {
+            Some(c) if mods & (CTRL | ALT | SUPER) == 0 => {
+                let options = self.choice.options.clone();
+                let options = options.borrow();
+                let start = current.unwrap_or(0);
+                let found = self.typed.push(options.len(), start, c, |n| &options[n]);
+
+                if let Some(index) = found {
+                    self.select(internal, index);
+                }
+            }
+            _ => self.typed.clear(),
+        }
+    }
+
+    fn handle_text(&mut self, internal: &mut WidgetInternal<T>, text: TextInput) {
+        self.with_field(internal, |edit, internal| edit.handle_text(internal, text));
+        self.choice.select_text();
+    }
+
+    /// An editable combo box selects all its text when the focus steps into it
+    fn step_focus(&mut self, internal: &mut WidgetInternal<T>, back: bool) -> bool {
+        match self.with_field(internal, |edit, internal| edit.step_focus(internal, back)) {
+            Some(focus) => focus,
+            None => {
+                internal.on(DRAW);
+                !internal.check(FOCUS)
+            }
+        }
+    }
+
+    fn pointer_out(&mut self, internal: &mut WidgetInternal<T>, pointer: PointerID) {
+        self.with_field(internal, |edit, internal| {
+            edit.pointer_out(internal, pointer)
+        });
+    }
+
+    fn focus_out(&mut self, internal: &mut WidgetInternal<T>) {
+        self.typed.clear();
+        self.with_field(internal, |edit, internal| edit.focus_out(internal));
+        internal.on(DRAW);
+    }
+
+    fn min_dimensions(&self) -> Dimensions<T> {
+        let style = &self.style;
+        let (font, size) = (style.font, style.font_size);
+        let space = (style.border.to_f32() + style.padding.to_f32()) * 2.0;
+
+        let widest = self
+            .choice
+            .options
+            .borrow()
+            .iter()
+            .fold(0.0f32, |w, option| {
+                w.max(self.measure.advance(font, size, option).to_f32())
+            });
+
+        let (mut w, mut h) = (widest + space, line(&self.measure, style) + space);
+        if let Some(field) = &self.field {
+            let (fw, fh) = <TextEdit<T> as Widget<T, CTX>>::min_dimensions(&field.edit);
+            w = w.max(fw.to_f32());
+            h = h.max(fh.to_f32());
+        }
+
+        (T::from_f32(w + self.button_width()), T::from_f32(h))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::container::Container;
+    use crate::display::DisplayList;
+    use crate::empty::{EmptyDecorator, EmptyLayout};
+    use crate::widgets::popup::{PopupLayer, Popups};
+    use crate::widgets::tests::Window;
+
+    type Combo = ComboBox<f32, DisplayList<f32>>;
+    type Selected = Rc<Cell<Option<usize>>>;
+
+    /// A combo box at (10, 10, 100, 24) that shows three options at a time, they
+    /// are 15 high from y 35
+    fn window(edit: Option<Rc<RefCell<TextBuffer>>>) -> (Window, Selected) {
+        let options = ["Red", "Green", "Blue", "Black", "White", "Yellow"];
+        let options = options.iter().map(|&option| String::from(option)).collect();
+        let selected = Rc::new(Cell::new(None));
+
+        let popups = Rc::new(RefCell::new(Popups::new()));
+        let mut combo: Combo = ComboBox::new(
+            Rc::new(RefCell::new(options)),
+            selected.clone(),
+            popups.clone(),
+            5,
+        );
+        combo.set_rows(3);
+        if let Some(buffer) = edit {
+            combo.set_editable(buffer);
+        }
+
+        let mut content = Container::new(EmptyDecorator, Box::new(EmptyLayout));
+        let bounds = (10.0, 10.0, 100.0, 24.0);
+        content.add_widget_b(Box::new(combo), WIDGET, Group::Any, bounds);
+
+        let layer = PopupLayer::new(Box::new(content), popups);
+        (Window::new(layer), selected)
+    }
+
+    #[test]
+    fn mouse() {
+        let (mut w, selected) = window(None);
+
+        w.mouse(MouseType::Pressed(1), (50.0, 20.0))
+            .mouse(MouseType::Released(1), (50.0, 20.0));
+        assert_eq!(w.open(), 1);
+        assert_eq!(w.texts(), ["Red", "Green", "Blue"]);
+
+        // Releasing over an option selects it
+        w.mouse(MouseType::CursorMoved, (30.0, 52.0))
+            .mouse(MouseType::Released(1), (30.0, 52.0));
+        assert_eq!(selected.get(), Some(1));
+        assert_eq!(w.open(), 0);
+        assert_eq!(w.texts(), ["Green"]);
+
+        // The popup starts at the selected option
+        w.mouse(MouseType::Pressed(1), (50.0, 20.0));
+        w.key(END, 0);
+        assert_eq!(w.texts(), ["Green", "Black", "White", "Yellow"]);
+        w.key(ESCAPE, 0);
+        assert_eq!(w.open(), 0);
+        assert_eq!(selected.get(), Some(1));
+    }
+
+    #[test]
+    fn keys() {
+        let (mut w, selected) = window(None);
+        w.mouse(MouseType::Pressed(1), (50.0, 20.0));
+        w.key(ESCAPE, 0);
+
+        // Closed, the keys change the option
+        w.key(DOWN, 0);
+        assert_eq!(selected.get(), Some(0));
+        w.key(DOWN, 0).key('w' as u32, 0);
+        assert_eq!(selected.get(), Some(4));
+        w.key(HOME, 0);
+        assert_eq!(selected.get(), Some(0));
+        assert_eq!(w.open(), 0);
+
+        // Open, they move the hovered option
+        w.key(function(4), 0).key(PAGE_DOWN, 0).key(ENTER, 0);
+        assert_eq!(selected.get(), Some(3));
+        w.key(DOWN, ALT).key('y' as u32, 0).key(UP, ALT);
+        assert_eq!(selected.get(), Some(3));
+        // Space is typed after other chars
+        w.key(SPACE, 0).key('y' as u32, 0).key(SPACE, 0);
+        assert_eq!(w.open(), 1);
+        w.key(ENTER, 0);
+        assert_eq!(selected.get(), Some(5));
+        assert_eq!(w.open(), 0);
+    }
+
+    #[test]
+    fn editable() {
+        let buffer = Rc::new(RefCell::new(TextBuffer::new("Blue", false)));
+        let (mut w, selected) = window(Some(buffer.clone()));
+        assert_eq!(selected.get(), Some(2));
+
+        // Typing selects the option with the same text
+        w.mouse(MouseType::Pressed(1), (20.0, 20.0))
+            .mouse(MouseType::Released(1), (20.0, 20.0));
+        w.key(END, 0).key(BACKSPACE, 0);
+        assert_eq!(selected.get(), None);
+        w.key('e' as u32, 0);
+        assert_eq!(selected.get(), Some(2));
+
+        // An option replaces the text, Enter and Space don't open the popup
+        w.key(DOWN, 0);
+        assert_eq!(buffer.borrow().text(), "Black");
+        w.key(SPACE, 0).key(ENTER, 0);
+        assert_eq!(w.open(), 0);
+        assert_eq!(selected.get(), None);
+
+        // The arrow opens it
+        w.mouse(MouseType::Pressed(1), (100.0, 20.0));
+        assert_eq!(w.open(), 1);
+        w.key(UP, 0).key(ENTER, 0);
+        assert_eq!(buffer.borrow().text(), "Yellow");
+        assert_eq!(selected.get(), Some(5));
+    }
+}
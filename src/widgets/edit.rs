@@ -0,0 +1,711 @@
+//! Text entry
+
+use super::{inset, line, mouse_pos, rect, Style};
+use crate::group::{push_event, GroupEvent, GroupID};
+use crate::math::Scalar;
+use crate::paint::Painter;
+use crate::state::keys::*;
+use crate::state::{Cursor, KeyState, MouseState, MouseType, PointerID, TextInput};
+use crate::text::buffer::TextBuffer;
+use crate::text::{Monospace, SharedMeasure};
+use crate::widget::flags::*;
+use crate::widget::{Boundaries, Dimensions, Widget, WidgetInternal};
+
+use std::cell::{Cell, RefCell};
+use std::ops::{Add, Sub};
+use std::rc::Rc;
+
+/// Clipboard of the system, the application provides it
+pub trait Clipboard {
+    /// Get the text of the clipboard, None if it doesn't have text
+    fn get(&mut self) -> Option<String>;
+    /// Replace the text of the clipboard
+    fn set(&mut self, text: &str);
+}
+
+/// Clipboard that only lives in the widget, it's used without a system clipboard
+impl Clipboard for String {
+    fn get(&mut self) -> Option<String> {
+        Some(self.clone()).filter(|text| !text.is_empty())
+    }
+
+    fn set(&mut self, text: &str) {
+        self.clear();
+        self.push_str(text);
+    }
+}
+
+/// Editable text of a single line or many lines
+///
+/// The text lives in a TextBuffer shared with the application, the signal is
+/// pushed every time the text is changed by the user. Lines are broken only at
+/// '\n', a text bigger than the field is scrolled for keep the caret visible
+///
+/// Keys: arrows, Home and End move the caret, with Ctrl they move by words or to
+/// the ends of the text and with Shift they select. Ctrl+A, C, X, V, Z and Y are
+/// select all, copy, cut, paste, undo and redo, Ctrl+Shift+Z is also redo
+pub struct TextEdit<T> {
+    buffer: Rc<RefCell<TextBuffer>>,
+    signal: GroupID,
+    style: Style<T>,
+    measure: SharedMeasure<T>,
+    clipboard: Box<dyn Clipboard>,
+    /// Caret area for the candidates window of an input method
+    ime_area: Option<Rc<Cell<Option<Boundaries<T>>>>>,
+    /// Distance that the text is scrolled inside the field
+    scroll: (f32, f32),
+    /// Horizontal position kept by vertical movements
+    goal: Option<f32>,
+    dragging: bool,
+    positions: Vec<T>,
+}
+
+impl<T: Scalar> TextEdit<T> {
+    /// Creates a TextEdit of a shared buffer and the signal ID pushed by changes,
+    /// the buffer decides if it has many lines
+    pub fn new(buffer: Rc<RefCell<TextBuffer>>, signal: GroupID) -> Self {
+        TextEdit {
+            buffer,
+            signal,
+            style: Style::default(),
+            measure: Rc::new(Monospace::new(0.6, 0.8, 0.2)),
+            clipboard: Box::new(String::new()),
+            ime_area: None,
+            scroll: (0.0, 0.0),
+            goal: None,
+            dragging: false,
+            positions: Vec::new(),
+        }
+    }
+
+    pub fn set_style(&mut self, style: Style<T>) {
+        self.style = style;
+    }
+
+    /// Use the clipboard of the system for copy, cut and paste
+    pub fn set_clipboard(&mut self, clipboard: Box<dyn Clipboard>) {
+        self.clipboard = clipboard;
+    }
+
+    /// Share the caret area with the backend for place the window of an input
+    /// method, it's None when the widget isn't focused
+    pub fn set_ime_area(&mut self, area: Rc<Cell<Option<Boundaries<T>>>>) {
+        self.ime_area = Some(area);
+    }
+
+    #[inline]
+    pub fn buffer(&self) -> &Rc<RefCell<TextBuffer>> {
+        &self.buffer
+    }
+
+    // -- Geometry --
+
+    fn advance(&self, text: &str) -> f32 {
+        let style = &self.style;
+        self.measure
+            .advance(style.font, style.font_size, text)
+            .to_f32()
+    }
+
+    #[inline]
+    fn line_height(&self) -> f32 {
+        line(&self.measure, &self.style)
+    }
+
+    /// Area of the text inside the border and the padding
+    fn area(&self, bounds: Boundaries<T>) -> Boundaries<T> {
+        let style = &self.style;
+        inset(
+            bounds,
+            T::from_f32(style.border.to_f32() + style.padding.to_f32()),
+        )
+    }
+
+    /// Absolute position of the top left of the text
+    fn origin(&self, area: Boundaries<T>, multiline: bool) -> (f32, f32) {
+        let (x, y, h) = (area.0.to_f32(), area.1.to_f32(), area.3.to_f32());
+        if multiline {
+            (x - self.scroll.0, y - self.scroll.1)
+        } else {
+            (x - self.scroll.0, y + (h - self.line_height()) * 0.5)
+        }
+    }
+
+    /// Position of the caret from the top left of the text, the preedit is
+    /// inserted at the caret
+    fn caret_pos(&self, buffer: &TextBuffer) -> (f32, f32) {
+        let caret = buffer.caret();
+        let text = buffer.text();
+        let line = text[..caret].matches('\n').count();
+
+        let preedit = buffer.preedit();
+        let cursor = buffer.preedit_cursor().unwrap_or(preedit.len());
+        let x =
+            self.advance(&text[buffer.line_start(caret)..caret]) + self.advance(&preedit[..cursor]);
+
+        (x, line as f32 * self.line_height())
+    }
+
+    /// Byte index of a line that is nearest to a horizontal position
+    fn index_at(&mut self, line: &str, x: f32) -> usize {
+        let style = &self.style;
+        self.measure
+            .glyph_positions(style.font, style.font_size, line, &mut self.positions);
+
+        for (n, (index, _)) in line.char_indices().enumerate() {
+            let start = self.positions.get(n).map_or(0.0, |p| p.to_f32());
+            let end = self.positions.get(n + 1).map_or(start, |p| p.to_f32());
+
+            if x < (start + end) * 0.5 {
+                return index;
+            }
+        }
+
+        line.len()
+    }
+
+    /// Byte index of the text nearest to a position from the top left of the text
+    fn hit(&mut self, buffer: &TextBuffer, (x, y): (f32, f32)) -> usize {
+        let row = (y / self.line_height()).max(0.0) as usize;
+        let text = buffer.text();
+
+        let mut start = 0;
+        for (n, line) in text.split('\n').enumerate() {
+            if n == row || start + line.len() == text.len() {
+                return start + self.index_at(line, x);
+            }
+
+            start += line.len() + 1;
+        }
+
+        text.len()
+    }
+
+    /// Byte index nearest to the mouse
+    fn hit_mouse(
+        &mut self,
+        internal: &WidgetInternal<T>,
+        buffer: &TextBuffer,
+        mouse: &MouseState<T>,
+    ) -> usize
+    where
+        T: Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+    {
+        let area = self.area(internal.boundaries_abs());
+        let origin = self.origin(area, buffer.is_multiline());
+        let pos = mouse_pos(mouse);
+
+        self.hit(buffer, (pos.0 - origin.0, pos.1 - origin.1))
+    }
+
+    /// Move the caret some lines up or down, keeping the horizontal position
+    fn vertical(&mut self, buffer: &mut TextBuffer, lines: isize, select: bool) {
+        let (x, y) = self.caret_pos(buffer);
+        let goal = self.goal.unwrap_or(x);
+        let row = (y / self.line_height()).round() as isize + lines;
+        let last = buffer.text().matches('\n').count() as isize;
+
+        let pos = if row < 0 {
+            0
+        } else if row > last {
+            buffer.text().len()
+        } else {
+            self.hit(buffer, (goal, (row as f32 + 0.5) * self.line_height()))
+        };
+
+        buffer.set_caret(pos, select);
+        self.goal = Some(goal);
+    }
+
+    /// Scroll for show the caret, draw it again and push the signal if the text
+    /// was edited
+    fn changed(&mut self, internal: &mut WidgetInternal<T>, edited: bool)
+    where
+        T: Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+    {
+        let buffer = self.buffer.clone();
+        let buffer = buffer.borrow();
+
+        let area = self.area(internal.boundaries_abs());
+        let (w, h) = (area.2.to_f32(), area.3.to_f32());
+        let line = self.line_height();
+        let (x, y) = self.caret_pos(&buffer);
+
+        // The caret is one unit wide
+        if x + 1.0 > self.scroll.0 + w {
+            self.scroll.0 = x + 1.0 - w;
+        }
+        if x < self.scroll.0 {
+            self.scroll.0 = x;
+        }
+        self.scroll.0 = self.scroll.0.max(0.0);
+
+        if buffer.is_multiline() {
+            if y + line > self.scroll.1 + h {
+                self.scroll.1 = y + line - h;
+            }
+            if y < self.scroll.1 {
+                self.scroll.1 = y;
+            }
+            self.scroll.1 = self.scroll.1.max(0.0);
+        }
+
+        if let Some(ime) = &self.ime_area {
+            let origin = self.origin(area, buffer.is_multiline());
+            ime.set(Some(rect(origin.0 + x, origin.1 + y, 1.0, line)));
+        }
+
+        internal.on(DRAW);
+        if edited {
+            push_event(GroupEvent::Signal(self.signal));
+        }
+    }
+
+    /// Handle a shortcut with Ctrl, returns None if it isn't a shortcut
+    fn shortcut(&mut self, buffer: &mut TextBuffer, c: char, shift: bool) -> Option<bool> {
+        let edited = match c.to_ascii_lowercase() {
+            'a' => {
+                buffer.select_all();
+                false
+            }
+            'c' | 'x' => {
+                if buffer.has_selection() {
+                    self.clipboard.set(buffer.selected_text());
+                }
+
+                c.eq_ignore_ascii_case(&'x') && buffer.delete_selection()
+            }
+            'v' => match self.clipboard.get() {
+                Some(text) => buffer.insert(&text),
+                None => false,
+            },
+            'z' if shift => buffer.redo(),
+            'z' => buffer.undo(),
+            'y' => buffer.redo(),
+            _ => return None,
+        };
+
+        Some(edited)
+    }
+}
+
+impl<T, CTX> Widget<T, CTX> for TextEdit<T>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+    CTX: Painter<T>,
+{
+    fn draw(&mut self, internal: &WidgetInternal<T>, ctx: &mut CTX) -> bool {
+        let bounds = internal.boundaries_abs();
+        let style = &self.style;
+
+        ctx.fill_rect(bounds, style.window);
+        ctx.fill_round_rect(bounds, style.radius, style.outline_color(internal));
+        ctx.fill_round_rect(inset(bounds, style.border), style.radius, style.field);
+
+        let buffer = self.buffer.borrow();
+        let area = self.area(bounds);
+        let (top, bottom) = (area.1.to_f32(), area.1.to_f32() + area.3.to_f32());
+        let (x, y) = self.origin(area, buffer.is_multiline());
+
+        let line_height = self.line_height();
+        let ascent = self.measure.ascent(style.font, style.font_size).to_f32();
+        let color = style.text_color(internal);
+
+        let text = buffer.text();
+        let (caret, selection) = (buffer.caret(), buffer.selection());
+        let preedit = buffer.preedit();
+
+        ctx.push_clip(area);
+
+        let mut start = 0;
+        for (n, line) in text.split('\n').enumerate() {
+            let end = start + line.len();
+            let line_y = y + line_height * n as f32;
+
+            if line_y + line_height >= top && line_y <= bottom {
+                if selection.start <= end && selection.end >= start && !selection.is_empty() {
+                    let from = self.advance(&line[..selection.start.max(start) - start]);
+                    let mut to = self.advance(&line[..selection.end.min(end) - start]);
+
+                    // Selected line break
+                    if selection.end > end {
+                        to += line_height * 0.25;
+                    }
+
+                    let bounds = rect(x + from, line_y, to - from, line_height);
+                    ctx.fill_rect(bounds, style.selection);
+                }
+
+                // Text being composed is shown underlined at the caret
+                let composing = !preedit.is_empty() && (start..=end).contains(&caret);
+                let split = if composing { caret - start } else { line.len() };
+                let parts = [
+                    (&line[..split], false),
+                    (preedit, composing),
+                    (&line[split..], false),
+                ];
+
+                let baseline = T::from_f32(line_y + ascent);
+                let mut pen = x;
+                for (n, &(part, mark)) in parts.iter().enumerate() {
+                    if part.is_empty() || (n == 1 && !composing) {
+                        continue;
+                    }
+
+                    let width = self.advance(part);
+                    let pos = (T::from_f32(pen), baseline);
+                    ctx.text(pos, style.font, style.font_size, part, color);
+
+                    if mark {
+                        let under = T::from_f32(line_y + ascent + 1.0);
+                        let (from, to) = (T::from_f32(pen), T::from_f32(pen + width));
+                        ctx.line((from, under), (to, under), T::from_f32(1.0), color);
+                    }
+
+                    pen += width;
+                }
+            }
+
+            start = end + 1;
+        }
+
+        if internal.check(FOCUS) {
+            let (caret_x, caret_y) = self.caret_pos(&buffer);
+            let bounds = rect(x + caret_x, y + caret_y, 1.0, line_height);
+            ctx.fill_rect(bounds, style.text);
+        }
+
+        ctx.pop_clip();
+
+        false
+    }
+
+    fn measure(&mut self, _: &mut WidgetInternal<T>, measure: &SharedMeasure<T>) {
+        self.measure = measure.clone();
+    }
+
+    /// The shared buffer was changed by the application
+    fn handle_signal(&mut self, internal: &mut WidgetInternal<T>, _: GroupID) {
+        self.changed(internal, false);
+    }
+
+    fn handle_mouse(&mut self, internal: &mut WidgetInternal<T>, mouse: &MouseState<T>) {
+        if !internal.check(ENABLED) {
+            return;
+        }
+
+        internal.set_cursor(Cursor::Text);
+
+        let buffer = self.buffer.clone();
+        let mut buffer = buffer.borrow_mut();
+
+        match mouse.m_type {
+            MouseType::Pressed(1) => {
+                self.dragging = true;
+                internal.on(GRAB | FOCUS);
+
+                let pos = self.hit_mouse(internal, &buffer, mouse);
+                buffer.set_preedit("", None);
+                buffer.set_caret(pos, mouse.check_modifiers(SHIFT));
+            }
+            MouseType::Released(1) if self.dragging => {
+                self.dragging = false;
+                internal.off(GRAB);
+                return;
+            }
+            MouseType::CursorMoved if self.dragging => {
+                let pos = self.hit_mouse(internal, &buffer, mouse);
+                buffer.set_caret(pos, true);
+            }
+            MouseType::Wheel(delta) if buffer.is_multiline() => {
+                let lines = buffer.text().matches('\n').count() + 1;
+                let height = self.area(internal.boundaries_abs()).3.to_f32();
+                let max = (lines as f32 * self.line_height() - height).max(0.0);
+
+                let step = delta * self.line_height() * 3.0;
+                self.scroll.1 = (self.scroll.1 - step).clamp(0.0, max);
+                internal.on(DRAW);
+                return;
+            }
+            _ => return,
+        }
+
+        self.goal = None;
+        drop(buffer);
+        self.changed(internal, false);
+    }
+
+    fn handle_keys(&mut self, internal: &mut WidgetInternal<T>, key: KeyState) {
+        let (code, mods) = match key {
+            KeyState::Pressed(code, mods) if internal.check(ENABLED) => (code, mods),
+            _ => return,
+        };
+
+        let select = mods & SHIFT != 0;
+        let word = mods & CTRL != 0;
+        let command = mods & (CTRL | ALT | SUPER) != 0;
+
+        let buffer = self.buffer.clone();
+        let mut buffer = buffer.borrow_mut();
+        let multiline = buffer.is_multiline();
+
+        let goal = self.goal.take();
+        let edited = match code {
+            LEFT if word => {
+                buffer.word_left(select);
+                false
+            }
+            LEFT => {
+                buffer.move_left(select);
+                false
+            }
+            RIGHT if word => {
+                buffer.word_right(select);
+                false
+            }
+            RIGHT => {
+                buffer.move_right(select);
+                false
+            }
+            HOME if word => {
+                buffer.set_caret(0, select);
+                false
+            }
+            HOME => {
+                buffer.home(select);
+                false
+            }
+            END if word => {
+                let end = buffer.text().len();
+                buffer.set_caret(end, select);
+                false
+            }
+            END => {
+                buffer.end(select);
+                false
+            }
+            UP | DOWN | PAGE_UP | PAGE_DOWN if multiline => {
+                let height = self.area(internal.boundaries_abs()).3.to_f32();
+                let page = ((height / self.line_height()) as isize).max(1);
+                let lines = match code {
+                    UP => -1,
+                    DOWN => 1,
+                    PAGE_UP => -page,
+                    _ => page,
+                };
+
+                self.goal = goal;
+                self.vertical(&mut buffer, lines, select);
+                false
+            }
+            BACKSPACE => buffer.backspace(word),
+            DELETE => buffer.delete(word),
+            ENTER if multiline && !command => buffer.insert("\n"),
+            _ => match to_char(code) {
+                Some(c) if mods & CTRL != 0 => match self.shortcut(&mut buffer, c, select) {
+                    Some(edited) => edited,
+                    None => return,
+                },
+                Some(c) if !command => buffer.insert(c.encode_utf8(&mut [0; 4])),
+                _ => return,
+            },
+        };
+
+        drop(buffer);
+        self.changed(internal, edited);
+    }
+
+    fn handle_text(&mut self, internal: &mut WidgetInternal<T>, text: TextInput) {
+        let edited = match text {
+            TextInput::Commit(text) => self.buffer.borrow_mut().commit(text),
+            TextInput::Preedit(text, cursor) => {
+                self.buffer.borrow_mut().set_preedit(text, cursor);
+                false
+            }
+        };
+
+        self.changed(internal, edited);
+    }
+
+    /// A single line selects all its text when the focus steps into it
+    fn step_focus(&mut self, internal: &mut WidgetInternal<T>, _: bool) -> bool {
+        internal.on(DRAW);
+        if internal.check(FOCUS) {
+            return false;
+        }
+
+        let mut buffer = self.buffer.borrow_mut();
+        if !buffer.is_multiline() {
+            buffer.select_all();
+        }
+
+        true
+    }
+
+    fn pointer_out(&mut self, internal: &mut WidgetInternal<T>, _: PointerID) {
+        self.dragging = false;
+        internal.off(GRAB);
+    }
+
+    /// The text being composed is discarded
+    fn focus_out(&mut self, internal: &mut WidgetInternal<T>) {
+        self.dragging = false;
+        self.buffer.borrow_mut().set_preedit("", None);
+
+        if let Some(ime) = &self.ime_area {
+            ime.set(None);
+        }
+
+        internal.on(DRAW);
+    }
+
+    fn min_dimensions(&self) -> Dimensions<T> {
+        let style = &self.style;
+        let space = (style.border.to_f32() + style.padding.to_f32()) * 2.0;
+
+        let line = self.line_height();
+        let lines = if self.buffer.borrow().is_multiline() {
+            3.0
+        } else {
+            1.0
+        };
+
+        (
+            T::from_f32(line * 6.0 + space),
+            T::from_f32(line * lines + space),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::DisplayList;
+    use crate::state::PRIMARY_POINTER;
+    use crate::widgets::tests::{signals, Harness};
+
+    fn edit(text: &str, multiline: bool, signal: GroupID) -> TextEdit<f32> {
+        TextEdit::new(
+            Rc::new(RefCell::new(TextBuffer::new(text, multiline))),
+            signal,
+        )
+    }
+
+    fn text(h: &Harness<TextEdit<f32>>) -> String {
+        h.widget.buffer().borrow().text().to_string()
+    }
+
+    #[test]
+    fn typing() {
+        let mut h = Harness::new(edit("", false, 4), (0.0, 0.0, 100.0, 30.0));
+
+        h.key('a' as u32, 0)
+            .key('b' as u32, 0)
+            .key(LEFT, 0)
+            .key('x' as u32, 0);
+        assert_eq!(text(&h), "axb");
+        // The queue keeps only one of equal events
+        assert_eq!(signals(), [4]);
+
+        // Copy all and paste at the end
+        h.key('a' as u32, CTRL).key('c' as u32, CTRL);
+        h.key(END, 0).key('v' as u32, CTRL);
+        assert_eq!(text(&h), "axbaxb");
+        h.key(BACKSPACE, CTRL);
+        assert_eq!(text(&h), "");
+        h.key('z' as u32, CTRL);
+        assert_eq!(text(&h), "axbaxb");
+        assert_eq!(signals(), [4]);
+
+        // Moving the caret doesn't signal, Ctrl+Q isn't a shortcut
+        h.key(HOME, SHIFT).key('q' as u32, CTRL);
+        assert_eq!(h.widget.buffer().borrow().selected_text(), "axbaxb");
+        assert!(signals().is_empty());
+
+        h.internal.off(ENABLED);
+        h.key('a' as u32, 0);
+        assert_eq!(text(&h), "axbaxb");
+    }
+
+    #[test]
+    fn mouse() {
+        // The text starts at 5 and each char is 11 wide
+        let mut h = Harness::new(edit("hello", false, 4), (0.0, 0.0, 100.0, 30.0));
+
+        h.click((18.0, 15.0));
+        assert_eq!(h.widget.buffer().borrow().caret(), 1);
+        assert!(h.internal.check(FOCUS) && !h.internal.check(GRAB));
+
+        // Dragging selects
+        h.mouse(PRIMARY_POINTER, MouseType::Pressed(1), (5.0, 15.0));
+        h.mouse(PRIMARY_POINTER, MouseType::CursorMoved, (40.0, 15.0));
+        assert_eq!(h.widget.buffer().borrow().selected_text(), "hel");
+        h.mouse(PRIMARY_POINTER, MouseType::Released(1), (40.0, 15.0));
+        h.mouse(PRIMARY_POINTER, MouseType::CursorMoved, (90.0, 15.0));
+        assert_eq!(h.widget.buffer().borrow().selected_text(), "hel");
+        assert!(signals().is_empty());
+    }
+
+    #[test]
+    fn scroll() {
+        let mut h = Harness::new(edit("", false, 4), (0.0, 0.0, 100.0, 30.0));
+
+        // 10 chars are 110 wide and the text area is 90
+        for _ in 0..10 {
+            h.key('m' as u32, 0);
+        }
+        assert_eq!(h.widget.scroll.0, 21.0);
+
+        h.key(HOME, 0);
+        assert_eq!(h.widget.scroll.0, 0.0);
+        signals();
+    }
+
+    #[test]
+    fn lines() {
+        let mut h = Harness::new(edit("three\ntwo\nthree", true, 4), (0.0, 0.0, 100.0, 60.0));
+        h.key(END, CTRL);
+
+        // The horizontal position is kept through shorter lines
+        h.key(UP, 0);
+        assert_eq!(h.widget.buffer().borrow().caret(), 9);
+        h.key(UP, 0);
+        assert_eq!(h.widget.buffer().borrow().caret(), 5);
+        h.key(DOWN, 0).key(DOWN, 0);
+        assert_eq!(h.widget.buffer().borrow().caret(), 15);
+
+        h.key(ENTER, 0);
+        assert_eq!(text(&h), "three\ntwo\nthree\n");
+        assert_eq!(signals(), [4]);
+    }
+
+    #[test]
+    fn focus() {
+        let mut h = Harness::new(edit("hello", false, 4), (0.0, 0.0, 100.0, 30.0));
+
+        // Stepping into a single line selects it
+        assert!(Widget::<f32, DisplayList<f32>>::step_focus(
+            &mut h.widget,
+            &mut h.internal,
+            false
+        ));
+        assert_eq!(h.widget.buffer().borrow().selected_text(), "hello");
+
+        Widget::<f32, DisplayList<f32>>::handle_text(
+            &mut h.widget,
+            &mut h.internal,
+            TextInput::Preedit("ka", None),
+        );
+        assert_eq!(h.widget.buffer().borrow().preedit(), "ka");
+        Widget::<f32, DisplayList<f32>>::focus_out(&mut h.widget, &mut h.internal);
+        assert_eq!(h.widget.buffer().borrow().preedit(), "");
+
+        Widget::<f32, DisplayList<f32>>::handle_text(
+            &mut h.widget,
+            &mut h.internal,
+            TextInput::Commit("hi"),
+        );
+        assert_eq!(text(&h), "hi");
+        assert_eq!(signals(), [4]);
+    }
+}
@@ -0,0 +1,113 @@
+//! Static text
+
+use super::{inset, padded, Style, TextBlock};
+use crate::group::{push_event, GroupEvent};
+use crate::math::Scalar;
+use crate::paint::Painter;
+use crate::text::lines::Align;
+use crate::text::SharedMeasure;
+use crate::widget::{Dimensions, Widget, WidgetInternal};
+
+use std::ops::{Add, Sub};
+
+/// Text that can have many lines, it doesn't handle input
+pub struct Label<T> {
+    text: TextBlock<T>,
+    align: Align,
+    style: Style<T>,
+}
+
+impl<T: Scalar> Label<T> {
+    /// Creates a Label aligned to left with the default style
+    pub fn new(text: &str) -> Self {
+        Label {
+            text: TextBlock::new(text),
+            align: Align::Left,
+            style: Style::default(),
+        }
+    }
+
+    pub fn set_style(&mut self, style: Style<T>) {
+        self.style = style;
+    }
+
+    pub fn set_align(&mut self, align: Align) {
+        self.align = align;
+    }
+
+    #[inline]
+    pub fn text(&self) -> &str {
+        self.text.text()
+    }
+
+    /// Replace the text, the tree is measured and layout again
+    pub fn set_text(&mut self, text: &str) {
+        self.text.set_text(text);
+        push_event(GroupEvent::Layout);
+    }
+}
+
+impl<T, CTX> Widget<T, CTX> for Label<T>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+    CTX: Painter<T>,
+{
+    fn draw(&mut self, internal: &WidgetInternal<T>, ctx: &mut CTX) -> bool {
+        let bounds = internal.boundaries_abs();
+        let style = &self.style;
+
+        ctx.fill_rect(bounds, style.window);
+        self.text.draw(
+            ctx,
+            inset(bounds, style.padding),
+            self.align,
+            style,
+            style.text_color(internal),
+        );
+
+        false
+    }
+
+    fn measure(&mut self, _: &mut WidgetInternal<T>, measure: &SharedMeasure<T>) {
+        self.text
+            .measure(measure.as_ref(), self.style.font, self.style.font_size);
+    }
+
+    fn min_dimensions(&self) -> Dimensions<T> {
+        padded(self.text.dimensions(), self.style.padding)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::{Command, DisplayList};
+    use crate::group::next_event;
+    use crate::widgets::tests::Harness;
+
+    #[test]
+    fn lines() {
+        let mut label = Label::new("ab\ncde");
+        label.set_align(Align::Right);
+        let mut h = Harness::new(label, (0.0, 0.0, 60.0, 40.0));
+
+        // Three characters of 11 units, two lines and the padding
+        let dim = Widget::<f32, DisplayList<f32>>::min_dimensions(&h.widget);
+        assert_eq!(dim, (33.0 + 8.0, 22.0 + 8.0));
+
+        let texts: Vec<(f32, String)> = h
+            .draw()
+            .commands()
+            .iter()
+            .filter_map(|command| match command {
+                Command::Text(pos, _, _, text, _) => Some((pos.0, text.clone())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(texts, [(34.0, "ab".to_string()), (23.0, "cde".to_string())]);
+
+        h.widget.set_text("x");
+        assert!(matches!(next_event(), Some(GroupEvent::Layout)));
+        assert_eq!(h.widget.text(), "x");
+    }
+}
@@ -0,0 +1,1039 @@
+//! Virtualized lists
+//!
+//! A ListView doesn't have a widget for each item, it asks a ListSource for the
+//! count of items and draws only the visible ones. Every item has the same height,
+//! so the visible items and the item under the pointer are found without
+//! visiting the others
+
+use super::{inset, line, mouse_pos, rect, Style};
+use crate::group::{push_event, GroupEvent, GroupID};
+use crate::math::Scalar;
+use crate::paint::Painter;
+use crate::state::keys::*;
+use crate::state::{KeyState, MouseState, MouseType, PointerID};
+use crate::text::{Monospace, SharedMeasure};
+use crate::widget::flags::*;
+use crate::widget::{Boundaries, Dimensions, Position, Widget, WidgetInternal};
+
+use std::cell::RefCell;
+use std::ops::{Add, Range, Sub};
+use std::rc::Rc;
+
+/// Maximum time between the clicks of a double click in milliseconds
+const DOUBLE_CLICK: u32 = 400;
+/// Minimum length of a scrollbar thumb
+const MIN_THUMB: f32 = 16.0;
+
+/// Selected indexes as sorted ranges
+///
+/// A million selected items is a single range, so selecting everything doesn't
+/// depend of the count of items. It also keeps the current item of the keyboard
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Selection {
+    ranges: Vec<Range<usize>>,
+    current: Option<usize>,
+}
+
+impl Selection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Count of selected indexes
+    pub fn len(&self) -> usize {
+        self.ranges.iter().map(|r| r.end - r.start).sum()
+    }
+
+    /// Selected ranges, sorted and without overlaps
+    #[inline]
+    pub fn ranges(&self) -> &[Range<usize>] {
+        &self.ranges
+    }
+
+    /// Selected indexes in order
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.ranges.iter().flat_map(|r| r.clone())
+    }
+
+    pub fn first(&self) -> Option<usize> {
+        self.ranges.first().map(|r| r.start)
+    }
+
+    pub fn contains(&self, index: usize) -> bool {
+        let n = self.ranges.partition_point(|r| r.end <= index);
+        self.ranges.get(n).is_some_and(|r| r.start <= index)
+    }
+
+    pub fn clear(&mut self) {
+        self.ranges.clear();
+    }
+
+    /// Select a range, merging it with the touching ranges
+    pub fn add(&mut self, range: Range<usize>) {
+        if range.start >= range.end {
+            return;
+        }
+
+        let first = self.ranges.partition_point(|r| r.end < range.start);
+        let last = self.ranges.partition_point(|r| r.start <= range.end);
+
+        let merged = match self.ranges.get(first..last) {
+            Some([a, .., b]) | Some([a @ b]) => a.start.min(range.start)..b.end.max(range.end),
+            _ => range,
+        };
+
+        self.ranges.splice(first..last, Some(merged));
+    }
+
+    /// Unselect a range
+    pub fn remove(&mut self, range: Range<usize>) {
+        if range.start >= range.end {
+            return;
+        }
+
+        let first = self.ranges.partition_point(|r| r.end <= range.start);
+        let last = self.ranges.partition_point(|r| r.start < range.end);
+
+        let mut parts = Vec::with_capacity(2);
+        if let Some(r) = self.ranges.get(first).filter(|r| r.start < range.start) {
+            parts.push(r.start..range.start);
+        }
+        if let Some(r) = self.ranges[first..last]
+            .last()
+            .filter(|r| r.end > range.end)
+        {
+            parts.push(range.end..r.end);
+        }
+
+        self.ranges.splice(first..last, parts);
+    }
+
+    /// Select only a range
+    pub fn set(&mut self, range: Range<usize>) {
+        self.ranges.clear();
+        self.add(range);
+    }
+
+    /// Select or unselect an index
+    pub fn toggle(&mut self, index: usize) {
+        if self.contains(index) {
+            self.remove(index..index + 1);
+        } else {
+            self.add(index..index + 1);
+        }
+    }
+
+    /// Item of the keyboard cursor
+    #[inline]
+    pub fn current(&self) -> Option<usize> {
+        self.current
+    }
+
+    #[inline]
+    pub fn set_current(&mut self, current: Option<usize>) {
+        self.current = current;
+    }
+
+    /// Remove the indexes from a count, when the items are less than before
+    pub fn truncate(&mut self, count: usize) {
+        self.remove(count..usize::MAX);
+        self.current = self.current.filter(|&c| c < count);
+    }
+}
+
+/// How many items can be selected
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SelectionMode {
+    None,
+    Single,
+    /// Shift selects ranges and Ctrl adds or removes items
+    Multiple,
+}
+
+/// State of an item for draw it
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct ItemState {
+    pub selected: bool,
+    /// Item of the keyboard cursor
+    pub current: bool,
+    pub hover: bool,
+    /// The list is focused
+    pub focused: bool,
+}
+
+/// Items of a ListView
+///
+/// Indexes go from zero to the count, the list draws the background of the
+/// selected and hovered items before draw_item
+pub trait ListSource<T, CTX> {
+    /// Count of items
+    fn count(&self) -> usize;
+    /// Height of every item
+    fn item_height(&self) -> T;
+    /// Draw a visible item in its absolute boundaries
+    fn draw_item(&mut self, index: usize, bounds: Boundaries<T>, state: ItemState, ctx: &mut CTX);
+    /// Handle a press or a release of the mouse over an item, the position is
+    /// relative to the item. Returns true if the item used it, then the list doesn't
+    /// select it
+    fn hit_item(&mut self, _: usize, _: Position<T>, _: &MouseState<T>) -> bool {
+        false
+    }
+    /// Store the text measurement of the Root if the items have text
+    fn measure(&mut self, _: &SharedMeasure<T>) {}
+}
+
+/// Texts shared with the application as a ListSource
+pub struct TextList<T> {
+    items: Rc<RefCell<Vec<String>>>,
+    style: Style<T>,
+    measure: SharedMeasure<T>,
+}
+
+impl<T: Scalar> TextList<T> {
+    pub fn new(items: Rc<RefCell<Vec<String>>>) -> Self {
+        TextList {
+            items,
+            style: Style::default(),
+            measure: Rc::new(Monospace::new(0.6, 0.8, 0.2)),
+        }
+    }
+
+    pub fn set_style(&mut self, style: Style<T>) {
+        self.style = style;
+    }
+
+    #[inline]
+    pub fn items(&self) -> &Rc<RefCell<Vec<String>>> {
+        &self.items
+    }
+}
+
+impl<T, CTX> ListSource<T, CTX> for TextList<T>
+where
+    T: Scalar,
+    CTX: Painter<T>,
+{
+    fn count(&self) -> usize {
+        self.items.borrow().len()
+    }
+
+    fn item_height(&self) -> T {
+        T::from_f32(line(&self.measure, &self.style) + self.style.padding.to_f32())
+    }
+
+    fn draw_item(&mut self, index: usize, bounds: Boundaries<T>, state: ItemState, ctx: &mut CTX) {
+        let items = self.items.borrow();
+        let text = match items.get(index) {
+            Some(text) => text,
+            None => return,
+        };
+
+        let style = &self.style;
+        let ascent = self.measure.ascent(style.font, style.font_size).to_f32();
+        let half = style.padding.to_f32() * 0.5;
+        let pos = (
+            T::from_f32(bounds.0.to_f32() + style.padding.to_f32()),
+            T::from_f32(bounds.1.to_f32() + half + ascent),
+        );
+
+        let color = if state.selected && state.focused {
+            style.accent_text
+        } else {
+            style.text
+        };
+
+        ctx.text(pos, style.font, style.font_size, text, color);
+    }
+
+    fn measure(&mut self, measure: &SharedMeasure<T>) {
+        self.measure = measure.clone();
+    }
+}
+
+/// Modifiers of the mouse that change a selection, Shift and Ctrl
+pub(crate) fn mouse_mods<T>(mouse: &MouseState<T>) -> u16
+where
+    T: Copy + Default + Sub<Output = T>,
+{
+    let mut mods = 0;
+    for m in [SHIFT, CTRL].iter().copied() {
+        if mouse.check_modifiers(m) {
+            mods |= m;
+        }
+    }
+
+    mods
+}
+
+/// Detection of double clicks on the same item
+pub(crate) struct Clicks {
+    /// Time and item of the last click
+    last: Option<(u32, usize)>,
+}
+
+impl Clicks {
+    pub(crate) fn new() -> Self {
+        Clicks { last: None }
+    }
+
+    /// Register a click, returns true if it's a double click. A backend without
+    /// time never makes them
+    pub(crate) fn click(&mut self, time: u32, item: usize) -> bool {
+        let double = matches!(self.last, Some((last, last_item))
+            if time != 0 && last_item == item && time.wrapping_sub(last) <= DOUBLE_CLICK);
+
+        self.last = if double { None } else { Some((time, item)) };
+        double
+    }
+}
+
+/// Vertical scrolling of items with the same height and its scrollbar
+///
+/// The count and the height of the items are updated before using it. The
+/// distance scrolled is f64 for keep the precision with many items
+pub(crate) struct ItemScroll {
+    scroll: f64,
+    count: usize,
+    height: f64,
+    /// Cursor coordinate and scroll when the thumb was pressed
+    thumb: Option<(f32, f64)>,
+}
+
+impl ItemScroll {
+    pub(crate) fn new() -> Self {
+        ItemScroll {
+            scroll: 0.0,
+            count: 0,
+            height: 1.0,
+            thumb: None,
+        }
+    }
+
+    /// Store the count and the height of the items
+    pub(crate) fn update(&mut self, count: usize, height: f32) {
+        self.count = count;
+        self.height = height.max(1.0) as f64;
+    }
+
+    #[inline]
+    pub(crate) fn height(&self) -> f64 {
+        self.height
+    }
+
+    fn total(&self) -> f64 {
+        self.count as f64 * self.height
+    }
+
+    /// Boundaries of the items and of the scrollbar if the items don't fit, both
+    /// are inside the border
+    pub(crate) fn areas<T: Scalar>(
+        &self,
+        bounds: Boundaries<T>,
+        style: &Style<T>,
+    ) -> (Boundaries<T>, Option<Boundaries<T>>) {
+        let inner = inset(bounds, style.border);
+        let (x, y, w, h) = (
+            inner.0.to_f32(),
+            inner.1.to_f32(),
+            inner.2.to_f32(),
+            inner.3.to_f32(),
+        );
+
+        if self.total() <= h as f64 {
+            return (inner, None);
+        }
+
+        let bar = (style.padding.to_f32() * 2.0).min(w);
+        (rect(x, y, w - bar, h), Some(rect(x + w - bar, y, bar, h)))
+    }
+
+    fn max_scroll<T: Scalar>(&self, area: Boundaries<T>) -> f64 {
+        (self.total() - area.3.to_f32() as f64).max(0.0)
+    }
+
+    /// Count of fully visible items
+    pub(crate) fn page<T: Scalar>(&self, area: Boundaries<T>) -> usize {
+        ((area.3.to_f32() as f64 / self.height) as usize).max(1)
+    }
+
+    /// Indexes of the visible items
+    pub(crate) fn visible<T: Scalar>(&self, area: Boundaries<T>) -> Range<usize> {
+        let first = (self.scroll / self.height) as usize;
+        let last = ((self.scroll + area.3.to_f32() as f64) / self.height).ceil() as usize;
+
+        first.min(self.count)..last.min(self.count)
+    }
+
+    /// Absolute top of an item
+    pub(crate) fn item_y<T: Scalar>(&self, area: Boundaries<T>, index: usize) -> f32 {
+        area.1.to_f32() + (index as f64 * self.height - self.scroll) as f32
+    }
+
+    /// Item at an absolute vertical position
+    pub(crate) fn index_at<T: Scalar>(&self, area: Boundaries<T>, y: f32) -> Option<usize> {
+        let offset = (y - area.1.to_f32()) as f64 + self.scroll;
+        let index = (offset / self.height) as usize;
+
+        Some(index).filter(|&index| offset >= 0.0 && index < self.count)
+    }
+
+    /// Nearest item to an absolute vertical position, it can be out of the area
+    pub(crate) fn nearest<T: Scalar>(&self, area: Boundaries<T>, y: f32) -> usize {
+        let offset = ((y - area.1.to_f32()) as f64 + self.scroll).max(0.0);
+        ((offset / self.height) as usize).min(self.count.saturating_sub(1))
+    }
+
+    pub(crate) fn set<T: Scalar>(
+        &mut self,
+        internal: &mut WidgetInternal<T>,
+        area: Boundaries<T>,
+        scroll: f64,
+    ) {
+        let scroll = scroll.clamp(0.0, self.max_scroll(area));
+        if scroll != self.scroll {
+            self.scroll = scroll;
+            internal.on(DRAW);
+        }
+    }
+
+    /// Keep the scroll inside the limits, the count could change since the last event
+    pub(crate) fn clamp<T: Scalar>(&mut self, area: Boundaries<T>) {
+        self.scroll = self.scroll.clamp(0.0, self.max_scroll(area));
+    }
+
+    /// Scroll the minimum distance for show an item
+    pub(crate) fn reveal<T: Scalar>(
+        &mut self,
+        internal: &mut WidgetInternal<T>,
+        area: Boundaries<T>,
+        index: usize,
+    ) {
+        let top = index as f64 * self.height;
+        let height = area.3.to_f32() as f64;
+
+        if top < self.scroll {
+            self.set(internal, area, top);
+        } else if top + self.height > self.scroll + height {
+            self.set(internal, area, top + self.height - height);
+        }
+    }
+
+    /// Scroll three items for each step of the wheel
+    pub(crate) fn wheel<T: Scalar>(
+        &mut self,
+        internal: &mut WidgetInternal<T>,
+        area: Boundaries<T>,
+        delta: f32,
+    ) {
+        let distance = -delta as f64 * self.height * 3.0;
+        self.set(internal, area, self.scroll + distance);
+    }
+
+    fn thumb_bounds<T: Scalar>(&self, area: Boundaries<T>, bar: Boundaries<T>) -> Boundaries<T> {
+        let (h, track) = (area.3.to_f32() as f64, bar.3.to_f32());
+        let length = ((h / self.total()) as f32 * track).clamp(MIN_THUMB.min(track), track);
+
+        let max = self.max_scroll(area);
+        let pos = if max > 0.0 {
+            (self.scroll / max) as f32 * (track - length)
+        } else {
+            0.0
+        };
+
+        rect(bar.0.to_f32(), bar.1.to_f32() + pos, bar.2.to_f32(), length)
+    }
+
+    /// Press the scrollbar, out of the thumb it scrolls a page. The thumb is dragged
+    /// until it's released
+    pub(crate) fn press<T: Scalar>(
+        &mut self,
+        internal: &mut WidgetInternal<T>,
+        area: Boundaries<T>,
+        bar: Boundaries<T>,
+        y: f32,
+    ) {
+        let thumb = self.thumb_bounds(area, bar);
+        let (start, end) = (thumb.1.to_f32(), thumb.1.to_f32() + thumb.3.to_f32());
+
+        if y < start || y >= end {
+            let page = area.3.to_f32() as f64;
+            let page = if y < start { -page } else { page };
+            self.set(internal, area, self.scroll + page);
+        }
+
+        self.thumb = Some((y, self.scroll));
+        internal.on(DRAW);
+    }
+
+    /// Move the pressed thumb, returns false if the thumb isn't pressed
+    pub(crate) fn drag<T: Scalar>(
+        &mut self,
+        internal: &mut WidgetInternal<T>,
+        area: Boundaries<T>,
+        bar: Option<Boundaries<T>>,
+        y: f32,
+    ) -> bool {
+        let (start, scroll) = match self.thumb {
+            Some(thumb) => thumb,
+            None => return false,
+        };
+
+        if let Some(bar) = bar {
+            let thumb = self.thumb_bounds(area, bar);
+            let track = (bar.3.to_f32() - thumb.3.to_f32()).max(1.0) as f64;
+            let distance = (y - start) as f64 / track * self.max_scroll(area);
+
+            self.set(internal, area, scroll + distance);
+        }
+
+        true
+    }
+
+    /// Release the thumb, returns false if it wasn't pressed
+    pub(crate) fn release(&mut self) -> bool {
+        self.thumb.take().is_some()
+    }
+
+    pub(crate) fn draw<T: Scalar, CTX: Painter<T>>(
+        &self,
+        ctx: &mut CTX,
+        style: &Style<T>,
+        area: Boundaries<T>,
+        bar: Boundaries<T>,
+    ) {
+        let face = if self.thumb.is_some() {
+            style.pressed
+        } else {
+            style.button
+        };
+
+        ctx.fill_rect(bar, style.window);
+        let thumb = self.thumb_bounds(area, bar);
+        ctx.fill_round_rect(inset(thumb, style.border), style.radius, face);
+    }
+}
+
+/// Scrollable list of items from a ListSource
+///
+/// The selection is shared with the application, the signal is pushed when the
+/// user changes it and the activation signal when an item is double clicked or
+/// Enter is pressed. Up, Down, Page Up, Page Down, Home and End move the current
+/// item, with Shift they select a range and with Ctrl they only move the current
+/// item. Space selects the current item, Ctrl+Space toggles it and Ctrl+A selects
+/// all the items
+pub struct ListView<T, CTX> {
+    source: Box<dyn ListSource<T, CTX>>,
+    selection: Rc<RefCell<Selection>>,
+    mode: SelectionMode,
+    signal: GroupID,
+    activate: Option<GroupID>,
+    style: Style<T>,
+    scroll: ItemScroll,
+    /// Start of the range selected with Shift
+    anchor: usize,
+    hover: Option<usize>,
+    /// Selecting the items under the pointer
+    selecting: bool,
+    clicks: Clicks,
+}
+
+impl<T, CTX> ListView<T, CTX>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+{
+    /// Creates a ListView of a source with the shared selection, the selection
+    /// mode and the signal ID pushed when the selection changes
+    pub fn new(
+        source: Box<dyn ListSource<T, CTX>>,
+        selection: Rc<RefCell<Selection>>,
+        mode: SelectionMode,
+        signal: GroupID,
+    ) -> Self {
+        ListView {
+            source,
+            selection,
+            mode,
+            signal,
+            activate: None,
+            style: Style::default(),
+            scroll: ItemScroll::new(),
+            anchor: 0,
+            hover: None,
+            selecting: false,
+            clicks: Clicks::new(),
+        }
+    }
+
+    pub fn set_style(&mut self, style: Style<T>) {
+        self.style = style;
+    }
+
+    /// Push a signal when an item is double clicked or Enter is pressed, the
+    /// item is the current item of the selection
+    pub fn set_activate(&mut self, signal: GroupID) {
+        self.activate = Some(signal);
+    }
+
+    #[inline]
+    pub fn source(&self) -> &dyn ListSource<T, CTX> {
+        self.source.as_ref()
+    }
+
+    #[inline]
+    pub fn source_mut(&mut self) -> &mut dyn ListSource<T, CTX> {
+        self.source.as_mut()
+    }
+
+    /// Update the scroll with the source, returns the areas of the items and the
+    /// scrollbar
+    fn areas(&mut self, internal: &WidgetInternal<T>) -> (Boundaries<T>, Option<Boundaries<T>>) {
+        let height = self.source.item_height().to_f32();
+        self.scroll.update(self.source.count(), height);
+        self.scroll.areas(internal.boundaries_abs(), &self.style)
+    }
+
+    /// Move the current item and change the selection like a click or a key
+    fn select(
+        &mut self,
+        internal: &mut WidgetInternal<T>,
+        area: Boundaries<T>,
+        index: usize,
+        mods: u16,
+    ) {
+        let count = self.source.count();
+        if count == 0 {
+            return;
+        }
+
+        let index = index.min(count - 1);
+        let mut selection = self.selection.borrow_mut();
+        let before = selection.ranges.clone();
+
+        selection.set_current(Some(index));
+        match self.mode {
+            SelectionMode::None => {}
+            SelectionMode::Multiple if mods & SHIFT != 0 => {
+                let range = self.anchor.min(index)..self.anchor.max(index) + 1;
+                if mods & CTRL != 0 {
+                    selection.add(range);
+                } else {
+                    selection.set(range);
+                }
+            }
+            SelectionMode::Multiple if mods & CTRL != 0 => {}
+            _ => {
+                selection.set(index..index + 1);
+                self.anchor = index;
+            }
+        }
+
+        if selection.ranges != before {
+            push_event(GroupEvent::Signal(self.signal));
+        }
+        drop(selection);
+
+        self.scroll.reveal(internal, area, index);
+        internal.on(DRAW);
+    }
+
+    /// Change the selection without moving the current item
+    fn change<F: FnOnce(&mut Selection)>(&mut self, internal: &mut WidgetInternal<T>, f: F) {
+        let mut selection = self.selection.borrow_mut();
+        let before = selection.ranges.clone();
+        f(&mut selection);
+
+        if selection.ranges != before {
+            push_event(GroupEvent::Signal(self.signal));
+            internal.on(DRAW);
+        }
+    }
+
+    fn activate(&self) {
+        if let Some(signal) = self.activate {
+            push_event(GroupEvent::Signal(signal));
+        }
+    }
+
+    /// Send a mouse event to the item under the pointer
+    fn hit_item(&mut self, area: Boundaries<T>, index: usize, mouse: &MouseState<T>) -> bool {
+        let pos = mouse_pos(mouse);
+        let relative = (
+            T::from_f32(pos.0 - area.0.to_f32()),
+            T::from_f32(pos.1 - self.scroll.item_y(area, index)),
+        );
+
+        self.source.hit_item(index, relative, mouse)
+    }
+}
+
+impl<T, CTX> Widget<T, CTX> for ListView<T, CTX>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+    CTX: Painter<T>,
+{
+    fn draw(&mut self, internal: &WidgetInternal<T>, ctx: &mut CTX) -> bool {
+        let bounds = internal.boundaries_abs();
+        let (area, bar) = self.areas(internal);
+        self.scroll.clamp(area);
+
+        let style = &self.style;
+        ctx.fill_rect(bounds, style.window);
+        ctx.fill_round_rect(bounds, style.radius, style.outline_color(internal));
+        ctx.fill_rect(inset(bounds, style.border), style.field);
+
+        let (x, w) = (area.0.to_f32(), area.2.to_f32());
+        let h = self.scroll.height() as f32;
+        let focused = internal.check(FOCUS);
+
+        ctx.push_clip(area);
+
+        let selection = self.selection.borrow();
+        for index in self.scroll.visible(area) {
+            let state = ItemState {
+                selected: selection.contains(index),
+                current: selection.current() == Some(index),
+                hover: self.hover == Some(index),
+                focused,
+            };
+
+            let item = rect(x, self.scroll.item_y(area, index), w, h);
+            let background = if state.selected && focused {
+                Some(style.accent)
+            } else if state.selected {
+                Some(style.selection)
+            } else if state.hover && internal.check(ENABLED) {
+                Some(style.hover)
+            } else {
+                None
+            };
+
+            if let Some(color) = background {
+                ctx.fill_rect(item, color);
+            }
+
+            self.source.draw_item(index, item, state, ctx);
+
+            if state.current && focused {
+                ctx.stroke_rect(item, style.border, style.focus);
+            }
+        }
+
+        ctx.pop_clip();
+
+        if let Some(bar) = bar {
+            self.scroll.draw(ctx, style, area, bar);
+        }
+
+        false
+    }
+
+    fn measure(&mut self, _: &mut WidgetInternal<T>, measure: &SharedMeasure<T>) {
+        self.source.measure(measure);
+    }
+
+    /// The items or the selection were changed by the application
+    fn handle_signal(&mut self, internal: &mut WidgetInternal<T>, _: GroupID) {
+        self.selection.borrow_mut().truncate(self.source.count());
+        internal.on(DRAW);
+    }
+
+    fn handle_mouse(&mut self, internal: &mut WidgetInternal<T>, mouse: &MouseState<T>) {
+        if !internal.check(ENABLED) {
+            return;
+        }
+
+        let (area, bar) = self.areas(internal);
+        let (x, y) = mouse_pos(mouse);
+
+        let item = self.scroll.index_at(area, y);
+        let on_bar = bar.is_some_and(|bar| x >= bar.0.to_f32());
+
+        match mouse.m_type {
+            MouseType::Pressed(1) => {
+                internal.on(GRAB | FOCUS | DRAW);
+
+                if let (true, Some(bar)) = (on_bar, bar) {
+                    self.scroll.press(internal, area, bar, y);
+                    return;
+                }
+
+                let index = match item {
+                    Some(index) => index,
+                    None => return,
+                };
+
+                if self.hit_item(area, index, mouse) {
+                    return;
+                }
+
+                let mods = mouse_mods(mouse);
+                if self.mode == SelectionMode::Multiple && mods == CTRL {
+                    self.change(internal, |s| s.toggle(index));
+                    self.anchor = index;
+                }
+
+                self.select(internal, area, index, mods);
+                self.selecting = true;
+
+                if self.clicks.click(mouse.time(), index) {
+                    self.activate();
+                }
+            }
+            MouseType::Released(1) => {
+                internal.off(GRAB);
+                internal.on(DRAW);
+
+                let dragged = self.scroll.release() || self.selecting;
+                self.selecting = false;
+
+                if let (false, Some(index)) = (dragged, item) {
+                    self.hit_item(area, index, mouse);
+                }
+            }
+            MouseType::CursorMoved => {
+                if self.scroll.drag(internal, area, bar, y) {
+                    return;
+                }
+
+                if self.selecting {
+                    // Out of the list it selects the next hidden item, so it scrolls
+                    let index = self.scroll.nearest(area, y);
+                    if self.selection.borrow().current() != Some(index) {
+                        self.select(internal, area, index, SHIFT);
+                    }
+                } else {
+                    let hover = item.filter(|_| !on_bar);
+                    if hover != self.hover {
+                        self.hover = hover;
+                        internal.on(DRAW);
+                    }
+                }
+            }
+            MouseType::Wheel(delta) => self.scroll.wheel(internal, area, delta),
+            _ => {}
+        }
+    }
+
+    fn handle_keys(&mut self, internal: &mut WidgetInternal<T>, key: KeyState) {
+        let (code, mods) = match key {
+            KeyState::Pressed(code, mods) if internal.check(ENABLED) => (code, mods),
+            _ => return,
+        };
+
+        let count = self.source.count();
+        if count == 0 {
+            return;
+        }
+
+        let (area, _) = self.areas(internal);
+        let page = self.scroll.page(area);
+        let current = self.selection.borrow().current();
+        let index = current.unwrap_or(0);
+
+        let target = match code {
+            UP if current.is_some() => index.saturating_sub(1),
+            DOWN if current.is_some() => index + 1,
+            UP | DOWN => index,
+            PAGE_UP => index.saturating_sub(page),
+            PAGE_DOWN => index + page,
+            HOME => 0,
+            END => count - 1,
+            SPACE if mods & CTRL != 0 && self.mode == SelectionMode::Multiple => {
+                self.change(internal, |s| s.toggle(index));
+                self.anchor = index;
+                return;
+            }
+            SPACE => index,
+            ENTER if current.is_some() => {
+                self.activate();
+                return;
+            }
+            _ => match to_char(code) {
+                Some('a') | Some('A')
+                    if mods & CTRL != 0 && self.mode == SelectionMode::Multiple =>
+                {
+                    self.change(internal, |s| s.set(0..count));
+                    return;
+                }
+                _ => return,
+            },
+        };
+
+        let mods = if code == SPACE { 0 } else { mods };
+        self.select(internal, area, target, mods);
+    }
+
+    fn hover_out(&mut self, internal: &mut WidgetInternal<T>) {
+        self.hover = None;
+        internal.on(DRAW);
+    }
+
+    fn pointer_out(&mut self, internal: &mut WidgetInternal<T>, _: PointerID) {
+        self.scroll.release();
+        self.selecting = false;
+        internal.off(GRAB);
+        internal.on(DRAW);
+    }
+
+    fn min_dimensions(&self) -> Dimensions<T> {
+        let space = (self.style.border.to_f32() + self.style.padding.to_f32()) * 2.0;
+        let h = self.source.item_height().to_f32().max(1.0);
+
+        (T::from_f32(h * 4.0 + space), T::from_f32(h * 3.0 + space))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::DisplayList;
+    use crate::state::PRIMARY_POINTER;
+    use crate::widgets::tests::{signals, Harness};
+
+    /// Items 10 high that record which ones are drawn
+    struct Rows {
+        count: usize,
+        drawn: Drawn,
+    }
+
+    impl ListSource<f32, DisplayList<f32>> for Rows {
+        fn count(&self) -> usize {
+            self.count
+        }
+
+        fn item_height(&self) -> f32 {
+            10.0
+        }
+
+        fn draw_item(
+            &mut self,
+            index: usize,
+            _: Boundaries<f32>,
+            _: ItemState,
+            _: &mut DisplayList<f32>,
+        ) {
+            self.drawn.borrow_mut().push(index);
+        }
+    }
+
+    type Drawn = Rc<RefCell<Vec<usize>>>;
+    type List = Harness<ListView<f32, DisplayList<f32>>>;
+
+    /// A list of 50 high items with a scrollbar from x 91
+    fn list(count: usize, mode: SelectionMode) -> (List, Rc<RefCell<Selection>>, Drawn) {
+        let drawn = Rc::new(RefCell::new(Vec::new()));
+        let selection = Rc::new(RefCell::new(Selection::new()));
+        let rows = Box::new(Rows {
+            count,
+            drawn: drawn.clone(),
+        });
+
+        let mut view = ListView::new(rows, selection.clone(), mode, 6);
+        view.set_activate(7);
+
+        (
+            Harness::new(view, (0.0, 0.0, 100.0, 52.0)),
+            selection,
+            drawn,
+        )
+    }
+
+    #[test]
+    fn selection() {
+        let mut selection = Selection::new();
+        selection.add(0..1_000_000);
+        assert_eq!(selection.len(), 1_000_000);
+
+        selection.remove(10..20);
+        selection.toggle(15);
+        assert_eq!(selection.ranges(), [0..10, 15..16, 20..1_000_000]);
+        selection.add(10..15);
+        assert_eq!(selection.ranges(), [0..16, 20..1_000_000]);
+        assert!(selection.contains(15) && !selection.contains(16));
+
+        selection.set_current(Some(500));
+        selection.truncate(12);
+        assert_eq!(
+            selection.iter().collect::<Vec<_>>(),
+            (0..12).collect::<Vec<_>>()
+        );
+        assert_eq!(selection.current(), None);
+    }
+
+    #[test]
+    fn virtualized() {
+        let (mut h, selection, drawn) = list(1_000_000, SelectionMode::Single);
+
+        h.draw();
+        assert_eq!(*drawn.borrow(), [0, 1, 2, 3, 4]);
+
+        // The last item is scrolled into the area
+        drawn.borrow_mut().clear();
+        h.key(END, 0);
+        h.draw();
+        assert_eq!(*drawn.borrow(), (999_995..1_000_000).collect::<Vec<_>>());
+        assert_eq!(selection.borrow().iter().collect::<Vec<_>>(), [999_999]);
+        assert_eq!(signals(), [6]);
+
+        h.key(PAGE_UP, 0).key(UP, 0);
+        assert_eq!(selection.borrow().current(), Some(999_993));
+        h.key(ENTER, 0);
+        assert_eq!(signals(), [6, 7]);
+    }
+
+    #[test]
+    fn mouse() {
+        let (mut h, selection, _) = list(100, SelectionMode::Multiple);
+
+        h.click((10.0, 15.0));
+        h.pointer(PRIMARY_POINTER).set_modifiers(SHIFT);
+        h.click((10.0, 35.0));
+        assert_eq!(selection.borrow().iter().collect::<Vec<_>>(), [1, 2, 3]);
+        h.pointer(PRIMARY_POINTER).set_modifiers(CTRL);
+        h.click((10.0, 25.0));
+        assert_eq!(selection.borrow().ranges(), [1..2, 3..4]);
+        assert_eq!(signals(), [6]);
+
+        // Dragging out of the list selects and scrolls with each move
+        h.pointer(PRIMARY_POINTER).set_modifiers(0);
+        h.mouse(PRIMARY_POINTER, MouseType::Pressed(1), (10.0, 5.0));
+        h.mouse(PRIMARY_POINTER, MouseType::CursorMoved, (10.0, 70.0));
+        h.mouse(PRIMARY_POINTER, MouseType::CursorMoved, (10.0, 70.0));
+        h.mouse(PRIMARY_POINTER, MouseType::Released(1), (10.0, 70.0));
+        assert_eq!(selection.borrow().len(), 9);
+        let (area, _) = h.widget.areas(&h.internal);
+        assert_eq!(h.widget.scroll.visible(area), 4..9);
+
+        // A double click needs the time of the backend
+        h.pointer(PRIMARY_POINTER).set_time(1000);
+        h.click((10.0, 15.0));
+        h.pointer(PRIMARY_POINTER).set_time(1200);
+        h.click((10.0, 15.0));
+        assert_eq!(signals(), [6, 7]);
+    }
+
+    #[test]
+    fn scrollbar() {
+        let (mut h, _, _) = list(100, SelectionMode::None);
+
+        // The thumb is 16 high, pressing below it scrolls a page
+        h.click((95.0, 40.0));
+        assert_eq!(h.widget.scroll.scroll, 50.0);
+
+        h.mouse(PRIMARY_POINTER, MouseType::Wheel(-1.0), (10.0, 10.0));
+        assert_eq!(h.widget.scroll.scroll, 80.0);
+        h.mouse(PRIMARY_POINTER, MouseType::Wheel(1000.0), (10.0, 10.0));
+        assert_eq!(h.widget.scroll.scroll, 0.0);
+
+        // Dragging the thumb to the end of the track
+        h.mouse(PRIMARY_POINTER, MouseType::Pressed(1), (95.0, 5.0));
+        h.mouse(PRIMARY_POINTER, MouseType::CursorMoved, (95.0, 100.0));
+        assert_eq!(h.widget.scroll.scroll, 950.0);
+        assert!(signals().is_empty());
+    }
+}
@@ -0,0 +1,1268 @@
+//! Menus
+//!
+//! A Menu is a declarative list of items that is shown by a MenuBar, a ContextMenu
+//! or any widget that opens a MenuPopup. Activating an item pushes its signal, an
+//! item with a check mark toggles its shared value before
+
+use super::popup::{anchor_bounds, Popup, PopupAction, SharedPopups, Side};
+use super::{arrow, inset, line, mouse_pos, rect, Direction, Style};
+use crate::damage::DamageRegion;
+use crate::group::{push_event, GroupEvent, GroupID};
+use crate::math::Scalar;
+use crate::paint::{Color, Painter, PathCommand};
+use crate::state::keys::*;
+use crate::state::{KeyState, MouseState, MouseType, PointerID, TextInput};
+use crate::text::{Monospace, SharedMeasure};
+use crate::widget::flags::*;
+use crate::widget::{Boundaries, Dimensions, Widget, WidgetInternal};
+
+use std::cell::Cell;
+use std::ops::{Add, Sub};
+use std::rc::Rc;
+
+enum Kind {
+    Action(GroupID),
+    Check(Rc<Cell<bool>>, GroupID),
+    Submenu(Rc<Menu>),
+    Separator,
+}
+
+/// Item of a Menu
+pub struct MenuItem {
+    label: String,
+    /// Byte position and lowercase char of the mnemonic
+    mnemonic: Option<(usize, char)>,
+    kind: Kind,
+    shortcut: Option<(u32, u16)>,
+    enabled: Cell<bool>,
+}
+
+/// Compare key codes without case
+fn same_key(a: u32, b: u32) -> bool {
+    match (to_char(a), to_char(b)) {
+        (Some(a), Some(b)) => a.to_lowercase().eq(b.to_lowercase()),
+        _ => a == b,
+    }
+}
+
+/// Lowercase char of a printable key code
+fn key_char(code: u32) -> Option<char> {
+    to_char(code).and_then(|c| c.to_lowercase().next())
+}
+
+impl MenuItem {
+    /// The '&' of the label marks the next char as the mnemonic, "&&" is a '&'
+    fn new(label: &str, kind: Kind) -> Self {
+        let mut text = String::new();
+        let mut mnemonic = None;
+        let mut chars = label.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '&' => match chars.next() {
+                    Some('&') => text.push('&'),
+                    Some(c) => {
+                        if mnemonic.is_none() {
+                            mnemonic = c.to_lowercase().next().map(|m| (text.len(), m));
+                        }
+                        text.push(c);
+                    }
+                    None => {}
+                },
+                c => text.push(c),
+            }
+        }
+
+        MenuItem {
+            label: text,
+            mnemonic,
+            kind,
+            shortcut: None,
+            enabled: Cell::new(true),
+        }
+    }
+
+    /// Label without the mnemonic marks
+    #[inline]
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    #[inline]
+    pub fn mnemonic(&self) -> Option<char> {
+        self.mnemonic.map(|(_, c)| c)
+    }
+
+    /// Key code and modifiers of the shortcut
+    #[inline]
+    pub fn shortcut(&self) -> Option<(u32, u16)> {
+        self.shortcut
+    }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.get()
+    }
+
+    #[inline]
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.set(enabled);
+    }
+
+    #[inline]
+    pub fn is_separator(&self) -> bool {
+        matches!(self.kind, Kind::Separator)
+    }
+
+    pub fn submenu(&self) -> Option<&Rc<Menu>> {
+        match &self.kind {
+            Kind::Submenu(menu) => Some(menu),
+            _ => None,
+        }
+    }
+
+    /// Value of the check mark, None if the item doesn't have it
+    pub fn checked(&self) -> Option<bool> {
+        match &self.kind {
+            Kind::Check(value, _) => Some(value.get()),
+            _ => None,
+        }
+    }
+
+    pub fn signal(&self) -> Option<GroupID> {
+        match self.kind {
+            Kind::Action(signal) | Kind::Check(_, signal) => Some(signal),
+            _ => None,
+        }
+    }
+
+    /// Toggle the check mark and push the signal, returns false if the item is
+    /// disabled or doesn't have a signal
+    pub fn activate(&self) -> bool {
+        if !self.is_enabled() {
+            return false;
+        }
+
+        match &self.kind {
+            Kind::Action(signal) => push_event(GroupEvent::Signal(*signal)),
+            Kind::Check(value, signal) => {
+                value.set(!value.get());
+                push_event(GroupEvent::Signal(*signal));
+            }
+            _ => return false,
+        }
+
+        true
+    }
+
+    /// The item can be hovered and activated
+    fn selectable(&self) -> bool {
+        self.is_enabled() && !self.is_separator()
+    }
+}
+
+/// List of menu items made with a builder
+///
+/// ```
+/// use std::cell::Cell;
+/// use std::rc::Rc;
+/// use trgui::state::keys::CTRL;
+/// use trgui::widgets::Menu;
+///
+/// const OPEN: usize = 1;
+/// const GRID: usize = 2;
+/// const QUIT: usize = 3;
+///
+/// let grid = Rc::new(Cell::new(true));
+/// let recent = Menu::new().item("notes.txt", OPEN);
+///
+/// let menu = Menu::new()
+///     .item("&Open...", OPEN).shortcut('o' as u32, CTRL)
+///     .check("Show &Grid", grid.clone(), GRID)
+///     .separator()
+///     .submenu("&Recent", recent)
+///     .item("&Quit", QUIT).disabled();
+///
+/// assert_eq!(menu.items().len(), 5);
+/// assert!(!menu.items()[4].is_enabled());
+/// ```
+#[derive(Default)]
+pub struct Menu {
+    items: Vec<MenuItem>,
+}
+
+impl Menu {
+    pub fn new() -> Self {
+        Menu { items: Vec::new() }
+    }
+
+    /// Add an item that pushes a signal ID
+    pub fn item(mut self, label: &str, signal: GroupID) -> Self {
+        self.items.push(MenuItem::new(label, Kind::Action(signal)));
+        self
+    }
+
+    /// Add an item with a check mark shared with the application
+    pub fn check(mut self, label: &str, value: Rc<Cell<bool>>, signal: GroupID) -> Self {
+        self.items
+            .push(MenuItem::new(label, Kind::Check(value, signal)));
+        self
+    }
+
+    /// Add an item that opens another menu
+    pub fn submenu(mut self, label: &str, menu: Menu) -> Self {
+        self.items
+            .push(MenuItem::new(label, Kind::Submenu(Rc::new(menu))));
+        self
+    }
+
+    pub fn separator(mut self) -> Self {
+        self.items.push(MenuItem::new("", Kind::Separator));
+        self
+    }
+
+    /// Set the shortcut of the last item, chars are compared without case
+    pub fn shortcut(mut self, code: u32, mods: u16) -> Self {
+        if let Some(item) = self.items.last_mut() {
+            item.shortcut = Some((code, mods));
+        }
+
+        self
+    }
+
+    /// Disable the last item
+    pub fn disabled(self) -> Self {
+        if let Some(item) = self.items.last() {
+            item.set_enabled(false);
+        }
+
+        self
+    }
+
+    #[inline]
+    pub fn items(&self) -> &[MenuItem] {
+        &self.items
+    }
+
+    /// Enable or disable the items of a signal ID in the menu and its submenus
+    pub fn set_enabled(&self, signal: GroupID, enabled: bool) {
+        for item in self.items.iter() {
+            if item.signal() == Some(signal) {
+                item.set_enabled(enabled);
+            } else if let Some(menu) = item.submenu() {
+                menu.set_enabled(signal, enabled);
+            }
+        }
+    }
+
+    /// Find an item of a shortcut in the menu and its submenus
+    pub fn find_shortcut(&self, code: u32, mods: u16) -> Option<&MenuItem> {
+        self.items.iter().find_map(|item| match item.submenu() {
+            Some(menu) if item.is_enabled() => menu.find_shortcut(code, mods),
+            _ => item
+                .shortcut
+                .filter(|&(s_code, s_mods)| s_mods == mods && same_key(s_code, code))
+                .map(|_| item),
+        })
+    }
+
+    /// Activate the item of a pressed shortcut, returns true if it's found and enabled
+    pub fn activate_shortcut(&self, key: &KeyState) -> bool {
+        match *key {
+            KeyState::Pressed(code, mods) => self
+                .find_shortcut(code, mods)
+                .is_some_and(|item| item.activate()),
+            _ => false,
+        }
+    }
+
+    fn find_mnemonic(&self, c: char) -> Option<usize> {
+        self.items
+            .iter()
+            .position(|item| item.selectable() && item.mnemonic() == Some(c))
+    }
+}
+
+/// Text of a shortcut, like "Ctrl+Shift+S"
+pub fn shortcut_text(code: u32, mods: u16) -> String {
+    let mut text = String::new();
+    for &(m, name) in [
+        (CTRL, "Ctrl+"),
+        (SHIFT, "Shift+"),
+        (ALT, "Alt+"),
+        (SUPER, "Super+"),
+    ]
+    .iter()
+    {
+        if mods & m != 0 {
+            text.push_str(name);
+        }
+    }
+
+    let name = match code {
+        BACKSPACE => "Backspace",
+        TAB => "Tab",
+        ENTER => "Enter",
+        ESCAPE => "Esc",
+        SPACE => "Space",
+        DELETE => "Del",
+        INSERT => "Ins",
+        UP => "Up",
+        DOWN => "Down",
+        LEFT => "Left",
+        RIGHT => "Right",
+        HOME => "Home",
+        END => "End",
+        PAGE_UP => "PgUp",
+        PAGE_DOWN => "PgDn",
+        _ => {
+            match (1..=12).find(|&n| function(n) == code) {
+                Some(n) => text.push_str(&format!("F{}", n)),
+                None => text.extend(to_char(code).into_iter().flat_map(char::to_uppercase)),
+            }
+
+            return text;
+        }
+    };
+
+    text.push_str(name);
+    text
+}
+
+/// Draw the label of an item with the mnemonic underlined
+fn draw_label<T: Scalar, CTX: Painter<T>>(
+    ctx: &mut CTX,
+    measure: &SharedMeasure<T>,
+    style: &Style<T>,
+    item: &MenuItem,
+    (x, baseline): (f32, f32),
+    color: Color,
+) {
+    let (font, size) = (style.font, style.font_size);
+    let pos = (T::from_f32(x), T::from_f32(baseline));
+    ctx.text(pos, font, size, &item.label, color);
+
+    if let Some((n, _)) = item.mnemonic {
+        let offset = measure.advance(font, size, &item.label[..n]).to_f32();
+        let c = item.label[n..].chars().next().map_or(0, char::len_utf8);
+        let width = measure.advance(font, size, &item.label[n..n + c]).to_f32();
+
+        let line = style.border.to_f32();
+        ctx.fill_rect(rect(x + offset, baseline + line, width, line), color);
+    }
+}
+
+/// Popup with the items of a menu
+///
+/// Hovering an item with a submenu opens it at the side, releasing a button
+/// over an item activates it, so a menu can be pressed, dragged and released.
+/// Up, Down, Home and End move the hovered item, Right opens a submenu, Left
+/// closes it, Enter and Space activate an item and the mnemonic chars activate
+/// their item
+pub struct MenuPopup<T> {
+    menu: Rc<Menu>,
+    anchor: Boundaries<T>,
+    side: Side,
+    style: Style<T>,
+    measure: SharedMeasure<T>,
+    bounds: Boundaries<T>,
+    /// Top of each item relative to the first, with the bottom of the last
+    tops: Vec<f32>,
+    /// Widths of the labels and the shortcuts
+    columns: (f32, f32),
+    hover: Option<usize>,
+    /// Item of the open submenu
+    open: Option<usize>,
+    /// It was opened by another menu
+    sub: bool,
+    /// Button that opened the popup, its release doesn't activate an item until
+    /// an item is hovered
+    opening: Option<u8>,
+}
+
+impl<T: Scalar + Sub<Output = T> + Default + 'static> MenuPopup<T> {
+    /// Creates a popup of a menu next to an anchor, a context menu uses the
+    /// position of the pointer as an empty anchor
+    pub fn new(menu: Rc<Menu>, anchor: Boundaries<T>, side: Side) -> Self {
+        MenuPopup {
+            menu,
+            anchor,
+            side,
+            style: Style::default(),
+            measure: Rc::new(Monospace::new(0.6, 0.8, 0.2)),
+            bounds: Default::default(),
+            tops: Vec::new(),
+            columns: (0.0, 0.0),
+            hover: None,
+            open: None,
+            sub: false,
+            opening: None,
+        }
+    }
+
+    pub fn set_style(&mut self, style: Style<T>) {
+        self.style = style;
+    }
+
+    /// Hover the first item, for menus opened with the keyboard
+    pub fn select_first(&mut self) {
+        self.hover = self.step(None, true);
+    }
+
+    /// Ignore the release of a button until an item is hovered, for menus opened
+    /// at the pointer when the button is pressed
+    pub fn ignore_release(&mut self, button: u8) {
+        self.opening = Some(button);
+    }
+
+    fn item_height(&self) -> f32 {
+        line(&self.measure, &self.style) + self.style.padding.to_f32()
+    }
+
+    /// Item under an absolute vertical position
+    fn item_at(&self, y: f32) -> Option<usize> {
+        let offset = y - self.bounds.1.to_f32() - self.style.border.to_f32();
+        let index = self.tops.iter().rposition(|&top| top <= offset)?;
+
+        Some(index).filter(|&index| index + 1 < self.tops.len())
+    }
+
+    /// Next or previous selectable item, it goes around
+    fn step(&self, from: Option<usize>, forward: bool) -> Option<usize> {
+        let count = self.menu.items.len();
+        let start = match from {
+            Some(n) if forward => n + 1,
+            Some(n) => n + count - 1,
+            None if forward => 0,
+            None => count.saturating_sub(1),
+        };
+
+        (0..count)
+            .map(|n| {
+                if forward {
+                    (start + n) % count
+                } else {
+                    (start + count - n) % count
+                }
+            })
+            .find(|&n| self.menu.items[n].selectable())
+    }
+
+    /// Open the submenu of an item or activate it
+    fn activate<CTX: Painter<T>>(&mut self, index: usize, keyboard: bool) -> PopupAction<T, CTX> {
+        self.hover = Some(index);
+
+        let item = &self.menu.items[index];
+        let menu = match item.submenu() {
+            Some(menu) if item.is_enabled() => menu.clone(),
+            _ if item.activate() => return PopupAction::CloseAll,
+            _ => return PopupAction::None,
+        };
+
+        let (x, y, w, _) = self.bounds;
+        let top = y.to_f32() + self.style.border.to_f32() + self.tops[index];
+        let anchor = rect(x.to_f32(), top, w.to_f32(), self.item_height());
+
+        let mut popup = MenuPopup::new(menu, anchor, Side::Right);
+        popup.style = self.style.clone();
+        popup.measure = self.measure.clone();
+        popup.sub = true;
+        if keyboard {
+            popup.select_first();
+        }
+
+        self.open = Some(index);
+        PopupAction::Open(Box::new(popup))
+    }
+}
+
+impl<T, CTX> Popup<T, CTX> for MenuPopup<T>
+where
+    T: Scalar + Sub<Output = T> + Default + 'static,
+    CTX: Painter<T>,
+{
+    fn bounds(&self) -> Boundaries<T> {
+        self.bounds
+    }
+
+    fn place(&mut self, area: Boundaries<T>) {
+        let style = &self.style;
+        let (font, size) = (style.font, style.font_size);
+        let (pad, border) = (style.padding.to_f32(), style.border.to_f32());
+        let item = self.item_height();
+
+        let (mut label, mut shortcut, mut top) = (0.0f32, 0.0f32, 0.0);
+        let mut submenu = false;
+        self.tops.clear();
+
+        for entry in self.menu.items.iter() {
+            self.tops.push(top);
+
+            if entry.is_separator() {
+                top += pad + border;
+                continue;
+            }
+
+            let advance = |text: &str| self.measure.advance(font, size, text).to_f32();
+            label = label.max(advance(&entry.label));
+            if let Some((code, mods)) = entry.shortcut {
+                shortcut = shortcut.max(advance(&shortcut_text(code, mods)));
+            }
+
+            submenu |= entry.submenu().is_some();
+            top += item;
+        }
+        self.tops.push(top);
+        self.columns = (label, shortcut);
+
+        // Check mark, label, space, shortcut and arrow
+        let gap = if shortcut > 0.0 { pad * 4.0 } else { 0.0 };
+        let end = if submenu { item } else { pad };
+        let dim = (
+            border * 2.0 + item + label + gap + shortcut + end,
+            border * 2.0 + top,
+        );
+
+        self.bounds = anchor_bounds(self.anchor, self.side, dim, area);
+    }
+
+    fn draw(&mut self, ctx: &mut CTX) {
+        let style = &self.style;
+        let (pad, border) = (style.padding.to_f32(), style.border.to_f32());
+        let bounds = self.bounds;
+
+        ctx.fill_rect(bounds, style.outline);
+        ctx.fill_rect(inset(bounds, style.border), style.field);
+
+        let x = bounds.0.to_f32() + border;
+        let w = bounds.2.to_f32() - border * 2.0;
+        let top = bounds.1.to_f32() + border;
+        let h = self.item_height();
+        let ascent = self.measure.ascent(style.font, style.font_size).to_f32();
+
+        for (n, item) in self.menu.items.iter().enumerate() {
+            let y = top + self.tops[n];
+
+            if item.is_separator() {
+                let line = rect(x + pad, y + pad * 0.5, w - pad * 2.0, border);
+                ctx.fill_rect(line, style.outline);
+                continue;
+            }
+
+            let hover = item.is_enabled() && (self.hover == Some(n) || self.open == Some(n));
+            let color = if !item.is_enabled() {
+                style.disabled
+            } else if hover {
+                style.accent_text
+            } else {
+                style.text
+            };
+
+            if hover {
+                ctx.fill_rect(rect(x, y, w, h), style.accent);
+            }
+
+            if item.checked() == Some(true) {
+                let p = |px: f32, py: f32| (T::from_f32(x + h * px), T::from_f32(y + h * py));
+                let path = [
+                    PathCommand::MoveTo(p(0.25, 0.52)),
+                    PathCommand::LineTo(p(0.42, 0.68)),
+                    PathCommand::LineTo(p(0.74, 0.32)),
+                ];
+
+                ctx.stroke_path(&path, T::from_f32((h / 8.0).max(1.5)), color);
+            }
+
+            let baseline = y + pad * 0.5 + ascent;
+            draw_label(ctx, &self.measure, style, item, (x + h, baseline), color);
+
+            if let Some((code, mods)) = item.shortcut {
+                let text = shortcut_text(code, mods);
+                let advance = self
+                    .measure
+                    .advance(style.font, style.font_size, &text)
+                    .to_f32();
+                let end = if item.submenu().is_some() { h } else { pad };
+
+                let pos = (T::from_f32(x + w - end - advance), T::from_f32(baseline));
+                let color = if hover { color } else { style.disabled };
+                ctx.text(pos, style.font, style.font_size, &text, color);
+            }
+
+            if item.submenu().is_some() {
+                let size = style.font_size.to_f32() * 0.6;
+                ctx.fill_path(
+                    &arrow(rect(x + w - h, y, h, h), size, Direction::Right),
+                    color,
+                );
+            }
+        }
+    }
+
+    fn handle_mouse(&mut self, mouse: &MouseState<T>) -> PopupAction<T, CTX> {
+        let y = mouse_pos(mouse).1;
+        let item = self.item_at(y).filter(|&n| self.menu.items[n].selectable());
+
+        match mouse.m_type {
+            MouseType::CursorMoved if item != self.hover => {
+                self.hover = item;
+                if item.is_some() {
+                    self.opening = None;
+                }
+
+                match item {
+                    Some(n) if self.menu.items[n].submenu().is_some() => self.activate(n, false),
+                    Some(_) if self.open.take().is_some() => PopupAction::CloseOver,
+                    _ => PopupAction::Draw,
+                }
+            }
+            MouseType::Pressed(_) => {
+                self.opening = None;
+                PopupAction::None
+            }
+            MouseType::Released(button) if self.opening == Some(button) => {
+                self.opening = None;
+                PopupAction::None
+            }
+            MouseType::Released(_) => match item {
+                Some(n) if self.menu.items[n].submenu().is_none() => self.activate(n, false),
+                _ => PopupAction::None,
+            },
+            _ => PopupAction::None,
+        }
+    }
+
+    fn handle_keys(&mut self, key: &KeyState) -> PopupAction<T, CTX> {
+        let code = match *key {
+            KeyState::Pressed(code, _) => code,
+            _ => return PopupAction::None,
+        };
+
+        let hover = match code {
+            UP => self.step(self.hover, false),
+            DOWN => self.step(self.hover, true),
+            HOME => self.step(None, true),
+            END => self.step(None, false),
+            RIGHT => {
+                return match self.hover {
+                    Some(n) if self.menu.items[n].submenu().is_some() => self.activate(n, true),
+                    _ => PopupAction::Ignore,
+                }
+            }
+            LEFT if self.sub => return PopupAction::Close,
+            ENTER | SPACE => {
+                return match self.hover {
+                    Some(n) => self.activate(n, true),
+                    None => PopupAction::None,
+                }
+            }
+            ESCAPE => return PopupAction::Close,
+            _ => {
+                return match key_char(code).and_then(|c| self.menu.find_mnemonic(c)) {
+                    Some(n) => self.activate(n, true),
+                    None => PopupAction::Ignore,
+                }
+            }
+        };
+
+        self.hover = hover;
+        PopupAction::Draw
+    }
+
+    fn hover_out(&mut self) -> bool {
+        self.hover.take().is_some()
+    }
+
+    fn measure(&mut self, measure: &SharedMeasure<T>) {
+        self.measure = measure.clone();
+    }
+}
+
+/// Horizontal bar with the titles of a menu
+///
+/// The items of the menu are the titles, a title with a submenu opens it below
+/// the bar. While a menu is open, hovering another title opens its menu and Left
+/// or Right go to the menu of the next title
+///
+/// The signal ID is the owner of the popups, add the bar with SIGNAL and a Group
+/// of the signal. The application can push the signal for focus the bar, like
+/// when Alt or F10 is pressed, then Left and Right move between the titles, Down,
+/// Enter or a mnemonic open the menu and Escape leaves the bar
+pub struct MenuBar<T, CTX> {
+    menu: Rc<Menu>,
+    popups: SharedPopups<T, CTX>,
+    signal: GroupID,
+    style: Style<T>,
+    measure: SharedMeasure<T>,
+    hover: Option<usize>,
+    /// Title of the open menu
+    open: Option<usize>,
+    /// Title of the keyboard cursor
+    current: usize,
+}
+
+impl<T, CTX> MenuBar<T, CTX>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default + 'static,
+    CTX: Painter<T>,
+{
+    /// Creates a MenuBar of a menu that opens its submenus in the popups
+    pub fn new(menu: Rc<Menu>, popups: SharedPopups<T, CTX>, signal: GroupID) -> Self {
+        MenuBar {
+            menu,
+            popups,
+            signal,
+            style: Style::default(),
+            measure: Rc::new(Monospace::new(0.6, 0.8, 0.2)),
+            hover: None,
+            open: None,
+            current: 0,
+        }
+    }
+
+    pub fn set_style(&mut self, style: Style<T>) {
+        self.style = style;
+    }
+
+    #[inline]
+    pub fn menu(&self) -> &Rc<Menu> {
+        &self.menu
+    }
+
+    /// Boundaries of each title
+    fn titles(&self, bounds: Boundaries<T>) -> Vec<Boundaries<T>> {
+        let (font, size) = (self.style.font, self.style.font_size);
+        let pad = self.style.padding.to_f32();
+        let mut x = bounds.0.to_f32();
+
+        self.menu
+            .items
+            .iter()
+            .map(|item| {
+                let w = self.measure.advance(font, size, &item.label).to_f32() + pad * 2.0;
+                x += w;
+
+                rect(x - w, bounds.1.to_f32(), w, bounds.3.to_f32())
+            })
+            .collect()
+    }
+
+    fn title_at(&self, bounds: Boundaries<T>, x: f32) -> Option<usize> {
+        self.titles(bounds).iter().position(|title| {
+            let start = title.0.to_f32();
+            x >= start && x < start + title.2.to_f32()
+        })
+    }
+
+    /// Open the menu of a title or activate it
+    fn open_title(&mut self, internal: &mut WidgetInternal<T>, index: usize, keyboard: bool) {
+        let menu = self.menu.clone();
+        let item = &menu.items[index];
+        self.current = index;
+        internal.on(DRAW);
+
+        match item.submenu() {
+            Some(submenu) if item.is_enabled() => {
+                let anchor = self.titles(internal.boundaries_abs())[index];
+                let mut popup = MenuPopup::new(submenu.clone(), anchor, Side::Below);
+                popup.set_style(self.style.clone());
+                if keyboard {
+                    popup.select_first();
+                }
+
+                self.popups
+                    .borrow_mut()
+                    .open(Box::new(popup), Some(self.signal));
+                self.open = Some(index);
+            }
+            _ => {
+                item.activate();
+            }
+        }
+    }
+
+    /// Next or previous enabled title, it goes around
+    fn step(&self, from: usize, forward: bool) -> usize {
+        let count = self.menu.items.len();
+
+        (1..=count)
+            .map(|n| {
+                if forward {
+                    (from + n) % count
+                } else {
+                    (from + count * 2 - n) % count
+                }
+            })
+            .find(|&n| self.menu.items[n].selectable())
+            .unwrap_or(from)
+    }
+}
+
+impl<T, CTX> Widget<T, CTX> for MenuBar<T, CTX>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default + 'static,
+    CTX: Painter<T>,
+{
+    fn draw(&mut self, internal: &WidgetInternal<T>, ctx: &mut CTX) -> bool {
+        let bounds = internal.boundaries_abs();
+        let style = &self.style;
+        ctx.fill_rect(bounds, style.window);
+
+        let ascent = self.measure.ascent(style.font, style.font_size).to_f32();
+        let text = line(&self.measure, style);
+        let focused = internal.check(FOCUS) && self.open.is_none();
+
+        for (n, title) in self.titles(bounds).into_iter().enumerate() {
+            let item = &self.menu.items[n];
+            let open = self.open == Some(n);
+
+            let color = if !item.is_enabled() || !internal.check(ENABLED) {
+                style.disabled
+            } else if open {
+                style.accent_text
+            } else {
+                style.text
+            };
+
+            if open {
+                ctx.fill_rect(title, style.accent);
+            } else if self.hover == Some(n) && item.is_enabled() {
+                ctx.fill_rect(title, style.hover);
+            }
+
+            if focused && self.current == n {
+                ctx.stroke_rect(title, style.border, style.focus);
+            }
+
+            let x = title.0.to_f32() + style.padding.to_f32();
+            let baseline = title.1.to_f32() + (title.3.to_f32() - text) * 0.5 + ascent;
+            draw_label(ctx, &self.measure, style, item, (x, baseline), color);
+        }
+
+        false
+    }
+
+    fn measure(&mut self, _: &mut WidgetInternal<T>, measure: &SharedMeasure<T>) {
+        self.measure = measure.clone();
+    }
+
+    /// The popups were closed or didn't use a key, otherwise it's a request of focus
+    fn handle_signal(&mut self, internal: &mut WidgetInternal<T>, _: GroupID) {
+        internal.on(DRAW);
+
+        let open = match self.open {
+            Some(open) => open,
+            None => {
+                internal.on(FOCUS);
+                return;
+            }
+        };
+
+        let mut popups = self.popups.borrow_mut();
+        let key = popups.take_key();
+        if popups.owner() != Some(self.signal) {
+            self.open = None;
+        }
+        drop(popups);
+
+        let next = match key {
+            Some((LEFT, _)) => self.step(open, false),
+            Some((RIGHT, _)) => self.step(open, true),
+            _ => return,
+        };
+
+        // A title without menu is only focused, it isn't activated by the arrows
+        if self.menu.items[next].submenu().is_some() {
+            self.open_title(internal, next, true);
+        } else {
+            self.current = next;
+            self.open = None;
+            self.popups.borrow_mut().close_all();
+            internal.on(FOCUS);
+        }
+    }
+
+    fn handle_mouse(&mut self, internal: &mut WidgetInternal<T>, mouse: &MouseState<T>) {
+        if !internal.check(ENABLED) {
+            return;
+        }
+
+        let x = mouse_pos(mouse).0;
+        let title = self.title_at(internal.boundaries_abs(), x);
+
+        match mouse.m_type {
+            MouseType::CursorMoved if title != self.hover => {
+                self.hover = title;
+                internal.on(DRAW);
+
+                // Another menu is opened while a menu is open
+                let open = self.popups.borrow().owner() == Some(self.signal);
+                let menu = title.filter(|&n| {
+                    let item = &self.menu.items[n];
+                    item.selectable() && item.submenu().is_some()
+                });
+
+                if let (true, Some(n)) = (open, menu) {
+                    if self.open != Some(n) {
+                        self.open_title(internal, n, false);
+                    }
+                }
+            }
+            MouseType::Pressed(1) => {
+                if let Some(n) = title.filter(|&n| self.menu.items[n].selectable()) {
+                    self.open_title(internal, n, false);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_keys(&mut self, internal: &mut WidgetInternal<T>, key: KeyState) {
+        let code = match key {
+            KeyState::Pressed(code, _) if internal.check(ENABLED) => code,
+            _ => return,
+        };
+
+        if self.menu.items.is_empty() {
+            return;
+        }
+
+        match code {
+            LEFT => self.current = self.step(self.current, false),
+            RIGHT => self.current = self.step(self.current, true),
+            DOWN | ENTER | SPACE => self.open_title(internal, self.current, true),
+            ESCAPE => internal.off(FOCUS),
+            _ => {
+                if let Some(n) = key_char(code).and_then(|c| self.menu.find_mnemonic(c)) {
+                    self.open_title(internal, n, true);
+                }
+            }
+        }
+
+        internal.on(DRAW);
+    }
+
+    /// The bar is focused only with its signal
+    fn step_focus(&mut self, _: &mut WidgetInternal<T>, _: bool) -> bool {
+        false
+    }
+
+    fn hover_out(&mut self, internal: &mut WidgetInternal<T>) {
+        self.hover = None;
+        internal.on(DRAW);
+    }
+
+    fn min_dimensions(&self) -> Dimensions<T> {
+        let (font, size) = (self.style.font, self.style.font_size);
+        let pad = self.style.padding.to_f32();
+        let width = self.menu.items.iter().fold(0.0, |w, item| {
+            w + self.measure.advance(font, size, &item.label).to_f32() + pad * 2.0
+        });
+
+        (
+            T::from_f32(width),
+            T::from_f32(line(&self.measure, &self.style) + pad * 2.0),
+        )
+    }
+}
+
+/// Widget that opens a menu at the pointer when it's pressed with the right button
+///
+/// The content shares the WidgetInternal of the ContextMenu and receives every
+/// event, Shift+F10 opens the menu at the top left corner when it's focused
+pub struct ContextMenu<T, CTX> {
+    content: Box<dyn Widget<T, CTX>>,
+    menu: Rc<Menu>,
+    popups: SharedPopups<T, CTX>,
+    style: Style<T>,
+}
+
+impl<T, CTX> ContextMenu<T, CTX>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default + 'static,
+    CTX: Painter<T>,
+{
+    pub fn new(
+        content: Box<dyn Widget<T, CTX>>,
+        menu: Rc<Menu>,
+        popups: SharedPopups<T, CTX>,
+    ) -> Self {
+        ContextMenu {
+            content,
+            menu,
+            popups,
+            style: Style::default(),
+        }
+    }
+
+    pub fn set_style(&mut self, style: Style<T>) {
+        self.style = style;
+    }
+
+    /// Open the menu at a position, the release of the button that opened it is ignored
+    fn open(&self, (x, y): (T, T), button: Option<u8>) {
+        let zero = T::default();
+        let mut popup = MenuPopup::new(self.menu.clone(), (x, y, zero, zero), Side::Below);
+        popup.set_style(self.style.clone());
+        match button {
+            Some(button) => popup.ignore_release(button),
+            None => popup.select_first(),
+        }
+
+        self.popups.borrow_mut().open(Box::new(popup), None);
+    }
+}
+
+impl<T, CTX> Widget<T, CTX> for ContextMenu<T, CTX>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default + 'static,
+    CTX: Painter<T>,
+{
+    fn draw(&mut self, internal: &WidgetInternal<T>, ctx: &mut CTX) -> bool {
+        self.content.draw(internal, ctx)
+    }
+
+    fn update(&mut self, internal: &mut WidgetInternal<T>) {
+        self.content.update(internal);
+    }
+
+    fn layout(&mut self, internal: &mut WidgetInternal<T>, complete: bool) {
+        self.content.layout(internal, complete);
+    }
+
+    fn measure(&mut self, internal: &mut WidgetInternal<T>, measure: &SharedMeasure<T>) {
+        self.content.measure(internal, measure);
+    }
+
+    fn handle_signal(&mut self, internal: &mut WidgetInternal<T>, group: GroupID) {
+        self.content.handle_signal(internal, group);
+    }
+
+    fn handle_mouse(&mut self, internal: &mut WidgetInternal<T>, mouse: &MouseState<T>) {
+        self.content.handle_mouse(internal, mouse);
+
+        if let MouseType::Pressed(3) = mouse.m_type {
+            if internal.check(ENABLED) {
+                self.open(mouse.absolute_pos(), Some(3));
+            }
+        }
+    }
+
+    fn handle_keys(&mut self, internal: &mut WidgetInternal<T>, key: KeyState) {
+        match key {
+            KeyState::Pressed(code, SHIFT) if code == function(10) => {
+                let (x, y, _, _) = internal.boundaries_abs();
+                self.open((x, y), None);
+            }
+            key => self.content.handle_keys(internal, key),
+        }
+    }
+
+    fn handle_text(&mut self, internal: &mut WidgetInternal<T>, text: TextInput) {
+        self.content.handle_text(internal, text);
+    }
+
+    fn step_focus(&mut self, internal: &mut WidgetInternal<T>, back: bool) -> bool {
+        self.content.step_focus(internal, back)
+    }
+
+    fn hover_in(&mut self, internal: &mut WidgetInternal<T>) {
+        self.content.hover_in(internal);
+    }
+
+    fn hover_out(&mut self, internal: &mut WidgetInternal<T>) {
+        self.content.hover_out(internal);
+    }
+
+    fn pointer_out(&mut self, internal: &mut WidgetInternal<T>, pointer: PointerID) {
+        self.content.pointer_out(internal, pointer);
+    }
+
+    fn focus_out(&mut self, internal: &mut WidgetInternal<T>) {
+        self.content.focus_out(internal);
+    }
+
+    fn expose(&mut self, internal: &mut WidgetInternal<T>, area: Boundaries<T>) {
+        self.content.expose(internal, area);
+    }
+
+    fn pending(&self) -> usize {
+        self.content.pending()
+    }
+
+    fn damage(&mut self, internal: &WidgetInternal<T>, region: &mut DamageRegion<T>) {
+        self.content.damage(internal, region);
+    }
+
+    fn min_dimensions(&self) -> Dimensions<T> {
+        self.content.min_dimensions()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::container::Container;
+    use crate::empty::{EmptyDecorator, EmptyLayout};
+    use crate::group::Group;
+    use crate::widgets::label::Label;
+    use crate::widgets::popup::{PopupLayer, Popups};
+    use crate::widgets::tests::{signals, Window};
+
+    use std::cell::RefCell;
+
+    const BAR: GroupID = 1;
+    const NEW: GroupID = 10;
+    const GRID: GroupID = 11;
+
+    fn file_menu(grid: &Rc<Cell<bool>>) -> Menu {
+        Menu::new()
+            .item("&New", NEW)
+            .check("&Grid", grid.clone(), GRID)
+            .shortcut('g' as u32, CTRL)
+            .separator()
+            .submenu("&Recent", Menu::new().item("a.txt", 12))
+    }
+
+    /// File, Edit and Help titles are 52 wide and 20 high. The items of the File
+    /// menu are 15 high from y 21, and Recent starts at y 56 after the separator
+    fn menu_bar(grid: &Rc<Cell<bool>>) -> Window {
+        let menu = Rc::new(
+            Menu::new()
+                .submenu("&File", file_menu(grid))
+                .submenu("&Edit", Menu::new().item("&Undo", 13))
+                .item("&Help", 14),
+        );
+
+        let popups = Rc::new(RefCell::new(Popups::new()));
+        let bar = MenuBar::new(menu.clone(), popups.clone(), BAR);
+
+        let mut content = Container::new(EmptyDecorator, Box::new(EmptyLayout));
+        let flags = WIDGET | SIGNAL;
+        content.add_widget_b(
+            Box::new(bar),
+            flags,
+            Group::Single(BAR),
+            (0.0, 0.0, 200.0, 20.0),
+        );
+
+        let mut layer = PopupLayer::new(Box::new(content), popups);
+        layer.add_shortcuts(menu);
+
+        Window::new(layer)
+    }
+
+    #[test]
+    fn items() {
+        let grid = Rc::new(Cell::new(false));
+        let menu = file_menu(&grid).item("A&&B", 15).disabled();
+        let items = menu.items();
+
+        assert_eq!((items[0].label(), items[0].mnemonic()), ("New", Some('n')));
+        assert_eq!((items[4].label(), items[4].mnemonic()), ("A&B", None));
+        assert!(items[2].is_separator() && items[3].submenu().is_some());
+
+        // Shortcuts are found without case and disabled items aren't activated
+        assert!(menu.activate_shortcut(&KeyState::Pressed('G' as u32, CTRL)));
+        assert_eq!((items[1].checked(), signals()), (Some(true), vec![GRID]));
+        assert!(!menu.activate_shortcut(&KeyState::Pressed('g' as u32, 0)));
+        menu.set_enabled(12, false);
+        assert!(!items[3].submenu().unwrap().items()[0].activate());
+        assert!(signals().is_empty());
+
+        assert_eq!(shortcut_text('s' as u32, CTRL | SHIFT), "Ctrl+Shift+S");
+        assert_eq!(shortcut_text(function(5), ALT), "Alt+F5");
+        assert_eq!(shortcut_text(PAGE_DOWN, 0), "PgDn");
+    }
+
+    #[test]
+    fn mouse() {
+        let grid = Rc::new(Cell::new(false));
+        let mut w = menu_bar(&grid);
+
+        // The menu stays open after the release over the title
+        w.mouse(MouseType::Pressed(1), (10.0, 10.0));
+        w.mouse(MouseType::Released(1), (10.0, 10.0));
+        assert_eq!(w.open(), 1);
+
+        // Hovering Recent opens its submenu and hovering other item closes it
+        w.mouse(MouseType::CursorMoved, (10.0, 60.0));
+        assert_eq!(w.open(), 2);
+        assert!(w.texts().contains(&String::from("a.txt")));
+        w.mouse(MouseType::CursorMoved, (10.0, 40.0));
+        assert_eq!(w.open(), 1);
+
+        // Hovering another title opens its menu
+        w.mouse(MouseType::CursorMoved, (60.0, 10.0));
+        assert!(w.texts().contains(&String::from("Undo")));
+        w.mouse(MouseType::CursorMoved, (10.0, 10.0));
+        w.mouse(MouseType::Pressed(1), (10.0, 40.0));
+        w.mouse(MouseType::Released(1), (10.0, 40.0));
+        assert!(grid.get());
+        assert_eq!(w.open(), 0);
+
+        // A press outside closes the menu without reaching the widgets
+        w.mouse(MouseType::Pressed(1), (10.0, 10.0));
+        w.mouse(MouseType::Released(1), (10.0, 10.0));
+        w.mouse(MouseType::Pressed(1), (150.0, 150.0));
+        assert_eq!(w.open(), 0);
+    }
+
+    #[test]
+    fn keys() {
+        let grid = Rc::new(Cell::new(false));
+        let mut w = menu_bar(&grid);
+
+        w.key('g' as u32, CTRL);
+        assert!(grid.get());
+
+        w.mouse(MouseType::Pressed(1), (10.0, 10.0));
+        w.key(DOWN, 0).key(DOWN, 0).key(ENTER, 0);
+        assert!(!grid.get());
+        assert_eq!(w.open(), 0);
+
+        // Right on an item without submenu goes to the next menu
+        w.mouse(MouseType::Pressed(1), (10.0, 10.0));
+        w.key(RIGHT, 0);
+        assert_eq!(w.open(), 1);
+        assert!(w.texts().contains(&String::from("Undo")));
+        w.key(ESCAPE, 0);
+        assert_eq!(w.open(), 0);
+
+        // Mnemonics open the submenu and activate the items
+        w.mouse(MouseType::Pressed(1), (10.0, 10.0));
+        w.key('r' as u32, 0);
+        assert_eq!(w.open(), 2);
+        w.key(LEFT, 0).key('g' as u32, 0);
+        assert!(grid.get());
+        assert_eq!(w.open(), 0);
+    }
+
+    #[test]
+    fn context_menu() {
+        let grid = Rc::new(Cell::new(false));
+        let menu = Rc::new(file_menu(&grid));
+        let popups = Rc::new(RefCell::new(Popups::new()));
+
+        let label = Box::new(Label::new("Canvas"));
+        let context = ContextMenu::new(label, menu.clone(), popups.clone());
+        let mut layer = PopupLayer::new(Box::new(context), popups);
+        layer.add_shortcuts(menu);
+        let mut w = Window::new(layer);
+
+        // The release of the right button that opened it doesn't activate an item,
+        // until an item is hovered. Grid is at y 66 under the pointer
+        w.mouse(MouseType::Pressed(3), (50.0, 50.0));
+        w.mouse(MouseType::Released(3), (50.0, 50.0));
+        assert_eq!(w.open(), 1);
+        w.mouse(MouseType::Pressed(3), (50.0, 50.0));
+        w.mouse(MouseType::CursorMoved, (60.0, 70.0));
+        w.mouse(MouseType::Released(3), (60.0, 70.0));
+        assert!(grid.get());
+        assert_eq!(w.open(), 0);
+
+        // Shift+F10 opens it with the first item hovered
+        w.key(function(10), SHIFT).key(DOWN, 0).key(ENTER, 0);
+        assert!(!grid.get());
+    }
+}
@@ -0,0 +1,661 @@
+//! Standard widgets drawn with the Painter trait
+//!
+//! The widgets only use the Widget trait, the WidgetInternal flags and the Group
+//! events, so they work with any CTX that implements Painter. Their text is
+//! measured by the TextMeasure of the Root, without it the minimum dimensions
+//! are only the padding
+//!
+//! Clicks push a GroupEvent::Signal with the ID given to the widget. Values that
+//! the application reads are shared with `Rc<Cell<T>>`, widgets that show the
+//! same value are drawn again if they are members of the signal Group and have
+//! the SIGNAL flag
+
+mod button;
+mod canvas;
+mod check;
+mod color;
+mod combo;
+mod edit;
+mod label;
+mod list;
+mod menu;
+mod number;
+mod popup;
+mod slider;
+mod tabs;
+mod tree;
+
+pub use self::button::{Button, ToggleButton};
+pub use self::canvas::{Canvas, CanvasSource, CanvasView, Sample, Stroke, StrokeList};
+pub use self::check::{CheckBox, RadioButton};
+pub use self::color::{ColorSlider, ColorWheel, HexField, Palette};
+pub use self::combo::ComboBox;
+pub use self::edit::{Clipboard, TextEdit};
+pub use self::label::Label;
+pub use self::list::{ItemState, ListSource, ListView, Selection, SelectionMode, TextList};
+pub use self::menu::{shortcut_text, ContextMenu, Menu, MenuBar, MenuItem, MenuPopup};
+pub use self::number::{NumberField, NumberRange, SpinBox};
+pub use self::popup::{anchor_bounds, Popup, PopupAction, PopupLayer, Popups, SharedPopups, Side};
+pub use self::slider::Slider;
+pub use self::tabs::{TabID, TabState, TabView};
+pub use self::tree::{DropPosition, NodeID, NodeSelection, TreeSource, TreeView};
+
+use crate::math::Scalar;
+use crate::paint::{Color, FontID, Painter, PathCommand};
+use crate::state::keys::{ENTER, SPACE};
+use crate::state::{KeyState, MouseState, MouseType, PointerID};
+use crate::text::lines::{align_offset, dimensions, line_height, wrap, Align, Line};
+use crate::text::{SharedMeasure, TextMeasure};
+use crate::widget::flags::*;
+use crate::widget::{Boundaries, Dimensions, WidgetInternal};
+
+use std::ops::Sub;
+
+/// Direction of the axis of a widget
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Orientation {
+    Horizontal,
+    Vertical,
+}
+
+/// Colors and metrics of the widgets
+#[derive(Clone, Debug)]
+pub struct Style<T> {
+    pub font: FontID,
+    pub font_size: T,
+    /// Space between the border and the content
+    pub padding: T,
+    pub radius: T,
+    pub border: T,
+    /// Background of labels, check boxes and radio buttons
+    pub window: Color,
+    pub text: Color,
+    pub disabled: Color,
+    /// Faces of buttons and check marks
+    pub button: Color,
+    pub hover: Color,
+    pub pressed: Color,
+    pub outline: Color,
+    pub focus: Color,
+    /// Faces of checked widgets
+    pub accent: Color,
+    /// Text and marks over the accent
+    pub accent_text: Color,
+    /// Background of text fields and knobs
+    pub field: Color,
+    /// Background of selected text
+    pub selection: Color,
+}
+
+/// Style that fits the embedded bitmap font of the rasterizer
+impl<T: Scalar> Default for Style<T> {
+    fn default() -> Self {
+        Style {
+            font: 0,
+            font_size: T::from_f32(11.0),
+            padding: T::from_f32(4.0),
+            radius: T::from_f32(3.0),
+            border: T::from_f32(1.0),
+            window: Color::hex(0xf0f0f0ff),
+            text: Color::hex(0x202020ff),
+            disabled: Color::hex(0xa0a0a0ff),
+            button: Color::hex(0xe0e0e0ff),
+            hover: Color::hex(0xeaeaeaff),
+            pressed: Color::hex(0xc8c8c8ff),
+            outline: Color::hex(0x909090ff),
+            focus: Color::hex(0x3c78d8ff),
+            accent: Color::hex(0x3c78d8ff),
+            accent_text: Color::WHITE,
+            field: Color::WHITE,
+            selection: Color::hex(0xb4cdf2ff),
+        }
+    }
+}
+
+impl<T: Scalar> Style<T> {
+    /// Color of the text, it depends if the widget is enabled
+    pub fn text_color(&self, internal: &WidgetInternal<T>) -> Color {
+        if internal.check(ENABLED) {
+            self.text
+        } else {
+            self.disabled
+        }
+    }
+
+    /// Color of the border, it depends if the widget is focused
+    pub fn outline_color(&self, internal: &WidgetInternal<T>) -> Color {
+        if internal.check(FOCUS) {
+            self.focus
+        } else {
+            self.outline
+        }
+    }
+}
+
+/// Text of a widget with the lines and metrics of the last measure
+///
+/// A text that was never measured is drawn from the top left, using the font
+/// size as line height
+pub struct TextBlock<T> {
+    text: String,
+    lines: Vec<Line<T>>,
+    dim: (f32, f32),
+    ascent: f32,
+    line: f32,
+    measured: bool,
+}
+
+impl<T: Scalar> TextBlock<T> {
+    pub fn new(text: &str) -> Self {
+        TextBlock {
+            text: String::from(text),
+            lines: Vec::new(),
+            dim: (0.0, 0.0),
+            ascent: 0.0,
+            line: 0.0,
+            measured: false,
+        }
+    }
+
+    #[inline]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replace the text, it needs a measure again
+    pub fn set_text(&mut self, text: &str) {
+        self.text.clear();
+        self.text.push_str(text);
+        self.measured = false;
+    }
+
+    /// Break the text at '\n' and store the metrics
+    pub fn measure(&mut self, measure: &dyn TextMeasure<T>, font: FontID, size: T) {
+        self.lines = wrap(measure, font, size, &self.text, None);
+
+        let (w, h) = dimensions(measure, font, size, &self.lines);
+        self.dim = (w.to_f32(), h.to_f32());
+        self.ascent = measure.ascent(font, size).to_f32();
+        self.line = line_height(measure, font, size).to_f32();
+        self.measured = true;
+    }
+
+    /// Dimensions of the text of the last measure
+    pub fn dimensions(&self) -> Dimensions<T> {
+        (T::from_f32(self.dim.0), T::from_f32(self.dim.1))
+    }
+
+    /// Draw the text centered vertically in the boundaries
+    pub fn draw<CTX: Painter<T>>(
+        &self,
+        ctx: &mut CTX,
+        bounds: Boundaries<T>,
+        align: Align,
+        style: &Style<T>,
+        color: Color,
+    ) {
+        let (x, y) = (bounds.0.to_f32(), bounds.1.to_f32());
+
+        if !self.measured {
+            let size = style.font_size.to_f32();
+            for (n, text) in self.text.lines().enumerate() {
+                let pos = (T::from_f32(x), T::from_f32(y + size * (n + 1) as f32));
+                ctx.text(pos, style.font, style.font_size, text, color);
+            }
+
+            return;
+        }
+
+        let top = y + (bounds.3.to_f32() - self.dim.1) * 0.5;
+        for (n, line) in self.lines.iter().enumerate() {
+            let offset = align_offset(align, line.width, bounds.2).to_f32();
+            let pos = (
+                T::from_f32(x + offset),
+                T::from_f32(top + self.ascent + self.line * n as f32),
+            );
+
+            let text = &self.text[line.range.clone()];
+            ctx.text(pos, style.font, style.font_size, text, color);
+        }
+    }
+}
+
+/// Boundaries shrunk by a distance on every side
+pub(crate) fn inset<T: Scalar>(bounds: Boundaries<T>, distance: T) -> Boundaries<T> {
+    let d = distance.to_f32();
+    (
+        T::from_f32(bounds.0.to_f32() + d),
+        T::from_f32(bounds.1.to_f32() + d),
+        T::from_f32((bounds.2.to_f32() - d * 2.0).max(0.0)),
+        T::from_f32((bounds.3.to_f32() - d * 2.0).max(0.0)),
+    )
+}
+
+/// Boundaries from f32 values
+pub(crate) fn rect<T: Scalar>(x: f32, y: f32, w: f32, h: f32) -> Boundaries<T> {
+    (
+        T::from_f32(x),
+        T::from_f32(y),
+        T::from_f32(w),
+        T::from_f32(h),
+    )
+}
+
+/// Dimensions with a padding added on every side
+pub(crate) fn padded<T: Scalar>(dim: Dimensions<T>, padding: T) -> Dimensions<T> {
+    let p = padding.to_f32() * 2.0;
+    (
+        T::from_f32(dim.0.to_f32() + p),
+        T::from_f32(dim.1.to_f32() + p),
+    )
+}
+
+/// Check if a position is inside boundaries
+pub(crate) fn contains<T: Scalar>(bounds: Boundaries<T>, (x, y): (f32, f32)) -> bool {
+    let (bx, by) = (bounds.0.to_f32(), bounds.1.to_f32());
+    x >= bx && y >= by && x < bx + bounds.2.to_f32() && y < by + bounds.3.to_f32()
+}
+
+/// Absolute position of the mouse in f32
+pub(crate) fn mouse_pos<T>(mouse: &MouseState<T>) -> (f32, f32)
+where
+    T: Scalar + Default + Sub<Output = T>,
+{
+    let pos = mouse.absolute_pos();
+    (pos.0.to_f32(), pos.1.to_f32())
+}
+
+/// Height of the text of the style
+pub(crate) fn line<T: Scalar>(measure: &SharedMeasure<T>, style: &Style<T>) -> f32 {
+    line_height(&**measure, style.font, style.font_size).to_f32()
+}
+
+/// Where an arrow points
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// Triangle pointing to a direction in the center of the boundaries
+pub(crate) fn arrow<T: Scalar>(
+    bounds: Boundaries<T>,
+    size: f32,
+    direction: Direction,
+) -> [PathCommand<T>; 4] {
+    let cx = bounds.0.to_f32() + bounds.2.to_f32() * 0.5;
+    let cy = bounds.1.to_f32() + bounds.3.to_f32() * 0.5;
+    let (dx, dy) = match direction {
+        Direction::Up => (0.0, -1.0),
+        Direction::Down => (0.0, 1.0),
+        Direction::Left => (-1.0, 0.0),
+        Direction::Right => (1.0, 0.0),
+    };
+
+    // Point along the direction and across it, from the center
+    let (w, h) = (size * 0.25, size * 0.5);
+    let p = |along: f32, across: f32| {
+        (
+            T::from_f32(cx + dx * along - dy * across),
+            T::from_f32(cy + dy * along + dx * across),
+        )
+    };
+
+    [
+        PathCommand::MoveTo(p(-w, -h)),
+        PathCommand::LineTo(p(w, 0.0)),
+        PathCommand::LineTo(p(-w, h)),
+        PathCommand::Close,
+    ]
+}
+
+/// Press and release tracking of a clickable widget
+///
+/// A click is completed when the left button is released over the widget
+pub(crate) struct Press {
+    /// Pointer that pressed the widget
+    pointer: Option<PointerID>,
+    hover: bool,
+}
+
+impl Press {
+    pub(crate) fn new() -> Self {
+        Press {
+            pointer: None,
+            hover: false,
+        }
+    }
+
+    /// Check if the widget should be drawn as pressed
+    #[inline]
+    pub(crate) fn is_down(&self) -> bool {
+        self.pointer.is_some() && self.hover
+    }
+
+    /// Handle a mouse state and return true if a click was completed, the events
+    /// of other pointers are ignored while the widget is pressed
+    pub(crate) fn mouse<T>(
+        &mut self,
+        internal: &mut WidgetInternal<T>,
+        mouse: &MouseState<T>,
+    ) -> bool
+    where
+        T: Copy + Default + Sub<Output = T>,
+    {
+        let pointer = mouse.pointer();
+        if !internal.check(ENABLED) || self.pointer.is_some_and(|p| p != pointer) {
+            return false;
+        }
+
+        let hover = internal.check(HOVER);
+        let click = match mouse.m_type {
+            MouseType::Pressed(1) if self.pointer.is_none() => {
+                self.pointer = Some(pointer);
+                internal.on(GRAB | FOCUS | DRAW);
+
+                false
+            }
+            MouseType::Released(1) if self.pointer.is_some() => {
+                self.pointer = None;
+                internal.off(GRAB);
+                internal.on(DRAW);
+
+                hover
+            }
+            _ => {
+                // Pressed look changes when the cursor leaves or enters
+                if self.pointer.is_some() && hover != self.hover {
+                    internal.on(DRAW);
+                }
+
+                false
+            }
+        };
+
+        self.hover = hover;
+        click
+    }
+
+    /// Cancel the press, every pointer is gone
+    pub(crate) fn cancel(&mut self) {
+        self.pointer = None;
+        self.hover = false;
+    }
+
+    /// Cancel the press if the pointer pressed the widget, returns true if it did
+    pub(crate) fn pointer_out(&mut self, pointer: PointerID) -> bool {
+        let pressed = self.pointer == Some(pointer);
+        if pressed {
+            self.cancel();
+        }
+
+        pressed
+    }
+}
+
+/// Check if a key activates a focused widget, Space or Enter
+pub(crate) fn activates<T>(internal: &WidgetInternal<T>, key: KeyState) -> bool {
+    internal.check(ENABLED)
+        && matches!(
+            key,
+            KeyState::Pressed(SPACE, _) | KeyState::Pressed(ENTER, _)
+        )
+}
+
+/// Typed text for go to the items that start with it, it doesn't have a timer
+#[derive(Default)]
+pub(crate) struct TypeAhead {
+    typed: String,
+}
+
+impl TypeAhead {
+    /// Add a char and find the item that starts with the typed text, ignoring the
+    /// case. If no item matches, the char starts a new text from the next item, so
+    /// typing the same char goes through the items that start with it
+    pub(crate) fn push<F, S>(
+        &mut self,
+        count: usize,
+        current: usize,
+        c: char,
+        text: F,
+    ) -> Option<usize>
+    where
+        F: Fn(usize) -> S,
+        S: AsRef<str>,
+    {
+        let find = |typed: &str, start: usize| {
+            (0..count)
+                .map(|n| (start + n) % count)
+                .find(|&n| text(n).as_ref().to_lowercase().starts_with(typed))
+        };
+
+        let c: String = c.to_lowercase().collect();
+        self.typed.push_str(&c);
+
+        find(&self.typed, current).or_else(|| {
+            self.typed = c;
+            find(&self.typed, current + 1)
+        })
+    }
+
+    #[inline]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.typed.is_empty()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.typed.clear();
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::display::{Command, DisplayList};
+    use crate::group::{next_event, Group, GroupEvent, GroupID};
+    use crate::root::Root;
+    use crate::state::PRIMARY_POINTER;
+    use crate::text::Monospace;
+    use crate::widget::Widget;
+    use crate::widgets::popup::{PopupLayer, SharedPopups};
+
+    use std::rc::Rc;
+
+    /// A widget driven without a Root, mouse events set HOVER like a Container
+    pub(crate) struct Harness<W> {
+        pub(crate) widget: W,
+        pub(crate) internal: WidgetInternal<f32>,
+        pub(crate) measure: SharedMeasure<f32>,
+        mice: Vec<MouseState<f32>>,
+    }
+
+    impl<W: Widget<f32, DisplayList<f32>>> Harness<W> {
+        /// Measure and layout a widget, a character is as wide as the font size
+        pub(crate) fn new(mut widget: W, bounds: Boundaries<f32>) -> Self {
+            // Events left by other tests that ran on this thread
+            while next_event().is_some() {}
+
+            let (x, y, w, h) = bounds;
+            let mut internal = WidgetInternal::new_with((x, y), (w, h), WIDGET, Group::Any);
+            let measure: SharedMeasure<f32> = Rc::new(Monospace::new(1.0, 0.8, 0.2));
+
+            widget.measure(&mut internal, &measure);
+            widget.layout(&mut internal, true);
+
+            Harness {
+                widget,
+                internal,
+                measure,
+                mice: Vec::new(),
+            }
+        }
+
+        /// Index of the state of a pointer, it's created by the first use
+        fn index(&mut self, pointer: PointerID) -> usize {
+            match self.mice.iter().position(|m| m.pointer() == pointer) {
+                Some(n) => n,
+                None => {
+                    self.mice.push(MouseState::new_pointer(pointer));
+                    self.mice.len() - 1
+                }
+            }
+        }
+
+        /// State of a pointer, for set its modifiers or pressure
+        pub(crate) fn pointer(&mut self, pointer: PointerID) -> &mut MouseState<f32> {
+            let n = self.index(pointer);
+            &mut self.mice[n]
+        }
+
+        /// Send a mouse event of a pointer
+        pub(crate) fn mouse(
+            &mut self,
+            pointer: PointerID,
+            m_type: MouseType,
+            pos: (f32, f32),
+        ) -> &mut Self {
+            let n = self.index(pointer);
+            let mouse = &mut self.mice[n];
+            mouse.set_type(m_type);
+            mouse.set_position(pos);
+
+            self.internal.turn(HOVER, self.internal.p_intersect(pos));
+            self.widget.handle_mouse(&mut self.internal, mouse);
+
+            self
+        }
+
+        /// Press and release the left button of the primary pointer
+        pub(crate) fn click(&mut self, pos: (f32, f32)) -> &mut Self {
+            self.mouse(PRIMARY_POINTER, MouseType::Pressed(1), pos)
+                .mouse(PRIMARY_POINTER, MouseType::Released(1), pos)
+        }
+
+        /// Press and release a key
+        pub(crate) fn key(&mut self, code: u32, mods: u16) -> &mut Self {
+            self.widget
+                .handle_keys(&mut self.internal, KeyState::Pressed(code, mods));
+            self.widget
+                .handle_keys(&mut self.internal, KeyState::Released(code, mods));
+
+            self
+        }
+
+        /// Draw the widget and return the recorded commands
+        pub(crate) fn draw(&mut self) -> DisplayList<f32> {
+            let mut list = DisplayList::new();
+            let draw = self.widget.draw(&self.internal, &mut list);
+            self.internal.turn(DRAW, draw);
+
+            list
+        }
+    }
+
+    /// A Root with a popup layer of 200x200, a character is as wide as the font size
+    pub(crate) struct Window {
+        pub(crate) root: Root<f32, DisplayList<f32>>,
+        pub(crate) popups: SharedPopups<f32, DisplayList<f32>>,
+        mouse: MouseState<f32>,
+    }
+
+    impl Window {
+        pub(crate) fn new(layer: PopupLayer<f32, DisplayList<f32>>) -> Self {
+            let popups = layer.popups().clone();
+            let mut root = Root::new(Box::new(layer), (200.0, 200.0));
+            root.set_measure(Rc::new(Monospace::new(1.0, 0.8, 0.2)));
+
+            Window {
+                root,
+                popups,
+                mouse: MouseState::new_pointer(PRIMARY_POINTER),
+            }
+        }
+
+        /// Send a mouse event of the primary pointer
+        pub(crate) fn mouse(&mut self, m_type: MouseType, pos: (f32, f32)) -> &mut Self {
+            self.mouse.set_type(m_type);
+            self.mouse.set_position(pos);
+            self.root.handle_mouse(&self.mouse);
+            self
+        }
+
+        /// Press and release a key
+        pub(crate) fn key(&mut self, code: u32, mods: u16) -> &mut Self {
+            self.root.handle_keys(KeyState::Pressed(code, mods));
+            self.root.handle_keys(KeyState::Released(code, mods));
+            self
+        }
+
+        /// Count of open popups
+        pub(crate) fn open(&self) -> usize {
+            self.popups.borrow().len()
+        }
+
+        /// Draw the whole window and return the drawn texts
+        pub(crate) fn texts(&mut self) -> Vec<String> {
+            let mut list = DisplayList::new();
+            self.root.redraw();
+            self.root.draw(&mut list);
+
+            texts(&list).into_iter().map(String::from).collect()
+        }
+    }
+
+    /// Texts of the recorded commands in order
+    pub(crate) fn texts(list: &DisplayList<f32>) -> Vec<&str> {
+        list.commands()
+            .iter()
+            .filter_map(|command| match command {
+                Command::Text(_, _, _, text, _) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Consume the signals pushed to the event queue
+    pub(crate) fn signals() -> Vec<GroupID> {
+        std::iter::from_fn(next_event)
+            .filter_map(|event| match event {
+                GroupEvent::Signal(id) => Some(id),
+                GroupEvent::Layout => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn type_ahead() {
+        let items = ["Apple", "banana", "Avocado", "", "Blueberry"];
+        let mut typed = TypeAhead::default();
+        let push =
+            |typed: &mut TypeAhead, current, c| typed.push(items.len(), current, c, |n| items[n]);
+
+        assert_eq!(push(&mut typed, 0, 'b'), Some(1));
+        assert_eq!(push(&mut typed, 1, 'L'), Some(4));
+        // The same char goes through the items that start with it
+        typed.clear();
+        assert_eq!(push(&mut typed, 0, 'a'), Some(0));
+        assert_eq!(push(&mut typed, 0, 'a'), Some(2));
+        assert_eq!(push(&mut typed, 2, 'a'), Some(0));
+        assert_eq!(push(&mut typed, 0, 'z'), None);
+        assert!(!typed.is_empty());
+
+        typed.clear();
+        assert!(typed.is_empty());
+        assert_eq!(typed.push(0, 0, 'a', |n| items[n]), None);
+    }
+
+    #[test]
+    fn arrow_points() {
+        let tip = |direction| match arrow::<f32>((0.0, 0.0, 20.0, 20.0), 8.0, direction)[1] {
+            PathCommand::LineTo(p) => p,
+            _ => unreachable!(),
+        };
+
+        assert_eq!(tip(Direction::Up), (10.0, 8.0));
+        assert_eq!(tip(Direction::Down), (10.0, 12.0));
+        assert_eq!(tip(Direction::Left), (8.0, 10.0));
+        assert_eq!(tip(Direction::Right), (12.0, 10.0));
+    }
+}
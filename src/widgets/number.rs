@@ -0,0 +1,809 @@
+//! Numeric values: spin boxes and draggable fields
+
+use super::{arrow, contains, inset, line, mouse_pos, rect, Direction, Style};
+use crate::group::{push_event, GroupEvent, GroupID};
+use crate::math::Scalar;
+use crate::paint::{Color, Painter};
+use crate::state::keys::*;
+use crate::state::{Cursor, KeyState, MouseState, MouseType, PointerID};
+use crate::text::lines::{align_offset, Align};
+use crate::text::{Monospace, SharedMeasure};
+use crate::widget::flags::*;
+use crate::widget::{Boundaries, Dimensions, Widget, WidgetInternal};
+
+use std::cell::Cell;
+use std::ops::{Add, Sub};
+use std::rc::Rc;
+
+/// Limits and steps of a numeric value
+///
+/// The minimum must not be bigger than the maximum
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NumberRange {
+    pub min: f32,
+    pub max: f32,
+    /// Step of the arrow keys, the buttons and the wheel
+    pub step: f32,
+    /// Step with Shift
+    pub fine: f32,
+    /// Step of Page Up and Page Down
+    pub page: f32,
+    /// Decimals of the shown value
+    pub decimals: usize,
+}
+
+/// Decimals needed for show a step
+fn decimals(step: f32) -> usize {
+    let mut scaled = step.abs();
+    let mut decimals = 0;
+
+    while decimals < 6 && (scaled - scaled.round()).abs() > 0.001 {
+        scaled *= 10.0;
+        decimals += 1;
+    }
+
+    decimals
+}
+
+impl NumberRange {
+    /// Creates a range with a fine step of a tenth of the step and pages of ten
+    /// steps, it shows the decimals of the fine step
+    pub fn new(min: f32, max: f32, step: f32) -> Self {
+        let (min, max) = if min <= max { (min, max) } else { (max, min) };
+        let fine = step * 0.1;
+
+        NumberRange {
+            min,
+            max,
+            step,
+            fine,
+            page: step * 10.0,
+            decimals: decimals(fine),
+        }
+    }
+
+    #[inline]
+    pub fn clamp(&self, value: f32) -> f32 {
+        value.clamp(self.min, self.max)
+    }
+
+    /// Round a value to a multiple of a step from the minimum
+    pub fn snap(&self, value: f32, step: f32) -> f32 {
+        if step > 0.0 {
+            self.min + ((value - self.min) / step).round() * step
+        } else {
+            value
+        }
+    }
+
+    /// Position of a value between the limits, from 0 to 1
+    pub fn fraction(&self, value: f32) -> f32 {
+        if self.max > self.min {
+            (self.clamp(value) - self.min) / (self.max - self.min)
+        } else {
+            0.0
+        }
+    }
+
+    /// Value at a position between the limits, from 0 to 1
+    pub fn from_fraction(&self, fraction: f32) -> f32 {
+        self.min + (self.max - self.min) * fraction.clamp(0.0, 1.0)
+    }
+
+    /// Text of a value with the decimals of the range
+    pub fn format(&self, value: f32) -> String {
+        format!("{:.*}", self.decimals, value)
+    }
+
+    /// Value after a key: Up and Right increase it, Down and Left decrease it,
+    /// Shift makes it a fine step. Page Up, Page Down, Home and End are also keys
+    /// of stepping. None if the key doesn't step
+    pub fn key_step(&self, value: f32, key: &KeyState) -> Option<f32> {
+        let (code, mods) = match *key {
+            KeyState::Pressed(code, mods) => (code, mods),
+            KeyState::Released(..) => return None,
+        };
+
+        let step = if mods & SHIFT != 0 {
+            self.fine
+        } else {
+            self.step
+        };
+
+        let value = match code {
+            UP | RIGHT => value + step,
+            DOWN | LEFT => value - step,
+            PAGE_UP => value + self.page,
+            PAGE_DOWN => value - self.page,
+            HOME => self.min,
+            END => self.max,
+            _ => return None,
+        };
+
+        Some(self.clamp(self.snap(value, step)))
+    }
+}
+
+/// Rate of a relative drag: Shift makes it ten times slower and a pen makes it
+/// slower with less pressure
+pub(crate) fn drag_rate<T>(mouse: &MouseState<T>) -> f32
+where
+    T: Copy + Default + Sub<Output = T>,
+{
+    let rate = if mouse.check_modifiers(SHIFT) {
+        0.1
+    } else {
+        1.0
+    };
+
+    if mouse.has_pressure() {
+        rate * mouse.tablet_pressure().clamp(0.1, 1.0)
+    } else {
+        rate
+    }
+}
+
+/// Drag of a numeric value, it keeps the value without limits so the pointer
+/// must come back for change it again
+pub(crate) struct Drag {
+    /// Last position of the pointer on the axis of the drag
+    last: f32,
+    value: f32,
+}
+
+impl Drag {
+    pub(crate) fn new(pos: f32, value: f32) -> Self {
+        Drag { last: pos, value }
+    }
+
+    /// Move the pointer, returns the new value snapped to the step, or to the fine
+    /// step if the rate is slower
+    pub(crate) fn drag(&mut self, pos: f32, per_unit: f32, rate: f32, range: &NumberRange) -> f32 {
+        self.value += (pos - self.last) * per_unit * rate;
+        self.last = pos;
+
+        let step = if rate < 1.0 { range.fine } else { range.step };
+        range.snap(self.value, step)
+    }
+}
+
+/// Value, range and text of the numeric widgets
+pub(crate) struct Number<T> {
+    pub(crate) value: Rc<Cell<f32>>,
+    pub(crate) range: NumberRange,
+    signal: GroupID,
+    pub(crate) style: Style<T>,
+    measure: SharedMeasure<T>,
+    /// Text typed with the keyboard, it's applied with Enter or when the focus goes out
+    typed: Option<String>,
+}
+
+impl<T: Scalar> Number<T> {
+    pub(crate) fn new(value: Rc<Cell<f32>>, range: NumberRange, signal: GroupID) -> Self {
+        Number {
+            value,
+            range,
+            signal,
+            style: Style::default(),
+            measure: Rc::new(Monospace::new(0.6, 0.8, 0.2)),
+            typed: None,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn get(&self) -> f32 {
+        self.value.get()
+    }
+
+    /// Clamp and store a value, the signal is pushed if it changed
+    pub(crate) fn set(&mut self, internal: &mut WidgetInternal<T>, value: f32) {
+        let value = self.range.clamp(value);
+        if value != self.value.get() {
+            self.value.set(value);
+            push_event(GroupEvent::Signal(self.signal));
+        }
+
+        internal.on(DRAW);
+    }
+
+    /// Add some steps or fine steps to the value
+    pub(crate) fn add(&mut self, internal: &mut WidgetInternal<T>, steps: f32, fine: bool) {
+        let step = if fine {
+            self.range.fine
+        } else {
+            self.range.step
+        };
+
+        let value = self.range.snap(self.get() + steps * step, step);
+        self.typed = None;
+        self.set(internal, value);
+    }
+
+    /// Handle a key of stepping, returns false if it isn't one
+    pub(crate) fn step(&mut self, internal: &mut WidgetInternal<T>, key: &KeyState) -> bool {
+        if !internal.check(ENABLED) {
+            return false;
+        }
+
+        match self.range.key_step(self.get(), key) {
+            Some(value) => {
+                self.typed = None;
+                self.set(internal, value);
+
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Handle typing of a number, Enter applies it and Escape cancels it
+    pub(crate) fn typing(&mut self, internal: &mut WidgetInternal<T>, key: &KeyState) {
+        let (code, mods) = match *key {
+            KeyState::Pressed(code, mods) if internal.check(ENABLED) => (code, mods),
+            _ => return,
+        };
+
+        match code {
+            ENTER => self.apply(internal),
+            ESCAPE => self.typed = None,
+            BACKSPACE => {
+                if let Some(typed) = &mut self.typed {
+                    typed.pop();
+                }
+            }
+            _ => match to_char(code) {
+                Some(c)
+                    if mods & (CTRL | ALT | SUPER) == 0
+                        && (c.is_ascii_digit() || "+-.eE".contains(c)) =>
+                {
+                    self.typed.get_or_insert_with(String::new).push(c);
+                }
+                _ => return,
+            },
+        }
+
+        internal.on(DRAW);
+    }
+
+    /// Replace the value with the typed text if it's a number
+    pub(crate) fn apply(&mut self, internal: &mut WidgetInternal<T>) {
+        if let Some(typed) = self.typed.take() {
+            match typed.parse::<f32>() {
+                Ok(value) if value.is_finite() => self.set(internal, value),
+                _ => internal.on(DRAW),
+            }
+        }
+    }
+
+    #[inline]
+    pub(crate) fn is_typing(&self) -> bool {
+        self.typed.is_some()
+    }
+
+    /// Typed text or the value
+    pub(crate) fn text(&self) -> String {
+        match &self.typed {
+            Some(typed) => typed.clone(),
+            None => self.range.format(self.get()),
+        }
+    }
+
+    pub(crate) fn set_measure(&mut self, measure: &SharedMeasure<T>) {
+        self.measure = measure.clone();
+    }
+
+    pub(crate) fn advance(&self, text: &str) -> f32 {
+        let style = &self.style;
+        self.measure
+            .advance(style.font, style.font_size, text)
+            .to_f32()
+    }
+
+    #[inline]
+    pub(crate) fn line_height(&self) -> f32 {
+        line(&self.measure, &self.style)
+    }
+
+    /// Width of the widest value and the line height
+    pub(crate) fn text_dimensions(&self) -> (f32, f32) {
+        let range = &self.range;
+        let min = self.advance(&range.format(range.min));
+        let max = self.advance(&range.format(range.max));
+
+        (min.max(max), self.line_height())
+    }
+
+    /// Draw a text centered vertically, returns the right of the text
+    pub(crate) fn draw_text<CTX: Painter<T>>(
+        &self,
+        ctx: &mut CTX,
+        bounds: Boundaries<T>,
+        text: &str,
+        align: Align,
+        color: Color,
+    ) -> f32 {
+        let style = &self.style;
+        let width = self.advance(text);
+        let ascent = self.measure.ascent(style.font, style.font_size).to_f32();
+
+        let (x, y, h) = (bounds.0.to_f32(), bounds.1.to_f32(), bounds.3.to_f32());
+        let x = x + align_offset(align, T::from_f32(width), bounds.2).to_f32();
+        let baseline = y + (h - self.line_height()) * 0.5 + ascent;
+
+        let pos = (T::from_f32(x), T::from_f32(baseline));
+        ctx.text(pos, style.font, style.font_size, text, color);
+
+        x + width
+    }
+
+    /// Draw the caret after the typed text
+    pub(crate) fn draw_caret<CTX: Painter<T>>(&self, ctx: &mut CTX, bounds: Boundaries<T>, x: f32) {
+        let line = self.line_height();
+        let y = bounds.1.to_f32() + (bounds.3.to_f32() - line) * 0.5;
+        ctx.fill_rect(rect(x, y, 1.0, line), self.style.text);
+    }
+}
+
+/// Field with a number and two buttons for step it up and down
+///
+/// The buttons, the wheel and the keys of NumberRange::key_step change the value,
+/// digits can be typed too. The signal is pushed when the value changes, add the
+/// spin box with the SIGNAL flag and the signal Group for draw it when other widgets
+/// change the value
+pub struct SpinBox<T> {
+    number: Number<T>,
+    /// Pressed button, true is the up button
+    pressed: Option<bool>,
+}
+
+impl<T: Scalar> SpinBox<T> {
+    /// Creates a SpinBox with the shared value, its range and the signal ID
+    pub fn new(value: Rc<Cell<f32>>, range: NumberRange, signal: GroupID) -> Self {
+        SpinBox {
+            number: Number::new(value, range, signal),
+            pressed: None,
+        }
+    }
+
+    pub fn set_style(&mut self, style: Style<T>) {
+        self.number.style = style;
+    }
+
+    #[inline]
+    pub fn value(&self) -> f32 {
+        self.number.get()
+    }
+
+    #[inline]
+    pub fn range(&self) -> &NumberRange {
+        &self.number.range
+    }
+
+    /// Width of the column of buttons
+    fn buttons_width(&self) -> f32 {
+        let style = &self.number.style;
+        style.font_size.to_f32() + style.padding.to_f32()
+    }
+
+    /// Boundaries of the text, the up button and the down button
+    fn parts(&self, bounds: Boundaries<T>) -> [Boundaries<T>; 3] {
+        let style = &self.number.style;
+        let inner = inset(bounds, style.border);
+        let (x, y, w, h) = (
+            inner.0.to_f32(),
+            inner.1.to_f32(),
+            inner.2.to_f32(),
+            inner.3.to_f32(),
+        );
+
+        let bw = self.buttons_width().min(w);
+        let pad = style.padding.to_f32();
+        let half = (h * 0.5).floor();
+
+        [
+            rect(x + pad, y, (w - bw - pad * 2.0).max(0.0), h),
+            rect(x + w - bw, y, bw, half),
+            rect(x + w - bw, y + half, bw, h - half),
+        ]
+    }
+}
+
+impl<T, CTX> Widget<T, CTX> for SpinBox<T>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+    CTX: Painter<T>,
+{
+    fn draw(&mut self, internal: &WidgetInternal<T>, ctx: &mut CTX) -> bool {
+        let bounds = internal.boundaries_abs();
+        let number = &self.number;
+        let style = &number.style;
+        let [text, up, down] = self.parts(bounds);
+
+        ctx.fill_rect(bounds, style.window);
+        ctx.fill_round_rect(bounds, style.radius, style.outline_color(internal));
+        ctx.fill_round_rect(inset(bounds, style.border), style.radius, style.field);
+
+        let color = style.text_color(internal);
+        ctx.push_clip(text);
+        let end = number.draw_text(ctx, text, &number.text(), Align::Left, color);
+        if number.is_typing() && internal.check(FOCUS) {
+            number.draw_caret(ctx, text, end);
+        }
+        ctx.pop_clip();
+
+        let size = style.font_size.to_f32() * 0.6;
+        for (bounds, is_up) in [(up, true), (down, false)].iter().copied() {
+            let face = if self.pressed == Some(is_up) {
+                style.pressed
+            } else {
+                style.button
+            };
+
+            ctx.fill_rect(bounds, face);
+            ctx.fill_path(
+                &arrow(
+                    bounds,
+                    size,
+                    if is_up {
+                        Direction::Up
+                    } else {
+                        Direction::Down
+                    },
+                ),
+                color,
+            );
+        }
+
+        // Separators of the buttons
+        let (x, y, h) = (up.0.to_f32(), up.1.to_f32(), up.3.to_f32());
+        let border = style.border.to_f32();
+        let outline = style.outline_color(internal);
+        ctx.fill_rect(rect(x, y, border, h + down.3.to_f32()), outline);
+        ctx.fill_rect(rect(x, y + h, up.2.to_f32(), border), outline);
+
+        false
+    }
+
+    fn measure(&mut self, _: &mut WidgetInternal<T>, measure: &SharedMeasure<T>) {
+        self.number.set_measure(measure);
+    }
+
+    /// The shared value was changed by another widget
+    fn handle_signal(&mut self, internal: &mut WidgetInternal<T>, _: GroupID) {
+        internal.on(DRAW);
+    }
+
+    fn handle_mouse(&mut self, internal: &mut WidgetInternal<T>, mouse: &MouseState<T>) {
+        if !internal.check(ENABLED) {
+            return;
+        }
+
+        let fine = mouse.check_modifiers(SHIFT);
+        match mouse.m_type {
+            MouseType::Pressed(1) => {
+                internal.on(GRAB | FOCUS | DRAW);
+
+                let [_, up, down] = self.parts(internal.boundaries_abs());
+                let pos = mouse_pos(mouse);
+                self.pressed = if contains(up, pos) {
+                    Some(true)
+                } else if contains(down, pos) {
+                    Some(false)
+                } else {
+                    None
+                };
+
+                if let Some(up) = self.pressed {
+                    self.number.add(internal, if up { 1.0 } else { -1.0 }, fine);
+                }
+            }
+            MouseType::Released(1) => {
+                self.pressed = None;
+                internal.off(GRAB);
+                internal.on(DRAW);
+            }
+            MouseType::Wheel(delta) if delta != 0.0 => {
+                self.number.add(internal, delta.signum(), fine)
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_keys(&mut self, internal: &mut WidgetInternal<T>, key: KeyState) {
+        if !self.number.step(internal, &key) {
+            self.number.typing(internal, &key);
+        }
+    }
+
+    fn pointer_out(&mut self, internal: &mut WidgetInternal<T>, _: PointerID) {
+        self.pressed = None;
+        internal.off(GRAB);
+        internal.on(DRAW);
+    }
+
+    /// The typed text is applied
+    fn focus_out(&mut self, internal: &mut WidgetInternal<T>) {
+        self.number.apply(internal);
+        internal.on(DRAW);
+    }
+
+    fn min_dimensions(&self) -> Dimensions<T> {
+        let style = &self.number.style;
+        let (w, h) = self.number.text_dimensions();
+        let space = (style.border.to_f32() + style.padding.to_f32()) * 2.0;
+
+        (
+            T::from_f32(w + self.buttons_width() + space),
+            T::from_f32(h + space),
+        )
+    }
+}
+
+/// Number with a label that changes by dragging it left or right
+///
+/// Every unit of distance is a step, with Shift it's a fine step and a pen with
+/// less pressure moves it slower. The wheel and the keys of NumberRange::key_step
+/// change the value too, and digits can be typed when it's focused
+pub struct NumberField<T> {
+    number: Number<T>,
+    label: String,
+    drag: Option<Drag>,
+}
+
+impl<T: Scalar> NumberField<T> {
+    /// Creates a NumberField with a label, the shared value, its range and the
+    /// signal ID
+    pub fn new(label: &str, value: Rc<Cell<f32>>, range: NumberRange, signal: GroupID) -> Self {
+        NumberField {
+            number: Number::new(value, range, signal),
+            label: String::from(label),
+            drag: None,
+        }
+    }
+
+    pub fn set_style(&mut self, style: Style<T>) {
+        self.number.style = style;
+    }
+
+    #[inline]
+    pub fn value(&self) -> f32 {
+        self.number.get()
+    }
+
+    #[inline]
+    pub fn range(&self) -> &NumberRange {
+        &self.number.range
+    }
+
+    /// Label and value as they are drawn
+    fn text(&self) -> String {
+        if self.label.is_empty() {
+            self.number.text()
+        } else {
+            format!("{}: {}", self.label, self.number.text())
+        }
+    }
+}
+
+impl<T, CTX> Widget<T, CTX> for NumberField<T>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+    CTX: Painter<T>,
+{
+    fn draw(&mut self, internal: &WidgetInternal<T>, ctx: &mut CTX) -> bool {
+        let bounds = internal.boundaries_abs();
+        let number = &self.number;
+        let style = &number.style;
+
+        let face = if self.drag.is_some() {
+            style.pressed
+        } else if internal.check(HOVER) && internal.check(ENABLED) {
+            style.hover
+        } else {
+            style.button
+        };
+
+        ctx.fill_rect(bounds, style.window);
+        ctx.fill_round_rect(bounds, style.radius, style.outline_color(internal));
+
+        let inner = inset(bounds, style.border);
+        ctx.fill_round_rect(inner, style.radius, face);
+
+        // Position of the value between the limits
+        let fraction = number.range.fraction(number.get());
+        let (x, y, w, h) = (
+            inner.0.to_f32(),
+            inner.1.to_f32(),
+            inner.2.to_f32(),
+            inner.3.to_f32(),
+        );
+        ctx.fill_round_rect(rect(x, y, w * fraction, h), style.radius, style.selection);
+
+        let text = inset(inner, style.padding);
+        let color = style.text_color(internal);
+        ctx.push_clip(text);
+        let end = number.draw_text(ctx, text, &self.text(), Align::Center, color);
+        if number.is_typing() && internal.check(FOCUS) {
+            number.draw_caret(ctx, text, end);
+        }
+        ctx.pop_clip();
+
+        false
+    }
+
+    fn measure(&mut self, _: &mut WidgetInternal<T>, measure: &SharedMeasure<T>) {
+        self.number.set_measure(measure);
+    }
+
+    /// The shared value was changed by another widget
+    fn handle_signal(&mut self, internal: &mut WidgetInternal<T>, _: GroupID) {
+        internal.on(DRAW);
+    }
+
+    fn handle_mouse(&mut self, internal: &mut WidgetInternal<T>, mouse: &MouseState<T>) {
+        if !internal.check(ENABLED) {
+            return;
+        }
+
+        internal.set_cursor(Cursor::ResizeH);
+
+        let x = mouse_pos(mouse).0;
+        match mouse.m_type {
+            MouseType::Pressed(1) => {
+                self.drag = Some(Drag::new(x, self.number.get()));
+                internal.on(GRAB | FOCUS | DRAW);
+            }
+            MouseType::Released(1) if self.drag.is_some() => {
+                self.drag = None;
+                internal.off(GRAB);
+                internal.on(DRAW);
+            }
+            MouseType::CursorMoved => {
+                if let Some(drag) = &mut self.drag {
+                    let range = &self.number.range;
+                    let value = drag.drag(x, range.step, drag_rate(mouse), range);
+
+                    self.number.typed = None;
+                    self.number.set(internal, value);
+                }
+            }
+            MouseType::Wheel(delta) if delta != 0.0 => {
+                let fine = mouse.check_modifiers(SHIFT);
+                self.number.add(internal, delta.signum(), fine);
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_keys(&mut self, internal: &mut WidgetInternal<T>, key: KeyState) {
+        if !self.number.step(internal, &key) {
+            self.number.typing(internal, &key);
+        }
+    }
+
+    fn pointer_out(&mut self, internal: &mut WidgetInternal<T>, _: PointerID) {
+        self.drag = None;
+        internal.off(GRAB);
+        internal.on(DRAW);
+    }
+
+    /// The typed text is applied
+    fn focus_out(&mut self, internal: &mut WidgetInternal<T>) {
+        self.number.apply(internal);
+        internal.on(DRAW);
+    }
+
+    fn min_dimensions(&self) -> Dimensions<T> {
+        let style = &self.number.style;
+        let (w, h) = self.number.text_dimensions();
+        let label = if self.label.is_empty() {
+            0.0
+        } else {
+            self.number.advance(&self.label) + self.number.advance(": ")
+        };
+
+        let space = (style.border.to_f32() + style.padding.to_f32()) * 2.0;
+        (T::from_f32(label + w + space), T::from_f32(h + space))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::DisplayList;
+    use crate::state::PRIMARY_POINTER;
+    use crate::widgets::tests::{signals, Harness};
+
+    fn range() -> NumberRange {
+        NumberRange::new(0.0, 100.0, 1.0)
+    }
+
+    #[test]
+    fn number_range() {
+        let range = NumberRange::new(10.0, -10.0, 0.5);
+        assert_eq!(
+            (range.min, range.max, range.fine, range.page),
+            (-10.0, 10.0, 0.05, 5.0)
+        );
+        assert_eq!(range.format(1.0), "1.00");
+
+        assert_eq!(range.snap(1.3, range.step), 1.5);
+        assert_eq!(range.fraction(20.0), 1.0);
+        assert_eq!(range.from_fraction(0.25), -5.0);
+
+        let key = |code, mods| range.key_step(9.8, &KeyState::Pressed(code, mods));
+        assert_eq!(key(UP, 0), Some(10.0));
+        assert_eq!(key(DOWN, SHIFT), Some(9.75));
+        assert_eq!(key(PAGE_DOWN, 0), Some(5.0));
+        assert_eq!(key(HOME, 0), Some(-10.0));
+        assert_eq!(key(ENTER, 0), None);
+    }
+
+    #[test]
+    fn rates() {
+        let mut mouse = MouseState::<f32>::new();
+        assert_eq!(drag_rate(&mouse), 1.0);
+
+        mouse.set_modifiers(SHIFT);
+        assert_eq!(drag_rate(&mouse), 0.1);
+        mouse.set_pressure(0.5);
+        assert_eq!(drag_rate(&mouse), 0.05);
+        // A pen barely touching is still moving
+        mouse.set_pressure(0.0);
+        assert!((drag_rate(&mouse) - 0.01).abs() < 1e-6);
+    }
+
+    #[test]
+    fn spin_box() {
+        let value = Rc::new(Cell::new(50.0));
+        let spin = SpinBox::new(value.clone(), range(), 3);
+        // The up button is at (44, 1, 15, 11) and the down button below it
+        let mut h = Harness::new(spin, (0.0, 0.0, 60.0, 24.0));
+
+        h.click((50.0, 5.0)).click((50.0, 5.0));
+        assert_eq!(value.get(), 52.0);
+        h.click((50.0, 18.0));
+        assert_eq!(value.get(), 51.0);
+        assert_eq!(signals(), [3]);
+
+        // Typing is applied with Enter or when the focus goes out
+        h.key('2' as u32, 0).key('x' as u32, 0).key('5' as u32, 0);
+        assert_eq!(h.widget.number.text(), "25");
+        h.key(ENTER, 0);
+        assert_eq!(value.get(), 25.0);
+
+        h.key('-' as u32, 0).key(ESCAPE, 0);
+        assert_eq!(h.widget.number.text(), "25.0");
+        h.key('1' as u32, 0).key('e' as u32, 0).key('3' as u32, 0);
+        Widget::<f32, DisplayList<f32>>::focus_out(&mut h.widget, &mut h.internal);
+        assert_eq!(value.get(), 100.0);
+        assert_eq!(signals(), [3]);
+    }
+
+    #[test]
+    fn number_field() {
+        let value = Rc::new(Cell::new(10.0));
+        let field = NumberField::new("Size", value.clone(), range(), 5);
+        let mut h = Harness::new(field, (0.0, 0.0, 100.0, 24.0));
+        assert_eq!(h.widget.text(), "Size: 10.0");
+
+        // Every unit is a step
+        h.mouse(PRIMARY_POINTER, MouseType::Pressed(1), (10.0, 10.0));
+        h.mouse(PRIMARY_POINTER, MouseType::CursorMoved, (15.0, 10.0));
+        assert_eq!(value.get(), 15.0);
+        h.mouse(PRIMARY_POINTER, MouseType::CursorMoved, (-40.0, 10.0));
+        assert_eq!(value.get(), 0.0);
+
+        // The drag isn't clamped, the pointer must come back
+        h.mouse(PRIMARY_POINTER, MouseType::CursorMoved, (-35.0, 10.0));
+        assert_eq!(value.get(), 0.0);
+        h.mouse(PRIMARY_POINTER, MouseType::CursorMoved, (5.0, 10.0));
+        assert_eq!(value.get(), 5.0);
+        h.mouse(PRIMARY_POINTER, MouseType::Released(1), (5.0, 10.0));
+        assert_eq!(signals(), [5]);
+
+        h.internal.off(ENABLED);
+        h.mouse(PRIMARY_POINTER, MouseType::Wheel(1.0), (10.0, 10.0));
+        assert_eq!(value.get(), 5.0);
+    }
+}
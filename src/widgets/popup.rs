@@ -0,0 +1,588 @@
+//! Popups drawn over the widgets
+//!
+//! A widget can't draw outside its boundaries, so menus and dropdowns are opened
+//! in a Popups stack shared with a PopupLayer. The PopupLayer wraps the top widget
+//! of the tree, it draws the popups after the widgets and sends them the mouse
+//! and the keys before the widgets
+
+use super::menu::Menu;
+use super::mouse_pos;
+use crate::damage::DamageRegion;
+use crate::group::{push_event, GroupEvent, GroupID};
+use crate::math::Scalar;
+use crate::paint::Painter;
+use crate::state::keys::ESCAPE;
+use crate::state::{KeyState, MouseState, MouseType, PointerID, TextInput};
+use crate::text::SharedMeasure;
+use crate::widget::flags::*;
+use crate::widget::{Boundaries, Dimensions, Widget, WidgetInternal};
+
+use std::cell::RefCell;
+use std::ops::{Add, Sub};
+use std::rc::Rc;
+
+/// Popups shared by a PopupLayer and the widgets that open them
+pub type SharedPopups<T, CTX> = Rc<RefCell<Popups<T, CTX>>>;
+
+/// Side of the anchor where a popup is opened
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Side {
+    /// Under the anchor, or over it if there is no space
+    Below,
+    /// At the right of the anchor, or at the left if there is no space
+    Right,
+}
+
+/// Boundaries of a popup next to an anchor, it's moved inside the area
+pub fn anchor_bounds<T: Scalar>(
+    anchor: Boundaries<T>,
+    side: Side,
+    (w, h): (f32, f32),
+    area: Boundaries<T>,
+) -> Boundaries<T> {
+    let (ax, ay, aw, ah) = (
+        anchor.0.to_f32(),
+        anchor.1.to_f32(),
+        anchor.2.to_f32(),
+        anchor.3.to_f32(),
+    );
+    let (left, top) = (area.0.to_f32(), area.1.to_f32());
+    let (right, bottom) = (left + area.2.to_f32(), top + area.3.to_f32());
+
+    let (x, y) = match side {
+        Side::Below if ay + ah + h > bottom && ay - h >= top => (ax, ay - h),
+        Side::Below => (ax, ay + ah),
+        Side::Right if ax + aw + w > right && ax - w >= left => (ax - w, ay),
+        Side::Right => (ax + aw, ay),
+    };
+
+    let (w, h) = (w.min(right - left), h.min(bottom - top));
+    let x = x.min(right - w).max(left);
+    let y = y.min(bottom - h).max(top);
+
+    (
+        T::from_f32(x),
+        T::from_f32(y),
+        T::from_f32(w),
+        T::from_f32(h),
+    )
+}
+
+/// Request of a popup after an event
+pub enum PopupAction<T, CTX> {
+    /// Nothing changed
+    None,
+    /// The popup must be drawn again
+    Draw,
+    /// Open a popup over this one, the popups over this one are closed before
+    Open(Box<dyn Popup<T, CTX>>),
+    /// Close the popups over this one
+    CloseOver,
+    /// Close this popup and the popups over it
+    Close,
+    /// Close every popup
+    CloseAll,
+    /// The key isn't used, it's sent to the owner of the popups
+    Ignore,
+}
+
+/// Content of a popup
+///
+/// The popup receives the mouse when the pointer is over it and the keys when
+/// it's the last opened popup
+pub trait Popup<T, CTX> {
+    /// Absolute boundaries of the popup
+    fn bounds(&self) -> Boundaries<T>;
+    /// Calculate the boundaries inside the area of the layer, it's called when
+    /// the popup is opened and when the layer is resized
+    fn place(&mut self, area: Boundaries<T>);
+    fn draw(&mut self, ctx: &mut CTX);
+    fn handle_mouse(&mut self, mouse: &MouseState<T>) -> PopupAction<T, CTX>;
+    fn handle_keys(&mut self, key: &KeyState) -> PopupAction<T, CTX>;
+    /// The pointer left the popup, returns true if it must be drawn again
+    fn hover_out(&mut self) -> bool {
+        false
+    }
+    /// Store the text measurement of the Root, it's called before place
+    fn measure(&mut self, _: &SharedMeasure<T>) {}
+}
+
+/// Stack of open popups, the last one is over the others
+///
+/// Every popup of the stack has the same owner, a signal ID that is pushed when
+/// the popups are closed and when they don't use a key
+pub struct Popups<T, CTX> {
+    stack: Vec<Box<dyn Popup<T, CTX>>>,
+    owner: Option<GroupID>,
+    /// Key that the popups didn't use
+    key: Option<(u32, u16)>,
+    area: Boundaries<T>,
+    measure: Option<SharedMeasure<T>>,
+    /// The popups must be drawn again
+    changed: bool,
+    /// Boundaries of the closed popups, the widgets under them must be drawn again
+    closed: Vec<Boundaries<T>>,
+}
+
+impl<T: Scalar + Default, CTX> Popups<T, CTX> {
+    pub fn new() -> Self {
+        Popups {
+            stack: Vec::new(),
+            owner: None,
+            key: None,
+            area: Default::default(),
+            measure: None,
+            changed: false,
+            closed: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Signal ID of the widget that opened the popups
+    #[inline]
+    pub fn owner(&self) -> Option<GroupID> {
+        self.owner
+    }
+
+    /// Close the popups and open a popup of an owner
+    pub fn open(&mut self, popup: Box<dyn Popup<T, CTX>>, owner: Option<GroupID>) {
+        self.close_all();
+        self.owner = owner;
+        self.push(popup);
+    }
+
+    /// Open a popup over the others
+    pub fn push(&mut self, mut popup: Box<dyn Popup<T, CTX>>) {
+        if let Some(measure) = &self.measure {
+            popup.measure(measure);
+        }
+
+        popup.place(self.area);
+        self.stack.push(popup);
+        self.changed = true;
+    }
+
+    /// Close the popups from a level, the owner is notified when every popup is closed
+    pub fn close(&mut self, level: usize) {
+        if level >= self.stack.len() {
+            return;
+        }
+
+        let closed = self.stack.drain(level..).map(|popup| popup.bounds());
+        self.closed.extend(closed);
+        self.changed = true;
+
+        if self.stack.is_empty() {
+            if let Some(owner) = self.owner.take() {
+                push_event(GroupEvent::Signal(owner));
+            }
+        }
+    }
+
+    pub fn close_all(&mut self) {
+        self.close(0);
+    }
+
+    /// Take the key that the popups didn't use, the owner reads it when it
+    /// receives its signal
+    pub fn take_key(&mut self) -> Option<(u32, u16)> {
+        self.key.take()
+    }
+
+    /// Apply the action of the popup of a level
+    fn apply(&mut self, level: usize, action: PopupAction<T, CTX>, key: Option<(u32, u16)>) {
+        match action {
+            PopupAction::None => {}
+            PopupAction::Draw => self.changed = true,
+            PopupAction::Open(popup) => {
+                self.close(level + 1);
+                self.push(popup);
+            }
+            PopupAction::CloseOver => self.close(level + 1),
+            PopupAction::Close => self.close(level),
+            PopupAction::CloseAll => self.close_all(),
+            PopupAction::Ignore => {
+                if let (Some(owner), Some(key)) = (self.owner, key) {
+                    self.key = Some(key);
+                    push_event(GroupEvent::Signal(owner));
+                }
+            }
+        }
+    }
+
+    /// Last popup under a position
+    fn popup_at(&self, pos: (f32, f32)) -> Option<usize> {
+        self.stack.iter().rposition(|popup| {
+            let (x, y, w, h) = popup.bounds();
+            let (x, y) = (x.to_f32(), y.to_f32());
+
+            pos.0 >= x && pos.1 >= y && pos.0 < x + w.to_f32() && pos.1 < y + h.to_f32()
+        })
+    }
+}
+
+impl<T: Scalar + Default, CTX> Default for Popups<T, CTX> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Top widget that draws the popups over the content
+///
+/// The content shares the WidgetInternal of the layer. While a popup is open, the
+/// keys go to the last popup and a press outside the popups closes them without
+/// reaching the content. When nothing is open, the shortcuts of the added menus
+/// are checked before sending the keys to the content
+pub struct PopupLayer<T, CTX> {
+    content: Box<dyn Widget<T, CTX>>,
+    popups: SharedPopups<T, CTX>,
+    shortcuts: Vec<Rc<Menu>>,
+    /// Popups drawn on the last frame
+    drawn: Vec<Boundaries<T>>,
+}
+
+impl<T, CTX> PopupLayer<T, CTX>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+{
+    pub fn new(content: Box<dyn Widget<T, CTX>>, popups: SharedPopups<T, CTX>) -> Self {
+        PopupLayer {
+            content,
+            popups,
+            shortcuts: Vec::new(),
+            drawn: Vec::new(),
+        }
+    }
+
+    /// Activate the items of a menu and its submenus with their shortcuts
+    pub fn add_shortcuts(&mut self, menu: Rc<Menu>) {
+        self.shortcuts.push(menu);
+    }
+
+    #[inline]
+    pub fn popups(&self) -> &SharedPopups<T, CTX> {
+        &self.popups
+    }
+
+    /// Request a draw if the popups changed, the content under the closed popups
+    /// is exposed and their boundaries are added to the damage
+    fn sync(&mut self, internal: &mut WidgetInternal<T>) {
+        let mut popups = self.popups.borrow_mut();
+
+        for bounds in popups.closed.drain(..) {
+            self.content.expose(internal, bounds);
+            self.drawn.push(bounds);
+        }
+        if popups.changed {
+            internal.on(DRAW);
+        }
+    }
+}
+
+impl<T, CTX> Widget<T, CTX> for PopupLayer<T, CTX>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+    CTX: Painter<T>,
+{
+    fn draw(&mut self, internal: &WidgetInternal<T>, ctx: &mut CTX) -> bool {
+        let draw = self.content.draw(internal, ctx);
+
+        let mut popups = self.popups.borrow_mut();
+        for popup in popups.stack.iter_mut() {
+            let bounds = popup.bounds();
+
+            ctx.push_clip(bounds);
+            popup.draw(ctx);
+            ctx.pop_clip();
+
+            self.drawn.push(bounds);
+        }
+
+        popups.changed = false;
+        draw
+    }
+
+    fn update(&mut self, internal: &mut WidgetInternal<T>) {
+        self.content.update(internal);
+        self.sync(internal);
+    }
+
+    fn layout(&mut self, internal: &mut WidgetInternal<T>, complete: bool) {
+        self.content.layout(internal, complete);
+
+        let area = internal.boundaries_abs();
+        let mut popups = self.popups.borrow_mut();
+        if popups.area != area {
+            popups.area = area;
+            popups.stack.iter_mut().for_each(|popup| popup.place(area));
+        }
+    }
+
+    fn measure(&mut self, internal: &mut WidgetInternal<T>, measure: &SharedMeasure<T>) {
+        self.content.measure(internal, measure);
+
+        let mut popups = self.popups.borrow_mut();
+        popups.measure = Some(measure.clone());
+        popups
+            .stack
+            .iter_mut()
+            .for_each(|popup| popup.measure(measure));
+    }
+
+    fn handle_signal(&mut self, internal: &mut WidgetInternal<T>, group: GroupID) {
+        self.content.handle_signal(internal, group);
+        self.sync(internal);
+    }
+
+    fn handle_mouse(&mut self, internal: &mut WidgetInternal<T>, mouse: &MouseState<T>) {
+        let pos = mouse_pos(mouse);
+
+        let mut popups = self.popups.borrow_mut();
+        if !popups.is_empty() {
+            match popups.popup_at(pos) {
+                Some(level) => {
+                    let action = popups.stack[level].handle_mouse(mouse);
+                    popups.apply(level, action, None);
+                    drop(popups);
+
+                    // The widgets under the popup aren't hovered
+                    self.content.pointer_out(internal, mouse.pointer());
+                    self.sync(internal);
+                    return;
+                }
+                None => {
+                    let hover = popups
+                        .stack
+                        .iter_mut()
+                        .fold(false, |h, p| p.hover_out() | h);
+                    popups.changed |= hover;
+
+                    if let MouseType::Pressed(_) = mouse.m_type {
+                        popups.close_all();
+                        drop(popups);
+
+                        self.sync(internal);
+                        return;
+                    }
+                }
+            }
+        }
+
+        drop(popups);
+        self.content.handle_mouse(internal, mouse);
+        self.sync(internal);
+    }
+
+    fn handle_keys(&mut self, internal: &mut WidgetInternal<T>, key: KeyState) {
+        let mut popups = self.popups.borrow_mut();
+
+        if let Some(level) = popups.len().checked_sub(1) {
+            if let KeyState::Pressed(code, mods) = key {
+                let action = match popups.stack[level].handle_keys(&key) {
+                    PopupAction::Ignore if code == ESCAPE => PopupAction::Close,
+                    action => action,
+                };
+
+                popups.apply(level, action, Some((code, mods)));
+            }
+
+            drop(popups);
+            self.sync(internal);
+            return;
+        }
+
+        drop(popups);
+        if self
+            .shortcuts
+            .iter()
+            .any(|menu| menu.activate_shortcut(&key))
+        {
+            return;
+        }
+
+        self.content.handle_keys(internal, key);
+        self.sync(internal);
+    }
+
+    fn handle_text(&mut self, internal: &mut WidgetInternal<T>, text: TextInput) {
+        if self.popups.borrow().is_empty() {
+            self.content.handle_text(internal, text);
+            self.sync(internal);
+        }
+    }
+
+    fn step_focus(&mut self, internal: &mut WidgetInternal<T>, back: bool) -> bool {
+        let focus = self.content.step_focus(internal, back);
+        self.sync(internal);
+
+        focus
+    }
+
+    fn hover_in(&mut self, internal: &mut WidgetInternal<T>) {
+        self.content.hover_in(internal);
+    }
+
+    fn hover_out(&mut self, internal: &mut WidgetInternal<T>) {
+        self.content.hover_out(internal);
+
+        let mut popups = self.popups.borrow_mut();
+        let hover = popups
+            .stack
+            .iter_mut()
+            .fold(false, |h, p| p.hover_out() | h);
+        popups.changed |= hover;
+        drop(popups);
+
+        self.sync(internal);
+    }
+
+    fn pointer_out(&mut self, internal: &mut WidgetInternal<T>, pointer: PointerID) {
+        self.content.pointer_out(internal, pointer);
+        self.sync(internal);
+    }
+
+    fn focus_out(&mut self, internal: &mut WidgetInternal<T>) {
+        self.content.focus_out(internal);
+        self.sync(internal);
+    }
+
+    fn pending(&self) -> usize {
+        self.content.pending()
+    }
+
+    /// Add the damage of the content and the drawn popups
+    fn damage(&mut self, internal: &WidgetInternal<T>, region: &mut DamageRegion<T>) {
+        self.content.damage(internal, region);
+
+        for bounds in self.drawn.drain(..) {
+            region.add(bounds);
+        }
+    }
+
+    fn min_dimensions(&self) -> Dimensions<T> {
+        self.content.min_dimensions()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::{Command, DisplayList};
+    use crate::state::keys::ENTER;
+    use crate::state::PRIMARY_POINTER;
+    use crate::widgets::tests::{signals, Harness};
+
+    type Exposed = Rc<RefCell<Vec<Boundaries<f32>>>>;
+
+    /// Content that records the exposed areas
+    struct Content(Exposed);
+
+    impl Widget<f32, DisplayList<f32>> for Content {
+        fn draw(&mut self, _: &WidgetInternal<f32>, _: &mut DisplayList<f32>) -> bool {
+            false
+        }
+
+        fn expose(&mut self, internal: &mut WidgetInternal<f32>, area: Boundaries<f32>) {
+            self.0.borrow_mut().push(area);
+            internal.on(DRAW);
+        }
+
+        /// It doesn't paint anything
+        fn damage(&mut self, _: &WidgetInternal<f32>, _: &mut DamageRegion<f32>) {}
+    }
+
+    /// Popup of fixed boundaries that closes itself with a press and ignores keys
+    struct Panel(Boundaries<f32>);
+
+    impl Popup<f32, DisplayList<f32>> for Panel {
+        fn bounds(&self) -> Boundaries<f32> {
+            self.0
+        }
+
+        fn place(&mut self, _: Boundaries<f32>) {}
+
+        fn draw(&mut self, ctx: &mut DisplayList<f32>) {
+            ctx.fill_rect(self.0, crate::paint::Color::WHITE);
+        }
+
+        fn handle_mouse(&mut self, mouse: &MouseState<f32>) -> PopupAction<f32, DisplayList<f32>> {
+            match mouse.m_type {
+                MouseType::Pressed(_) => PopupAction::Close,
+                _ => PopupAction::None,
+            }
+        }
+
+        fn handle_keys(&mut self, _: &KeyState) -> PopupAction<f32, DisplayList<f32>> {
+            PopupAction::Ignore
+        }
+    }
+
+    #[test]
+    fn anchor() {
+        let area = (0.0, 0.0, 100.0, 100.0);
+        let place = |anchor, side| anchor_bounds(anchor, side, (30.0, 40.0), area);
+
+        assert_eq!(
+            place((10.0, 10.0, 20.0, 10.0), Side::Below),
+            (10.0, 20.0, 30.0, 40.0)
+        );
+        // Over the anchor without space below, and moved inside the area
+        assert_eq!(
+            place((80.0, 70.0, 20.0, 10.0), Side::Below),
+            (70.0, 30.0, 30.0, 40.0)
+        );
+        assert_eq!(
+            place((10.0, 10.0, 20.0, 10.0), Side::Right),
+            (30.0, 10.0, 30.0, 40.0)
+        );
+        assert_eq!(
+            place((60.0, 10.0, 20.0, 10.0), Side::Right),
+            (30.0, 10.0, 30.0, 40.0)
+        );
+        assert_eq!(
+            place((10.0, 80.0, 20.0, 10.0), Side::Right),
+            (30.0, 60.0, 30.0, 40.0)
+        );
+    }
+
+    #[test]
+    fn layer() {
+        let exposed = Exposed::default();
+        let popups = Rc::new(RefCell::new(Popups::new()));
+        let layer = PopupLayer::new(Box::new(Content(exposed.clone())), popups.clone());
+        let mut h = Harness::new(layer, (0.0, 0.0, 100.0, 100.0));
+
+        popups
+            .borrow_mut()
+            .open(Box::new(Panel((10.0, 10.0, 30.0, 30.0))), Some(4));
+        let list = h.draw();
+        assert!(list
+            .commands()
+            .contains(&Command::PushClip((10.0, 10.0, 30.0, 30.0))));
+
+        // Unused keys are sent to the owner
+        h.key(ENTER, 0);
+        assert_eq!(popups.borrow_mut().take_key(), Some((ENTER, 0)));
+        assert_eq!(signals(), [4]);
+
+        // The content under a closed popup is drawn again
+        h.mouse(PRIMARY_POINTER, MouseType::Pressed(1), (20.0, 20.0));
+        assert!(popups.borrow().is_empty());
+        assert_eq!(*exposed.borrow(), [(10.0, 10.0, 30.0, 30.0)]);
+        assert!(h.internal.check(DRAW));
+        assert_eq!(signals(), [4]);
+
+        let mut region = DamageRegion::new();
+        h.widget.damage(&h.internal, &mut region);
+        assert_eq!(region.rects(), [(10.0, 10.0, 30.0, 30.0)]);
+    }
+}
@@ -0,0 +1,301 @@
+//! Sliders
+
+use super::number::{drag_rate, Drag, Number, NumberRange};
+use super::{inset, mouse_pos, rect, Orientation, Style};
+use crate::group::GroupID;
+use crate::math::Scalar;
+use crate::paint::Painter;
+use crate::state::keys::SHIFT;
+use crate::state::{KeyState, MouseState, MouseType, PointerID};
+use crate::widget::flags::*;
+use crate::widget::{Boundaries, Dimensions, Widget, WidgetInternal};
+
+use std::cell::Cell;
+use std::ops::{Add, Sub};
+use std::rc::Rc;
+
+/// Track with a knob that selects a value between the limits of a range
+///
+/// Pressing the track moves the knob to the pointer and dragging follows it. With
+/// Shift the drag is relative and ten times slower, and a pen with less pressure
+/// makes it slower too. A vertical slider increases upwards. The wheel and the keys
+/// of NumberRange::key_step change the value when it's focused
+pub struct Slider<T> {
+    number: Number<T>,
+    orientation: Orientation,
+    drag: Option<Drag>,
+}
+
+impl<T: Scalar> Slider<T> {
+    /// Creates a Slider with the shared value, its range, the orientation and the
+    /// signal ID pushed when the value changes
+    pub fn new(
+        value: Rc<Cell<f32>>,
+        range: NumberRange,
+        orientation: Orientation,
+        signal: GroupID,
+    ) -> Self {
+        Slider {
+            number: Number::new(value, range, signal),
+            orientation,
+            drag: None,
+        }
+    }
+
+    pub fn set_style(&mut self, style: Style<T>) {
+        self.number.style = style;
+    }
+
+    #[inline]
+    pub fn value(&self) -> f32 {
+        self.number.get()
+    }
+
+    #[inline]
+    pub fn range(&self) -> &NumberRange {
+        &self.number.range
+    }
+
+    /// Side of the knob
+    fn knob(&self) -> f32 {
+        self.number.style.font_size.to_f32()
+    }
+
+    /// Start and length of the path of the knob center, and the center on the
+    /// other axis. The start is the minimum value
+    fn track(&self, bounds: Boundaries<T>) -> (f32, f32, f32) {
+        let (x, y, w, h) = inset(bounds, self.number.style.padding);
+        let (x, y, w, h) = (x.to_f32(), y.to_f32(), w.to_f32(), h.to_f32());
+        let knob = self.knob();
+
+        match self.orientation {
+            Orientation::Horizontal => (x + knob * 0.5, (w - knob).max(0.0), y + h * 0.5),
+            Orientation::Vertical => (y + h - knob * 0.5, (h - knob).max(0.0), x + w * 0.5),
+        }
+    }
+
+    /// Position on the axis of the slider, it increases with the value
+    fn axis(&self, (x, y): (f32, f32)) -> f32 {
+        match self.orientation {
+            Orientation::Horizontal => x,
+            Orientation::Vertical => -y,
+        }
+    }
+
+    /// Center of the knob
+    fn knob_center(&self, bounds: Boundaries<T>) -> (f32, f32) {
+        let (start, length, cross) = self.track(bounds);
+        let offset = self.number.range.fraction(self.number.get()) * length;
+
+        match self.orientation {
+            Orientation::Horizontal => (start + offset, cross),
+            Orientation::Vertical => (cross, start - offset),
+        }
+    }
+}
+
+impl<T, CTX> Widget<T, CTX> for Slider<T>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+    CTX: Painter<T>,
+{
+    fn draw(&mut self, internal: &WidgetInternal<T>, ctx: &mut CTX) -> bool {
+        let bounds = internal.boundaries_abs();
+        let style = &self.number.style;
+
+        ctx.fill_rect(bounds, style.window);
+
+        let (start, length, cross) = self.track(bounds);
+        let (cx, cy) = self.knob_center(bounds);
+        let thickness = (self.knob() * 0.35).round().max(2.0);
+        let (radius, half) = (T::from_f32(thickness * 0.5), thickness * 0.5);
+
+        // Track and the part of the value
+        let fill = if internal.check(ENABLED) {
+            style.accent
+        } else {
+            style.disabled
+        };
+
+        let (track, value) = match self.orientation {
+            Orientation::Horizontal => (
+                rect(start, cross - half, length, thickness),
+                rect(start, cross - half, cx - start, thickness),
+            ),
+            Orientation::Vertical => (
+                rect(cross - half, start - length, thickness, length),
+                rect(cross - half, cy, thickness, start - cy),
+            ),
+        };
+
+        ctx.fill_round_rect(track, radius, style.button);
+        ctx.fill_round_rect(value, radius, fill);
+
+        let knob = self.knob();
+        let face = if self.drag.is_some() {
+            style.pressed
+        } else if internal.check(HOVER) && internal.check(ENABLED) {
+            style.hover
+        } else {
+            style.field
+        };
+
+        let bounds = rect(cx - knob * 0.5, cy - knob * 0.5, knob, knob);
+        let radius = T::from_f32(knob * 0.5);
+        ctx.fill_round_rect(bounds, radius, style.outline_color(internal));
+        ctx.fill_round_rect(inset(bounds, style.border), radius, face);
+
+        false
+    }
+
+    /// The shared value was changed by another widget
+    fn handle_signal(&mut self, internal: &mut WidgetInternal<T>, _: GroupID) {
+        internal.on(DRAW);
+    }
+
+    fn handle_mouse(&mut self, internal: &mut WidgetInternal<T>, mouse: &MouseState<T>) {
+        if !internal.check(ENABLED) {
+            return;
+        }
+
+        let bounds = internal.boundaries_abs();
+        let pos = mouse_pos(mouse);
+        let fine = mouse.check_modifiers(SHIFT);
+
+        match mouse.m_type {
+            MouseType::Pressed(1) => {
+                internal.on(GRAB | FOCUS | DRAW);
+
+                // The knob doesn't jump when it's pressed or with Shift
+                let (cx, cy) = self.knob_center(bounds);
+                let half = self.knob() * 0.5;
+                let on_knob = (pos.0 - cx).abs() <= half && (pos.1 - cy).abs() <= half;
+
+                let value = if on_knob || fine {
+                    self.number.get()
+                } else {
+                    let (start, length, _) = self.track(bounds);
+                    let range = &self.number.range;
+                    let fraction = (self.axis(pos) - self.axis((start, start))) / length.max(1.0);
+
+                    let value = range.snap(range.from_fraction(fraction), range.step);
+                    self.number.set(internal, value);
+                    value
+                };
+
+                self.drag = Some(Drag::new(self.axis(pos), value));
+            }
+            MouseType::Released(1) if self.drag.is_some() => {
+                self.drag = None;
+                internal.off(GRAB);
+                internal.on(DRAW);
+            }
+            MouseType::CursorMoved => {
+                let (_, length, _) = self.track(bounds);
+                let axis = self.axis(pos);
+                let range = &self.number.range;
+
+                if let Some(drag) = &mut self.drag {
+                    // The knob follows the pointer without Shift
+                    let rate = if fine { drag_rate(mouse) } else { 1.0 };
+                    let per_unit = (range.max - range.min) / length.max(1.0);
+                    let value = drag.drag(axis, per_unit, rate, range);
+
+                    self.number.set(internal, value);
+                }
+            }
+            MouseType::Wheel(delta) if delta != 0.0 => {
+                self.number.add(internal, delta.signum(), fine)
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_keys(&mut self, internal: &mut WidgetInternal<T>, key: KeyState) {
+        self.number.step(internal, &key);
+    }
+
+    fn pointer_out(&mut self, internal: &mut WidgetInternal<T>, _: PointerID) {
+        self.drag = None;
+        internal.off(GRAB);
+        internal.on(DRAW);
+    }
+
+    fn min_dimensions(&self) -> Dimensions<T> {
+        let knob = self.knob();
+        let pad = self.number.style.padding.to_f32() * 2.0;
+        let (long, short) = (knob * 6.0 + pad, knob + pad);
+
+        match self.orientation {
+            Orientation::Horizontal => (T::from_f32(long), T::from_f32(short)),
+            Orientation::Vertical => (T::from_f32(short), T::from_f32(long)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::keys::{HOME, UP};
+    use crate::state::PRIMARY_POINTER;
+    use crate::widgets::tests::{signals, Harness};
+
+    fn slider(orientation: Orientation, bounds: Boundaries<f32>) -> Harness<Slider<f32>> {
+        let value = Rc::new(Cell::new(0.0));
+        let slider = Slider::new(value, NumberRange::new(0.0, 100.0, 1.0), orientation, 2);
+
+        Harness::new(slider, bounds)
+    }
+
+    #[test]
+    fn horizontal() {
+        // The knob center goes from 9.5 to 110.5
+        let mut h = slider(Orientation::Horizontal, (0.0, 0.0, 120.0, 20.0));
+
+        h.mouse(PRIMARY_POINTER, MouseType::Pressed(1), (60.0, 10.0));
+        assert_eq!(h.widget.value(), 50.0);
+        assert!(h.internal.check(GRAB | FOCUS));
+
+        h.mouse(PRIMARY_POINTER, MouseType::CursorMoved, (70.1, 10.0));
+        assert_eq!(h.widget.value(), 60.0);
+        h.mouse(PRIMARY_POINTER, MouseType::CursorMoved, (200.0, 10.0));
+        assert_eq!(h.widget.value(), 100.0);
+        h.mouse(PRIMARY_POINTER, MouseType::Released(1), (200.0, 10.0));
+        assert!(!h.internal.check(GRAB));
+        assert_eq!(signals(), [2]);
+
+        h.mouse(PRIMARY_POINTER, MouseType::CursorMoved, (60.0, 10.0));
+        assert_eq!(h.widget.value(), 100.0);
+        h.mouse(PRIMARY_POINTER, MouseType::Wheel(-1.0), (60.0, 10.0));
+        assert_eq!(h.widget.value(), 99.0);
+        h.key(HOME, 0).key(UP, 0);
+        assert_eq!(h.widget.value(), 1.0);
+    }
+
+    #[test]
+    fn vertical() {
+        // The knob center goes from 110.5 to 9.5
+        let mut h = slider(Orientation::Vertical, (0.0, 0.0, 20.0, 120.0));
+
+        h.mouse(PRIMARY_POINTER, MouseType::Pressed(1), (10.0, 9.5));
+        assert_eq!(h.widget.value(), 100.0);
+        h.mouse(PRIMARY_POINTER, MouseType::CursorMoved, (10.0, 60.0));
+        assert_eq!(h.widget.value(), 50.0);
+    }
+
+    #[test]
+    fn fine_drag() {
+        let mut h = slider(Orientation::Horizontal, (0.0, 0.0, 120.0, 20.0));
+
+        // Pressing the knob with Shift doesn't move it, a pen at half pressure makes
+        // the drag twenty times slower
+        h.pointer(PRIMARY_POINTER).set_modifiers(SHIFT);
+        h.pointer(PRIMARY_POINTER).set_pressure(0.5);
+        h.mouse(PRIMARY_POINTER, MouseType::Pressed(1), (60.0, 10.0));
+        assert_eq!(h.widget.value(), 0.0);
+
+        h.mouse(PRIMARY_POINTER, MouseType::CursorMoved, (80.2, 10.0));
+        assert_eq!(h.widget.value(), 1.0);
+        assert_eq!(signals(), [2]);
+    }
+}
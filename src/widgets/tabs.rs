@@ -0,0 +1,1180 @@
+//! Tabbed pages
+//!
+//! A TabView has a strip of tabs over its pages, every page is a widget with its
+//! own WidgetInternal, usually a Container. Only the active page is laid out,
+//! drawn and receives the mouse and the keys, the other pages keep their state
+//! until they are shown again
+
+use super::{arrow, contains, line, mouse_pos, rect, Direction, Style};
+use crate::clip::overlaps;
+use crate::damage::DamageRegion;
+use crate::group::{push_event, Group, GroupEvent, GroupID};
+use crate::math::Scalar;
+use crate::paint::Painter;
+use crate::state::keys::*;
+use crate::state::{KeyState, MouseState, MouseType, PointerID, TextInput};
+use crate::text::{Monospace, SharedMeasure};
+use crate::widget::flags::*;
+use crate::widget::{Boundaries, Dimensions, Widget, WidgetInternal};
+
+use std::cell::RefCell;
+use std::ops::{Add, Sub};
+use std::rc::Rc;
+
+/// Distance that the pointer moves before a press drags the tab
+const DRAG_DISTANCE: f32 = 4.0;
+
+/// ID of a tab given by the application
+pub type TabID = usize;
+
+/// Tabs of a TabView shared with the application
+///
+/// The view pushes its signal when the user switches, moves or closes a tab. The
+/// application changes the active tab or closes tabs here, then it pushes the
+/// signal of the view for apply the changes
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TabState {
+    order: Vec<TabID>,
+    active: Option<TabID>,
+    /// Tab of the last close button, when the view asks before closing
+    request: Option<TabID>,
+    /// Tabs that the view will close
+    close: Vec<TabID>,
+    /// Tabs closed since the application took them
+    closed: Vec<TabID>,
+}
+
+impl TabState {
+    pub fn new() -> Self {
+        TabState::default()
+    }
+
+    /// IDs of the tabs in the order of the strip
+    #[inline]
+    pub fn order(&self) -> &[TabID] {
+        &self.order
+    }
+
+    #[inline]
+    pub fn active(&self) -> Option<TabID> {
+        self.active
+    }
+
+    pub fn set_active(&mut self, id: TabID) {
+        self.active = Some(id);
+    }
+
+    /// Take the tab of the last pressed close button, the view only stores it
+    /// when it asks before closing
+    pub fn take_request(&mut self) -> Option<TabID> {
+        self.request.take()
+    }
+
+    /// Close a tab when the view receives its signal
+    pub fn close(&mut self, id: TabID) {
+        self.close.push(id);
+    }
+
+    /// Take the tabs that were closed since the last call
+    pub fn take_closed(&mut self) -> Vec<TabID> {
+        std::mem::take(&mut self.closed)
+    }
+}
+
+/// Page of a TabView with the data of its tab
+struct Page<T, CTX> {
+    id: TabID,
+    title: String,
+    closable: bool,
+    widget: Box<dyn Widget<T, CTX>>,
+    internal: WidgetInternal<T>,
+}
+
+/// Press of the left button on the strip
+struct Press {
+    /// Index of the pressed tab, it changes while the tab is dragged
+    index: usize,
+    start: f32,
+    /// The close button of the tab was pressed
+    close: bool,
+    dragging: bool,
+    moved: bool,
+}
+
+/// Strip of tabs over pages
+///
+/// Pressing a tab shows its page and dragging it moves the tab. The close button
+/// or the middle button close a closable tab. When the tabs don't fit, the strip
+/// is scrolled with the wheel or the arrows at its end. Ctrl+Tab and Ctrl+PageDown
+/// show the next page, with Shift or Ctrl+PageUp the previous one. The other keys
+/// go to the active page
+///
+/// Add the view with SIGNAL and a Group of the signal, the pages receive every
+/// signal of the view
+pub struct TabView<T, CTX> {
+    pages: Vec<Page<T, CTX>>,
+    state: Rc<RefCell<TabState>>,
+    signal: GroupID,
+    style: Style<T>,
+    measure: SharedMeasure<T>,
+    /// Closing only stores the request in the TabState
+    confirm: bool,
+    /// Page that is laid out
+    shown: Option<TabID>,
+    /// Distance that the strip is scrolled
+    scroll: f32,
+    /// Hovered tab and if the pointer is over its close button
+    hover: Option<(usize, bool)>,
+    press: Option<Press>,
+    /// The strip must be drawn
+    strip: bool,
+    /// The background of the page must be drawn, the page changed
+    clear: bool,
+    /// The strip and the background of the page were drawn on the last frame
+    drawn: bool,
+    cleared: bool,
+}
+
+impl<T, CTX> TabView<T, CTX>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+    CTX: Painter<T>,
+{
+    /// Creates an empty TabView with the shared state and the signal ID pushed when
+    /// the tabs change
+    pub fn new(state: Rc<RefCell<TabState>>, signal: GroupID) -> Self {
+        TabView {
+            pages: Vec::new(),
+            state,
+            signal,
+            style: Style::default(),
+            measure: Rc::new(Monospace::new(0.6, 0.8, 0.2)),
+            confirm: false,
+            shown: None,
+            scroll: 0.0,
+            hover: None,
+            press: None,
+            strip: true,
+            clear: true,
+            drawn: false,
+            cleared: false,
+        }
+    }
+
+    /// Add a page at the end of the strip, the first page is the active one
+    pub fn add_tab(
+        &mut self,
+        id: TabID,
+        title: &str,
+        page: Box<dyn Widget<T, CTX>>,
+        closable: bool,
+    ) {
+        let mut internal = WidgetInternal::new(WIDGET_S, Group::Any);
+        internal.set_min_dimensions(page.min_dimensions());
+
+        self.pages.push(Page {
+            id,
+            title: String::from(title),
+            closable,
+            widget: page,
+            internal,
+        });
+
+        let mut state = self.state.borrow_mut();
+        state.order.push(id);
+        if state.active.is_none() {
+            state.active = Some(id);
+        }
+    }
+
+    pub fn set_style(&mut self, style: Style<T>) {
+        self.style = style;
+    }
+
+    /// Store the pressed close buttons in the TabState instead of closing the
+    /// tabs, the application closes them with TabState::close
+    pub fn set_confirm_close(&mut self, confirm: bool) {
+        self.confirm = confirm;
+    }
+
+    #[inline]
+    pub fn state(&self) -> &Rc<RefCell<TabState>> {
+        &self.state
+    }
+
+    // -- Geometry --
+
+    fn strip_height(&self) -> f32 {
+        line(&self.measure, &self.style) + self.style.padding.to_f32() * 2.0
+    }
+
+    /// Side of the close buttons
+    fn close_size(&self) -> f32 {
+        line(&self.measure, &self.style)
+    }
+
+    fn tab_width(&self, page: &Page<T, CTX>) -> f32 {
+        let style = &self.style;
+        let pad = style.padding.to_f32();
+        let title = self
+            .measure
+            .advance(style.font, style.font_size, &page.title)
+            .to_f32();
+
+        if page.closable {
+            title + pad * 3.0 + self.close_size()
+        } else {
+            title + pad * 2.0
+        }
+    }
+
+    /// Boundaries of the strip and of the page
+    fn areas(&self, bounds: Boundaries<T>) -> (Boundaries<T>, Boundaries<T>) {
+        let (x, y, w, h) = (
+            bounds.0.to_f32(),
+            bounds.1.to_f32(),
+            bounds.2.to_f32(),
+            bounds.3.to_f32(),
+        );
+        let strip = self.strip_height().min(h);
+
+        (rect(x, y, w, strip), rect(x, y + strip, w, h - strip))
+    }
+
+    /// Boundaries of the visible part of the tabs and of the scroll arrows when
+    /// the tabs don't fit
+    fn tabs_area(&self, strip: Boundaries<T>) -> (Boundaries<T>, Option<Boundaries<T>>) {
+        let total: f32 = self.pages.iter().map(|page| self.tab_width(page)).sum();
+        let (x, y, w, h) = (
+            strip.0.to_f32(),
+            strip.1.to_f32(),
+            strip.2.to_f32(),
+            strip.3.to_f32(),
+        );
+
+        if total <= w {
+            return (strip, None);
+        }
+
+        let arrows = (h * 2.0).min(w);
+        (
+            rect(x, y, w - arrows, h),
+            Some(rect(x + w - arrows, y, arrows, h)),
+        )
+    }
+
+    /// Boundaries of every tab, they can be out of the visible area
+    fn tabs(&self, area: Boundaries<T>) -> Vec<Boundaries<T>> {
+        let (y, h) = (area.1.to_f32(), area.3.to_f32());
+        let mut x = area.0.to_f32() - self.scroll;
+
+        self.pages
+            .iter()
+            .map(|page| {
+                let w = self.tab_width(page);
+                x += w;
+                rect(x - w, y, w, h)
+            })
+            .collect()
+    }
+
+    fn close_bounds(&self, tab: Boundaries<T>) -> Boundaries<T> {
+        let size = self.close_size();
+        let pad = self.style.padding.to_f32();
+        let (x, y) = (tab.0.to_f32() + tab.2.to_f32(), tab.1.to_f32());
+
+        rect(
+            x - pad - size,
+            y + (tab.3.to_f32() - size) * 0.5,
+            size,
+            size,
+        )
+    }
+
+    /// Tab under a position and if it's over the close button
+    fn hit(&self, area: Boundaries<T>, pos: (f32, f32)) -> Option<(usize, bool)> {
+        if !contains(area, pos) {
+            return None;
+        }
+
+        let tabs = self.tabs(area);
+        let index = tabs.iter().position(|&tab| contains(tab, pos))?;
+        let close = self.pages[index].closable && contains(self.close_bounds(tabs[index]), pos);
+
+        Some((index, close))
+    }
+
+    fn max_scroll(&self, area: Boundaries<T>) -> f32 {
+        let total: f32 = self.pages.iter().map(|page| self.tab_width(page)).sum();
+        (total - area.2.to_f32()).max(0.0)
+    }
+
+    fn scroll_to(&mut self, internal: &mut WidgetInternal<T>, area: Boundaries<T>, scroll: f32) {
+        let scroll = scroll.clamp(0.0, self.max_scroll(area));
+        if scroll != self.scroll {
+            self.scroll = scroll;
+            self.strip = true;
+            internal.on(DRAW);
+        }
+    }
+
+    /// Scroll the minimum distance for show a tab
+    fn reveal(&mut self, internal: &mut WidgetInternal<T>, index: usize) {
+        let (strip, _) = self.areas(internal.boundaries_abs());
+        let (area, _) = self.tabs_area(strip);
+        let tab = self.tabs(area)[index];
+
+        let (start, end) = (area.0.to_f32(), area.0.to_f32() + area.2.to_f32());
+        let (left, right) = (tab.0.to_f32(), tab.0.to_f32() + tab.2.to_f32());
+
+        if left < start {
+            self.scroll_to(internal, area, self.scroll - (start - left));
+        } else if right > end {
+            self.scroll_to(internal, area, self.scroll + (right - end));
+        }
+    }
+
+    // -- Pages --
+
+    fn index_of(&self, id: TabID) -> Option<usize> {
+        self.pages.iter().position(|page| page.id == id)
+    }
+
+    fn active(&self) -> Option<usize> {
+        self.shown.and_then(|id| self.index_of(id))
+    }
+
+    /// Propagate the flags of the active page, a layout is requested if the page
+    /// needs it
+    fn drain(&mut self, internal: &mut WidgetInternal<T>) {
+        if let Some(n) = self.active() {
+            let page = &self.pages[n].internal;
+            internal.on(page.val(DRAW | UPDATE | LAYOUT));
+
+            if page.check(PREV_LAYOUT) {
+                internal.on(LAYOUT);
+            }
+        }
+    }
+
+    /// Place the active page in its area and lay it out
+    fn place(&mut self, internal: &mut WidgetInternal<T>, complete: bool) {
+        let (_, area) = self.areas(internal.boundaries_abs());
+        let n = match self.active() {
+            Some(n) => n,
+            None => return,
+        };
+
+        let page = &mut self.pages[n];
+        let changed = page.internal.boundaries_abs() != area;
+        page.internal.set_pivot((area.0, area.1));
+        page.internal
+            .set_boundaries((T::default(), T::default(), area.2, area.3));
+
+        let backup = page.internal.flags;
+        page.widget.layout(&mut page.internal, complete || changed);
+
+        // DRAW | UPDATE & LAYOUT | PREV_LAYOUT
+        page.internal.turn(DRAW, page.internal.check(VISIBLE));
+        internal.on(page.internal.drain(DRAW | UPDATE, LAYOUT | PREV_LAYOUT));
+
+        // HOVER | GRAB | FOCUS
+        page.internal.replace(0b11100000, backup);
+    }
+
+    /// Show the page of the active tab of the state, the old page loses the
+    /// pointer and the focus
+    fn show(&mut self, internal: &mut WidgetInternal<T>) {
+        let active = self.state.borrow().active;
+        let active = active
+            .filter(|&id| self.index_of(id).is_some())
+            .or_else(|| self.pages.first().map(|page| page.id));
+        self.state.borrow_mut().active = active;
+
+        if active == self.shown {
+            return;
+        }
+
+        if let Some(n) = self.active() {
+            let page = &mut self.pages[n];
+            page.widget.hover_out(&mut page.internal);
+
+            if page.internal.check(FOCUS) {
+                page.widget.focus_out(&mut page.internal);
+            }
+            page.internal.off(HOVER | GRAB | FOCUS);
+        }
+
+        self.shown = active;
+        self.place(internal, true);
+
+        if let Some(n) = self.active() {
+            self.reveal(internal, n);
+        }
+
+        self.strip = true;
+        self.clear = true;
+        internal.on(DRAW);
+    }
+
+    /// Make a tab active by the user
+    fn switch(&mut self, internal: &mut WidgetInternal<T>, index: usize) {
+        let id = self.pages[index].id;
+        if self.shown == Some(id) {
+            return;
+        }
+
+        self.state.borrow_mut().active = Some(id);
+        self.show(internal);
+        push_event(GroupEvent::Signal(self.signal));
+    }
+
+    /// Close a tab, or store the request if the view asks before closing
+    fn request_close(&mut self, internal: &mut WidgetInternal<T>, index: usize) {
+        let id = self.pages[index].id;
+
+        if self.confirm {
+            self.state.borrow_mut().request = Some(id);
+        } else {
+            self.close(internal, id);
+        }
+
+        push_event(GroupEvent::Signal(self.signal));
+    }
+
+    /// Remove a tab, the next tab becomes active if it was the active one
+    fn close(&mut self, internal: &mut WidgetInternal<T>, id: TabID) {
+        let index = match self.index_of(id) {
+            Some(index) => index,
+            None => return,
+        };
+
+        self.pages.remove(index);
+        self.hover = None;
+
+        // The pressed tab is after the closed one or it's the closed one
+        match &mut self.press {
+            Some(press) if press.index > index => press.index -= 1,
+            Some(press) if press.index == index => {
+                self.press = None;
+                internal.off(GRAB);
+            }
+            _ => {}
+        }
+
+        let mut state = self.state.borrow_mut();
+        state.order.retain(|&tab| tab != id);
+        state.closed.push(id);
+
+        if state.active == Some(id) {
+            let next = self.pages.get(index).or_else(|| self.pages.last());
+            state.active = next.map(|page| page.id);
+        }
+        drop(state);
+
+        if self.shown == Some(id) {
+            self.shown = None;
+        }
+
+        self.show(internal);
+
+        let (strip, _) = self.areas(internal.boundaries_abs());
+        let (area, _) = self.tabs_area(strip);
+        self.scroll_to(internal, area, self.scroll);
+
+        self.strip = true;
+        internal.on(DRAW);
+    }
+
+    /// Move the dragged tab over its neighbors when the pointer passes their center
+    fn drag_tab(&mut self, area: Boundaries<T>, x: f32) {
+        let mut press = match self.press.take() {
+            Some(press) => press,
+            None => return,
+        };
+
+        loop {
+            let tabs = self.tabs(area);
+            let index = press.index;
+            let center = |n: usize| tabs[n].0.to_f32() + tabs[n].2.to_f32() * 0.5;
+
+            let target = if index + 1 < tabs.len() && x > center(index + 1) {
+                index + 1
+            } else if index > 0 && x < center(index - 1) {
+                index - 1
+            } else {
+                break;
+            };
+
+            self.pages.swap(index, target);
+            press.index = target;
+            press.moved = true;
+            self.strip = true;
+        }
+
+        self.press = Some(press);
+    }
+
+    /// Step the active tab, it goes around
+    fn step(&mut self, internal: &mut WidgetInternal<T>, forward: bool) {
+        let count = self.pages.len();
+        if let Some(n) = self.active() {
+            let next = if forward {
+                (n + 1) % count
+            } else {
+                (n + count - 1) % count
+            };
+
+            self.switch(internal, next);
+        }
+    }
+
+    fn draw_strip(&self, ctx: &mut CTX, strip: Boundaries<T>, focused: bool) {
+        let style = &self.style;
+        let (pad, border) = (style.padding.to_f32(), style.border.to_f32());
+        let ascent = self.measure.ascent(style.font, style.font_size).to_f32();
+        let text = line(&self.measure, style);
+
+        ctx.fill_rect(strip, style.window);
+
+        let (area, arrows) = self.tabs_area(strip);
+        let (sx, sy, sw, sh) = (
+            strip.0.to_f32(),
+            strip.1.to_f32(),
+            strip.2.to_f32(),
+            strip.3.to_f32(),
+        );
+        ctx.fill_rect(rect(sx, sy + sh - border, sw, border), style.outline);
+
+        let active = self.active();
+        let dragged = self.press.as_ref().filter(|p| p.dragging).map(|p| p.index);
+
+        ctx.push_clip(area);
+        for (n, tab) in self.tabs(area).into_iter().enumerate() {
+            let page = &self.pages[n];
+            let (x, y, w, h) = (
+                tab.0.to_f32(),
+                tab.1.to_f32(),
+                tab.2.to_f32(),
+                tab.3.to_f32(),
+            );
+
+            let is_active = active == Some(n);
+            let face = if is_active {
+                style.field
+            } else if dragged == Some(n) {
+                style.pressed
+            } else if self.hover.map(|(h, _)| h) == Some(n) {
+                style.hover
+            } else {
+                style.button
+            };
+
+            // The active tab is open to its page
+            let bottom = if is_active { 0.0 } else { border };
+            ctx.fill_rect(rect(x, y + pad * 0.5, w, h - pad * 0.5), style.outline);
+            ctx.fill_rect(
+                rect(
+                    x + border,
+                    y + pad * 0.5 + border,
+                    w - border * 2.0,
+                    h - pad * 0.5 - border - bottom,
+                ),
+                face,
+            );
+
+            if is_active && focused {
+                let line = rect(x + border, y + pad * 0.5 + border, w - border * 2.0, border);
+                ctx.fill_rect(line, style.focus);
+            }
+
+            let baseline = y + pad * 0.5 + (h - pad * 0.5 - text) * 0.5 + ascent;
+            let pos = (T::from_f32(x + pad), T::from_f32(baseline));
+            ctx.text(pos, style.font, style.font_size, &page.title, style.text);
+
+            if page.closable {
+                let close = self.close_bounds(tab);
+                if self.hover == Some((n, true)) {
+                    ctx.fill_round_rect(close, style.radius, style.pressed);
+                }
+
+                let (cx, cy, size) = (close.0.to_f32(), close.1.to_f32(), close.2.to_f32());
+                let (a, b) = (size * 0.3, size * 0.7);
+                let p = |px: f32, py: f32| (T::from_f32(cx + px), T::from_f32(cy + py));
+                let width = T::from_f32(border.max(1.0) * 1.5);
+
+                ctx.line(p(a, a), p(b, b), width, style.text);
+                ctx.line(p(b, a), p(a, b), width, style.text);
+            }
+        }
+        ctx.pop_clip();
+
+        if let Some(arrows) = arrows {
+            let (x, y, w, h) = (
+                arrows.0.to_f32(),
+                arrows.1.to_f32(),
+                arrows.2.to_f32(),
+                arrows.3.to_f32(),
+            );
+            let size = style.font_size.to_f32() * 0.6;
+            let max = self.max_scroll(area);
+
+            for (n, &left) in [true, false].iter().enumerate() {
+                let button = rect(x + w * 0.5 * n as f32, y, w * 0.5, h - border);
+                let enabled = if left {
+                    self.scroll > 0.0
+                } else {
+                    self.scroll < max
+                };
+                let color = if enabled { style.text } else { style.disabled };
+
+                ctx.fill_rect(button, style.window);
+                ctx.fill_path(
+                    &arrow(
+                        button,
+                        size,
+                        if left {
+                            Direction::Left
+                        } else {
+                            Direction::Right
+                        },
+                    ),
+                    color,
+                );
+            }
+        }
+    }
+}
+
+impl<T, CTX> Widget<T, CTX> for TabView<T, CTX>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+    CTX: Painter<T>,
+{
+    fn draw(&mut self, internal: &WidgetInternal<T>, ctx: &mut CTX) -> bool {
+        let (strip, area) = self.areas(internal.boundaries_abs());
+
+        if std::mem::replace(&mut self.strip, false) {
+            let focused = internal.check(FOCUS);
+            self.draw_strip(ctx, strip, focused);
+            self.drawn = true;
+        }
+
+        if std::mem::replace(&mut self.clear, false) {
+            ctx.fill_rect(area, self.style.window);
+            self.cleared = true;
+        }
+
+        let n = match self.active() {
+            Some(n) => n,
+            None => return false,
+        };
+
+        let page = &mut self.pages[n];
+        let mut draw = false;
+
+        // DRAW | VISIBLE
+        if page.internal.check(0b00001010) {
+            ctx.push_clip(area);
+            draw = page.widget.draw(&page.internal, ctx);
+            ctx.pop_clip();
+
+            page.internal.turn(DRAW, draw);
+        }
+
+        draw
+    }
+
+    fn update(&mut self, internal: &mut WidgetInternal<T>) {
+        let n = match self.active() {
+            Some(n) => n,
+            None => return internal.off(UPDATE),
+        };
+
+        let page = &mut self.pages[n];
+        if page.internal.check(UPDATE) {
+            page.widget.update(&mut page.internal);
+            self.drain(internal);
+        }
+
+        internal.turn(UPDATE, self.pages[n].internal.check(UPDATE));
+    }
+
+    fn layout(&mut self, internal: &mut WidgetInternal<T>, complete: bool) {
+        if self.shown.is_none() {
+            self.show(internal);
+        }
+
+        if complete {
+            self.strip = true;
+            self.clear = true;
+
+            let (strip, _) = self.areas(internal.boundaries_abs());
+            let (area, _) = self.tabs_area(strip);
+            self.scroll_to(internal, area, self.scroll);
+        }
+
+        self.place(internal, complete);
+    }
+
+    fn measure(&mut self, _: &mut WidgetInternal<T>, measure: &SharedMeasure<T>) {
+        self.measure = measure.clone();
+
+        for page in self.pages.iter_mut() {
+            page.widget.measure(&mut page.internal, measure);
+            page.internal
+                .set_min_dimensions(page.widget.min_dimensions());
+        }
+    }
+
+    /// Apply the changes of the TabState, then every page receives the signal
+    fn handle_signal(&mut self, internal: &mut WidgetInternal<T>, group: GroupID) {
+        if group == self.signal {
+            let close = std::mem::take(&mut self.state.borrow_mut().close);
+            for id in close {
+                self.close(internal, id);
+            }
+
+            self.show(internal);
+        }
+
+        for page in self.pages.iter_mut() {
+            page.widget.handle_signal(&mut page.internal, group);
+        }
+
+        self.drain(internal);
+    }
+
+    fn handle_mouse(&mut self, internal: &mut WidgetInternal<T>, mouse: &MouseState<T>) {
+        let (strip, page_area) = self.areas(internal.boundaries_abs());
+        let (area, arrows) = self.tabs_area(strip);
+        let pos = mouse_pos(mouse);
+
+        // The active page receives the mouse like a ScrollView content
+        if self.press.is_none() {
+            let grab = internal.check(GRAB);
+            let inside = contains(page_area, pos);
+
+            if let Some(n) = self.active() {
+                let page = &mut self.pages[n];
+
+                if grab || inside {
+                    page.internal.turn(GRAB, grab);
+                    page.internal.turn(HOVER, inside);
+
+                    page.widget.handle_mouse(&mut page.internal, mouse);
+                    let (grab, focus) = (page.internal.check(GRAB), page.internal.check(FOCUS));
+                    self.drain(internal);
+                    internal.turn(GRAB, grab);
+
+                    // A widget of the page took the focus
+                    if focus && !internal.check(FOCUS) {
+                        internal.on(FOCUS);
+                        self.strip = true;
+                        internal.on(DRAW);
+                    }
+
+                    if self.hover.take().is_some() {
+                        self.strip = true;
+                        internal.on(DRAW);
+                    }
+                    return;
+                }
+
+                page.widget.pointer_out(&mut page.internal, mouse.pointer());
+                self.drain(internal);
+            }
+        }
+
+        let hit = self.hit(area, pos);
+        if self.press.is_none() && hit != self.hover {
+            self.hover = hit;
+            self.strip = true;
+            internal.on(DRAW);
+        }
+
+        if !internal.check(ENABLED) {
+            return;
+        }
+
+        match mouse.m_type {
+            MouseType::Pressed(1) => {
+                if let Some(arrows) = arrows.filter(|&arrows| contains(arrows, pos)) {
+                    let left = pos.0 < arrows.0.to_f32() + arrows.2.to_f32() * 0.5;
+                    let step = area.2.to_f32() * 0.5;
+                    let step = if left { -step } else { step };
+
+                    self.scroll_to(internal, area, self.scroll + step);
+                    return;
+                }
+
+                if let Some((index, close)) = hit {
+                    if !close {
+                        self.switch(internal, index);
+                    }
+
+                    if !internal.check(FOCUS) {
+                        self.strip = true;
+                        internal.on(FOCUS | DRAW);
+                    }
+
+                    self.press = Some(Press {
+                        index,
+                        start: pos.0,
+                        close,
+                        dragging: false,
+                        moved: false,
+                    });
+                    internal.on(GRAB);
+                }
+            }
+            MouseType::Pressed(2) => {
+                if let Some((index, _)) = hit.filter(|&(n, _)| self.pages[n].closable) {
+                    self.request_close(internal, index);
+                }
+            }
+            MouseType::CursorMoved => {
+                let press = match &mut self.press {
+                    Some(press) if !press.close => press,
+                    _ => return,
+                };
+
+                if !press.dragging && (pos.0 - press.start).abs() >= DRAG_DISTANCE {
+                    press.dragging = true;
+                }
+
+                if press.dragging {
+                    self.drag_tab(area, pos.0);
+                    self.strip = true;
+                    internal.on(DRAW);
+                }
+            }
+            MouseType::Released(1) => {
+                let press = match self.press.take() {
+                    Some(press) => press,
+                    None => return,
+                };
+
+                internal.off(GRAB);
+                self.strip = true;
+                internal.on(DRAW);
+
+                if press.close {
+                    if hit == Some((press.index, true)) {
+                        self.request_close(internal, press.index);
+                    }
+                } else if press.moved {
+                    let order = self.pages.iter().map(|page| page.id).collect();
+                    self.state.borrow_mut().order = order;
+                    push_event(GroupEvent::Signal(self.signal));
+                }
+            }
+            MouseType::Wheel(delta) => {
+                let step = delta * self.strip_height();
+                self.scroll_to(internal, area, self.scroll - step);
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_keys(&mut self, internal: &mut WidgetInternal<T>, key: KeyState) {
+        if let KeyState::Pressed(code, mods) = key {
+            let back = mods & SHIFT != 0;
+
+            match code {
+                TAB if mods & CTRL != 0 => return self.step(internal, !back),
+                PAGE_DOWN if mods & CTRL != 0 => return self.step(internal, true),
+                PAGE_UP if mods & CTRL != 0 => return self.step(internal, false),
+                _ => {}
+            }
+        }
+
+        if let Some(n) = self.active() {
+            let page = &mut self.pages[n];
+            page.widget.handle_keys(&mut page.internal, key);
+            self.drain(internal);
+        }
+    }
+
+    fn handle_text(&mut self, internal: &mut WidgetInternal<T>, text: TextInput) {
+        if let Some(n) = self.active() {
+            let page = &mut self.pages[n];
+            page.widget.handle_text(&mut page.internal, text);
+            self.drain(internal);
+        }
+    }
+
+    fn step_focus(&mut self, internal: &mut WidgetInternal<T>, back: bool) -> bool {
+        let n = match self.active() {
+            Some(n) => n,
+            None => return false,
+        };
+
+        let page = &mut self.pages[n];
+        let focus = page.widget.step_focus(&mut page.internal, back);
+        page.internal.turn(FOCUS, focus);
+        self.drain(internal);
+
+        self.strip = true;
+        internal.on(DRAW);
+        focus
+    }
+
+    fn hover_in(&mut self, _: &mut WidgetInternal<T>) {}
+
+    fn hover_out(&mut self, internal: &mut WidgetInternal<T>) {
+        if self.hover.take().is_some() {
+            self.strip = true;
+            internal.on(DRAW);
+        }
+
+        if let Some(n) = self.active() {
+            let page = &mut self.pages[n];
+            page.widget.hover_out(&mut page.internal);
+            page.internal.off(HOVER | GRAB);
+            self.drain(internal);
+        }
+    }
+
+    fn pointer_out(&mut self, internal: &mut WidgetInternal<T>, pointer: PointerID) {
+        if self.press.take().is_some() {
+            internal.off(GRAB);
+            self.strip = true;
+            internal.on(DRAW);
+        }
+
+        if let Some(n) = self.active() {
+            let page = &mut self.pages[n];
+            page.widget.pointer_out(&mut page.internal, pointer);
+            self.drain(internal);
+        }
+    }
+
+    fn focus_out(&mut self, internal: &mut WidgetInternal<T>) {
+        if let Some(n) = self.active() {
+            let page = &mut self.pages[n];
+            page.widget.focus_out(&mut page.internal);
+            page.internal.off(FOCUS);
+            self.drain(internal);
+        }
+
+        self.strip = true;
+        internal.on(DRAW);
+    }
+
+    /// Expose the strip or the active page, the page area is cleared if there isn't one
+    fn expose(&mut self, internal: &mut WidgetInternal<T>, area: Boundaries<T>) {
+        let (strip, page_area) = self.areas(internal.boundaries_abs());
+        self.strip |= overlaps(area, strip);
+
+        if overlaps(area, page_area) {
+            match self.active() {
+                Some(n) => {
+                    let page = &mut self.pages[n];
+                    page.widget.expose(&mut page.internal, area);
+                    self.drain(internal);
+                }
+                None => self.clear = true,
+            }
+        }
+
+        internal.on(DRAW);
+    }
+
+    fn pending(&self) -> usize {
+        match self.active() {
+            Some(n) if self.pages[n].internal.check(DRAW) => self.pages[n].widget.pending().max(1),
+            _ => 0,
+        }
+    }
+
+    /// Add the strip and the page area if they were drawn and the damage of the active page
+    fn damage(&mut self, internal: &WidgetInternal<T>, region: &mut DamageRegion<T>) {
+        let (strip, area) = self.areas(internal.boundaries_abs());
+
+        if std::mem::replace(&mut self.drawn, false) {
+            region.add(strip);
+        }
+
+        if let Some(n) = self.active() {
+            let page = &mut self.pages[n];
+            page.widget.damage(&page.internal, region);
+        }
+
+        if std::mem::replace(&mut self.cleared, false) {
+            region.add(area);
+        }
+    }
+
+    /// The biggest page fits below the strip, so switching doesn't need a new layout
+    fn min_dimensions(&self) -> Dimensions<T> {
+        let (w, h) = self.pages.iter().fold((0.0f32, 0.0f32), |(w, h), page| {
+            let (pw, ph) = page.internal.min_dimensions();
+            (w.max(pw.to_f32()), h.max(ph.to_f32()))
+        });
+
+        let strip = self.strip_height();
+        let arrows = strip * 2.0;
+
+        (T::from_f32(w.max(arrows)), T::from_f32(h + strip))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::DisplayList;
+    use crate::state::PRIMARY_POINTER;
+    use crate::widgets::tests::{signals, texts, Harness};
+
+    const TABS: GroupID = 3;
+
+    type Log = Rc<RefCell<Vec<String>>>;
+
+    /// Page that records its events
+    struct Content {
+        name: &'static str,
+        log: Log,
+    }
+
+    impl<CTX> Widget<f32, CTX> for Content {
+        fn draw(&mut self, _: &WidgetInternal<f32>, _: &mut CTX) -> bool {
+            self.log.borrow_mut().push(format!("{} draw", self.name));
+            false
+        }
+
+        fn handle_keys(&mut self, _: &mut WidgetInternal<f32>, _: KeyState) {
+            self.log.borrow_mut().push(format!("{} key", self.name));
+        }
+    }
+
+    /// Tabs One and Two are closable and 56 wide, the close button of One is at
+    /// (41, 4, 11, 11). Three is 63 wide and the strip is 19 high
+    fn view(bounds: Boundaries<f32>) -> (Harness<TabView<f32, DisplayList<f32>>>, Log) {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut view = TabView::new(Rc::new(RefCell::new(TabState::new())), TABS);
+
+        for (id, &(name, closable)) in [("One", true), ("Two", true), ("Three", false)]
+            .iter()
+            .enumerate()
+        {
+            let log = log.clone();
+            view.add_tab(id + 1, name, Box::new(Content { name, log }), closable);
+        }
+
+        (Harness::new(view, bounds), log)
+    }
+
+    fn state(h: &Harness<TabView<f32, DisplayList<f32>>>) -> TabState {
+        h.widget.state().borrow().clone()
+    }
+
+    #[test]
+    fn switch() {
+        let (mut h, log) = view((0.0, 0.0, 200.0, 100.0));
+        assert_eq!(texts(&h.draw()), ["One", "Two", "Three"]);
+        assert_eq!(*log.borrow(), ["One draw"]);
+
+        h.click((60.0, 10.0));
+        assert_eq!(state(&h).active(), Some(2));
+        assert_eq!(signals(), [TABS]);
+        h.draw();
+        assert_eq!(log.borrow().last().unwrap(), "Two draw");
+
+        // The keys go around the tabs, the others go to the page
+        h.key(TAB, CTRL);
+        assert_eq!(state(&h).active(), Some(3));
+        h.key(PAGE_DOWN, CTRL)
+            .key(TAB, CTRL | SHIFT)
+            .key(PAGE_UP, CTRL);
+        assert_eq!(state(&h).active(), Some(2));
+        h.key('a' as u32, 0);
+        assert_eq!(log.borrow().last().unwrap(), "Two key");
+
+        // The application switches with the signal
+        h.widget.state().borrow_mut().set_active(1);
+        Widget::<f32, DisplayList<f32>>::handle_signal(&mut h.widget, &mut h.internal, TABS);
+        h.key('a' as u32, 0);
+        assert_eq!(log.borrow().last().unwrap(), "One key");
+    }
+
+    #[test]
+    fn close() {
+        let (mut h, _) = view((0.0, 0.0, 200.0, 100.0));
+        signals();
+
+        // The next tab becomes active
+        h.click((45.0, 9.0));
+        assert_eq!(state(&h).order(), [2, 3]);
+        assert_eq!(state(&h).active(), Some(2));
+        assert_eq!(h.widget.state().borrow_mut().take_closed(), [1]);
+        assert_eq!(signals(), [TABS]);
+
+        // The middle button closes, Three isn't closable
+        h.mouse(PRIMARY_POINTER, MouseType::Pressed(2), (80.0, 10.0));
+        assert_eq!(state(&h).order(), [2, 3]);
+        h.mouse(PRIMARY_POINTER, MouseType::Pressed(2), (10.0, 10.0));
+        assert_eq!(state(&h).order(), [3]);
+        assert_eq!(state(&h).active(), Some(3));
+    }
+
+    #[test]
+    fn confirm_close() {
+        let (mut h, _) = view((0.0, 0.0, 200.0, 100.0));
+        h.widget.set_confirm_close(true);
+
+        // Released out of the button it doesn't close
+        h.mouse(PRIMARY_POINTER, MouseType::Pressed(1), (45.0, 9.0))
+            .mouse(PRIMARY_POINTER, MouseType::Released(1), (30.0, 9.0));
+        assert_eq!(h.widget.state().borrow_mut().take_request(), None);
+
+        h.click((45.0, 9.0));
+        let request = h.widget.state().borrow_mut().take_request();
+        assert_eq!(request, Some(1));
+        assert_eq!(state(&h).order(), [1, 2, 3]);
+
+        h.widget.state().borrow_mut().close(1);
+        Widget::<f32, DisplayList<f32>>::handle_signal(&mut h.widget, &mut h.internal, TABS);
+        assert_eq!(state(&h).order(), [2, 3]);
+        assert_eq!(state(&h).active(), Some(2));
+    }
+
+    #[test]
+    fn drag() {
+        let (mut h, _) = view((0.0, 0.0, 200.0, 100.0));
+        signals();
+
+        // The tab moves when the pointer passes the center of Two at 84, the state
+        // changes on the release
+        h.mouse(PRIMARY_POINTER, MouseType::Pressed(1), (10.0, 10.0))
+            .mouse(PRIMARY_POINTER, MouseType::CursorMoved, (80.0, 10.0));
+        assert_eq!(texts(&h.draw()), ["One", "Two", "Three"]);
+        h.mouse(PRIMARY_POINTER, MouseType::CursorMoved, (100.0, 10.0));
+        assert_eq!(texts(&h.draw()), ["Two", "One", "Three"]);
+        assert_eq!(state(&h).order(), [1, 2, 3]);
+        assert!(signals().is_empty());
+
+        h.mouse(PRIMARY_POINTER, MouseType::Released(1), (100.0, 10.0));
+        assert_eq!(state(&h).order(), [2, 1, 3]);
+        assert_eq!(signals(), [TABS]);
+    }
+
+    #[test]
+    fn scroll() {
+        // The tabs are 175 wide, 62 are visible before the arrows
+        let (mut h, _) = view((0.0, 0.0, 100.0, 60.0));
+
+        h.click((90.0, 10.0));
+        assert_eq!(h.widget.scroll, 31.0);
+        h.click((70.0, 10.0)).click((70.0, 10.0));
+        assert_eq!(h.widget.scroll, 0.0);
+
+        h.mouse(PRIMARY_POINTER, MouseType::Wheel(-1.0), (10.0, 10.0));
+        assert_eq!(h.widget.scroll, 19.0);
+
+        // The active tab is revealed
+        h.key(PAGE_UP, CTRL);
+        assert_eq!(state(&h).active(), Some(3));
+        assert_eq!(h.widget.scroll, 113.0);
+    }
+}
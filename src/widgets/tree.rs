@@ -0,0 +1,1001 @@
+//! Virtualized trees
+//!
+//! A TreeView keeps the visible nodes flattened in rows, the children of a node
+//! are asked to the TreeSource only when it's expanded. Every row has the same
+//! height, so it's scrolled like a ListView
+
+use super::list::{mouse_mods, Clicks, ItemScroll, ItemState, SelectionMode};
+use super::{arrow, inset, mouse_pos, rect, Direction, Style, TypeAhead};
+use crate::group::{push_event, GroupEvent, GroupID};
+use crate::math::Scalar;
+use crate::paint::Painter;
+use crate::state::keys::*;
+use crate::state::{KeyState, MouseState, MouseType, PointerID};
+use crate::text::SharedMeasure;
+use crate::widget::flags::*;
+use crate::widget::{Boundaries, Dimensions, Widget, WidgetInternal};
+
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::ops::{Add, Sub};
+use std::rc::Rc;
+
+/// Distance that the pointer moves before a press drags the node
+const DRAG_DISTANCE: f32 = 4.0;
+
+/// ID of a node given by the TreeSource
+pub type NodeID = usize;
+
+/// Where a dragged node is dropped relative to the target node
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DropPosition {
+    Before,
+    After,
+    /// As a child of the target
+    Inside,
+}
+
+/// Selected nodes of a TreeView and the node of the keyboard cursor
+///
+/// The nodes stay selected when their parent is collapsed
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NodeSelection {
+    nodes: HashSet<NodeID>,
+    current: Option<NodeID>,
+}
+
+impl NodeSelection {
+    pub fn new() -> Self {
+        NodeSelection::default()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    #[inline]
+    pub fn contains(&self, node: NodeID) -> bool {
+        self.nodes.contains(&node)
+    }
+
+    /// Selected nodes without order
+    pub fn iter(&self) -> impl Iterator<Item = NodeID> + '_ {
+        self.nodes.iter().copied()
+    }
+
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+    }
+
+    pub fn add(&mut self, node: NodeID) {
+        self.nodes.insert(node);
+    }
+
+    pub fn remove(&mut self, node: NodeID) {
+        self.nodes.remove(&node);
+    }
+
+    /// Replace the selected nodes
+    pub fn set<I: IntoIterator<Item = NodeID>>(&mut self, nodes: I) {
+        self.nodes.clear();
+        self.nodes.extend(nodes);
+    }
+
+    pub fn toggle(&mut self, node: NodeID) {
+        if !self.nodes.remove(&node) {
+            self.nodes.insert(node);
+        }
+    }
+
+    /// Node of the keyboard cursor
+    #[inline]
+    pub fn current(&self) -> Option<NodeID> {
+        self.current
+    }
+
+    #[inline]
+    pub fn set_current(&mut self, node: Option<NodeID>) {
+        self.current = node;
+    }
+}
+
+/// Tree of nodes drawn by a TreeView
+///
+/// The nodes without parent are the roots. The tree draws the background, the
+/// indentation and the expander before draw_node
+pub trait TreeSource<T, CTX> {
+    /// Children of a node or the roots with None, it's called when the node is
+    /// expanded and when the tree is loaded again
+    fn children(&mut self, parent: Option<NodeID>) -> Vec<NodeID>;
+    /// Check if a node has children without loading them
+    fn has_children(&self, node: NodeID) -> bool;
+    /// Height of every node
+    fn item_height(&self) -> T;
+    /// Draw a visible node in its absolute boundaries, after the indentation
+    fn draw_node(&mut self, node: NodeID, bounds: Boundaries<T>, state: ItemState, ctx: &mut CTX);
+    /// Text of a node for find it typing, empty nodes are skipped
+    fn text(&self, _: NodeID) -> String {
+        String::new()
+    }
+    /// Check if a node can be dropped at a position of the target, nothing can be
+    /// dragged by default
+    fn can_drop(&self, _: NodeID, _: NodeID, _: DropPosition) -> bool {
+        false
+    }
+    /// Move a node dropped at a position of the target, the tree loads the
+    /// children again after it
+    fn drop_node(&mut self, _: NodeID, _: NodeID, _: DropPosition) {}
+    /// Store the text measurement of the Root if the nodes have text
+    fn measure(&mut self, _: &SharedMeasure<T>) {}
+}
+
+/// Visible node
+struct Row {
+    node: NodeID,
+    depth: usize,
+    has_children: bool,
+}
+
+/// Node pressed by the mouse, it's dragged after it moves
+struct NodePress {
+    node: NodeID,
+    y: f32,
+    dragging: bool,
+}
+
+/// Scrollable tree of nodes from a TreeSource
+///
+/// Up, Down, Page Up, Page Down, Home and End move the current node like in a
+/// ListView. Right expands the current node or goes to its first child and Left
+/// collapses it or goes to its parent. Typing a char goes to the next node that
+/// starts with the typed text. Double clicking a node with children expands or
+/// collapses it, a node without children is activated like with Enter
+///
+/// A pressed node can be dragged over the others when the source allows the
+/// drop, the signal is pushed after the source moves it
+pub struct TreeView<T, CTX> {
+    source: Box<dyn TreeSource<T, CTX>>,
+    selection: Rc<RefCell<NodeSelection>>,
+    mode: SelectionMode,
+    signal: GroupID,
+    activate: Option<GroupID>,
+    style: Style<T>,
+    rows: Vec<Row>,
+    expanded: HashSet<NodeID>,
+    /// The rows were loaded from the source
+    loaded: bool,
+    scroll: ItemScroll,
+    /// Start of the range selected with Shift
+    anchor: Option<NodeID>,
+    hover: Option<usize>,
+    press: Option<NodePress>,
+    target: Option<(NodeID, DropPosition)>,
+    typed: TypeAhead,
+    clicks: Clicks,
+}
+
+impl<T, CTX> TreeView<T, CTX>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+{
+    /// Creates a TreeView of a source with the shared selection, the selection
+    /// mode and the signal ID pushed when the selection or the tree changes
+    pub fn new(
+        source: Box<dyn TreeSource<T, CTX>>,
+        selection: Rc<RefCell<NodeSelection>>,
+        mode: SelectionMode,
+        signal: GroupID,
+    ) -> Self {
+        TreeView {
+            source,
+            selection,
+            mode,
+            signal,
+            activate: None,
+            style: Style::default(),
+            rows: Vec::new(),
+            expanded: HashSet::new(),
+            loaded: false,
+            scroll: ItemScroll::new(),
+            anchor: None,
+            hover: None,
+            press: None,
+            target: None,
+            typed: TypeAhead::default(),
+            clicks: Clicks::new(),
+        }
+    }
+
+    pub fn set_style(&mut self, style: Style<T>) {
+        self.style = style;
+    }
+
+    /// Push a signal when a node without children is double clicked or Enter is
+    /// pressed, the node is the current node of the selection
+    pub fn set_activate(&mut self, signal: GroupID) {
+        self.activate = Some(signal);
+    }
+
+    #[inline]
+    pub fn source(&self) -> &dyn TreeSource<T, CTX> {
+        self.source.as_ref()
+    }
+
+    /// Source of the nodes, call reload after changing the tree
+    #[inline]
+    pub fn source_mut(&mut self) -> &mut dyn TreeSource<T, CTX> {
+        self.source.as_mut()
+    }
+
+    #[inline]
+    pub fn is_expanded(&self, node: NodeID) -> bool {
+        self.expanded.contains(&node)
+    }
+
+    /// Expand or collapse a node, the children of a collapsed parent are shown
+    /// when it's expanded
+    pub fn set_expanded(&mut self, node: NodeID, expanded: bool) {
+        match self.row_of(node) {
+            Some(row) if self.loaded && expanded => self.expand(row),
+            Some(row) if self.loaded => self.collapse(row),
+            _ if expanded => {
+                self.expanded.insert(node);
+            }
+            _ => {
+                self.expanded.remove(&node);
+            }
+        }
+    }
+
+    /// Ask the source again for the children of the roots and the expanded nodes
+    pub fn reload(&mut self) {
+        let mut rows = Vec::new();
+        append(self.source.as_mut(), &self.expanded, &mut rows, None, 0);
+
+        self.rows = rows;
+        self.loaded = true;
+        self.hover = None;
+    }
+
+    fn load(&mut self) {
+        if !self.loaded {
+            self.reload();
+        }
+    }
+
+    fn row_of(&self, node: NodeID) -> Option<usize> {
+        self.rows.iter().position(|row| row.node == node)
+    }
+
+    /// Rows after a row that are its descendants
+    fn descendants(&self, row: usize) -> usize {
+        let depth = self.rows[row].depth;
+        self.rows[row + 1..]
+            .iter()
+            .take_while(|r| r.depth > depth)
+            .count()
+    }
+
+    fn parent(&self, row: usize) -> Option<usize> {
+        let depth = self.rows[row].depth;
+        self.rows[..row].iter().rposition(|r| r.depth < depth)
+    }
+
+    fn expand(&mut self, row: usize) {
+        let (node, depth) = (self.rows[row].node, self.rows[row].depth);
+        if !self.rows[row].has_children || !self.expanded.insert(node) {
+            return;
+        }
+
+        let mut children = Vec::new();
+        append(
+            self.source.as_mut(),
+            &self.expanded,
+            &mut children,
+            Some(node),
+            depth + 1,
+        );
+
+        self.rows.splice(row + 1..row + 1, children);
+        self.hover = None;
+    }
+
+    fn collapse(&mut self, row: usize) {
+        if !self.expanded.remove(&self.rows[row].node) {
+            return;
+        }
+
+        let count = self.descendants(row);
+        let removed: Vec<Row> = self.rows.drain(row + 1..row + 1 + count).collect();
+        self.hover = None;
+
+        // The cursor can't stay in a hidden node
+        let mut selection = self.selection.borrow_mut();
+        if removed.iter().any(|r| Some(r.node) == selection.current()) {
+            selection.set_current(Some(self.rows[row].node));
+        }
+    }
+
+    fn toggle(&mut self, internal: &mut WidgetInternal<T>, row: usize) {
+        if self.expanded.contains(&self.rows[row].node) {
+            self.collapse(row);
+        } else {
+            self.expand(row);
+        }
+
+        internal.on(DRAW);
+    }
+
+    /// Update the scroll with the rows, returns the areas of the rows and the
+    /// scrollbar
+    fn areas(&mut self, internal: &WidgetInternal<T>) -> (Boundaries<T>, Option<Boundaries<T>>) {
+        self.load();
+
+        let height = self.source.item_height().to_f32();
+        self.scroll.update(self.rows.len(), height);
+        self.scroll.areas(internal.boundaries_abs(), &self.style)
+    }
+
+    /// Width of each level of indentation and of the expander
+    fn indent(&self) -> f32 {
+        self.scroll.height() as f32
+    }
+
+    /// Move the current node and change the selection like a click or a key
+    fn select(
+        &mut self,
+        internal: &mut WidgetInternal<T>,
+        area: Boundaries<T>,
+        row: usize,
+        mods: u16,
+    ) {
+        if self.rows.is_empty() {
+            return;
+        }
+
+        let row = row.min(self.rows.len() - 1);
+        let node = self.rows[row].node;
+        let mut selection = self.selection.borrow_mut();
+        let before = selection.nodes.clone();
+
+        selection.set_current(Some(node));
+        match self.mode {
+            SelectionMode::None => {}
+            SelectionMode::Multiple if mods & SHIFT != 0 => {
+                let anchor = self.anchor.and_then(|n| self.row_of(n)).unwrap_or(row);
+                let range = self.rows[anchor.min(row)..anchor.max(row) + 1]
+                    .iter()
+                    .map(|r| r.node);
+
+                if mods & CTRL != 0 {
+                    selection.nodes.extend(range);
+                } else {
+                    selection.set(range);
+                }
+            }
+            SelectionMode::Multiple if mods & CTRL != 0 => {}
+            _ => {
+                selection.set(Some(node));
+                self.anchor = Some(node);
+            }
+        }
+
+        if selection.nodes != before {
+            push_event(GroupEvent::Signal(self.signal));
+        }
+        drop(selection);
+
+        self.scroll.reveal(internal, area, row);
+        internal.on(DRAW);
+    }
+
+    /// Change the selection without moving the current node
+    fn change<F: FnOnce(&mut NodeSelection)>(&mut self, internal: &mut WidgetInternal<T>, f: F) {
+        let mut selection = self.selection.borrow_mut();
+        let before = selection.nodes.clone();
+        f(&mut selection);
+
+        if selection.nodes != before {
+            push_event(GroupEvent::Signal(self.signal));
+            internal.on(DRAW);
+        }
+    }
+
+    fn activate(&self) {
+        if let Some(signal) = self.activate {
+            push_event(GroupEvent::Signal(signal));
+        }
+    }
+
+    /// Add a char to the typed text and go to the node that starts with it. If no
+    /// node matches, the char starts a new text from the next node, so typing the
+    /// same char goes through the nodes that start with it
+    fn type_ahead(&mut self, internal: &mut WidgetInternal<T>, area: Boundaries<T>, c: char) {
+        let current = self.selection.borrow().current();
+        let row = current.and_then(|node| self.row_of(node)).unwrap_or(0);
+
+        let (source, rows) = (&self.source, &self.rows);
+        let found = self
+            .typed
+            .push(rows.len(), row, c, |n| source.text(rows[n].node));
+
+        if let Some(found) = found {
+            self.select(internal, area, found, 0);
+        }
+    }
+
+    /// Node and position where a dragged node would be dropped
+    fn drop_target(&self, area: Boundaries<T>, y: f32) -> Option<(NodeID, DropPosition)> {
+        let press = self.press.as_ref()?;
+        let row = self.scroll.index_at(area, y)?;
+        let source = self.row_of(press.node)?;
+
+        // A node can't be dropped inside itself
+        if row >= source && row <= source + self.descendants(source) {
+            return None;
+        }
+
+        let fraction = (y - self.scroll.item_y(area, row)) / self.indent();
+        let position = if fraction < 0.25 {
+            DropPosition::Before
+        } else if fraction > 0.75 {
+            DropPosition::After
+        } else {
+            DropPosition::Inside
+        };
+
+        let target = self.rows[row].node;
+        Some((target, position)).filter(|_| self.source.can_drop(press.node, target, position))
+    }
+}
+
+/// Flatten the children of a parent and the children of the expanded ones
+fn append<T, CTX>(
+    source: &mut dyn TreeSource<T, CTX>,
+    expanded: &HashSet<NodeID>,
+    rows: &mut Vec<Row>,
+    parent: Option<NodeID>,
+    depth: usize,
+) {
+    for node in source.children(parent) {
+        let has_children = source.has_children(node);
+        rows.push(Row {
+            node,
+            depth,
+            has_children,
+        });
+
+        if has_children && expanded.contains(&node) {
+            append(source, expanded, rows, Some(node), depth + 1);
+        }
+    }
+}
+
+impl<T, CTX> Widget<T, CTX> for TreeView<T, CTX>
+where
+    T: Scalar + Add<Output = T> + Sub<Output = T> + PartialOrd + Default,
+    CTX: Painter<T>,
+{
+    fn draw(&mut self, internal: &WidgetInternal<T>, ctx: &mut CTX) -> bool {
+        let bounds = internal.boundaries_abs();
+        let (area, bar) = self.areas(internal);
+        self.scroll.clamp(area);
+
+        let style = &self.style;
+        ctx.fill_rect(bounds, style.window);
+        ctx.fill_round_rect(bounds, style.radius, style.outline_color(internal));
+        ctx.fill_rect(inset(bounds, style.border), style.field);
+
+        let (x, w) = (area.0.to_f32(), area.2.to_f32());
+        let (h, indent) = (self.scroll.height() as f32, self.indent());
+        let focused = internal.check(FOCUS);
+        let size = style.font_size.to_f32() * 0.6;
+
+        ctx.push_clip(area);
+
+        let selection = self.selection.borrow();
+        for index in self.scroll.visible(area) {
+            let row = &self.rows[index];
+            let state = ItemState {
+                selected: selection.contains(row.node),
+                current: selection.current() == Some(row.node),
+                hover: self.hover == Some(index) && self.press.is_none(),
+                focused,
+            };
+
+            let y = self.scroll.item_y(area, index);
+            let item = rect(x, y, w, h);
+            let background = if state.selected && focused {
+                Some(style.accent)
+            } else if state.selected {
+                Some(style.selection)
+            } else if state.hover && internal.check(ENABLED) {
+                Some(style.hover)
+            } else {
+                None
+            };
+
+            if let Some(color) = background {
+                ctx.fill_rect(item, color);
+            }
+
+            let left = x + indent * row.depth as f32;
+            if row.has_children {
+                let color = if state.selected && focused {
+                    style.accent_text
+                } else {
+                    style.text_color(internal)
+                };
+
+                let direction = if self.expanded.contains(&row.node) {
+                    Direction::Down
+                } else {
+                    Direction::Right
+                };
+                let bounds = rect(left, y, indent, h);
+                ctx.fill_path(&arrow(bounds, size, direction), color);
+            }
+
+            let node = rect(left + indent, y, (w - indent - left + x).max(0.0), h);
+            self.source.draw_node(row.node, node, state, ctx);
+
+            if state.current && focused {
+                ctx.stroke_rect(item, style.border, style.focus);
+            }
+
+            // Place where the dragged node will be dropped
+            match self.target {
+                Some((target, position)) if target == row.node => {
+                    let line = style.border.to_f32() * 2.0;
+                    let (start, width) = (left + indent, (x + w - left - indent).max(0.0));
+
+                    match position {
+                        DropPosition::Before => {
+                            ctx.fill_rect(rect(start, y, width, line), style.focus)
+                        }
+                        DropPosition::After => {
+                            ctx.fill_rect(rect(start, y + h - line, width, line), style.focus)
+                        }
+                        DropPosition::Inside => {
+                            ctx.stroke_rect(item, T::from_f32(line), style.focus)
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        ctx.pop_clip();
+
+        if let Some(bar) = bar {
+            self.scroll.draw(ctx, style, area, bar);
+        }
+
+        false
+    }
+
+    fn measure(&mut self, _: &mut WidgetInternal<T>, measure: &SharedMeasure<T>) {
+        self.source.measure(measure);
+    }
+
+    /// The tree or the selection were changed by the application
+    fn handle_signal(&mut self, internal: &mut WidgetInternal<T>, _: GroupID) {
+        self.reload();
+        internal.on(DRAW);
+    }
+
+    fn handle_mouse(&mut self, internal: &mut WidgetInternal<T>, mouse: &MouseState<T>) {
+        if !internal.check(ENABLED) {
+            return;
+        }
+
+        let (area, bar) = self.areas(internal);
+        let (x, y) = mouse_pos(mouse);
+
+        let row = self.scroll.index_at(area, y);
+        let on_bar = bar.is_some_and(|bar| x >= bar.0.to_f32());
+
+        match mouse.m_type {
+            MouseType::Pressed(1) => {
+                internal.on(GRAB | FOCUS | DRAW);
+                self.typed.clear();
+
+                if let (true, Some(bar)) = (on_bar, bar) {
+                    self.scroll.press(internal, area, bar, y);
+                    return;
+                }
+
+                let row = match row {
+                    Some(row) => row,
+                    None => return,
+                };
+
+                // The expander only expands or collapses
+                let left = area.0.to_f32() + self.indent() * self.rows[row].depth as f32;
+                if self.rows[row].has_children && x >= left && x < left + self.indent() {
+                    self.toggle(internal, row);
+                    return;
+                }
+
+                let node = self.rows[row].node;
+                let mods = mouse_mods(mouse);
+                if self.mode == SelectionMode::Multiple && mods == CTRL {
+                    self.change(internal, |s| s.toggle(node));
+                    self.anchor = Some(node);
+                }
+
+                self.select(internal, area, row, mods);
+                self.press = Some(NodePress {
+                    node,
+                    y,
+                    dragging: false,
+                });
+
+                if self.clicks.click(mouse.time(), node) {
+                    if self.rows[row].has_children {
+                        self.toggle(internal, row);
+                    } else {
+                        self.activate();
+                    }
+                }
+            }
+            MouseType::Released(1) => {
+                internal.off(GRAB);
+                internal.on(DRAW);
+                self.scroll.release();
+
+                let press = self.press.take();
+                if let (Some(press), Some((target, position))) = (press, self.target.take()) {
+                    self.source.drop_node(press.node, target, position);
+                    self.reload();
+                    push_event(GroupEvent::Signal(self.signal));
+                }
+            }
+            MouseType::CursorMoved => {
+                if self.scroll.drag(internal, area, bar, y) {
+                    return;
+                }
+
+                match &mut self.press {
+                    Some(press) => {
+                        if !press.dragging && (y - press.y).abs() < DRAG_DISTANCE {
+                            return;
+                        }
+                        press.dragging = true;
+
+                        // Out of the tree it scrolls to the hidden rows
+                        let nearest = self.scroll.nearest(area, y);
+                        self.scroll.reveal(internal, area, nearest);
+
+                        self.target = self.drop_target(area, y);
+                        internal.on(DRAW);
+                    }
+                    None => {
+                        let hover = row.filter(|_| !on_bar);
+                        if hover != self.hover {
+                            self.hover = hover;
+                            internal.on(DRAW);
+                        }
+                    }
+                }
+            }
+            MouseType::Wheel(delta) => self.scroll.wheel(internal, area, delta),
+            _ => {}
+        }
+    }
+
+    fn handle_keys(&mut self, internal: &mut WidgetInternal<T>, key: KeyState) {
+        let (code, mods) = match key {
+            KeyState::Pressed(code, mods) if internal.check(ENABLED) => (code, mods),
+            _ => return,
+        };
+
+        let (area, _) = self.areas(internal);
+        let count = self.rows.len();
+        if count == 0 {
+            return;
+        }
+
+        let page = self.scroll.page(area);
+        let node = self.selection.borrow().current();
+        let current = node.and_then(|node| self.row_of(node));
+        let row = current.unwrap_or(0);
+
+        let target = match code {
+            UP if current.is_some() => row.saturating_sub(1),
+            DOWN if current.is_some() => row + 1,
+            UP | DOWN => row,
+            PAGE_UP => row.saturating_sub(page),
+            PAGE_DOWN => row + page,
+            HOME => 0,
+            END => count - 1,
+            LEFT if self.expanded.contains(&self.rows[row].node) => {
+                self.toggle(internal, row);
+                row
+            }
+            LEFT => self.parent(row).unwrap_or(row),
+            RIGHT
+                if self.rows[row].has_children && !self.expanded.contains(&self.rows[row].node) =>
+            {
+                self.toggle(internal, row);
+                row
+            }
+            RIGHT if self.descendants(row) > 0 => row + 1,
+            RIGHT => row,
+            SPACE if mods & CTRL != 0 && self.mode == SelectionMode::Multiple => {
+                let node = self.rows[row].node;
+                self.change(internal, |s| s.toggle(node));
+                self.anchor = Some(node);
+                return;
+            }
+            SPACE if self.typed.is_empty() => row,
+            ENTER if current.is_some() => {
+                self.activate();
+                return;
+            }
+            _ => match to_char(code) {
+                Some('a') | Some('A')
+                    if mods & CTRL != 0 && self.mode == SelectionMode::Multiple =>
+                {
+                    let nodes = self.rows.iter().map(|r| r.node);
+                    self.selection.borrow_mut().nodes.extend(nodes);
+                    push_event(GroupEvent::Signal(self.signal));
+                    internal.on(DRAW);
+                    return;
+                }
+                Some(c) if mods & (CTRL | ALT | SUPER) == 0 => {
+                    self.type_ahead(internal, area, c);
+                    return;
+                }
+                _ => return,
+            },
+        };
+
+        self.typed.clear();
+        let mods = match code {
+            SPACE | LEFT | RIGHT => 0,
+            _ => mods,
+        };
+        self.select(internal, area, target, mods);
+    }
+
+    fn hover_out(&mut self, internal: &mut WidgetInternal<T>) {
+        self.hover = None;
+        internal.on(DRAW);
+    }
+
+    fn pointer_out(&mut self, internal: &mut WidgetInternal<T>, _: PointerID) {
+        self.scroll.release();
+        self.press = None;
+        self.target = None;
+        internal.off(GRAB);
+        internal.on(DRAW);
+    }
+
+    fn min_dimensions(&self) -> Dimensions<T> {
+        let space = (self.style.border.to_f32() + self.style.padding.to_f32()) * 2.0;
+        let h = self.source.item_height().to_f32().max(1.0);
+
+        (T::from_f32(h * 6.0 + space), T::from_f32(h * 3.0 + space))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::DisplayList;
+    use crate::state::PRIMARY_POINTER;
+    use crate::widgets::tests::{signals, Harness};
+
+    use std::cell::Cell;
+
+    /// Nodes with their parents in order, the folders are the nodes without a dot
+    type Nodes = Rc<RefCell<Vec<(NodeID, Option<NodeID>, &'static str)>>>;
+
+    struct Files {
+        nodes: Nodes,
+        /// Calls of children
+        loads: Rc<Cell<usize>>,
+    }
+
+    impl Files {
+        fn name(&self, node: NodeID) -> &'static str {
+            let nodes = self.nodes.borrow();
+            nodes.iter().find(|n| n.0 == node).map_or("", |n| n.2)
+        }
+    }
+
+    impl TreeSource<f32, DisplayList<f32>> for Files {
+        fn children(&mut self, parent: Option<NodeID>) -> Vec<NodeID> {
+            self.loads.set(self.loads.get() + 1);
+            let nodes = self.nodes.borrow();
+            nodes
+                .iter()
+                .filter(|n| n.1 == parent)
+                .map(|n| n.0)
+                .collect()
+        }
+
+        fn has_children(&self, node: NodeID) -> bool {
+            self.nodes.borrow().iter().any(|n| n.1 == Some(node))
+        }
+
+        fn item_height(&self) -> f32 {
+            10.0
+        }
+
+        fn draw_node(
+            &mut self,
+            _: NodeID,
+            _: Boundaries<f32>,
+            _: ItemState,
+            _: &mut DisplayList<f32>,
+        ) {
+        }
+
+        fn text(&self, node: NodeID) -> String {
+            String::from(self.name(node))
+        }
+
+        fn can_drop(&self, _: NodeID, target: NodeID, position: DropPosition) -> bool {
+            position != DropPosition::Inside || !self.name(target).contains('.')
+        }
+
+        fn drop_node(&mut self, node: NodeID, target: NodeID, position: DropPosition) {
+            let mut nodes = self.nodes.borrow_mut();
+            let n = nodes.iter().position(|n| n.0 == node).unwrap();
+            let mut moved = nodes.remove(n);
+
+            let n = nodes.iter().position(|n| n.0 == target).unwrap();
+            let (n, parent) = match position {
+                DropPosition::Before => (n, nodes[n].1),
+                DropPosition::After => (n + 1, nodes[n].1),
+                DropPosition::Inside => (nodes.len(), Some(target)),
+            };
+
+            moved.1 = parent;
+            nodes.insert(n, moved);
+        }
+    }
+
+    type Tree = Harness<TreeView<f32, DisplayList<f32>>>;
+
+    /// A tree of 50 high rows that are 10 high, each level is indented 10
+    fn tree(mode: SelectionMode) -> (Tree, Rc<RefCell<NodeSelection>>, Rc<Cell<usize>>) {
+        let nodes = vec![
+            (1, None, "docs"),
+            (2, Some(1), "a.txt"),
+            (3, Some(1), "b.txt"),
+            (4, None, "src"),
+            (5, Some(4), "main.rs"),
+            (6, None, "readme.md"),
+        ];
+
+        let loads = Rc::new(Cell::new(0));
+        let files = Box::new(Files {
+            nodes: Rc::new(RefCell::new(nodes)),
+            loads: loads.clone(),
+        });
+
+        let selection = Rc::new(RefCell::new(NodeSelection::new()));
+        let mut view = TreeView::new(files, selection.clone(), mode, 8);
+        view.set_activate(9);
+
+        (
+            Harness::new(view, (0.0, 0.0, 100.0, 52.0)),
+            selection,
+            loads,
+        )
+    }
+
+    fn rows(h: &Tree) -> Vec<(NodeID, usize)> {
+        h.widget.rows.iter().map(|r| (r.node, r.depth)).collect()
+    }
+
+    #[test]
+    fn lazy_children() {
+        let (mut h, selection, loads) = tree(SelectionMode::Single);
+
+        h.draw();
+        assert_eq!(rows(&h), [(1, 0), (4, 0), (6, 0)]);
+        assert_eq!(loads.get(), 1);
+
+        // Right expands and then goes to the first child, Left goes back
+        h.key(RIGHT, 0);
+        assert_eq!(rows(&h), [(1, 0), (2, 1), (3, 1), (4, 0), (6, 0)]);
+        assert_eq!(loads.get(), 2);
+        h.key(RIGHT, 0).key(DOWN, 0);
+        assert_eq!(selection.borrow().current(), Some(3));
+        h.key(LEFT, 0);
+        assert_eq!(selection.borrow().current(), Some(1));
+        assert_eq!(signals(), [8]);
+
+        // The cursor goes to the parent of the hidden nodes
+        h.key(DOWN, 0);
+        h.widget.set_expanded(1, false);
+        assert_eq!(rows(&h), [(1, 0), (4, 0), (6, 0)]);
+        assert_eq!(selection.borrow().current(), Some(1));
+
+        // Expanding again loads the children again
+        h.widget.set_expanded(1, true);
+        assert_eq!(loads.get(), 3);
+        signals();
+    }
+
+    #[test]
+    fn mouse() {
+        let (mut h, selection, _) = tree(SelectionMode::Multiple);
+
+        // The expander of docs is from x 1 to 11
+        h.click((5.0, 5.0));
+        assert!(h.widget.is_expanded(1));
+        assert!(selection.borrow().is_empty());
+
+        h.click((30.0, 15.0));
+        h.pointer(PRIMARY_POINTER).set_modifiers(SHIFT);
+        h.click((30.0, 35.0));
+        let mut selected: Vec<_> = selection.borrow().iter().collect();
+        selected.sort_unstable();
+        assert_eq!(selected, [2, 3, 4]);
+        assert_eq!(signals(), [8]);
+
+        // A double click expands a folder and activates a file
+        h.pointer(PRIMARY_POINTER).set_modifiers(0);
+        h.pointer(PRIMARY_POINTER).set_time(1000);
+        h.click((30.0, 35.0)).click((30.0, 35.0));
+        assert!(h.widget.is_expanded(4));
+        h.click((30.0, 45.0)).click((30.0, 45.0));
+        assert_eq!(selection.borrow().current(), Some(5));
+        assert_eq!(signals(), [8, 9]);
+    }
+
+    #[test]
+    fn type_ahead() {
+        let (mut h, selection, _) = tree(SelectionMode::Single);
+
+        h.key('r' as u32, 0);
+        assert_eq!(selection.borrow().current(), Some(6));
+        h.key('e' as u32, 0);
+        assert_eq!(selection.borrow().current(), Some(6));
+
+        // A char that doesn't match starts a new text
+        h.key('s' as u32, 0);
+        assert_eq!(selection.borrow().current(), Some(4));
+        h.key(HOME, 0).key('s' as u32, 0);
+        assert_eq!(selection.borrow().current(), Some(4));
+        signals();
+    }
+
+    #[test]
+    fn drag_and_drop() {
+        let (mut h, _, _) = tree(SelectionMode::Single);
+        h.widget.set_expanded(1, true);
+
+        // readme.md is dropped before a.txt, the rows are 10 high from y 1
+        h.mouse(PRIMARY_POINTER, MouseType::Pressed(1), (30.0, 45.0));
+        h.mouse(PRIMARY_POINTER, MouseType::CursorMoved, (30.0, 12.0));
+        assert_eq!(h.widget.target, Some((2, DropPosition::Before)));
+
+        // A file can't be a parent
+        h.mouse(PRIMARY_POINTER, MouseType::CursorMoved, (30.0, 16.0));
+        assert_eq!(h.widget.target, None);
+        h.mouse(PRIMARY_POINTER, MouseType::CursorMoved, (30.0, 12.0));
+        h.mouse(PRIMARY_POINTER, MouseType::Released(1), (30.0, 12.0));
+        assert_eq!(rows(&h), [(1, 0), (6, 1), (2, 1), (3, 1), (4, 0)]);
+        assert_eq!(signals(), [8]);
+
+        // A folder can't be dropped inside itself
+        h.mouse(PRIMARY_POINTER, MouseType::Pressed(1), (30.0, 5.0));
+        h.mouse(PRIMARY_POINTER, MouseType::CursorMoved, (30.0, 26.0));
+        assert_eq!(h.widget.target, None);
+        h.mouse(PRIMARY_POINTER, MouseType::Released(1), (30.0, 26.0));
+        assert_eq!(rows(&h), [(1, 0), (6, 1), (2, 1), (3, 1), (4, 0)]);
+        signals();
+    }
+}